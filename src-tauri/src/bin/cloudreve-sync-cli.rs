@@ -0,0 +1,414 @@
+//! 不启动图形界面的命令行入口，供服务器与 NAS 使用。所有输出均为每行一个 JSON 对象。
+
+use cloudreve_sync_app::core::backup::{backups_dir, create_backup};
+use cloudreve_sync_app::core::config::{db_path, settings_path, ApiPaths, AppSettings};
+use cloudreve_sync_app::core::db::{
+    get_account_status, get_next_due, get_scan_schedule, list_tasks, mark_account_needs_login,
+    now_ms, record_account_auth, record_scan, record_task_run, set_next_due, TaskRow,
+};
+use cloudreve_sync_app::core::error::SyncError;
+use cloudreve_sync_app::core::events::{EventBus, SyncEvent};
+use cloudreve_sync_app::core::logging::LogStore;
+use cloudreve_sync_app::core::migrations::migrate_with_backup;
+use cloudreve_sync_app::core::pool::{connect, PooledConn};
+use cloudreve_sync_app::core::runner::{
+    build_engine, deep_scan_allowed, in_quiet_hours_now, run_settings, system_pause_reason,
+    try_lock_task, RunSettings,
+};
+use cloudreve_sync_app::core::schedule::{
+    RunPlan, ScanSchedule, SyncWindow, DEFAULT_DEEP_SCAN_INTERVAL_SECS,
+};
+use cloudreve_sync_app::core::sync::{ScanMode, SyncStats};
+use cloudreve_sync_app::core::system_state::PauseReason;
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+const DAEMON_TICK: Duration = Duration::from_secs(5);
+
+const USAGE: &str = "用法: cloudreve-sync-cli [--db <数据库路径>] <命令>
+
+命令:
+  list                         列出所有任务
+  run [--deep] <任务 ID>...     立即同步指定任务一轮
+  run [--deep] --all           立即同步所有已启用的任务一轮
+  daemon                       按各任务的同步间隔持续运行";
+
+enum Command {
+    List,
+    Run {
+        task_ids: Vec<String>,
+        all: bool,
+        deep: bool,
+    },
+    Daemon,
+}
+
+/// 列表与调度用到的任务设置。
+#[derive(Deserialize)]
+struct TaskInfo {
+    #[serde(default)]
+    name: String,
+    sync_interval_secs: u64,
+    #[serde(default = "default_deep_scan_interval_secs")]
+    deep_scan_interval_secs: u64,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default)]
+    sync_cron: String,
+    #[serde(default)]
+    sync_windows: Vec<SyncWindow>,
+}
+
+fn default_deep_scan_interval_secs() -> u64 {
+    DEFAULT_DEEP_SCAN_INTERVAL_SECS
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn task_info(task: &TaskRow) -> Result<(TaskInfo, RunSettings), Box<dyn Error>> {
+    let info = serde_json::from_str(&task.settings_json)
+        .map_err(|err| format!("任务 {} 的设置无效: {}", task.task_id, err))?;
+    Ok((info, run_settings(task)?))
+}
+
+struct Cli {
+    db_path: Option<PathBuf>,
+    command: Command,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+    let mut args = args.into_iter();
+    let mut db_path = None;
+    let mut command = None;
+    let mut task_ids = Vec::new();
+    let mut all = false;
+    let mut deep = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => {
+                let value = args.next().ok_or("--db 需要一个路径")?;
+                db_path = Some(PathBuf::from(value));
+            }
+            "--all" => all = true,
+            "--deep" => deep = true,
+            "-h" | "--help" => return Err(String::new()),
+            _ if command.is_none() => command = Some(arg),
+            _ => task_ids.push(arg),
+        }
+    }
+    let command = match command.as_deref() {
+        Some("list") => Command::List,
+        Some("daemon") => Command::Daemon,
+        Some("run") if all || !task_ids.is_empty() => Command::Run {
+            task_ids,
+            all,
+            deep,
+        },
+        Some("run") => return Err("run 需要任务 ID 或 --all".to_string()),
+        Some(other) => return Err(format!("未知命令: {}", other)),
+        None => return Err(String::new()),
+    };
+    Ok(Cli { db_path, command })
+}
+
+fn emit(value: Value) {
+    println!("{}", value);
+}
+
+fn stats_json(stats: &SyncStats) -> Value {
+    json!({
+        "uploaded_bytes": stats.uploaded_bytes,
+        "downloaded_bytes": stats.downloaded_bytes,
+        "operations": stats.operations,
+        "integrity_retries": stats.integrity_retries,
+        "active_transfers": stats.active_transfers,
+        "held_conflicts": stats.held_conflicts,
+        "new_conflicts": stats.new_conflicts,
+    })
+}
+
+fn event_json(event: &SyncEvent) -> Value {
+    match event {
+        SyncEvent::Log(entry) => json!({ "type": "log", "entry": entry }),
+        SyncEvent::Status { task_id, status } => {
+            json!({ "type": "status", "task_id": task_id, "status": status })
+        }
+        SyncEvent::Progress { task_id, stats } => {
+            json!({ "type": "progress", "task_id": task_id, "stats": stats_json(stats) })
+        }
+        SyncEvent::Transfer(progress) => json!({ "type": "transfer", "progress": progress }),
+    }
+}
+
+fn open_db(path: &Path) -> Result<PooledConn, Box<dyn Error>> {
+    let conn = connect(path)?;
+    migrate_with_backup(&conn, || {
+        let keep = AppSettings::load().unwrap_or_default().backup_keep as usize;
+        create_backup(
+            &backups_dir()?,
+            path,
+            &settings_path()?,
+            "pre-migration",
+            keep,
+        )
+        .map(|_| ())
+    })?;
+    Ok(conn)
+}
+
+fn list_command(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    for task in list_tasks(conn)? {
+        let info = task_info(&task).ok().map(|(info, _)| info);
+        emit(json!({
+            "type": "task",
+            "task_id": task.task_id,
+            "name": info.as_ref().map(|info| info.name.clone()),
+            "enabled": info.as_ref().map(|info| info.enabled),
+            "mode": task.mode,
+            "local_root": task.local_root,
+            "remote_root": task.remote_root_uri,
+            "next_due_ms": get_next_due(conn, &task.task_id)?,
+        }));
+    }
+    Ok(())
+}
+
+/// 同步一轮并记录运行结果，与桌面端写入同一张日志与运行记录表。
+fn run_task(
+    runtime: &Runtime,
+    db_path: &Path,
+    task: TaskRow,
+    settings: &RunSettings,
+    mode: ScanMode,
+) -> bool {
+    let task_id = task.task_id.clone();
+    // 与桌面端共用任务锁，同一任务不会被两个进程同时同步。
+    let _lock = match try_lock_task(db_path, &task_id) {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) => {
+            emit(json!({ "type": "skipped", "task_id": task_id, "reason": "locked" }));
+            return false;
+        }
+        Err(err) => {
+            emit(json!({ "type": "warning", "task_id": task_id, "error": err.to_string() }));
+            None
+        }
+    };
+    let events = EventBus::new();
+    events.subscribe_log_store(LogStore::new(db_path.to_path_buf()));
+    events.subscribe(|event| emit(event_json(event)));
+    let deep = mode == ScanMode::Deep;
+    emit(json!({ "type": "started", "task_id": task_id, "deep": deep }));
+    let result = build_engine(
+        db_path,
+        &ApiPaths::default(),
+        task,
+        settings,
+        events,
+        Arc::new(AtomicBool::new(false)),
+    )
+    .map_err(SyncError::from)
+    .and_then(|engine| runtime.block_on(engine.sync_with_mode(mode)));
+    let finished_ms = now_ms();
+    let auth_failure = match &result {
+        Err(SyncError::Auth(code)) => Some(code.to_string()),
+        _ => None,
+    };
+    let success = match result {
+        Ok(stats) => {
+            emit(json!({ "type": "finished", "task_id": task_id, "stats": stats_json(&stats) }));
+            true
+        }
+        Err(err) => {
+            emit(json!({
+                "type": "failed",
+                "task_id": task_id,
+                "error": err.to_string(),
+                "kind": err.kind(),
+                "retryable": err.is_retryable(),
+            }));
+            false
+        }
+    };
+    if let Ok(conn) = connect(db_path) {
+        if success {
+            let _ = record_scan(&conn, &task_id, deep, finished_ms);
+            let _ = record_account_auth(&conn, &settings.account_key, finished_ms);
+        } else if let Some(reason) = auth_failure {
+            // 与桌面端一致：刷新令牌也被拒绝时暂停该账户的任务，重新登录后恢复。
+            let _ = mark_account_needs_login(&conn, &settings.account_key, &reason, finished_ms);
+        }
+        let _ = record_task_run(&conn, &task_id, success, finished_ms);
+    }
+    success
+}
+
+fn run_command(
+    runtime: &Runtime,
+    db_path: &Path,
+    conn: &Connection,
+    task_ids: &[String],
+    all: bool,
+    deep: bool,
+) -> Result<bool, Box<dyn Error>> {
+    let tasks = list_tasks(conn)?;
+    for task_id in task_ids {
+        if !tasks.iter().any(|task| &task.task_id == task_id) {
+            return Err(format!("任务不存在: {}", task_id).into());
+        }
+    }
+    let mode = if deep {
+        ScanMode::Deep
+    } else {
+        ScanMode::Quick
+    };
+    let mut success = true;
+    for task in tasks {
+        let (info, settings) = task_info(&task)?;
+        let selected = if all {
+            info.enabled
+        } else {
+            task_ids.contains(&task.task_id)
+        };
+        if selected {
+            success &= run_task(runtime, db_path, task, &settings, mode);
+        }
+    }
+    Ok(success)
+}
+
+/// 暂停原因变化时输出一次，恢复后清除记录。
+fn report_paused(paused: &mut HashMap<String, String>, task_id: &str, reason: Option<String>) {
+    match reason {
+        Some(reason) => {
+            if paused.get(task_id) != Some(&reason) {
+                emit(json!({ "type": "paused", "task_id": task_id, "reason": reason }));
+                paused.insert(task_id.to_string(), reason);
+            }
+        }
+        None => {
+            paused.remove(task_id);
+        }
+    }
+}
+
+/// 依次运行到期且处于时间窗口内的已启用任务，每轮结束后按任务的定时规则记录下次运行时间。
+/// 与桌面端一样遵守系统状态暂停规则、静默时段与空闲时才做定期深度扫描；
+/// 账户登录已失效的任务暂停，直到在桌面端重新登录。
+fn daemon_command(runtime: &Runtime, db_path: &Path) -> Result<(), Box<dyn Error>> {
+    emit(json!({ "type": "daemon", "db_path": db_path }));
+    let mut paused = HashMap::new();
+    loop {
+        let system_reason = match system_pause_reason() {
+            Some(PauseReason::Metered) => Some("metered".to_string()),
+            Some(PauseReason::Interface(_)) => Some("interface".to_string()),
+            Some(_) => Some("battery".to_string()),
+            None if in_quiet_hours_now() => Some("quiet_hours".to_string()),
+            None => None,
+        };
+        let conn = connect(db_path)?;
+        for task in list_tasks(&conn)? {
+            let (info, settings) = match task_info(&task) {
+                Ok((info, settings)) if info.enabled => (info, settings),
+                Ok(_) => continue,
+                Err(err) => {
+                    emit(
+                        json!({ "type": "failed", "task_id": task.task_id, "error": err.to_string() }),
+                    );
+                    continue;
+                }
+            };
+            let task_id = task.task_id.clone();
+            let needs_login = get_account_status(&conn, &settings.account_key)?
+                .is_some_and(|status| status.needs_login);
+            let reason = if needs_login {
+                Some("needs_login".to_string())
+            } else {
+                system_reason.clone()
+            };
+            let skip = reason.is_some();
+            report_paused(&mut paused, &task_id, reason);
+            if skip {
+                continue;
+            }
+            if get_next_due(&conn, &task_id)?.is_some_and(|due| due > now_ms()) {
+                continue;
+            }
+            let schedule = ScanSchedule::new(info.sync_interval_secs, info.deep_scan_interval_secs);
+            let plan = match RunPlan::new(
+                schedule.quick_interval_secs,
+                &info.sync_cron,
+                &info.sync_windows,
+            ) {
+                Ok(plan) => plan,
+                Err(err) => {
+                    emit(json!({ "type": "failed", "task_id": task_id, "error": err }));
+                    RunPlan::every(schedule.quick_interval_secs)
+                }
+            };
+            if !plan.allows_ms(now_ms()) {
+                continue;
+            }
+            let mode =
+                match schedule.next_mode(get_scan_schedule(&conn, &task_id)?.as_ref(), now_ms()) {
+                    ScanMode::Deep if !deep_scan_allowed() => ScanMode::Quick,
+                    mode => mode,
+                };
+            run_task(runtime, db_path, task, &settings, mode);
+            set_next_due(&conn, &task_id, plan.next_run_ms(now_ms()))?;
+        }
+        drop(conn);
+        thread::sleep(DAEMON_TICK);
+    }
+}
+
+fn run(cli: Cli) -> Result<bool, Box<dyn Error>> {
+    let db_path = match cli.db_path {
+        Some(path) => path,
+        None => db_path()?,
+    };
+    let conn = open_db(&db_path)?;
+    let runtime = Runtime::new()?;
+    match cli.command {
+        Command::List => list_command(&conn).map(|_| true),
+        Command::Run {
+            task_ids,
+            all,
+            deep,
+        } => run_command(&runtime, &db_path, &conn, &task_ids, all, deep),
+        Command::Daemon => daemon_command(&runtime, &db_path).map(|_| true),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = match parse_args(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("{}\n", message);
+            }
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(cli) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            emit(json!({ "type": "error", "error": err.to_string() }));
+            ExitCode::FAILURE
+        }
+    }
+}
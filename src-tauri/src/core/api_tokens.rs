@@ -0,0 +1,126 @@
+use crate::core::db::{find_api_token, insert_api_token, list_api_tokens, now_ms, ApiTokenRow};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use uuid::Uuid;
+
+const TOKEN_PREFIX: &str = "crs_";
+
+/// 本地接口令牌的权限范围。`Full` 包含全部权限，其余范围只允许对应的操作，
+/// 例如只读状态的令牌无法修改或删除任务。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiScope {
+    /// 只读取任务状态、指标与健康检查。
+    Status,
+    /// 只创建分享链接。
+    Share,
+    /// 完全控制。
+    Full,
+}
+
+impl ApiScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::Status => "status",
+            ApiScope::Share => "share",
+            ApiScope::Full => "full",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "status" => Some(ApiScope::Status),
+            "share" => Some(ApiScope::Share),
+            "full" => Some(ApiScope::Full),
+            _ => None,
+        }
+    }
+
+    pub fn allows(self, required: ApiScope) -> bool {
+        self == ApiScope::Full || self == required
+    }
+}
+
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// 生成新令牌并保存其哈希，返回记录与只显示这一次的明文令牌。
+pub fn create_api_token(
+    conn: &Connection,
+    name: &str,
+    scope: ApiScope,
+) -> Result<(ApiTokenRow, String), Box<dyn Error>> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("令牌名称不能为空".into());
+    }
+    let token = format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+    let row = ApiTokenRow {
+        token_id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        scope: scope.as_str().to_string(),
+        token_sha256: hash_token(&token),
+        created_at_ms: now_ms(),
+    };
+    insert_api_token(conn, &row)?;
+    Ok((row, token))
+}
+
+/// 还没有创建任何令牌时本地接口保持开放，与旧版本行为一致；
+/// 创建令牌后每个请求都必须携带权限足够的令牌。
+pub fn authorize(
+    conn: &Connection,
+    token: Option<&str>,
+    required: ApiScope,
+) -> Result<bool, Box<dyn Error>> {
+    if list_api_tokens(conn)?.is_empty() {
+        return Ok(true);
+    }
+    let Some(token) = token.filter(|token| !token.is_empty()) else {
+        return Ok(false);
+    };
+    Ok(find_api_token(conn, &hash_token(token))?
+        .and_then(|row| ApiScope::parse(&row.scope))
+        .is_some_and(|scope| scope.allows(required)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::db::{delete_api_token, init_db};
+
+    #[test]
+    fn scoped_tokens_only_allow_their_scope() {
+        let conn = Connection::open_in_memory().expect("open db");
+        init_db(&conn).expect("init db");
+        assert!(authorize(&conn, None, ApiScope::Full).expect("authorize"));
+
+        let (status_row, status) =
+            create_api_token(&conn, "widget", ApiScope::Status).expect("create status");
+        let (_, full) = create_api_token(&conn, "admin", ApiScope::Full).expect("create full");
+        assert!(create_api_token(&conn, " ", ApiScope::Share).is_err());
+        assert!(status.starts_with(TOKEN_PREFIX));
+        assert_ne!(status_row.token_sha256, status);
+
+        assert!(!authorize(&conn, None, ApiScope::Status).expect("authorize"));
+        assert!(authorize(&conn, Some(&status), ApiScope::Status).expect("authorize"));
+        assert!(!authorize(&conn, Some(&status), ApiScope::Full).expect("authorize"));
+        assert!(!authorize(&conn, Some(&status), ApiScope::Share).expect("authorize"));
+        assert!(authorize(&conn, Some(&full), ApiScope::Share).expect("authorize"));
+        assert!(!authorize(&conn, Some("crs_unknown"), ApiScope::Status).expect("authorize"));
+
+        assert_eq!(
+            delete_api_token(&conn, &status_row.token_id).expect("revoke"),
+            1
+        );
+        assert!(!authorize(&conn, Some(&status), ApiScope::Status).expect("authorize"));
+    }
+}
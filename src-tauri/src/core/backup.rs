@@ -0,0 +1,341 @@
+use crate::core::config::{config_dir, ensure_dir};
+use crate::core::pool::close;
+use chrono::Local;
+use rusqlite::{Connection, ErrorCode};
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const DB_FILE: &str = "cloudreve.db";
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub path: String,
+    pub reason: String,
+    pub created_at_ms: i64,
+}
+
+/// 启动时发现数据库损坏后的处理结果，供界面提示用户。
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryReport {
+    pub problem: String,
+    pub moved_to: String,
+    pub restored_from: Option<String>,
+    pub recovered_at_ms: i64,
+}
+
+pub fn backups_dir() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(config_dir()?.join("backups"))
+}
+
+/// 备份数据库与设置到 `backup_root/<时间>-<原因>/`，并只保留最近 `keep` 份。
+pub fn create_backup(
+    backup_root: &Path,
+    db_path: &Path,
+    settings_path: &Path,
+    reason: &str,
+    keep: usize,
+) -> Result<BackupInfo, Box<dyn Error>> {
+    let name = format!("{}-{}", Local::now().format("%Y%m%d-%H%M%S%3f"), reason);
+    let dir = backup_root.join(&name);
+    ensure_dir(&dir)?;
+    if db_path.exists() {
+        let conn = Connection::open(db_path)?;
+        let target = dir.join(DB_FILE);
+        conn.execute("VACUUM INTO ?1", [target.to_string_lossy().to_string()])?;
+    }
+    if settings_path.exists() {
+        fs::copy(settings_path, dir.join(SETTINGS_FILE))?;
+    }
+    rotate_backups(backup_root, keep)?;
+    backup_info(&dir).ok_or_else(|| "备份目录无效".into())
+}
+
+pub fn list_backups(backup_root: &Path) -> Result<Vec<BackupInfo>, Box<dyn Error>> {
+    if !backup_root.exists() {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    for item in fs::read_dir(backup_root)? {
+        let path = item?.path();
+        if path.is_dir() {
+            if let Some(info) = backup_info(&path) {
+                out.push(info);
+            }
+        }
+    }
+    out.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(out)
+}
+
+pub fn latest_backup_age(backup_root: &Path) -> Option<Duration> {
+    let latest = list_backups(backup_root).ok()?.into_iter().next()?;
+    let age_ms = Local::now().timestamp_millis() - latest.created_at_ms;
+    Some(Duration::from_millis(age_ms.max(0) as u64))
+}
+
+pub fn rotate_backups(backup_root: &Path, keep: usize) -> Result<(), Box<dyn Error>> {
+    let backups = list_backups(backup_root)?;
+    for backup in backups.into_iter().skip(keep.max(1)) {
+        fs::remove_dir_all(&backup.path)?;
+    }
+    Ok(())
+}
+
+/// 用指定备份覆盖当前数据库与设置；调用方需先停止所有同步任务。
+pub fn restore_backup(
+    backup_root: &Path,
+    name: &str,
+    db_path: &Path,
+    settings_path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err("备份名称无效".into());
+    }
+    let dir = backup_root.join(name);
+    let db_backup = dir.join(DB_FILE);
+    if !db_backup.exists() {
+        return Err(format!("备份不存在: {}", name).into());
+    }
+    if integrity_problem(&db_backup)?.is_some() {
+        return Err(format!("备份已损坏: {}", name).into());
+    }
+
+    let staging = db_path.with_extension("restore");
+    fs::copy(&db_backup, &staging)?;
+    if let Err(err) = close(db_path) {
+        let _ = fs::remove_file(&staging);
+        return Err(err);
+    }
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = PathBuf::from(format!("{}{}", db_path.to_string_lossy(), suffix));
+        if sidecar.exists() {
+            fs::remove_file(sidecar)?;
+        }
+    }
+    fs::rename(&staging, db_path)?;
+
+    let settings_backup = dir.join(SETTINGS_FILE);
+    if settings_backup.exists() {
+        if let Some(parent) = settings_path.parent() {
+            ensure_dir(parent)?;
+        }
+        fs::copy(settings_backup, settings_path)?;
+    }
+    Ok(())
+}
+
+/// 返回 `PRAGMA integrity_check` 发现的问题。只有检查结果不是 "ok"，或 SQLite 明确报告
+/// 文件损坏、不是数据库时才算损坏；被占用、被锁或无法打开等情况作为错误返回。
+pub fn integrity_problem(db_path: &Path) -> Result<Option<String>, Box<dyn Error>> {
+    let check = Connection::open(db_path).and_then(|conn| {
+        conn.query_row("PRAGMA integrity_check", [], |row| row.get::<_, String>(0))
+    });
+    match check {
+        Ok(check) if check == "ok" => Ok(None),
+        Ok(check) => Ok(Some(check)),
+        Err(rusqlite::Error::SqliteFailure(err, message))
+            if matches!(
+                err.code,
+                ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase
+            ) =>
+        {
+            Ok(Some(message.unwrap_or_else(|| err.to_string())))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// 数据库损坏时移到旁边保留，并从最新的可用备份恢复；没有可用备份时留空，由 init_db 重建。
+pub fn recover_corrupted_db(
+    db_path: &Path,
+    backup_root: &Path,
+) -> Result<Option<RecoveryReport>, Box<dyn Error>> {
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let Some(problem) = integrity_problem(db_path)? else {
+        return Ok(None);
+    };
+
+    close(db_path)?;
+    let suffix = format!("corrupt-{}", Local::now().format("%Y%m%d-%H%M%S"));
+    let moved_to = PathBuf::from(format!("{}.{}", db_path.to_string_lossy(), suffix));
+    for sidecar in ["-wal", "-shm"] {
+        let path = PathBuf::from(format!("{}{}", db_path.to_string_lossy(), sidecar));
+        if path.exists() {
+            fs::rename(
+                &path,
+                format!("{}{}.{}", db_path.to_string_lossy(), sidecar, suffix),
+            )?;
+        }
+    }
+    fs::rename(db_path, &moved_to)?;
+
+    let mut restored_from = None;
+    for backup in list_backups(backup_root)? {
+        let candidate = Path::new(&backup.path).join(DB_FILE);
+        if candidate.exists() && matches!(integrity_problem(&candidate), Ok(None)) {
+            fs::copy(&candidate, db_path)?;
+            restored_from = Some(backup.name);
+            break;
+        }
+    }
+    Ok(Some(RecoveryReport {
+        problem,
+        moved_to: moved_to.to_string_lossy().to_string(),
+        restored_from,
+        recovered_at_ms: Local::now().timestamp_millis(),
+    }))
+}
+
+fn backup_info(dir: &Path) -> Option<BackupInfo> {
+    let name = dir.file_name()?.to_string_lossy().to_string();
+    let mut parts = name.splitn(3, '-');
+    let date = parts.next()?;
+    let time = parts.next()?;
+    let reason = parts.next().unwrap_or("").to_string();
+    let stamp = format!("{}{}", date, &time[..time.len().min(6)]);
+    let created_at_ms = chrono::NaiveDateTime::parse_from_str(&stamp, "%Y%m%d%H%M%S")
+        .ok()?
+        .and_local_timezone(Local)
+        .single()?
+        .timestamp_millis();
+    Some(BackupInfo {
+        name,
+        path: dir.to_string_lossy().to_string(),
+        reason,
+        created_at_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn seed_db(path: &Path, value: &str) {
+        let conn = Connection::open(path).expect("open db");
+        conn.execute_batch("CREATE TABLE IF NOT EXISTS t (v TEXT); DELETE FROM t;")
+            .expect("create");
+        conn.execute("INSERT INTO t (v) VALUES (?1)", [value])
+            .expect("insert");
+    }
+
+    fn read_db(path: &Path) -> String {
+        let conn = Connection::open(path).expect("open db");
+        conn.query_row("SELECT v FROM t", [], |row| row.get(0))
+            .expect("select")
+    }
+
+    #[test]
+    fn backup_and_restore_roundtrip() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().join("backups");
+        let db = dir.path().join("cloudreve.db");
+        let settings = dir.path().join("settings.json");
+        seed_db(&db, "before");
+        fs::write(&settings, "{\"a\":1}").expect("write settings");
+
+        let info = create_backup(&root, &db, &settings, "manual", 5).expect("backup");
+        assert_eq!(info.reason, "manual");
+
+        seed_db(&db, "after");
+        fs::write(&settings, "{\"a\":2}").expect("write settings");
+        restore_backup(&root, &info.name, &db, &settings).expect("restore");
+        assert_eq!(read_db(&db), "before");
+        assert_eq!(fs::read_to_string(&settings).expect("read"), "{\"a\":1}");
+    }
+
+    #[test]
+    fn rotation_keeps_newest() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().join("backups");
+        for name in [
+            "20240101-000000000-scheduled",
+            "20240102-000000000-scheduled",
+            "20240103-000000000-scheduled",
+        ] {
+            fs::create_dir_all(root.join(name)).expect("mkdir");
+        }
+        rotate_backups(&root, 2).expect("rotate");
+        let names = list_backups(&root)
+            .expect("list")
+            .into_iter()
+            .map(|item| item.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "20240103-000000000-scheduled",
+                "20240102-000000000-scheduled"
+            ]
+        );
+    }
+
+    #[test]
+    fn corrupted_db_is_moved_aside_and_restored_from_newest_valid_backup() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().join("backups");
+        let db = dir.path().join("cloudreve.db");
+        let older = root.join("20240101-000000000-scheduled");
+        let newer = root.join("20240102-000000000-scheduled");
+        fs::create_dir_all(&older).expect("mkdir");
+        fs::create_dir_all(&newer).expect("mkdir");
+        seed_db(&older.join(DB_FILE), "good");
+        fs::write(newer.join(DB_FILE), vec![0xAB; 4096]).expect("write bad backup");
+        fs::write(&db, vec![0xCD; 4096]).expect("write bad db");
+
+        let report = recover_corrupted_db(&db, &root)
+            .expect("recover")
+            .expect("report");
+        assert_eq!(
+            report.restored_from.as_deref(),
+            Some("20240101-000000000-scheduled")
+        );
+        assert_eq!(read_db(&db), "good");
+        assert!(Path::new(&report.moved_to).exists());
+        assert!(recover_corrupted_db(&db, &root).expect("recheck").is_none());
+    }
+
+    #[test]
+    fn corrupted_db_without_backup_is_left_for_rebuild() {
+        let dir = tempdir().expect("tempdir");
+        let db = dir.path().join("cloudreve.db");
+        fs::write(&db, b"not a database at all, just some text").expect("write");
+
+        let report = recover_corrupted_db(&db, &dir.path().join("backups"))
+            .expect("recover")
+            .expect("report");
+        assert!(report.restored_from.is_none());
+        assert!(!db.exists());
+    }
+
+    #[test]
+    fn locked_db_is_not_treated_as_corrupted() {
+        let dir = tempdir().expect("tempdir");
+        let db = dir.path().join("cloudreve.db");
+        seed_db(&db, "live");
+        let holder = Connection::open(&db).expect("open db");
+        holder.execute_batch("BEGIN EXCLUSIVE").expect("lock");
+
+        assert!(recover_corrupted_db(&db, &dir.path().join("backups")).is_err());
+        holder.execute_batch("COMMIT").expect("unlock");
+        assert_eq!(read_db(&db), "live");
+        assert!(recover_corrupted_db(&db, &dir.path().join("backups"))
+            .expect("recheck")
+            .is_none());
+    }
+
+    #[test]
+    fn restore_rejects_path_traversal() {
+        let dir = tempdir().expect("tempdir");
+        let db = dir.path().join("cloudreve.db");
+        let settings = dir.path().join("settings.json");
+        assert!(restore_backup(dir.path(), "../x", &db, &settings).is_err());
+    }
+}
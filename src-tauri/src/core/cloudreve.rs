@@ -1,9 +1,24 @@
+use crate::core::coalesce::{self, CachedResponse, DEFAULT_COALESCE_TTL};
 use crate::core::config::ApiPaths;
-use crate::core::error::CloudreveError;
+use crate::core::credentials::StoredToken;
+use crate::core::error::{CloudreveError, SyncError};
+use crate::core::network::{download_bandwidth, retry_jitter, RetryPolicy};
+use crate::core::push::{PushEvent, PushUnsupported, SseParser};
+use crate::core::transport;
+use futures_util::StreamExt;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::future::Future;
+use std::io::{Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio_util::io::ReaderStream;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
@@ -24,12 +39,38 @@ struct ApiEnvelope {
 async fn parse_api_response<T: DeserializeOwned>(
     response: reqwest::Response,
 ) -> Result<ApiResponse<T>, Box<dyn Error>> {
+    transport::counters().record_response(response.version());
     let status = response.status();
     let text = response.text().await?;
-    let envelope = serde_json::from_str::<ApiEnvelope>(&text).map_err(|err| {
-        let message = format!("响应解析失败: status={} err={} body={}", status, err, text);
-        std::io::Error::new(std::io::ErrorKind::InvalidData, message)
-    })?;
+    parse_api_text(status, &text)
+}
+
+/// 以请求往返的中点作为服务端生成 Date 头的本机时间。
+fn clock_skew_ms(date: &str, sent_ms: i64, latency: Duration) -> Option<i64> {
+    let server_ms = chrono::DateTime::parse_from_rfc2822(date)
+        .ok()?
+        .timestamp_millis();
+    Some(server_ms - (sent_ms + latency.as_millis() as i64 / 2))
+}
+
+fn parse_api_text<T: DeserializeOwned>(
+    status: reqwest::StatusCode,
+    text: &str,
+) -> Result<ApiResponse<T>, Box<dyn Error>> {
+    let envelope = match serde_json::from_str::<ApiEnvelope>(text) {
+        Ok(envelope) => envelope,
+        // 网关等在到达服务端前拒绝时返回的不是 JSON。
+        Err(_) if status == reqwest::StatusCode::UNAUTHORIZED => {
+            return Err(Box::new(CloudreveError::NotLoggedIn));
+        }
+        Err(err) => {
+            let message = format!("响应解析失败: status={} err={} body={}", status, err, text);
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                message,
+            )));
+        }
+    };
     if envelope.code != 0 {
         return Err(Box::new(CloudreveError::from_u32(envelope.code)));
     }
@@ -56,7 +97,7 @@ async fn parse_api_response<T: DeserializeOwned>(
     })
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteFile {
     pub id: String,
     pub name: String,
@@ -65,6 +106,13 @@ pub struct RemoteFile {
     pub updated_at: String,
     pub metadata: HashMap<String, String>,
     pub is_dir: bool,
+    /// 分享给当前用户的文件为 false，只能读取。
+    #[serde(default = "default_owned")]
+    pub owned: bool,
+}
+
+fn default_owned() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -90,6 +138,18 @@ pub struct UploadSession {
     pub completeURL: Option<String>,
 }
 
+/// 连接诊断结果，供账户连接测试显示。
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ServerReport {
+    pub version: Option<String>,
+    pub latency_ms: u64,
+    /// 服务端时间减本机时间；服务端未返回 Date 头时为空，精度为秒。
+    pub clock_skew_ms: Option<i64>,
+    /// 当前凭据能否访问需要登录的接口。
+    pub authenticated: bool,
+    pub storage_policies: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenPair {
     pub access_token: String,
@@ -114,6 +174,56 @@ pub struct PermissionSetting {
     pub everyone: Option<String>,
 }
 
+/// 分享链接的信息，只取判断权限用到的字段。
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShareInfo {
+    #[serde(default)]
+    pub permission_setting: Option<PermissionSetting>,
+}
+
+/// 权限位集中“修改”所在的位，0 为读取，2 为下载。
+const PERMISSION_UPDATE: usize = 1;
+
+impl ShareInfo {
+    /// 登录用户能否修改分享的内容：除匿名访问外，任一授权带有修改权限即可写入。
+    pub fn writable(&self) -> bool {
+        let Some(setting) = &self.permission_setting else {
+            return false;
+        };
+        setting
+            .everyone
+            .iter()
+            .chain(&setting.same_group)
+            .chain(&setting.other)
+            .chain(setting.user_explicit.iter().flat_map(|map| map.values()))
+            .chain(setting.group_explicit.iter().flat_map(|map| map.values()))
+            .any(|encoded| boolset_contains(encoded, PERMISSION_UPDATE))
+    }
+}
+
+/// 权限以 base64 编码的位集传输，检查第 `bit` 位是否置位。
+fn boolset_contains(encoded: &str, bit: usize) -> bool {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut pending = 0;
+    for ch in encoded.bytes().take_while(|ch| *ch != b'=') {
+        let Some(value) = ALPHABET.iter().position(|known| *known == ch) else {
+            return false;
+        };
+        buffer = (buffer << 6) | value as u32;
+        pending += 6;
+        if pending >= 8 {
+            pending -= 8;
+            bytes.push((buffer >> pending) as u8);
+            buffer &= (1 << pending) - 1;
+        }
+    }
+    bytes
+        .get(bit / 8)
+        .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShareCreateService {
     pub permissions: PermissionSetting,
@@ -144,12 +254,149 @@ pub struct RemoteEntry {
     pub is_dir: bool,
 }
 
+/// 目录列表的一页；`next_page_token` 为空表示已经是最后一页。
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteEntryPage {
+    pub entries: Vec<RemoteEntry>,
+    pub next_page_token: Option<String>,
+}
+
+/// 用户的存储容量，单位为字节；`total` 已包含容量包。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capacity {
+    pub total: u64,
+    pub used: u64,
+    #[serde(default)]
+    pub storage_pack_total: u64,
+}
+
+impl Capacity {
+    pub fn remaining(&self) -> u64 {
+        self.total.saturating_sub(self.used)
+    }
+}
+
+/// 远端文件的一个历史版本；`current` 表示当前使用的版本。
+#[derive(Debug, Clone, Serialize)]
+pub struct FileVersion {
+    pub id: String,
+    pub size: u64,
+    pub created_at: String,
+    pub current: bool,
+}
+
+/// 已收到响应头的下载：`offset` 为写入起点，`etag` 为响应的 ETag（没有时为空），供下次续传校验。
+pub struct Download {
+    response: reqwest::Response,
+    pub offset: u64,
+    pub etag: String,
+}
+
+/// 每次重试前的回调，参数为重试说明，用于写入日志。
+#[derive(Clone)]
+pub struct RetryLog(Arc<dyn Fn(&str) + Send + Sync>);
+
+impl RetryLog {
+    pub fn new(log: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(log))
+    }
+}
+
+impl fmt::Debug for RetryLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RetryLog")
+    }
+}
+
+/// 每次写操作（无论成功与否）之后收到涉及的每个远端地址。
+#[derive(Clone)]
+pub struct WriteLog(Arc<dyn Fn(&str) + Send + Sync>);
+
+impl WriteLog {
+    pub fn new(log: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(log))
+    }
+}
+
+impl fmt::Debug for WriteLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WriteLog")
+    }
+}
+
+/// 访问令牌失效时换取新令牌的方式：`load` 读取当前保存的令牌（可能已被其他客户端刷新），
+/// `store` 保存换到的新令牌。
+#[derive(Clone)]
+pub struct TokenRefresh {
+    load: Arc<dyn Fn() -> Option<StoredToken> + Send + Sync>,
+    store: Arc<dyn Fn(&TokenPair) + Send + Sync>,
+}
+
+impl TokenRefresh {
+    pub fn new(
+        load: impl Fn() -> Option<StoredToken> + Send + Sync + 'static,
+        store: impl Fn(&TokenPair) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            load: Arc::new(load),
+            store: Arc::new(store),
+        }
+    }
+}
+
+/// 所有客户端的令牌刷新依次进行，轮换后的刷新令牌不会被并发请求重复使用。
+static REFRESH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+impl fmt::Debug for TokenRefresh {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenRefresh")
+    }
+}
+
+/// 克隆的客户端共享访问令牌，任一处刷新后其余请求随之使用新令牌。
 #[derive(Debug, Clone)]
 pub struct CloudreveClient {
     client: reqwest::Client,
+    upload_client: reqwest::Client,
     base_url: String,
-    access_token: Option<String>,
+    access_token: Arc<RwLock<Option<String>>>,
+    token_refresh: Option<TokenRefresh>,
     api_paths: ApiPaths,
+    retry: RetryPolicy,
+    retry_log: Option<RetryLog>,
+    write_log: Option<WriteLog>,
+    cancel: CancellationToken,
+}
+
+/// 登录已失效且无法自动刷新时，请求最终返回的错误。
+pub fn is_not_logged_in(err: &(dyn Error + 'static)) -> bool {
+    err.downcast_ref::<CloudreveError>()
+        .is_some_and(|err| matches!(err, CloudreveError::NotLoggedIn))
+}
+
+/// 刷新接口明确拒绝了刷新令牌（过期、被吊销或账户不可用），只能重新登录。
+fn is_refresh_rejected(err: &(dyn Error + 'static)) -> bool {
+    err.downcast_ref::<CloudreveError>().is_some_and(|err| {
+        matches!(
+            err,
+            CloudreveError::NotLoggedIn
+                | CloudreveError::NoPermissionToAccess
+                | CloudreveError::InvalidCredentials
+                | CloudreveError::LoginSessionDoesNotExist
+                | CloudreveError::UserNotFound
+                | CloudreveError::UserBanned
+                | CloudreveError::UserNotActivated
+        )
+    })
+}
+
+/// 连接失败与超时视为瞬时错误，其余请求错误（如构造请求失败）重试也无济于事。
+fn is_transient_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+fn is_transient_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
 }
 
 #[derive(Debug, Deserialize)]
@@ -162,6 +409,14 @@ pub struct ListFilesData {
     pub next_marker: Option<String>,
     #[serde(default, alias = "next_page_token")]
     pub next_page_token: Option<String>,
+    #[serde(default)]
+    pub pagination: Option<ListPagination>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListPagination {
+    #[serde(default)]
+    pub next_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -175,8 +430,36 @@ pub struct FileEntry {
     path: String,
     #[serde(default)]
     metadata: Option<HashMap<String, String>>,
+    #[serde(default)]
+    owned: Option<bool>,
 }
 
+#[derive(Debug, Deserialize)]
+struct FileVersionsData {
+    #[serde(default)]
+    primary_entity: Option<String>,
+    #[serde(default)]
+    extended_info: Option<FileExtendedInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileExtendedInfo {
+    #[serde(default)]
+    entities: Vec<FileEntity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileEntity {
+    id: String,
+    #[serde(rename = "type")]
+    entity_type: i64,
+    size: u64,
+    created_at: String,
+}
+
+/// 文件实体类型中表示内容版本的取值，其余为缩略图、动态图等附属内容。
+const ENTITY_VERSION: i64 = 0;
+
 impl CloudreveClient {
     fn decode_uri(value: &str) -> String {
         urlencoding::decode(value)
@@ -184,10 +467,26 @@ impl CloudreveClient {
             .unwrap_or_else(|_| value.to_string())
     }
 
+    /// 写操作后丢弃涉及地址的合并读取结果（含所在目录的列表），之后的读取重新请求服务端。
+    fn forget_cached<S: AsRef<str>>(&self, uris: impl IntoIterator<Item = S>) {
+        for uri in uris {
+            let uri = Self::decode_uri(uri.as_ref());
+            coalesce::shared().invalidate(&uri);
+            if let Some(log) = &self.write_log {
+                (log.0)(&uri);
+            }
+        }
+    }
+
     fn effective_next_token(data: &ListFilesData) -> Option<String> {
         data.next_marker
             .clone()
             .or_else(|| data.next_page_token.clone())
+            .or_else(|| {
+                data.pagination
+                    .as_ref()
+                    .and_then(|pagination| pagination.next_token.clone())
+            })
             .filter(|token| !token.trim().is_empty())
     }
 
@@ -200,111 +499,300 @@ impl CloudreveClient {
             format!("{}/api/v4", base_url)
         };
         Self {
-            client: reqwest::Client::new(),
+            client: transport::shared_client(),
+            upload_client: transport::upload_client(),
             base_url,
-            access_token,
+            access_token: Arc::new(RwLock::new(access_token)),
+            token_refresh: None,
             api_paths,
+            retry: RetryPolicy::default(),
+            retry_log: None,
+            write_log: None,
+            cancel: CancellationToken::new(),
         }
     }
 
     pub fn set_access_token(&mut self, token: Option<String>) {
-        self.access_token = token;
+        self.access_token = Arc::new(RwLock::new(token));
+    }
+
+    /// 请求因访问令牌失效被拒绝时，用 `refresh` 换取新令牌后重试一次。
+    pub fn set_token_refresh(&mut self, refresh: Option<TokenRefresh>) {
+        self.token_refresh = refresh;
+    }
+
+    fn access_token(&self) -> Option<String> {
+        self.access_token
+            .read()
+            .map(|token| token.clone())
+            .unwrap_or_default()
+    }
+
+    /// 幂等请求遇到连接失败、超时或服务端 5xx/429 时按 `retry` 重试，每次重试前调用 `log`。
+    pub fn set_retry(&mut self, retry: RetryPolicy, log: Option<RetryLog>) {
+        self.retry = retry;
+        self.retry_log = log;
+    }
+
+    pub fn set_write_log(&mut self, log: Option<WriteLog>) {
+        self.write_log = log;
+    }
+
+    /// 取消后进行中的请求、下载与重试等待立即结束，返回 `SyncError::Cancelled`。
+    pub fn set_cancel(&mut self, cancel: CancellationToken) {
+        self.cancel = cancel;
     }
 
-    pub async fn ping(&self) -> Result<(), Box<dyn Error>> {
+    async fn unless_cancelled<T>(&self, fut: impl Future<Output = T>) -> Result<T, Box<dyn Error>> {
+        self.cancel
+            .run_until_cancelled(fut)
+            .await
+            .ok_or_else(|| SyncError::Cancelled.into())
+    }
+
+    /// 测量 ping 的往返时间并读取服务端版本与时间，再用当前凭据列出可用的存储策略。
+    pub async fn diagnose(&self) -> Result<ServerReport, Box<dyn Error>> {
         let url = format!("{}/site/ping", self.base_url);
-        let response = self.client.get(url).send().await?;
-        let _response = parse_api_response::<Value>(response).await?;
-        Ok(())
+        let sent_ms = chrono::Utc::now().timestamp_millis();
+        let started = std::time::Instant::now();
+        let response = self.send_idempotent(|| Ok(self.client.get(&url))).await?;
+        let latency = started.elapsed();
+        let server_date = response
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let status = response.status();
+        let text = response.text().await?;
+        let version = parse_api_text::<Value>(status, &text)?
+            .data
+            .as_str()
+            .map(str::to_string);
+        let storage_policies = match self.access_token() {
+            Some(_) => self.list_storage_policies().await.ok(),
+            None => None,
+        };
+        Ok(ServerReport {
+            version,
+            latency_ms: latency.as_millis() as u64,
+            clock_skew_ms: server_date.and_then(|date| clock_skew_ms(&date, sent_ms, latency)),
+            authenticated: storage_policies.is_some(),
+            storage_policies: storage_policies
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|policy| policy.get("name").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect(),
+        })
     }
 
     pub async fn list_files(
         &self,
         uri: &str,
-        page: Option<u32>,
         next_page_token: Option<&str>,
     ) -> Result<ListFilesData, Box<dyn Error>> {
-        let normalized_uri = Self::decode_uri(uri);
+        self.list_page(&Self::decode_uri(uri), next_page_token)
+            .await
+    }
+
+    /// 按原样发送 `uri`，不再解码；搜索地址中已编码的关键字需要保持编码。
+    async fn list_page(
+        &self,
+        uri: &str,
+        next_page_token: Option<&str>,
+    ) -> Result<ListFilesData, Box<dyn Error>> {
         let mut url = format!(
             "{}{}?uri={}&page_size=200",
             self.base_url,
             self.api_paths.list_files,
-            urlencoding::encode(&normalized_uri)
+            urlencoding::encode(uri)
         );
         if let Some(token) = next_page_token {
             url.push_str(&format!("&next_page_token={}", urlencoding::encode(token)));
-        } else if let Some(page) = page {
-            url.push_str(&format!("&page={}", page));
         }
-        let response = self.apply_auth(self.client.get(url)).send().await?;
-        let response = parse_api_response::<ListFilesData>(response).await?;
+        let response = self
+            .send_coalesced::<ListFilesData>(reqwest::Method::GET, url, None, uri)
+            .await?;
         Ok(response.data)
     }
 
-    pub async fn list_all_files(&self, uri: &str) -> Result<Vec<RemoteFile>, Box<dyn Error>> {
-        let mut page = 1u32;
+    /// 按服务端返回的分页标记逐页读取，直到没有下一页；标记重复时报错而不是截断结果。
+    async fn list_all_entries(&self, uri: &str) -> Result<Vec<FileEntry>, Box<dyn Error>> {
         let mut next_page_token: Option<String> = None;
+        let mut seen_tokens = HashSet::new();
         let mut output = Vec::new();
         loop {
-            let data = self
-                .list_files(uri, Some(page), next_page_token.as_deref())
-                .await?;
-            let next_token = Self::effective_next_token(&data);
-            for item in data.files {
-                let metadata = item.metadata.unwrap_or_default();
-                let is_dir = item.file_type == 1;
-                output.push(RemoteFile {
-                    id: item.id,
-                    name: item.name,
-                    uri: Self::decode_uri(&item.path),
-                    size: item.size,
-                    updated_at: item.updated_at,
-                    metadata,
-                    is_dir,
-                });
-            }
-            next_page_token = next_token;
-            if next_page_token.is_none() {
-                break;
+            let data = self.list_files(uri, next_page_token.as_deref()).await?;
+            next_page_token = Self::effective_next_token(&data);
+            output.extend(data.files);
+            match &next_page_token {
+                Some(token) if !seen_tokens.insert(token.clone()) => {
+                    return Err(format!("分页标记重复，列表可能不完整: {}", uri).into());
+                }
+                Some(_) => {}
+                None => break,
             }
-            page += 1;
         }
         Ok(output)
     }
 
+    fn remote_file(item: FileEntry) -> RemoteFile {
+        RemoteFile {
+            id: item.id,
+            name: item.name,
+            uri: Self::decode_uri(&item.path),
+            size: item.size,
+            updated_at: item.updated_at,
+            metadata: item.metadata.unwrap_or_default(),
+            is_dir: item.file_type == 1,
+            owned: item.owned.unwrap_or(true),
+        }
+    }
+
+    pub async fn list_all_files(&self, uri: &str) -> Result<Vec<RemoteFile>, Box<dyn Error>> {
+        Ok(self
+            .list_all_entries(uri)
+            .await?
+            .into_iter()
+            .map(Self::remote_file)
+            .collect())
+    }
+
+    pub async fn get_file_info(&self, uri: &str) -> Result<RemoteFile, Box<dyn Error>> {
+        let uri = Self::decode_uri(uri);
+        let url = format!(
+            "{}{}?uri={}",
+            self.base_url,
+            self.api_paths.file_info,
+            urlencoding::encode(&uri)
+        );
+        let response = self
+            .send_coalesced::<FileEntry>(reqwest::Method::GET, url, None, &uri)
+            .await?;
+        Ok(Self::remote_file(response.data))
+    }
+
+    /// 列出文件的历史版本，最新的在前。
+    pub async fn list_file_versions(&self, uri: &str) -> Result<Vec<FileVersion>, Box<dyn Error>> {
+        let url = format!(
+            "{}{}?uri={}&extended=true",
+            self.base_url,
+            self.api_paths.file_info,
+            urlencoding::encode(&Self::decode_uri(uri))
+        );
+        let response = self
+            .send_api_idempotent::<FileVersionsData>(|| Ok(self.apply_auth(self.client.get(&url))))
+            .await?;
+        let primary = response.data.primary_entity.unwrap_or_default();
+        let mut versions = response
+            .data
+            .extended_info
+            .map(|info| info.entities)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entity| entity.entity_type == ENTITY_VERSION)
+            .map(|entity| FileVersion {
+                current: entity.id == primary,
+                id: entity.id,
+                size: entity.size,
+                created_at: entity.created_at,
+            })
+            .collect::<Vec<_>>();
+        versions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(versions)
+    }
+
+    /// 将 `version_id` 设为文件的当前版本，其余版本保留。
+    pub async fn set_current_version(
+        &self,
+        uri: &str,
+        version_id: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let url = format!("{}{}", self.base_url, self.api_paths.set_current_version);
+        let body = serde_json::json!({
+            "uri": uri,
+            "version": version_id
+        });
+        let result = self
+            .send_api::<Value>(|| Ok(self.apply_auth(self.client.post(&url)).json(&body)))
+            .await;
+        self.forget_cached([uri]);
+        result?;
+        Ok(())
+    }
+
     pub async fn list_directory_entries(
         &self,
         uri: &str,
     ) -> Result<Vec<RemoteEntry>, Box<dyn Error>> {
-        let mut page = 1u32;
-        let mut next_page_token: Option<String> = None;
-        let mut output = Vec::new();
-        loop {
-            let data = self
-                .list_files(uri, Some(page), next_page_token.as_deref())
-                .await?;
-            let next_token = Self::effective_next_token(&data);
-            for item in data.files {
-                let is_dir = item.file_type == 1;
-                output.push(RemoteEntry {
+        Ok(self
+            .list_all_entries(uri)
+            .await?
+            .into_iter()
+            .map(|item| RemoteEntry {
+                name: item.name,
+                uri: Self::decode_uri(&item.path),
+                is_dir: item.file_type == 1,
+            })
+            .collect())
+    }
+
+    /// 只读取一页，界面按需加载超大目录时使用。
+    pub async fn list_directory_page(
+        &self,
+        uri: &str,
+        next_page_token: Option<&str>,
+    ) -> Result<RemoteEntryPage, Box<dyn Error>> {
+        let data = self.list_files(uri, next_page_token).await?;
+        Ok(Self::entry_page(data))
+    }
+
+    /// 在 `uri` 及其各级子目录中按名称搜索目录（不区分大小写），结果同样分页返回。
+    pub async fn search_directories(
+        &self,
+        uri: &str,
+        keyword: &str,
+        next_page_token: Option<&str>,
+    ) -> Result<RemoteEntryPage, Box<dyn Error>> {
+        let search_uri = format!(
+            "{}?name={}&type=folder&case_folding=true",
+            Self::decode_uri(uri).trim_end_matches('/'),
+            urlencoding::encode(keyword)
+        );
+        let data = self.list_page(&search_uri, next_page_token).await?;
+        let mut page = Self::entry_page(data);
+        page.entries.retain(|entry| entry.is_dir);
+        Ok(page)
+    }
+
+    fn entry_page(data: ListFilesData) -> RemoteEntryPage {
+        RemoteEntryPage {
+            next_page_token: Self::effective_next_token(&data),
+            entries: data
+                .files
+                .into_iter()
+                .map(|item| RemoteEntry {
                     name: item.name,
                     uri: Self::decode_uri(&item.path),
-                    is_dir,
-                });
-            }
-            next_page_token = next_token;
-            if next_page_token.is_none() {
-                break;
-            }
-            page += 1;
+                    is_dir: item.file_type == 1,
+                })
+                .collect(),
         }
-        Ok(output)
     }
 
     pub async fn list_storage_policies(&self) -> Result<Vec<Value>, Box<dyn Error>> {
         let url = format!("{}/user/setting/policies", self.base_url);
-        let response = self.apply_auth(self.client.get(url)).send().await?;
-        let response = parse_api_response::<Vec<Value>>(response).await?;
+        let response = self
+            .send_api_idempotent::<Vec<Value>>(|| Ok(self.apply_auth(self.client.get(&url))))
+            .await?;
+        Ok(response.data)
+    }
+
+    pub async fn get_capacity(&self) -> Result<Capacity, Box<dyn Error>> {
+        let url = format!("{}/user/capacity", self.base_url);
+        let response = self
+            .send_api_idempotent::<Capacity>(|| Ok(self.apply_auth(self.client.get(&url))))
+            .await?;
         Ok(response.data)
     }
 
@@ -314,35 +802,192 @@ impl CloudreveClient {
         download: bool,
     ) -> Result<DownloadUrlResponse, Box<dyn Error>> {
         let url = format!("{}{}", self.base_url, self.api_paths.create_download);
+        let body = serde_json::json!({
+            "uris": uris,
+            "download": download
+        });
+        // 只生成临时链接，不改动服务端文件，可以安全重试。
         let response = self
-            .apply_auth(self.client.post(url))
-            .json(&serde_json::json!({
-                "uris": uris,
-                "download": download
-            }))
-            .send()
+            .send_api_idempotent::<DownloadUrlResponse>(|| {
+                Ok(self.apply_auth(self.client.post(&url)).json(&body))
+            })
             .await?;
-        let response = parse_api_response::<DownloadUrlResponse>(response).await?;
         Ok(response.data)
     }
 
-    pub async fn download_file(&self, uri: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    async fn download_url(&self, uri: &str) -> Result<String, Box<dyn Error>> {
         let result = self
             .create_download_urls(vec![uri.to_string()], true)
             .await?;
-        let url = result
+        Ok(result
             .urls
             .first()
             .map(|item| item.url.clone())
-            .ok_or("download url missing")?;
-        let bytes = self.client.get(url).send().await?.bytes().await?;
-        Ok(bytes.to_vec())
+            .ok_or("download url missing")?)
+    }
+
+    /// 流式写入 `file`，不在内存中缓冲整个文件。`offset` 大于 0 时用 Range 续传，
+    /// 服务端不支持续传时从头写入。每写入一块回调一次（已写入总字节数, 本块字节数），返回文件最终大小。
+    pub async fn download_to_file<F>(
+        &self,
+        uri: &str,
+        file: &mut File,
+        offset: u64,
+        on_chunk: F,
+    ) -> Result<u64, Box<dyn Error>>
+    where
+        F: FnMut(u64, u64),
+    {
+        self.download_to_file_limited(uri, file, offset, None, on_chunk)
+            .await
+    }
+
+    /// 同 `download_to_file`，按共享的下载限速 `limit_bytes_per_sec` 异步等待。
+    pub async fn download_to_file_limited<F>(
+        &self,
+        uri: &str,
+        file: &mut File,
+        offset: u64,
+        limit_bytes_per_sec: Option<u64>,
+        on_chunk: F,
+    ) -> Result<u64, Box<dyn Error>>
+    where
+        F: FnMut(u64, u64),
+    {
+        let download = self.open_download(uri, offset, None).await?;
+        self.write_download(download, file, limit_bytes_per_sec, on_chunk)
+            .await
+    }
+
+    /// 发起下载请求。`offset` 大于 0 时请求续传，并把上次响应的 ETag 作为 If-Range 发送，
+    /// 远端内容已变化时服务端返回完整内容；返回值中的 `offset` 为实际的写入起点。
+    pub async fn open_download(
+        &self,
+        uri: &str,
+        offset: u64,
+        if_range: Option<&str>,
+    ) -> Result<Download, Box<dyn Error>> {
+        let url = self.download_url(uri).await?;
+        let mut offset = offset;
+        let response = loop {
+            let response = self
+                .send_idempotent(|| {
+                    let mut request = self.client.get(&url);
+                    if offset > 0 {
+                        request =
+                            request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+                        if let Some(etag) = if_range {
+                            request = request.header(reqwest::header::IF_RANGE, etag);
+                        }
+                    }
+                    Ok(request)
+                })
+                .await?;
+            if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+                offset = 0;
+                continue;
+            }
+            break response;
+        };
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("下载失败: HTTP {}", status).into());
+        }
+        if status != reqwest::StatusCode::PARTIAL_CONTENT {
+            offset = 0;
+        }
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        Ok(Download {
+            response,
+            offset,
+            etag,
+        })
+    }
+
+    /// 把 `open_download` 的响应从 `download.offset` 处流式写入 `file`，返回文件最终大小。
+    pub async fn write_download<F>(
+        &self,
+        download: Download,
+        file: &mut File,
+        limit_bytes_per_sec: Option<u64>,
+        mut on_chunk: F,
+    ) -> Result<u64, Box<dyn Error>>
+    where
+        F: FnMut(u64, u64),
+    {
+        let Download {
+            mut response,
+            offset: mut written,
+            ..
+        } = download;
+        file.set_len(written)?;
+        file.seek(SeekFrom::Start(written))?;
+        while let Some(chunk) = self.unless_cancelled(response.chunk()).await?? {
+            file.write_all(&chunk)?;
+            written += chunk.len() as u64;
+            on_chunk(written, chunk.len() as u64);
+            self.unless_cancelled(
+                download_bandwidth().acquire(chunk.len() as u64, limit_bytes_per_sec),
+            )
+            .await?;
+        }
+        file.flush()?;
+        Ok(written)
     }
 
     pub async fn update_file_content(
         &self,
         uri: &str,
         content: &[u8],
+        mime_type: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
+        self.put_content(
+            uri,
+            || Ok(reqwest::Body::from(content.to_vec())),
+            content.len() as u64,
+            mime_type,
+        )
+        .await
+    }
+
+    /// 与 `update_file_content` 相同，但边读文件边发送，内存占用与文件大小无关。
+    /// `sent` 随发送累加本次尝试已读出的字节数，供调用方上报进度。
+    pub async fn update_file_content_from_file(
+        &self,
+        uri: &str,
+        file: File,
+        len: u64,
+        mime_type: Option<&str>,
+        sent: &Arc<AtomicU64>,
+    ) -> Result<(), Box<dyn Error>> {
+        // 每次重试都从文件开头重新读取。
+        let body = || {
+            let mut file = file.try_clone()?;
+            file.seek(SeekFrom::Start(0))?;
+            sent.store(0, Ordering::Relaxed);
+            let sent = sent.clone();
+            let stream = ReaderStream::new(tokio::fs::File::from_std(file)).inspect(move |chunk| {
+                if let Ok(chunk) = chunk {
+                    sent.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                }
+            });
+            Ok(reqwest::Body::wrap_stream(stream))
+        };
+        self.put_content(uri, body, len, mime_type).await
+    }
+
+    /// 整体覆盖文件内容，重复发送结果相同，因此可以重试；`body` 每次尝试生成一份新的请求体。
+    async fn put_content(
+        &self,
+        uri: &str,
+        body: impl Fn() -> Result<reqwest::Body, Box<dyn Error>>,
+        len: u64,
+        mime_type: Option<&str>,
     ) -> Result<(), Box<dyn Error>> {
         let url = format!(
             "{}{}?uri={}",
@@ -350,12 +995,21 @@ impl CloudreveClient {
             self.api_paths.update_content,
             urlencoding::encode(uri)
         );
-        let request = self
-            .apply_auth(self.client.put(url))
-            .header(reqwest::header::CONTENT_LENGTH, content.len() as u64)
-            .body(content.to_vec());
-        let response = request.send().await?;
-        let _response = parse_api_response::<Value>(response).await?;
+        let timeout = transport::upload_timeout(len);
+        let result = self
+            .send_api_idempotent::<Value>(|| {
+                let mut request = self
+                    .apply_auth(self.upload_client.put(&url))
+                    .timeout(timeout)
+                    .header(reqwest::header::CONTENT_LENGTH, len);
+                if let Some(mime_type) = mime_type {
+                    request = request.header(reqwest::header::CONTENT_TYPE, mime_type);
+                }
+                Ok(request.body(body()?))
+            })
+            .await;
+        self.forget_cached([uri]);
+        result?;
         Ok(())
     }
 
@@ -366,6 +1020,7 @@ impl CloudreveClient {
         policy_id: Option<&str>,
         last_modified: Option<i64>,
         mime_type: Option<&str>,
+        preferred_chunk_size: Option<u64>,
     ) -> Result<UploadSession, Box<dyn Error>> {
         let url = format!("{}{}", self.base_url, self.api_paths.create_upload_session);
         let mut payload = serde_json::json!({
@@ -381,13 +1036,16 @@ impl CloudreveClient {
         if let Some(mime_type) = mime_type {
             payload["mime_type"] = serde_json::json!(mime_type);
         }
-        let response = self
-            .apply_auth(self.client.put(url))
-            .json(&payload)
-            .send()
-            .await?;
-        let response = parse_api_response::<UploadSession>(response).await?;
-        Ok(response.data)
+        // 分片大小由存储策略决定；不支持的服务端会忽略该字段，调用方以返回的 chunk_size 为准。
+        if let Some(chunk_size) = preferred_chunk_size {
+            payload["chunk_size"] = serde_json::json!(chunk_size);
+        }
+        let result = self
+            .send_api::<UploadSession>(|| Ok(self.apply_auth(self.client.put(&url)).json(&payload)))
+            .await;
+        // 服务端创建会话时就会建立占位文件。
+        self.forget_cached([uri]);
+        Ok(result?.data)
     }
 
     pub async fn upload_chunk(
@@ -395,18 +1053,57 @@ impl CloudreveClient {
         session_id: &str,
         index: u64,
         chunk: &[u8],
+        timeout: Duration,
     ) -> Result<(), Box<dyn Error>> {
         let url = format!(
             "{}{}/{}/{}",
             self.base_url, self.api_paths.upload_chunk, session_id, index
         );
-        let response = self
-            .apply_auth(self.client.post(url))
-            .header(reqwest::header::CONTENT_LENGTH, chunk.len() as u64)
-            .body(chunk.to_vec())
-            .send()
+        // 同一序号的分片重复上传会覆盖原有内容。
+        let _response = self
+            .send_api_idempotent::<Value>(|| {
+                Ok(self
+                    .apply_auth(self.upload_client.post(&url))
+                    .timeout(timeout)
+                    .header(reqwest::header::CONTENT_LENGTH, chunk.len() as u64)
+                    .body(chunk.to_vec()))
+            })
             .await?;
-        let _response = parse_api_response::<Value>(response).await?;
+        Ok(())
+    }
+
+    /// 通知服务端分片已全部上传。本机中转的存储策略在最后一个分片后自动完成，不返回 completeURL；
+    /// 只有指向本站的地址才携带登录凭据。
+    pub async fn complete_upload_session(
+        &self,
+        uri: &str,
+        session: &UploadSession,
+    ) -> Result<(), Box<dyn Error>> {
+        self.forget_cached([uri]);
+        let Some(complete_url) = session.completeURL.as_deref().filter(|url| !url.is_empty())
+        else {
+            return Ok(());
+        };
+        let (url, same_site) = if complete_url.starts_with('/') {
+            (format!("{}{}", self.base_url, complete_url), true)
+        } else {
+            (
+                complete_url.to_string(),
+                complete_url.starts_with(&self.base_url),
+            )
+        };
+        let result = self
+            .send_api::<Value>(|| {
+                let request = self.client.post(&url);
+                Ok(if same_site {
+                    self.apply_auth(request)
+                } else {
+                    request
+                })
+            })
+            .await;
+        self.forget_cached([uri]);
+        result?;
         Ok(())
     }
 
@@ -416,15 +1113,15 @@ impl CloudreveClient {
         patches: Vec<MetadataPatch>,
     ) -> Result<(), Box<dyn Error>> {
         let url = format!("{}{}", self.base_url, self.api_paths.patch_metadata);
-        let response = self
-            .apply_auth(self.client.patch(url))
-            .json(&serde_json::json!({
-                "uris": uris,
-                "patches": patches
-            }))
-            .send()
-            .await?;
-        let _response = parse_api_response::<Value>(response).await?;
+        let body = serde_json::json!({
+            "uris": uris,
+            "patches": patches
+        });
+        let result = self
+            .send_coalesced::<Value>(reqwest::Method::PATCH, url, Some(body), "")
+            .await;
+        self.forget_cached(&uris);
+        result?;
         Ok(())
     }
 
@@ -437,16 +1134,182 @@ impl CloudreveClient {
             return Ok(());
         }
         let url = format!("{}{}", self.base_url, self.api_paths.delete_file);
-        let response = self
-            .apply_auth(self.client.delete(url))
-            .json(&serde_json::json!({
-                "uris": uris,
-                "skip_soft_delete": skip_soft_delete,
-                "unlink": false
-            }))
-            .send()
-            .await?;
-        let _response = parse_api_response::<Value>(response).await?;
+        let body = serde_json::json!({
+            "uris": uris,
+            "skip_soft_delete": skip_soft_delete,
+            "unlink": false
+        });
+        let result = self
+            .send_api::<Value>(|| Ok(self.apply_auth(self.client.delete(&url)).json(&body)))
+            .await;
+        self.forget_cached(&uris);
+        result?;
+        Ok(())
+    }
+
+    pub async fn rename_file(&self, uri: &str, new_name: &str) -> Result<(), Box<dyn Error>> {
+        let url = format!("{}{}", self.base_url, self.api_paths.rename_file);
+        let body = serde_json::json!({
+            "uri": uri,
+            "new_name": new_name
+        });
+        let result = self
+            .send_api::<Value>(|| Ok(self.apply_auth(self.client.post(&url)).json(&body)))
+            .await;
+        let renamed = match uri.rsplit_once('/') {
+            Some((parent, _)) => format!("{}/{}", parent, new_name),
+            None => new_name.to_string(),
+        };
+        self.forget_cached([uri, renamed.as_str()]);
+        result?;
+        Ok(())
+    }
+
+    /// 将文件移动到 `dst` 目录下，文件名不变。
+    pub async fn move_files(&self, uris: Vec<String>, dst: &str) -> Result<(), Box<dyn Error>> {
+        self.relocate_files(uris, dst, false).await
+    }
+
+    /// 在服务端把文件复制到 `dst` 目录下，文件名不变，不经过本机传输内容。
+    pub async fn copy_files(&self, uris: Vec<String>, dst: &str) -> Result<(), Box<dyn Error>> {
+        self.relocate_files(uris, dst, true).await
+    }
+
+    async fn relocate_files(
+        &self,
+        uris: Vec<String>,
+        dst: &str,
+        copy: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let url = format!("{}{}", self.base_url, self.api_paths.move_file);
+        let body = serde_json::json!({
+            "uris": uris,
+            "dst": dst,
+            "copy": copy
+        });
+        let result = self
+            .send_api::<Value>(|| Ok(self.apply_auth(self.client.post(&url)).json(&body)))
+            .await;
+        let moved = uris.iter().filter_map(|uri| {
+            let name = uri.trim_end_matches('/').rsplit('/').next()?;
+            Some(format!("{}/{}", dst.trim_end_matches('/'), name))
+        });
+        self.forget_cached(uris.iter().cloned().chain(moved));
+        result?;
+        Ok(())
+    }
+
+    /// 将单个文件移动到 `dst_uri`：文件名不同先在原目录重命名，目录不同再移动过去。
+    /// 移动失败时把重命名改回去，不留下只做了一半的改名。
+    pub async fn move_file(&self, src_uri: &str, dst_uri: &str) -> Result<(), Box<dyn Error>> {
+        let (src_dir, src_name) = src_uri
+            .rsplit_once('/')
+            .ok_or_else(|| format!("无效的文件地址: {}", src_uri))?;
+        let (dst_dir, dst_name) = dst_uri
+            .rsplit_once('/')
+            .ok_or_else(|| format!("无效的文件地址: {}", dst_uri))?;
+        let mut current = src_uri.to_string();
+        if src_name != dst_name {
+            self.rename_file(&current, dst_name).await?;
+            current = format!("{}/{}", src_dir, dst_name);
+        }
+        if src_dir != dst_dir {
+            if let Err(err) = self.move_files(vec![current.clone()], dst_dir).await {
+                if current != src_uri {
+                    if let Err(undo) = self.rename_file(&current, src_name).await {
+                        return Err(format!(
+                            "移动失败: {}；撤销重命名也失败，文件停留在 {}: {}",
+                            err, current, undo
+                        )
+                        .into());
+                    }
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn create_directory(&self, uri: &str) -> Result<(), Box<dyn Error>> {
+        let url = format!("{}{}", self.base_url, self.api_paths.create_file);
+        let body = serde_json::json!({
+            "type": "folder",
+            "uri": uri,
+            "err_on_conflict": true
+        });
+        let result = self
+            .send_api::<Value>(|| Ok(self.apply_auth(self.client.post(&url)).json(&body)))
+            .await;
+        self.forget_cached([uri]);
+        result?;
+        Ok(())
+    }
+
+    /// 订阅 `uri` 下的变更推送，每条非心跳事件调用一次 `on_event`，连接结束时返回。
+    /// 推送连接可能长时间没有数据，与上传一样使用不设读取超时的客户端。访问令牌失效时先刷新再连接一次。
+    pub async fn subscribe_changes<F>(
+        &self,
+        uri: &str,
+        mut on_event: F,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(PushEvent),
+    {
+        let url = format!(
+            "{}{}?uri={}",
+            self.base_url,
+            self.api_paths.file_events,
+            urlencoding::encode(uri)
+        );
+        let connect = || {
+            self.apply_auth(self.upload_client.get(&url))
+                .header(reqwest::header::ACCEPT, "text/event-stream")
+                .send()
+        };
+        let token = self.access_token();
+        let mut response = connect().await?;
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            // 不经过 with_token_refresh：订阅在后台任务中运行，等待期间不能持有错误对象。
+            if !self.refresh_access_token(token.as_deref()).await? {
+                return Err(Box::new(CloudreveError::NotLoggedIn));
+            }
+            response = connect().await?;
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+                return Err(Box::new(CloudreveError::NotLoggedIn));
+            }
+        }
+        let status = response.status();
+        if matches!(
+            status,
+            reqwest::StatusCode::NOT_FOUND
+                | reqwest::StatusCode::METHOD_NOT_ALLOWED
+                | reqwest::StatusCode::NOT_IMPLEMENTED
+        ) {
+            return Err(Box::new(PushUnsupported {
+                detail: format!("HTTP {}", status),
+            }));
+        }
+        if !status.is_success() {
+            return Err(format!("订阅变更推送失败: HTTP {}", status).into());
+        }
+        let is_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        if !is_stream {
+            return Err(Box::new(PushUnsupported {
+                detail: "响应不是事件流".to_string(),
+            }));
+        }
+        let mut parser = SseParser::default();
+        while let Some(chunk) = response.chunk().await? {
+            for event in parser.push(&chunk) {
+                if !event.is_keepalive() {
+                    on_event(event);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -477,14 +1340,114 @@ impl CloudreveClient {
             show_readme: None,
         };
         let response = self
-            .apply_auth(self.client.put(url))
-            .json(&payload)
-            .send()
+            .send_api::<String>(|| Ok(self.apply_auth(self.client.put(&url)).json(&payload)))
             .await?;
-        let response = parse_api_response::<String>(response).await?;
         Ok(response.data)
     }
 
+    /// 读取分享地址对应的分享信息，带上地址中的访问密码。
+    pub async fn get_share_info(&self, uri: &str) -> Result<ShareInfo, Box<dyn Error>> {
+        let range = Self::share_credentials(uri).ok_or("不是分享地址")?;
+        let id = uri[range].split(':').next().unwrap_or_default();
+        let mut url = format!(
+            "{}{}/{}",
+            self.base_url,
+            self.api_paths.share_info,
+            urlencoding::encode(id)
+        );
+        if let Some(password) = Self::share_password(uri) {
+            url.push_str(&format!("?password={}", urlencoding::encode(password)));
+        }
+        let response = self
+            .send_api_idempotent::<ShareInfo>(|| Ok(self.apply_auth(self.client.get(&url))))
+            .await?;
+        Ok(response.data)
+    }
+
+    /// 当前用户能否写入分享地址下的内容：自己的分享总可写入，别人的分享看分享设置的修改权限。
+    pub async fn share_writable(&self, uri: &str) -> Result<bool, Box<dyn Error>> {
+        if self.get_file_info(uri).await?.owned {
+            return Ok(true);
+        }
+        Ok(self.get_share_info(uri).await?.writable())
+    }
+
+    /// 地址所属的文件系统，如 `my`、`share`；分享地址 `cloudreve://<分享 ID>[:密码]@share/...`
+    /// 取 `@` 之后的部分。
+    pub fn uri_filesystem(uri: &str) -> &str {
+        let rest = uri.strip_prefix("cloudreve://").unwrap_or_default();
+        let host = rest.split(['/', '?']).next().unwrap_or_default();
+        host.rsplit('@').next().unwrap_or(host)
+    }
+
+    pub fn is_share_uri(uri: &str) -> bool {
+        Self::uri_filesystem(uri) == "share"
+    }
+
+    /// 分享地址中 `@` 之前的 `<分享 ID>[:密码]` 部分在地址中的位置。
+    fn share_credentials(uri: &str) -> Option<std::ops::Range<usize>> {
+        let start = "cloudreve://".len();
+        let host_len = uri
+            .strip_prefix("cloudreve://")?
+            .find(['/', '?'])
+            .unwrap_or(uri.len() - start);
+        let host = &uri[start..start + host_len];
+        let (credentials, filesystem) = host.rsplit_once('@')?;
+        (filesystem == "share").then_some(start..start + credentials.len())
+    }
+
+    /// 分享地址中的访问密码，没有密码时返回 `None`。
+    pub fn share_password(uri: &str) -> Option<&str> {
+        let range = Self::share_credentials(uri)?;
+        uri[range]
+            .split_once(':')
+            .map(|(_, password)| password)
+            .filter(|password| !password.is_empty())
+    }
+
+    /// 去掉分享地址中的访问密码，只保留分享 ID；其他地址原样返回。
+    pub fn without_share_password(uri: &str) -> String {
+        match Self::share_credentials(uri) {
+            Some(range) => match uri[range.clone()].split_once(':') {
+                Some((id, _)) => format!("{}{}{}", &uri[..range.start], id, &uri[range.end..]),
+                None => uri.to_string(),
+            },
+            None => uri.to_string(),
+        }
+    }
+
+    /// 在分享地址中填入访问密码，已有的密码被替换。
+    pub fn with_share_password(uri: &str, password: &str) -> String {
+        let plain = Self::without_share_password(uri);
+        match Self::share_credentials(&plain) {
+            Some(range) if !password.is_empty() => format!(
+                "{}:{}{}",
+                &plain[..range.end],
+                password,
+                &plain[range.end..]
+            ),
+            _ => plain,
+        }
+    }
+
+    /// 去掉文本中所有分享地址的访问密码，用于写入日志的内容。
+    pub fn redact_share_passwords(text: &str) -> String {
+        let mut redacted = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(pos) = rest.find("cloudreve://") {
+            redacted.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+            let start = "cloudreve://".len();
+            let end = rest[start..]
+                .find(|ch: char| ch.is_whitespace() || matches!(ch, '/' | '?' | ',' | ')' | '"'))
+                .map_or(rest.len(), |len| start + len);
+            redacted.push_str(&Self::without_share_password(&rest[..end]));
+            rest = &rest[end..];
+        }
+        redacted.push_str(rest);
+        redacted
+    }
+
     pub fn build_file_uri(remote_path: &str) -> String {
         if remote_path.starts_with("cloudreve://") {
             return remote_path.to_string();
@@ -496,8 +1459,187 @@ impl CloudreveClient {
         format!("cloudreve://my{}", path)
     }
 
+    /// 访问令牌失效时用刷新令牌换取新令牌，再执行一次 `op`；未配置刷新或服务端拒绝刷新令牌时
+    /// 返回原来的登录失效错误，刷新请求本身遇到网络等瞬时错误时返回该错误，留待下次重试。
+    /// 服务端在鉴权阶段就拒绝了请求，重试写请求不会重复执行。
+    async fn with_token_refresh<T, Fut>(&self, op: impl Fn() -> Fut) -> Result<T, Box<dyn Error>>
+    where
+        Fut: Future<Output = Result<T, Box<dyn Error>>>,
+    {
+        let token = self.access_token();
+        match self.unless_cancelled(op()).await? {
+            Err(err) if is_not_logged_in(err.as_ref()) => {
+                if self.refresh_access_token(token.as_deref()).await? {
+                    self.unless_cancelled(op()).await?
+                } else {
+                    Err(err)
+                }
+            }
+            result => result,
+        }
+    }
+
+    /// 换取新的访问令牌；`stale` 已被其他请求或客户端换掉时直接改用新令牌。
+    /// 返回是否换到了令牌，只有服务端明确拒绝刷新令牌时才返回 `false`。
+    async fn refresh_access_token(&self, stale: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let Some(refresh) = &self.token_refresh else {
+            return Ok(false);
+        };
+        let _guard = REFRESH_LOCK.lock().await;
+        if self.access_token().as_deref() != stale {
+            return Ok(true);
+        }
+        let Some(stored) = (refresh.load)().filter(|tokens| !tokens.refresh_token.is_empty())
+        else {
+            return Ok(false);
+        };
+        if Some(stored.access_token.as_str()) != stale {
+            if let Ok(mut token) = self.access_token.write() {
+                *token = Some(stored.access_token);
+            }
+            return Ok(true);
+        }
+        let pair = match refresh_token(&self.base_url, &stored.refresh_token).await {
+            Ok(pair) => pair,
+            Err(err) => {
+                if let Some(log) = &self.retry_log {
+                    (log.0)(&format!("访问令牌已失效，刷新失败: {}", err));
+                }
+                if is_refresh_rejected(err.as_ref()) {
+                    return Ok(false);
+                }
+                return Err(err);
+            }
+        };
+        if let Ok(mut token) = self.access_token.write() {
+            *token = Some(pair.access_token.clone());
+        }
+        (refresh.store)(&pair);
+        Ok(true)
+    }
+
+    /// 主动用刷新令牌换新访问令牌，与请求遇到令牌失效时的刷新共用同一把锁。
+    /// 返回 false 表示没有可用的刷新令牌，或刷新令牌已被拒绝。
+    pub async fn renew_tokens(&self) -> Result<bool, Box<dyn Error>> {
+        let current = self.access_token();
+        self.refresh_access_token(current.as_deref()).await
+    }
+
+    /// 发送不可重复执行的请求并解析响应。
+    async fn send_api<T: DeserializeOwned>(
+        &self,
+        build: impl Fn() -> Result<reqwest::RequestBuilder, Box<dyn Error>>,
+    ) -> Result<ApiResponse<T>, Box<dyn Error>> {
+        self.with_token_refresh(|| async {
+            let response = build()?.send().await?;
+            parse_api_response::<T>(response).await
+        })
+        .await
+    }
+
+    /// 与 `send_api` 相同，但瞬时错误时按 `send_idempotent` 重试。
+    async fn send_api_idempotent<T: DeserializeOwned>(
+        &self,
+        build: impl Fn() -> Result<reqwest::RequestBuilder, Box<dyn Error>>,
+    ) -> Result<ApiResponse<T>, Box<dyn Error>> {
+        self.with_token_refresh(|| async {
+            let response = self.send_idempotent(&build).await?;
+            parse_api_response::<T>(response).await
+        })
+        .await
+    }
+
+    async fn send_coalesced<T: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        url: String,
+        body: Option<Value>,
+        scope: &str,
+    ) -> Result<ApiResponse<T>, Box<dyn Error>> {
+        self.with_token_refresh(|| {
+            self.send_coalesced_once(method.clone(), url.clone(), body.clone(), scope)
+        })
+        .await
+    }
+
+    /// 相同方法、URL、请求体与凭据的并发请求只发送一次；GET 结果短暂复用，`scope` 为读取的远端地址，
+    /// 写请求仅合并进行中的调用，由调用方在完成后丢弃涉及地址的读取结果。
+    async fn send_coalesced_once<T: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        url: String,
+        body: Option<Value>,
+        scope: &str,
+    ) -> Result<ApiResponse<T>, Box<dyn Error>> {
+        let payload = body.as_ref().map(Value::to_string).unwrap_or_default();
+        let token = self.access_token();
+        let key =
+            coalesce::request_key(method.as_str(), &url, payload.as_bytes(), token.as_deref());
+        let is_read = method == reqwest::Method::GET;
+        let ttl = if is_read {
+            DEFAULT_COALESCE_TTL
+        } else {
+            std::time::Duration::ZERO
+        };
+        let cached = coalesce::shared()
+            .run(key, scope, ttl, || async {
+                // 只有 GET 与 PATCH 元数据会经过这里，都可以安全重试。
+                let response = self
+                    .send_idempotent(|| {
+                        let request = self.apply_auth(self.client.request(method.clone(), &url));
+                        Ok(match &body {
+                            Some(body) => request.json(body),
+                            None => request,
+                        })
+                    })
+                    .await
+                    .map_err(|err| err.to_string())?;
+                transport::counters().record_response(response.version());
+                let status = response.status().as_u16();
+                let body = response.text().await.map_err(|err| err.to_string())?;
+                Ok(CachedResponse { status, body })
+            })
+            .await?;
+        let status = reqwest::StatusCode::from_u16(cached.status)?;
+        parse_api_text(status, &cached.body)
+    }
+
+    /// 发送可以重复执行的请求。瞬时错误时按重试策略等待后重发，重试用尽后返回最后一次的结果，
+    /// 由调用方照常解析错误响应。
+    async fn send_idempotent(
+        &self,
+        build: impl Fn() -> Result<reqwest::RequestBuilder, Box<dyn Error>>,
+    ) -> Result<reqwest::Response, Box<dyn Error>> {
+        let mut attempt = 0;
+        loop {
+            let result = self.unless_cancelled(build()?.send()).await?;
+            let reason = match &result {
+                Ok(response) if is_transient_status(response.status()) => {
+                    format!("HTTP {}", response.status())
+                }
+                Err(err) if is_transient_error(err) => err.to_string(),
+                _ => return Ok(result?),
+            };
+            if attempt >= self.retry.retries {
+                return Ok(result?);
+            }
+            attempt += 1;
+            let delay = self.retry.delay_for(attempt, retry_jitter());
+            if let Some(log) = &self.retry_log {
+                (log.0)(&format!(
+                    "请求失败，{} 毫秒后第 {}/{} 次重试: {}",
+                    delay.as_millis(),
+                    attempt,
+                    self.retry.retries,
+                    reason
+                ));
+            }
+            self.unless_cancelled(tokio::time::sleep(delay)).await?;
+        }
+    }
+
     fn apply_auth(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
-        if let Some(token) = &self.access_token {
+        if let Some(token) = self.access_token() {
             request.bearer_auth(token)
         } else {
             request
@@ -521,6 +1663,7 @@ pub enum SignInResult {
 }
 
 async fn parse_api_envelope(response: reqwest::Response) -> Result<ApiEnvelope, Box<dyn Error>> {
+    transport::counters().record_response(response.version());
     let status = response.status();
     let text = response.text().await?;
     let envelope = serde_json::from_str::<ApiEnvelope>(&text).map_err(|err| {
@@ -555,7 +1698,11 @@ pub async fn password_sign_in(
     if let Some(ticket) = ticket {
         body["ticket"] = serde_json::json!(ticket);
     }
-    let response = reqwest::Client::new().post(url).json(&body).send().await?;
+    let response = transport::shared_client()
+        .post(url)
+        .json(&body)
+        .send()
+        .await?;
     let response = parse_api_envelope(response).await?;
     if response.code == 0 {
         let data_value = response.data.ok_or_else(|| {
@@ -595,7 +1742,7 @@ pub async fn finish_sign_in_with_2fa(
         format!("{}/api/v4", base_url)
     };
     let url = format!("{}/session/token/2fa", base_url);
-    let response = reqwest::Client::new()
+    let response = transport::shared_client()
         .post(url)
         .json(&serde_json::json!({
             "opt": opt,
@@ -620,7 +1767,7 @@ pub async fn refresh_token(
         format!("{}/api/v4", base_url)
     };
     let url = format!("{}/session/token/refresh", base_url);
-    let response = reqwest::Client::new()
+    let response = transport::shared_client()
         .post(url)
         .json(&serde_json::json!({ "refresh_token": refresh_token }))
         .send()
@@ -644,7 +1791,7 @@ pub async fn get_captcha(base_url: &str) -> Result<CaptchaData, Box<dyn Error>>
         format!("{}/api/v4", base_url)
     };
     let url = format!("{}/site/captcha", base_url);
-    let response = reqwest::Client::new().get(url).send().await?;
+    let response = transport::shared_client().get(url).send().await?;
     let response = parse_api_response::<CaptchaData>(response).await?;
     Ok(response.data)
 }
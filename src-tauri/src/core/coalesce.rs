@@ -0,0 +1,326 @@
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+pub const DEFAULT_COALESCE_TTL: Duration = Duration::from_secs(2);
+
+lazy_static! {
+    static ref SHARED: Coalescer = Coalescer::new();
+}
+
+/// 进程内共享的合并器，所有 CloudreveClient 实例共用。
+pub fn shared() -> &'static Coalescer {
+    &SHARED
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+enum SlotState {
+    Pending,
+    Done {
+        expires_at: Instant,
+        result: Result<CachedResponse, String>,
+    },
+    /// 发起请求的调用被取消，等待者改由自己重新请求。
+    Abandoned,
+}
+
+struct Slot {
+    /// 请求涉及的远端地址，写操作按地址丢弃相关结果。
+    scope: String,
+    state: Mutex<SlotState>,
+    ready: Notify,
+}
+
+impl Slot {
+    fn finish(&self, state: SlotState) {
+        if let Ok(mut current) = self.state.lock() {
+            if matches!(*current, SlotState::Pending) {
+                *current = state;
+            }
+        }
+        self.ready.notify_waiters();
+    }
+}
+
+enum Role {
+    Leader(Arc<Slot>),
+    Follower(Arc<Slot>),
+}
+
+/// 发起请求的 future 在完成前被丢弃时通知等待者，避免它们一直等下去。
+struct LeaderGuard(Arc<Slot>);
+
+impl Drop for LeaderGuard {
+    fn drop(&mut self) {
+        self.0.finish(SlotState::Abandoned);
+    }
+}
+
+/// 去掉查询参数与末尾的斜杠，同一目录的不同写法得到相同的地址。
+fn normalize_scope(uri: &str) -> &str {
+    uri.split('?').next().unwrap_or(uri).trim_end_matches('/')
+}
+
+#[derive(Default)]
+pub struct Coalescer {
+    slots: Mutex<HashMap<String, Arc<Slot>>>,
+}
+
+/// 按方法、URL、请求体与凭据生成合并键；不同账户的同一请求不会被合并。
+pub fn request_key(method: &str, url: &str, body: &[u8], auth: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update([0]);
+    hasher.update(url.as_bytes());
+    hasher.update([0]);
+    hasher.update(body);
+    hasher.update([0]);
+    hasher.update(auth.unwrap_or("").as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+impl Coalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 丢弃与 `uri` 相关的已完成结果：该地址本身、其下的各项以及所在目录的列表。
+    /// 写操作后对涉及的每个地址调用，其余结果继续复用。
+    pub fn invalidate(&self, uri: &str) {
+        let uri = normalize_scope(uri);
+        let prefix = format!("{}/", uri);
+        let parent = uri.rsplit_once('/').map(|(parent, _)| parent);
+        self.retain(|slot| {
+            slot.scope != uri && !slot.scope.starts_with(&prefix) && Some(&*slot.scope) != parent
+        });
+    }
+
+    fn retain(&self, keep: impl Fn(&Slot) -> bool) {
+        if let Ok(mut slots) = self.slots.lock() {
+            slots.retain(|_, slot| {
+                matches!(slot.state.lock().as_deref(), Ok(SlotState::Pending)) || keep(slot)
+            });
+        }
+    }
+
+    /// 同一键的并发请求只执行一次；成功结果在 `ttl` 内复用，失败结果不缓存。
+    /// 等待者异步等待，不占用运行时的工作线程。`scope` 为请求涉及的远端地址。
+    pub async fn run<F, Fut>(
+        &self,
+        key: String,
+        scope: &str,
+        ttl: Duration,
+        fetch: F,
+    ) -> Result<CachedResponse, String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<CachedResponse, String>>,
+    {
+        loop {
+            match self.role(&key, scope)? {
+                Role::Leader(slot) => {
+                    let guard = LeaderGuard(slot);
+                    let result = fetch().await;
+                    guard.0.finish(SlotState::Done {
+                        expires_at: Instant::now() + ttl,
+                        result: result.clone(),
+                    });
+                    return result;
+                }
+                Role::Follower(slot) => {
+                    if let Some(result) = Self::wait(&slot).await {
+                        return result;
+                    }
+                }
+            }
+        }
+    }
+
+    fn role(&self, key: &str, scope: &str) -> Result<Role, String> {
+        let mut slots = self.slots.lock().map_err(|_| "coalesce lock error")?;
+        let now = Instant::now();
+        slots.retain(|_, slot| match slot.state.lock() {
+            Ok(state) => match &*state {
+                SlotState::Pending => true,
+                SlotState::Done { expires_at, result } => result.is_ok() && now < *expires_at,
+                SlotState::Abandoned => false,
+            },
+            Err(_) => false,
+        });
+        if let Some(slot) = slots.get(key) {
+            return Ok(Role::Follower(slot.clone()));
+        }
+        let slot = Arc::new(Slot {
+            scope: normalize_scope(scope).to_string(),
+            state: Mutex::new(SlotState::Pending),
+            ready: Notify::new(),
+        });
+        slots.insert(key.to_string(), slot.clone());
+        Ok(Role::Leader(slot))
+    }
+
+    /// 等待发起者完成；发起者被取消时返回 `None`。
+    async fn wait(slot: &Slot) -> Option<Result<CachedResponse, String>> {
+        loop {
+            // 先登记等待再检查状态，检查之后才到达的通知不会丢失。
+            let notified = slot.ready.notified();
+            match slot.state.lock().as_deref() {
+                Ok(SlotState::Done { result, .. }) => return Some(result.clone()),
+                Ok(SlotState::Pending) => {}
+                Ok(SlotState::Abandoned) | Err(_) => return None,
+            }
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::thread;
+
+    const TTL: Duration = Duration::from_secs(60);
+    const DIR: &str = "cloudreve://my/Docs";
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Runtime::new()
+            .expect("runtime")
+            .block_on(future)
+    }
+
+    fn ok(body: &str) -> Result<CachedResponse, String> {
+        Ok(CachedResponse {
+            status: 200,
+            body: body.to_string(),
+        })
+    }
+
+    #[test]
+    fn reuses_result_within_ttl() {
+        let coalescer = Coalescer::new();
+        let calls = AtomicU32::new(0);
+        for _ in 0..3 {
+            let result = block_on(coalescer.run("k".to_string(), DIR, TTL, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                ok("a")
+            }));
+            assert_eq!(result, ok("a"));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        coalescer.invalidate("cloudreve://my/Other/a.txt");
+        coalescer.invalidate("cloudreve://my/Docs/sub/a.txt");
+        block_on(coalescer.run("k".to_string(), DIR, TTL, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            ok("a")
+        }))
+        .expect("reuse");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 目录下的文件被修改后，该目录的列表不再复用。
+        coalescer.invalidate("cloudreve://my/Docs/new.txt");
+        block_on(coalescer.run("k".to_string(), DIR, TTL, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            ok("a")
+        }))
+        .expect("refetch");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn errors_are_not_cached() {
+        let coalescer = Coalescer::new();
+        let first = block_on(coalescer.run("k".to_string(), DIR, TTL, || async {
+            Err::<CachedResponse, String>("boom".to_string())
+        }));
+        assert!(first.is_err());
+        let second = block_on(coalescer.run("k".to_string(), DIR, TTL, || async { ok("b") }));
+        assert_eq!(second, ok("b"));
+    }
+
+    #[test]
+    fn concurrent_callers_share_one_fetch() {
+        let coalescer = Arc::new(Coalescer::new());
+        let calls = Arc::new(AtomicU32::new(0));
+        let handles = (0..4)
+            .map(|_| {
+                let coalescer = coalescer.clone();
+                let calls = calls.clone();
+                thread::spawn(move || {
+                    block_on(
+                        coalescer.run("k".to_string(), DIR, Duration::ZERO, || async {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            thread::sleep(Duration::from_millis(50));
+                            ok("c")
+                        }),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().expect("join"), ok("c"));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn followers_wait_without_blocking_the_runtime() {
+        let coalescer = Coalescer::new();
+        let calls = AtomicU32::new(0);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("runtime");
+        let results = runtime.block_on(futures_util::future::join_all((0..4).map(|_| {
+            coalescer.run("k".to_string(), DIR, TTL, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                ok("d")
+            })
+        })));
+        assert!(results.into_iter().all(|result| result == ok("d")));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn abandoned_leader_hands_over_to_a_follower() {
+        let coalescer = Coalescer::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("runtime");
+        let result = runtime.block_on(async {
+            let leader = coalescer.run("k".to_string(), DIR, TTL, || async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                ok("never")
+            });
+            let follower = async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                coalescer
+                    .run("k".to_string(), DIR, TTL, || async { ok("e") })
+                    .await
+            };
+            let abandon = tokio::time::timeout(Duration::from_millis(30), leader);
+            tokio::join!(abandon, follower).1
+        });
+        assert_eq!(result, ok("e"));
+    }
+
+    #[test]
+    fn key_depends_on_auth() {
+        assert_ne!(
+            request_key("GET", "/file", b"", Some("a")),
+            request_key("GET", "/file", b"", Some("b"))
+        );
+    }
+}
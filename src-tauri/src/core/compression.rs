@@ -0,0 +1,190 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// 本身已经压缩的格式，压缩只会浪费时间。
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "7z", "aac", "apk", "avi", "br", "bz2", "docx", "epub", "flac", "gif", "gz", "heic", "jar",
+    "jpeg", "jpg", "m4a", "m4v", "mkv", "mov", "mp3", "mp4", "odt", "ogg", "opus", "pdf", "png",
+    "pptx", "rar", "tgz", "webm", "webp", "xlsx", "xz", "zip", "zst",
+];
+
+/// zstd 的默认压缩级别，兼顾速度与压缩率。
+const ZSTD_LEVEL: i32 = 3;
+
+fn default_min_size_bytes() -> u64 {
+    4096
+}
+
+/// 压缩算法，名称写入远端元数据，下载时据此解压。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionCodec {
+    #[default]
+    Gzip,
+    /// 压缩与解压都比 gzip 快，压缩率相近或更高。
+    Zstd,
+}
+
+impl CompressionCodec {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CompressionCodec::Gzip => "gzip",
+            CompressionCodec::Zstd => "zstd",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "gzip" => Some(CompressionCodec::Gzip),
+            "zstd" => Some(CompressionCodec::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// 任务的传输压缩设置：上传前压缩、下载后解压，远端保存压缩后的内容。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub codec: CompressionCodec,
+    /// 小于此大小的文件不压缩。
+    #[serde(default = "default_min_size_bytes")]
+    pub min_size_bytes: u64,
+    /// 在内置的已压缩格式之外，额外不压缩的扩展名。
+    #[serde(default)]
+    pub exclude_extensions: Vec<String>,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            codec: CompressionCodec::default(),
+            min_size_bytes: default_min_size_bytes(),
+            exclude_extensions: Vec::new(),
+        }
+    }
+}
+
+impl CompressionSettings {
+    pub fn is_disabled(&self) -> bool {
+        !self.enabled
+    }
+
+    /// 是否压缩 `relpath`；同一文件的判断结果总是相同，上传与写入元数据时各自判断。
+    pub fn applies(&self, relpath: &str, size: u64) -> bool {
+        if !self.enabled || size < self.min_size_bytes.max(1) {
+            return false;
+        }
+        let Some(ext) = Path::new(relpath)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+        else {
+            return true;
+        };
+        !COMPRESSED_EXTENSIONS.contains(&ext.as_str())
+            && !self.exclude_extensions.iter().any(|item| {
+                item.trim()
+                    .trim_start_matches('.')
+                    .eq_ignore_ascii_case(&ext)
+            })
+    }
+}
+
+/// 远端元数据中的压缩标记：`<算法>:<原始大小>`。
+pub fn compression_tag(codec: CompressionCodec, size: u64) -> String {
+    format!("{}:{}", codec.as_str(), size)
+}
+
+/// 解析压缩标记，返回算法名称与原始大小。
+pub fn parse_compression_tag(tag: &str) -> Option<(&str, u64)> {
+    let (codec, size) = tag.split_once(':')?;
+    Some((codec, size.parse().ok()?))
+}
+
+pub fn compress_file(
+    codec: CompressionCodec,
+    src: &Path,
+    dst: &Path,
+) -> Result<u64, Box<dyn Error>> {
+    let mut input = BufReader::new(fs::File::open(src)?);
+    let output = BufWriter::new(fs::File::create(dst)?);
+    match codec {
+        CompressionCodec::Gzip => {
+            let mut encoder = GzEncoder::new(output, Compression::default());
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?.flush()?;
+        }
+        CompressionCodec::Zstd => {
+            let mut encoder = zstd::Encoder::new(output, ZSTD_LEVEL)?;
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?.flush()?;
+        }
+    }
+    Ok(fs::metadata(dst)?.len())
+}
+
+/// 按远端标记的算法解压，返回解压后的大小；不认识的算法（可能来自更新的版本）返回错误。
+pub fn decompress_file(codec: &str, src: &Path, dst: &Path) -> Result<u64, Box<dyn Error>> {
+    let codec =
+        CompressionCodec::parse(codec).ok_or_else(|| format!("不支持的压缩格式: {}", codec))?;
+    let input = BufReader::new(fs::File::open(src)?);
+    let mut output = BufWriter::new(fs::File::create(dst)?);
+    let size = match codec {
+        CompressionCodec::Gzip => io::copy(&mut GzDecoder::new(input), &mut output),
+        CompressionCodec::Zstd => io::copy(&mut zstd::Decoder::with_buffer(input)?, &mut output),
+    }
+    .map_err(|err| format!("解压失败: {}", err))?;
+    output.flush()?;
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn compresses_text_and_skips_compressed_formats() {
+        let settings = CompressionSettings {
+            enabled: true,
+            exclude_extensions: vec![".LOG".to_string()],
+            ..CompressionSettings::default()
+        };
+        assert!(settings.applies("notes/a.txt", 10_000));
+        assert!(settings.applies("Makefile", 10_000));
+        assert!(!settings.applies("notes/a.txt", 100));
+        assert!(!settings.applies("photos/a.JPG", 10_000));
+        assert!(!settings.applies("server.log", 10_000));
+        assert!(!CompressionSettings::default().applies("notes/a.txt", 10_000));
+
+        let dir = tempdir().expect("tempdir");
+        let plain = dir.path().join("plain.txt");
+        let packed = dir.path().join("plain.packed");
+        let unpacked = dir.path().join("unpacked.txt");
+        let content = "line of text\n".repeat(1000);
+        fs::write(&plain, &content).expect("write");
+        for codec in [CompressionCodec::Gzip, CompressionCodec::Zstd] {
+            let packed_size = compress_file(codec, &plain, &packed).expect("pack");
+            assert!(packed_size < content.len() as u64 / 10);
+            let tag = compression_tag(codec, content.len() as u64);
+            let (name, size) = parse_compression_tag(&tag).expect("tag");
+            assert_eq!(size, content.len() as u64);
+            assert_eq!(
+                decompress_file(name, &packed, &unpacked).expect("unpack"),
+                size
+            );
+            assert_eq!(fs::read_to_string(&unpacked).expect("read"), content);
+        }
+        assert!(decompress_file("brotli", &packed, &unpacked).is_err());
+    }
+}
@@ -1,31 +1,54 @@
+use crate::core::credentials::{clear_proxy_password, load_proxy_password, store_proxy_password};
+use crate::core::maintenance::RetentionPolicy;
+use crate::core::network::{
+    Backoff, ChunkSettings, NetworkProfile, ProxySettings, RetryPolicy, TimeoutSettings,
+};
+use crate::core::system_state::PauseRules;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ApiPaths {
     pub list_files: String,
+    pub file_info: String,
     pub create_download: String,
     pub update_content: String,
     pub create_upload_session: String,
     pub upload_chunk: String,
     pub patch_metadata: String,
     pub create_share_link: String,
+    pub share_info: String,
     pub delete_file: String,
+    pub rename_file: String,
+    pub create_file: String,
+    pub move_file: String,
+    pub set_current_version: String,
+    pub file_events: String,
 }
 
 impl Default for ApiPaths {
     fn default() -> Self {
         Self {
             list_files: "/file".to_string(),
+            file_info: "/file/info".to_string(),
             create_download: "/file/url".to_string(),
             update_content: "/file/content".to_string(),
             create_upload_session: "/file/upload".to_string(),
             upload_chunk: "/file/upload".to_string(),
             patch_metadata: "/file/metadata".to_string(),
             create_share_link: "/share".to_string(),
+            share_info: "/share/info".to_string(),
             delete_file: "/file".to_string(),
+            rename_file: "/file/rename".to_string(),
+            create_file: "/file/create".to_string(),
+            move_file: "/file/move".to_string(),
+            set_current_version: "/file/version/current".to_string(),
+            file_events: "/file/events".to_string(),
         }
     }
 }
@@ -68,6 +91,14 @@ pub fn logs_path() -> Result<PathBuf, Box<dyn Error>> {
     Ok(config_dir()?.join("sync.log.jsonl"))
 }
 
+pub fn db_path() -> Result<PathBuf, Box<dyn Error>> {
+    let path = config_dir()?.join("cloudreve.db");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(path)
+}
+
 pub fn settings_path() -> Result<PathBuf, Box<dyn Error>> {
     Ok(config_dir()?.join("settings.json"))
 }
@@ -85,14 +116,172 @@ pub struct AppSettings {
     pub tray: bool,
     pub language: String,
     pub proxy: String,
+    /// `system`、`none` 或 `manual`；旧配置文件中没有该字段时，填写了代理地址即视为手动代理。
+    #[serde(default)]
+    pub proxy_mode: String,
+    #[serde(default)]
+    pub proxy_username: String,
+    /// 只用于从前端接收新密码，保存在钥匙串中，不写入设置文件也不返回给前端；留空表示不修改。
+    #[serde(default, skip_serializing)]
+    pub proxy_password: String,
+    /// 钥匙串中是否保存了代理密码。
+    #[serde(default)]
+    pub proxy_password_saved: bool,
     pub retries: u32,
     pub backoff: String,
     pub upload: u32,
     pub download: u32,
     pub sha_threads: u32,
+    /// 文件在 Office 等程序中打开（同目录存在锁文件）时暂缓上传。
     pub lock_pause: bool,
     pub debug: bool,
     pub trace: bool,
+    #[serde(default)]
+    pub network_profiles: Vec<NetworkProfile>,
+    #[serde(default)]
+    pub network_profile: String,
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: u32,
+    #[serde(default = "default_backup_interval_hours")]
+    pub backup_interval_hours: u64,
+    /// 日志总行数上限，超出时删除最旧的日志，0 表示不限。
+    #[serde(default = "default_log_max_rows")]
+    pub log_max_rows: u64,
+    /// 各级别日志的保留天数，0 表示一直保留。
+    #[serde(default = "default_log_info_days")]
+    pub log_info_days: u64,
+    #[serde(default = "default_log_warn_days")]
+    pub log_warn_days: u64,
+    #[serde(default = "default_log_error_days")]
+    pub log_error_days: u64,
+    /// 删除记录的保留天数；超过后不再用它判断删除方向，0 表示一直保留。
+    #[serde(default = "default_tombstone_days")]
+    pub tombstone_days: u64,
+    #[serde(default)]
+    pub idle_defer_minutes: u64,
+    #[serde(default)]
+    pub quiet_hours_start: u32,
+    #[serde(default)]
+    pub quiet_hours_end: u32,
+    /// 按流量计费的网络上暂停同步。
+    #[serde(default)]
+    pub pause_on_metered: bool,
+    /// 使用电池供电时暂停同步。
+    #[serde(default)]
+    pub pause_on_battery: bool,
+    /// 使用电池且电量低于该百分比时暂停同步，0 表示不检查。
+    #[serde(default)]
+    pub low_battery_percent: u8,
+    /// 这些网络接口（如 VPN 的 `tun0`，可用 `tun*`）连接时暂停同步。
+    #[serde(default)]
+    pub pause_interfaces: Vec<String>,
+    #[serde(default)]
+    pub mime_types: BTreeMap<String, String>,
+    #[serde(default)]
+    pub scan_command: String,
+    #[serde(default = "default_scan_timeout_secs")]
+    pub scan_timeout_secs: u64,
+    /// 本地 `/metrics` 与 `/healthz` 的监听端口，0 表示关闭。
+    #[serde(default)]
+    pub metrics_port: u16,
+    /// 监听本地目录变化，修改后数秒内触发同步；定时扫描仍作为兜底。
+    #[serde(default = "default_watch_local")]
+    pub watch_local: bool,
+    /// 服务器支持变更推送时订阅远端目录，远端修改后立即触发同步；不支持时继续定时轮询。
+    #[serde(default = "default_watch_remote")]
+    pub watch_remote: bool,
+    /// 分片上传时期望的最大分片大小（KB），0 表示使用服务端的分片大小。
+    #[serde(default)]
+    pub max_chunk_kb: u64,
+    /// 分片请求的基础超时，实际超时还会按分片大小延长。
+    #[serde(default = "default_chunk_timeout_secs")]
+    pub chunk_timeout_secs: u64,
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// 等待响应及读取响应体时两次收到数据之间的最长间隔。
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+    /// 单个文件一次传输的截止时间，0 表示不限制。
+    #[serde(default)]
+    pub file_deadline_secs: u64,
+    /// 启动时快速比对索引与本地文件（只看是否存在与大小），有差异的任务下一轮完整扫描。
+    #[serde(default = "default_startup_check")]
+    pub startup_check: bool,
+    /// 有传输的一轮同步完成后发送系统通知。
+    #[serde(default)]
+    pub notify_completed: bool,
+    #[serde(default = "default_notify")]
+    pub notify_errors: bool,
+    #[serde(default = "default_notify")]
+    pub notify_conflicts: bool,
+    /// 登录失效、令牌无法刷新时提醒重新登录。
+    #[serde(default = "default_notify")]
+    pub notify_auth: bool,
+    /// 通知的免打扰时段（本地小时），与同步的静默时段相互独立。
+    #[serde(default)]
+    pub notify_quiet_start: u32,
+    #[serde(default)]
+    pub notify_quiet_end: u32,
+}
+
+fn default_backup_keep() -> u32 {
+    10
+}
+
+fn default_backup_interval_hours() -> u64 {
+    24
+}
+
+fn default_log_max_rows() -> u64 {
+    200_000
+}
+
+fn default_log_info_days() -> u64 {
+    30
+}
+
+fn default_log_warn_days() -> u64 {
+    90
+}
+
+fn default_log_error_days() -> u64 {
+    180
+}
+
+fn default_tombstone_days() -> u64 {
+    365
+}
+
+fn default_scan_timeout_secs() -> u64 {
+    120
+}
+
+fn default_watch_local() -> bool {
+    true
+}
+
+fn default_watch_remote() -> bool {
+    true
+}
+
+fn default_chunk_timeout_secs() -> u64 {
+    60
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    15
+}
+
+fn default_read_timeout_secs() -> u64 {
+    60
+}
+
+fn default_startup_check() -> bool {
+    true
+}
+
+fn default_notify() -> bool {
+    true
 }
 
 impl Default for AppSettings {
@@ -102,6 +291,10 @@ impl Default for AppSettings {
             tray: true,
             language: "zh".to_string(),
             proxy: String::new(),
+            proxy_mode: "system".to_string(),
+            proxy_username: String::new(),
+            proxy_password: String::new(),
+            proxy_password_saved: false,
             retries: 5,
             backoff: "指数退避".to_string(),
             upload: 4,
@@ -110,21 +303,155 @@ impl Default for AppSettings {
             lock_pause: false,
             debug: false,
             trace: false,
+            network_profiles: Vec::new(),
+            network_profile: String::new(),
+            backup_keep: default_backup_keep(),
+            backup_interval_hours: default_backup_interval_hours(),
+            log_max_rows: default_log_max_rows(),
+            log_info_days: default_log_info_days(),
+            log_warn_days: default_log_warn_days(),
+            log_error_days: default_log_error_days(),
+            tombstone_days: default_tombstone_days(),
+            idle_defer_minutes: 0,
+            quiet_hours_start: 0,
+            quiet_hours_end: 0,
+            pause_on_metered: false,
+            pause_on_battery: false,
+            low_battery_percent: 0,
+            pause_interfaces: Vec::new(),
+            mime_types: BTreeMap::new(),
+            scan_command: String::new(),
+            scan_timeout_secs: default_scan_timeout_secs(),
+            metrics_port: 0,
+            watch_local: default_watch_local(),
+            watch_remote: default_watch_remote(),
+            max_chunk_kb: 0,
+            chunk_timeout_secs: default_chunk_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            read_timeout_secs: default_read_timeout_secs(),
+            file_deadline_secs: 0,
+            startup_check: default_startup_check(),
+            notify_completed: false,
+            notify_errors: default_notify(),
+            notify_conflicts: default_notify(),
+            notify_auth: default_notify(),
+            notify_quiet_start: 0,
+            notify_quiet_end: 0,
         }
     }
 }
 
 impl AppSettings {
+    pub fn pause_rules(&self) -> PauseRules {
+        PauseRules {
+            metered: self.pause_on_metered,
+            on_battery: self.pause_on_battery,
+            low_battery_percent: self.low_battery_percent.min(100),
+            interfaces: self.pause_interfaces.clone(),
+        }
+    }
+
+    pub fn retention_policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            max_log_rows: self.log_max_rows,
+            info_days: self.log_info_days,
+            warn_days: self.log_warn_days,
+            error_days: self.log_error_days,
+            tombstone_days: self.tombstone_days,
+        }
+    }
+
+    pub fn chunk_settings(&self) -> ChunkSettings {
+        ChunkSettings {
+            max_chunk_bytes: Some(self.max_chunk_kb * 1024).filter(|bytes| *bytes > 0),
+            timeout_base: Duration::from_secs(self.chunk_timeout_secs.max(1)),
+        }
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            retries: self.retries,
+            backoff: Backoff::from_label(&self.backoff),
+            ..RetryPolicy::default()
+        }
+    }
+
+    pub fn timeout_settings(&self) -> TimeoutSettings {
+        TimeoutSettings {
+            connect: Duration::from_secs(self.connect_timeout_secs.max(1)),
+            read: Duration::from_secs(self.read_timeout_secs.max(1)),
+            file_deadline: Some(Duration::from_secs(self.file_deadline_secs))
+                .filter(|deadline| !deadline.is_zero()),
+        }
+    }
+
+    pub fn proxy_settings(&self) -> ProxySettings {
+        match self.proxy_mode.as_str() {
+            "none" => ProxySettings::Direct,
+            _ if self.uses_manual_proxy() => ProxySettings::Manual {
+                url: self.proxy.trim().to_string(),
+                username: self.proxy_username.clone(),
+                password: self.proxy_password(),
+            },
+            _ => ProxySettings::System,
+        }
+    }
+
+    fn uses_manual_proxy(&self) -> bool {
+        match self.proxy_mode.as_str() {
+            "manual" => true,
+            "" => !self.proxy.trim().is_empty(),
+            _ => false,
+        }
+    }
+
+    /// 新输入的密码优先，否则读取钥匙串中保存的密码。
+    fn proxy_password(&self) -> String {
+        if !self.proxy_password.is_empty() || !self.proxy_password_saved {
+            return self.proxy_password.clone();
+        }
+        load_proxy_password().unwrap_or_else(|err| {
+            eprintln!("读取代理密码失败: {}", err);
+            String::new()
+        })
+    }
+
+    /// 旧版本把代理密码明文写在设置文件中，读取时移入钥匙串并重写设置文件。
     pub fn load() -> Result<Self, Box<dyn Error>> {
         let path = settings_path()?;
         if !path.exists() {
             return Ok(Self::default());
         }
         let text = fs::read_to_string(path)?;
-        Ok(serde_json::from_str(&text)?)
+        let mut settings: Self = serde_json::from_str(&text)?;
+        if !settings.proxy_password.is_empty() {
+            match store_proxy_password(&settings.proxy_password) {
+                Ok(()) => {
+                    settings.proxy_password_saved = true;
+                    settings.write()?;
+                }
+                Err(err) => eprintln!("代理密码移入钥匙串失败: {}", err),
+            }
+        }
+        Ok(settings)
     }
 
-    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+    /// 保存设置；填写了新的代理密码时写入钥匙串，不再使用手动代理认证时删除已保存的密码。
+    pub fn save(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.proxy_password.is_empty() {
+            store_proxy_password(&self.proxy_password)
+                .map_err(|err| format!("代理密码无法保存到钥匙串: {}", err))?;
+            self.proxy_password_saved = true;
+        } else if self.proxy_password_saved
+            && (!self.uses_manual_proxy() || self.proxy_username.is_empty())
+        {
+            clear_proxy_password()?;
+            self.proxy_password_saved = false;
+        }
+        self.write()
+    }
+
+    fn write(&self) -> Result<(), Box<dyn Error>> {
         let path = settings_path()?;
         ensure_dir(path.parent().ok_or("settings path invalid")?)?;
         fs::write(path, serde_json::to_string_pretty(self)?)?;
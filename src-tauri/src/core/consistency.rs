@@ -0,0 +1,132 @@
+use crate::core::db::{list_entries_by_task, list_name_mappings, TaskRow};
+use crate::core::names::{long_path, NameMap};
+use crate::core::roots::{resolve_local_path, task_roots};
+use crate::core::sync::ENTRY_QUARANTINED;
+use rusqlite::Connection;
+use std::error::Error;
+use std::fs;
+
+const SAMPLE_LIMIT: usize = 5;
+
+/// 启动时索引与磁盘的快速比对结果。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsistencyReport {
+    pub checked: usize,
+    pub missing: Vec<String>,
+    pub resized: Vec<String>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.missing.is_empty() && self.resized.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        if self.is_consistent() {
+            return format!("启动检查：{} 个文件与索引一致", self.checked);
+        }
+        let sample = self
+            .missing
+            .iter()
+            .chain(&self.resized)
+            .take(SAMPLE_LIMIT)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "启动检查：{} 个文件中 {} 个已不存在、{} 个大小变化，下次同步将完整扫描: {}",
+            self.checked,
+            self.missing.len(),
+            self.resized.len(),
+            sample
+        )
+    }
+}
+
+/// 只比对索引中的文件是否仍存在、大小是否一致，不计算哈希，用于发现程序关闭期间的修改。
+/// 被隔离的文件本地本就不存在，不计入。
+pub fn check_entries(
+    conn: &Connection,
+    task: &TaskRow,
+) -> Result<ConsistencyReport, Box<dyn Error>> {
+    let roots = task_roots(task);
+    let names = NameMap::new(list_name_mappings(conn, &task.task_id)?);
+    let mut report = ConsistencyReport::default();
+    for entry in list_entries_by_task(conn, &task.task_id)? {
+        if entry.state == ENTRY_QUARANTINED {
+            continue;
+        }
+        let Some(path) = resolve_local_path(&roots, &names.local_relpath(&entry.local_relpath))
+        else {
+            continue;
+        };
+        let path = long_path(path);
+        report.checked += 1;
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.is_file() => {
+                if entry.last_local_size >= 0 && metadata.len() as i64 != entry.last_local_size {
+                    report.resized.push(entry.local_relpath);
+                }
+            }
+            _ => report.missing.push(entry.local_relpath),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::db::{create_task, init_db, upsert_entry, EntryRow};
+    use tempfile::tempdir;
+
+    fn entry(relpath: &str, size: i64) -> EntryRow {
+        EntryRow {
+            task_id: "task-1".to_string(),
+            local_relpath: relpath.to_string(),
+            cloud_file_id: String::new(),
+            cloud_uri: String::new(),
+            last_local_mtime_ms: 0,
+            last_local_sha256: "x".to_string(),
+            last_local_size: size,
+            last_remote_mtime_ms: 0,
+            last_remote_sha256: "x".to_string(),
+            last_sync_ts_ms: 0,
+            state: "ok".to_string(),
+        }
+    }
+
+    #[test]
+    fn flags_missing_and_resized_files() {
+        let dir = tempdir().expect("tempdir");
+        fs::write(dir.path().join("same.txt"), "abc").expect("write");
+        fs::write(dir.path().join("grown.txt"), "abcdef").expect("write");
+        fs::write(dir.path().join("legacy.txt"), "abcdef").expect("write");
+        let conn = Connection::open_in_memory().expect("db");
+        init_db(&conn).expect("init");
+        let task = TaskRow {
+            task_id: "task-1".to_string(),
+            base_url: String::new(),
+            local_root: dir.path().to_string_lossy().to_string(),
+            remote_root_uri: "cloudreve://my/Work".to_string(),
+            device_id: "device".to_string(),
+            mode: "双向".to_string(),
+            settings_json: "{}".to_string(),
+            created_at_ms: 0,
+        };
+        create_task(&conn, &task).expect("task");
+        upsert_entry(&conn, &entry("same.txt", 3)).expect("entry");
+        upsert_entry(&conn, &entry("grown.txt", 3)).expect("entry");
+        upsert_entry(&conn, &entry("legacy.txt", -1)).expect("entry");
+        upsert_entry(&conn, &entry("gone.txt", 3)).expect("entry");
+        let mut quarantined = entry("held.txt", 3);
+        quarantined.state = ENTRY_QUARANTINED.to_string();
+        upsert_entry(&conn, &quarantined).expect("entry");
+
+        let report = check_entries(&conn, &task).expect("check");
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.missing, vec!["gone.txt".to_string()]);
+        assert_eq!(report.resized, vec!["grown.txt".to_string()]);
+        assert!(report.summary().contains("1 个已不存在、1 个大小变化"));
+    }
+}
@@ -1,6 +1,25 @@
+use crate::core::cloudreve::CloudreveClient;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
+use std::fs;
+use std::sync::Mutex;
 
 const SERVICE_NAME: &str = "cloudreve-sync";
+pub const ACCESS_TOKEN_ENV: &str = "CLOUDREVE_SYNC_ACCESS_TOKEN";
+pub const REFRESH_TOKEN_ENV: &str = "CLOUDREVE_SYNC_REFRESH_TOKEN";
+/// 设置后环境变量中的令牌只用于这个账户（`base_url|email` 或邮箱），否则用于所有账户。
+pub const TOKEN_ACCOUNT_ENV: &str = "CLOUDREVE_SYNC_ACCOUNT";
+/// 密钥文件为 TOML，每个账户一个表，表名为 `base_url|email` 或邮箱。
+pub const SECRETS_FILE_ENV: &str = "CLOUDREVE_SYNC_SECRETS_FILE";
+/// 加密任务的密码，供无法使用钥匙串的命令行环境使用，对所有加密任务生效。
+pub const ENCRYPTION_PASSPHRASE_ENV: &str = "CLOUDREVE_SYNC_E2E_PASSPHRASE";
+
+lazy_static! {
+    /// 注入的令牌以及外部令牌刷新后得到的新令牌，只保存在内存中。
+    static ref SESSION_TOKENS: Mutex<HashMap<String, StoredToken>> = Mutex::new(HashMap::new());
+}
 
 #[derive(Debug, Clone)]
 pub struct StoredToken {
@@ -8,18 +27,109 @@ pub struct StoredToken {
     pub refresh_token: String,
 }
 
+#[derive(Deserialize)]
+struct SecretEntry {
+    #[serde(default)]
+    access_token: String,
+    #[serde(default)]
+    refresh_token: String,
+}
+
+fn matches_account(reference: &str, account: &str) -> bool {
+    reference == account
+        || account
+            .rsplit_once('|')
+            .is_some_and(|(_, email)| email == reference)
+}
+
+fn env_tokens(account: &str, var: impl Fn(&str) -> Option<String>) -> Option<StoredToken> {
+    if var(TOKEN_ACCOUNT_ENV).is_some_and(|reference| !matches_account(&reference, account)) {
+        return None;
+    }
+    let access_token = var(ACCESS_TOKEN_ENV).unwrap_or_default();
+    let refresh_token = var(REFRESH_TOKEN_ENV).unwrap_or_default();
+    if access_token.is_empty() && refresh_token.is_empty() {
+        return None;
+    }
+    Some(StoredToken {
+        access_token,
+        refresh_token,
+    })
+}
+
+fn file_tokens(text: &str, account: &str) -> Result<Option<StoredToken>, Box<dyn Error>> {
+    let entries = toml::from_str::<BTreeMap<String, SecretEntry>>(text)
+        .map_err(|err| format!("密钥文件格式错误: {}", err))?;
+    // 完整的账户键优先于只写邮箱的条目。
+    let entry = entries.get(account).or_else(|| {
+        entries
+            .iter()
+            .find(|(reference, _)| matches_account(reference, account))
+            .map(|(_, entry)| entry)
+    });
+    Ok(entry.map(|entry| StoredToken {
+        access_token: entry.access_token.clone(),
+        refresh_token: entry.refresh_token.clone(),
+    }))
+}
+
+fn external_tokens(account: &str) -> Result<Option<StoredToken>, Box<dyn Error>> {
+    let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    if let Some(tokens) = env_tokens(account, var) {
+        return Ok(Some(tokens));
+    }
+    let Some(path) = var(SECRETS_FILE_ENV) else {
+        return Ok(None);
+    };
+    let text =
+        fs::read_to_string(&path).map_err(|err| format!("无法读取密钥文件 {}: {}", path, err))?;
+    file_tokens(&text, account)
+}
+
+fn session_tokens(account: &str) -> Option<StoredToken> {
+    SESSION_TOKENS
+        .lock()
+        .ok()
+        .and_then(|tokens| tokens.get(account).cloned())
+}
+
+/// 只在本次运行中使用的令牌，优先于环境变量、密钥文件与钥匙串。
+pub fn inject_tokens(account: &str, access_token: &str, refresh_token: &str) {
+    if let Ok(mut tokens) = SESSION_TOKENS.lock() {
+        tokens.insert(
+            account.to_string(),
+            StoredToken {
+                access_token: access_token.to_string(),
+                refresh_token: refresh_token.to_string(),
+            },
+        );
+    }
+}
+
+/// 账户的令牌来自外部时写入钥匙串也不会被读取，改为只保存在内存中。
 pub fn store_tokens(
     account: &str,
     access_token: &str,
     refresh_token: &str,
 ) -> Result<(), Box<dyn Error>> {
+    if session_tokens(account).is_some() || external_tokens(account)?.is_some() {
+        inject_tokens(account, access_token, refresh_token);
+        return Ok(());
+    }
     let entry = keyring::Entry::new(SERVICE_NAME, account)?;
     let payload = format!("{}\n{}", access_token, refresh_token);
     entry.set_password(&payload)?;
     Ok(())
 }
 
+/// 读取顺序：本次运行中注入或刷新的令牌、环境变量、密钥文件、系统钥匙串。
 pub fn load_tokens(account: &str) -> Result<StoredToken, Box<dyn Error>> {
+    if let Some(tokens) = session_tokens(account) {
+        return Ok(tokens);
+    }
+    if let Some(tokens) = external_tokens(account)? {
+        return Ok(tokens);
+    }
     let entry = keyring::Entry::new(SERVICE_NAME, account)?;
     let payload = entry.get_password()?;
     let mut parts = payload.splitn(2, '\n');
@@ -32,7 +142,151 @@ pub fn load_tokens(account: &str) -> Result<StoredToken, Box<dyn Error>> {
 }
 
 pub fn clear_tokens(account: &str) -> Result<(), Box<dyn Error>> {
+    if let Ok(mut tokens) = SESSION_TOKENS.lock() {
+        tokens.remove(account);
+    }
     let entry = keyring::Entry::new(SERVICE_NAME, account)?;
     entry.delete_password()?;
     Ok(())
 }
+
+fn task_key_entry(task_id: &str) -> Result<keyring::Entry, Box<dyn Error>> {
+    Ok(keyring::Entry::new(
+        SERVICE_NAME,
+        &format!("e2e|{}", task_id),
+    )?)
+}
+
+/// 加密任务的密钥（十六进制）保存在钥匙串中，不写入数据库。
+pub fn store_task_key(task_id: &str, key: &[u8; 32]) -> Result<(), Box<dyn Error>> {
+    let hex = key
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    task_key_entry(task_id)?.set_password(&hex)?;
+    Ok(())
+}
+
+pub fn load_task_key(task_id: &str) -> Result<[u8; 32], Box<dyn Error>> {
+    let hex = task_key_entry(task_id)?.get_password()?;
+    let mut key = [0u8; 32];
+    if hex.len() != key.len() * 2 {
+        return Err("钥匙串中的加密密钥无效".into());
+    }
+    for (index, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16)
+            .map_err(|_| "钥匙串中的加密密钥无效")?;
+    }
+    Ok(key)
+}
+
+pub fn clear_task_key(task_id: &str) -> Result<(), Box<dyn Error>> {
+    task_key_entry(task_id)?.delete_password()?;
+    Ok(())
+}
+
+fn proxy_entry() -> Result<keyring::Entry, Box<dyn Error>> {
+    Ok(keyring::Entry::new(SERVICE_NAME, "proxy")?)
+}
+
+/// 手动代理的密码保存在钥匙串中，不写入设置文件。
+pub fn store_proxy_password(password: &str) -> Result<(), Box<dyn Error>> {
+    proxy_entry()?.set_password(password)?;
+    Ok(())
+}
+
+pub fn load_proxy_password() -> Result<String, Box<dyn Error>> {
+    Ok(proxy_entry()?.get_password()?)
+}
+
+pub fn clear_proxy_password() -> Result<(), Box<dyn Error>> {
+    match proxy_entry()?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn share_entry(task_id: &str) -> Result<keyring::Entry, Box<dyn Error>> {
+    Ok(keyring::Entry::new(
+        SERVICE_NAME,
+        &format!("share|{}", task_id),
+    )?)
+}
+
+/// 分享的访问密码保存在钥匙串中，任务的远端地址只记录分享 ID。
+pub fn store_share_password(task_id: &str, password: &str) -> Result<(), Box<dyn Error>> {
+    share_entry(task_id)?.set_password(password)?;
+    Ok(())
+}
+
+pub fn load_share_password(task_id: &str) -> Result<Option<String>, Box<dyn Error>> {
+    match share_entry(task_id)?.get_password() {
+        Ok(password) => Ok(Some(password)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn clear_share_password(task_id: &str) -> Result<(), Box<dyn Error>> {
+    match share_entry(task_id)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// 访问远端使用的地址：任务的分享地址填入钥匙串中的密码。读不到密码时按无密码访问，
+/// 需要密码的分享会由服务端拒绝并报告错误。
+pub fn with_saved_share_password(task_id: &str, uri: &str) -> String {
+    if !CloudreveClient::is_share_uri(uri) || CloudreveClient::share_password(uri).is_some() {
+        return uri.to_string();
+    }
+    match load_share_password(task_id) {
+        Ok(Some(password)) => CloudreveClient::with_share_password(uri, &password),
+        _ => uri.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: &str = "https://cloud.example.com|ops@example.com";
+
+    #[test]
+    fn env_tokens_respect_account_filter() {
+        let vars = HashMap::from([
+            (ACCESS_TOKEN_ENV, "access"),
+            (TOKEN_ACCOUNT_ENV, "ops@example.com"),
+        ]);
+        let var = |name: &str| vars.get(name).map(|value| value.to_string());
+        let tokens = env_tokens(ACCOUNT, var).expect("tokens");
+        assert_eq!(tokens.access_token, "access");
+        assert!(tokens.refresh_token.is_empty());
+        assert!(env_tokens("https://other.example.com|dev@example.com", var).is_none());
+        assert!(env_tokens(ACCOUNT, |_| None).is_none());
+    }
+
+    #[test]
+    fn secrets_file_prefers_full_account_key() {
+        let text = r#"
+["ops@example.com"]
+refresh_token = "by-email"
+
+["https://cloud.example.com|ops@example.com"]
+access_token = "a"
+refresh_token = "by-key"
+"#;
+        let tokens = file_tokens(text, ACCOUNT).expect("parse").expect("found");
+        assert_eq!(tokens.refresh_token, "by-key");
+        let other = file_tokens(text, "https://mirror.example.com|ops@example.com")
+            .expect("parse")
+            .expect("found");
+        assert_eq!(other.refresh_token, "by-email");
+        assert!(
+            file_tokens(text, "https://cloud.example.com|dev@example.com")
+                .expect("parse")
+                .is_none()
+        );
+        assert!(file_tokens("not toml [", ACCOUNT).is_err());
+    }
+}
@@ -1,8 +1,15 @@
+use crate::core::cloudreve::CloudreveClient;
+use crate::core::migrations::migrate;
 use chrono::Utc;
 use rusqlite::types::Value;
-use rusqlite::{params, params_from_iter, Connection, Result};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result};
 use serde::Serialize;
 
+pub(crate) const ENTRY_KIND_FILE: &str = "file";
+const ENTRY_KIND_DIR: &str = "dir";
+/// 每个任务保留的运行记录数量，更早运行时的文件列表无法再还原。
+const RUN_HISTORY_LIMIT: i64 = 200;
+
 #[derive(Debug, Clone)]
 pub struct TaskRow {
     pub task_id: String,
@@ -31,6 +38,8 @@ pub struct EntryRow {
     pub cloud_uri: String,
     pub last_local_mtime_ms: i64,
     pub last_local_sha256: String,
+    /// 上次同步时本地文件的大小，-1 表示未知（旧版本写入的记录与目录记录）。
+    pub last_local_size: i64,
     pub last_remote_mtime_ms: i64,
     pub last_remote_sha256: String,
     pub last_sync_ts_ms: i64,
@@ -55,6 +64,68 @@ pub struct ConflictRow {
     pub reason: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct ScanScheduleRow {
+    pub task_id: String,
+    pub last_quick_scan_ms: Option<i64>,
+    pub last_deep_scan_ms: Option<i64>,
+}
+
+/// 自适应扫描记录的目录变化频率。
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirActivityRow {
+    pub relpath: String,
+    /// 按轮衰减的变化分数，越高表示最近越常变化。
+    pub score: f64,
+    /// 上次扫描后跳过的快速扫描轮数。
+    pub skipped_passes: i64,
+}
+
+/// 最近一次同步尝试与最近一次成功同步的时间，与日志无关。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskRunRow {
+    pub task_id: String,
+    pub last_attempt_ms: Option<i64>,
+    pub last_success_ms: Option<i64>,
+}
+
+/// 运行记录表中的一次同步尝试，按结束时间还原当时的文件列表。
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncRunRow {
+    pub run_id: i64,
+    pub task_id: String,
+    pub finished_at_ms: i64,
+    pub success: bool,
+}
+
+/// 某个文件在某一时刻的内容；`size` 为 -1 表示未知。
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryVersionRow {
+    pub relpath: String,
+    pub size: i64,
+    pub sha256: String,
+    pub cloud_file_id: String,
+    pub recorded_at_ms: i64,
+}
+
+/// 本地接口的访问令牌；只保存令牌的哈希，明文只在创建时返回一次。
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiTokenRow {
+    pub token_id: String,
+    pub name: String,
+    pub scope: String,
+    #[serde(skip_serializing)]
+    pub token_sha256: String,
+    pub created_at_ms: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountDefaultsRow {
+    pub account_key: String,
+    pub settings_json: String,
+    pub updated_at_ms: i64,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct LogRow {
     pub task_id: String,
@@ -62,71 +133,99 @@ pub struct LogRow {
     pub event: String,
     pub detail: String,
     pub created_at_ms: i64,
+    pub batch_id: Option<String>,
 }
 
-pub fn init_db(conn: &Connection) -> Result<()> {
-    conn.execute_batch(
-        r#"
-        CREATE TABLE IF NOT EXISTS tasks (
-            task_id TEXT PRIMARY KEY,
-            base_url TEXT NOT NULL,
-            local_root TEXT NOT NULL,
-            remote_root_uri TEXT NOT NULL,
-            device_id TEXT NOT NULL,
-            mode TEXT NOT NULL,
-            settings_json TEXT NOT NULL,
-            created_at_ms INTEGER NOT NULL
-        );
+/// 一批同类传输的汇总：同一次同步中、同一顶层目录下的上传或下载。
+#[derive(Debug, Clone, Serialize)]
+pub struct LogBatchRow {
+    pub batch_id: String,
+    pub task_id: String,
+    pub event: String,
+    pub dir: String,
+    pub file_count: i64,
+    pub total_bytes: i64,
+    pub updated_at_ms: i64,
+}
 
-        CREATE TABLE IF NOT EXISTS accounts (
-            account_key TEXT PRIMARY KEY,
-            base_url TEXT NOT NULL,
-            email TEXT NOT NULL,
-            created_at_ms INTEGER NOT NULL
-        );
+/// 远端目录的上次列表结果，目录修改时间未变时可直接复用。
+#[derive(Debug, Clone)]
+pub struct RemoteDirRow {
+    pub task_id: String,
+    pub relpath: String,
+    pub updated_at: String,
+    pub children_json: String,
+    pub listed_at_ms: i64,
+}
 
-        CREATE TABLE IF NOT EXISTS entries (
-            task_id TEXT NOT NULL,
-            local_relpath TEXT NOT NULL,
-            cloud_file_id TEXT NOT NULL,
-            cloud_uri TEXT NOT NULL,
-            last_local_mtime_ms INTEGER NOT NULL,
-            last_local_sha256 TEXT NOT NULL,
-            last_remote_mtime_ms INTEGER NOT NULL,
-            last_remote_sha256 TEXT NOT NULL,
-            last_sync_ts_ms INTEGER NOT NULL,
-            state TEXT NOT NULL,
-            PRIMARY KEY (task_id, local_relpath)
-        );
+/// 冲突处理后归档的落选版本，到期后本地与远端一并删除。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictArchiveRow {
+    pub task_id: String,
+    pub archive_relpath: String,
+    pub original_relpath: String,
+    pub archived_at_ms: i64,
+    pub expires_at_ms: i64,
+}
 
-        CREATE TABLE IF NOT EXISTS tombstones (
-            task_id TEXT NOT NULL,
-            cloud_file_id TEXT NOT NULL,
-            local_relpath TEXT NOT NULL,
-            deleted_at_ms INTEGER NOT NULL,
-            origin TEXT NOT NULL,
-            PRIMARY KEY (task_id, local_relpath)
-        );
+/// 未完成的下载，远端文件未变化时可从 `downloaded_bytes` 处续传。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialDownloadRow {
+    pub task_id: String,
+    pub relpath: String,
+    pub file_id: String,
+    pub sha256: String,
+    pub total_bytes: i64,
+    pub downloaded_bytes: i64,
+    pub temp_path: String,
+    pub updated_at_ms: i64,
+    /// 开始下载时响应的 ETag；为空时无法确认远端未变化，不续传。
+    pub etag: String,
+}
 
-        CREATE TABLE IF NOT EXISTS conflicts (
-            task_id TEXT NOT NULL,
-            original_relpath TEXT NOT NULL,
-            conflict_relpath TEXT NOT NULL,
-            created_at_ms INTEGER NOT NULL,
-            reason TEXT NOT NULL
-        );
+/// 被其他程序占用而暂缓同步的本地文件，到 `next_retry_ms` 后再试。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedFileRow {
+    pub task_id: String,
+    pub relpath: String,
+    pub attempts: i64,
+    pub next_retry_ms: i64,
+    pub reason: String,
+}
 
-        CREATE TABLE IF NOT EXISTS logs (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            task_id TEXT NOT NULL,
-            level TEXT NOT NULL,
-            event TEXT NOT NULL,
-            detail TEXT NOT NULL,
-            created_at_ms INTEGER NOT NULL
-        );
-        "#,
-    )?;
-    Ok(())
+/// 账户的登录状态；没有记录的账户视为正常。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountStatusRow {
+    pub account_key: String,
+    pub last_auth_ms: Option<i64>,
+    pub needs_login: bool,
+    pub reason: String,
+    pub updated_at_ms: i64,
+}
+
+/// 传输队列中的一项；同一轮同步里按 `priority` 从高到低、再按 `position` 取出。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransferRow {
+    pub task_id: String,
+    pub relpath: String,
+    pub kind: String,
+    pub size: i64,
+    pub status: String,
+    pub priority: i64,
+    pub position: i64,
+    pub error: Option<String>,
+    pub updated_at_ms: i64,
+}
+
+pub const TRANSFER_QUEUED: &str = "queued";
+pub const TRANSFER_ACTIVE: &str = "active";
+pub const TRANSFER_FAILED: &str = "failed";
+pub const TRANSFER_DONE: &str = "done";
+pub const TRANSFER_CANCELLED: &str = "cancelled";
+
+/// 打开数据库后调用，把结构升级到当前版本，见 `migrations`。
+pub fn init_db(conn: &Connection) -> Result<()> {
+    migrate(conn).map(|_| ())
 }
 
 pub fn upsert_account(conn: &Connection, account: &AccountRow) -> Result<()> {
@@ -163,6 +262,86 @@ pub fn list_accounts(conn: &Connection) -> Result<Vec<AccountRow>> {
 
 pub fn delete_all_accounts(conn: &Connection) -> Result<()> {
     conn.execute("DELETE FROM accounts", [])?;
+    conn.execute("DELETE FROM account_defaults", [])?;
+    conn.execute("DELETE FROM account_status", [])?;
+    Ok(())
+}
+
+pub fn list_account_statuses(conn: &Connection) -> Result<Vec<AccountStatusRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT account_key, last_auth_ms, needs_login, reason, updated_at_ms FROM account_status",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(AccountStatusRow {
+            account_key: row.get(0)?,
+            last_auth_ms: row.get(1)?,
+            needs_login: row.get::<_, i64>(2)? != 0,
+            reason: row.get(3)?,
+            updated_at_ms: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn get_account_status(
+    conn: &Connection,
+    account_key: &str,
+) -> Result<Option<AccountStatusRow>> {
+    Ok(list_account_statuses(conn)?
+        .into_iter()
+        .find(|status| status.account_key == account_key))
+}
+
+/// 登录或使用令牌成功后记录鉴权时间，并清除需要重新登录的标记。
+pub fn record_account_auth(conn: &Connection, account_key: &str, at_ms: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO account_status (account_key, last_auth_ms, needs_login, reason, updated_at_ms) VALUES (?1, ?2, 0, '', ?2) ON CONFLICT(account_key) DO UPDATE SET last_auth_ms=excluded.last_auth_ms, needs_login=0, reason='', updated_at_ms=excluded.updated_at_ms",
+        params![account_key, at_ms],
+    )?;
+    Ok(())
+}
+
+/// 标记账户需要重新登录；返回此前是否正常，同一次失效只提醒一次。
+pub fn mark_account_needs_login(
+    conn: &Connection,
+    account_key: &str,
+    reason: &str,
+    at_ms: i64,
+) -> Result<bool> {
+    let was_ok = !get_account_status(conn, account_key)?.is_some_and(|status| status.needs_login);
+    conn.execute(
+        "INSERT INTO account_status (account_key, last_auth_ms, needs_login, reason, updated_at_ms) VALUES (?1, NULL, 1, ?2, ?3) ON CONFLICT(account_key) DO UPDATE SET needs_login=1, reason=excluded.reason, updated_at_ms=excluded.updated_at_ms",
+        params![account_key, reason, at_ms],
+    )?;
+    Ok(was_ok)
+}
+
+pub fn get_account_defaults(
+    conn: &Connection,
+    account_key: &str,
+) -> Result<Option<AccountDefaultsRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT account_key, settings_json, updated_at_ms FROM account_defaults WHERE account_key = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![account_key], |row| {
+        Ok(AccountDefaultsRow {
+            account_key: row.get(0)?,
+            settings_json: row.get(1)?,
+            updated_at_ms: row.get(2)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
+pub fn upsert_account_defaults(conn: &Connection, defaults: &AccountDefaultsRow) -> Result<()> {
+    conn.execute(
+        "INSERT INTO account_defaults (account_key, settings_json, updated_at_ms) VALUES (?1, ?2, ?3) ON CONFLICT(account_key) DO UPDATE SET settings_json=excluded.settings_json, updated_at_ms=excluded.updated_at_ms",
+        params![
+            defaults.account_key,
+            defaults.settings_json,
+            defaults.updated_at_ms
+        ],
+    )?;
     Ok(())
 }
 
@@ -183,6 +362,37 @@ pub fn create_task(conn: &Connection, task: &TaskRow) -> Result<()> {
     Ok(())
 }
 
+pub fn update_task_settings(conn: &Connection, task_id: &str, settings_json: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE tasks SET settings_json = ?1 WHERE task_id = ?2",
+        params![settings_json, task_id],
+    )?;
+    Ok(())
+}
+
+/// 更换任务的远端根地址，条目中以旧地址开头的远端地址一并改写。
+pub fn replace_task_remote_root(
+    conn: &Connection,
+    task_id: &str,
+    old_root: &str,
+    new_root: &str,
+) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE tasks SET remote_root_uri = ?1 WHERE task_id = ?2",
+        params![new_root, task_id],
+    )?;
+    tx.execute(
+        "UPDATE entries SET cloud_uri = ?1 || substr(cloud_uri, length(?2) + 1) WHERE task_id = ?3 AND substr(cloud_uri, 1, length(?2)) = ?2",
+        params![
+            new_root.trim_end_matches('/'),
+            old_root.trim_end_matches('/'),
+            task_id
+        ],
+    )?;
+    tx.commit()
+}
+
 pub fn list_tasks(conn: &Connection) -> Result<Vec<TaskRow>> {
     let mut stmt = conn.prepare(
         "SELECT task_id, base_url, local_root, remote_root_uri, device_id, mode, settings_json, created_at_ms FROM tasks ORDER BY created_at_ms DESC",
@@ -214,47 +424,308 @@ pub fn delete_task(conn: &Connection, task_id: &str) -> Result<()> {
     )?;
     conn.execute("DELETE FROM conflicts WHERE task_id = ?1", params![task_id])?;
     conn.execute("DELETE FROM logs WHERE task_id = ?1", params![task_id])?;
+    conn.execute(
+        "DELETE FROM log_batches WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute(
+        "DELETE FROM remote_dirs WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute(
+        "DELETE FROM partial_downloads WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute(
+        "DELETE FROM conflict_archive WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute(
+        "DELETE FROM selective_excludes WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute(
+        "DELETE FROM scan_schedule WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute("DELETE FROM task_runs WHERE task_id = ?1", params![task_id])?;
+    conn.execute("DELETE FROM sync_runs WHERE task_id = ?1", params![task_id])?;
+    conn.execute(
+        "DELETE FROM entry_versions WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute(
+        "DELETE FROM sync_queue WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute(
+        "DELETE FROM transfer_queue WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute(
+        "DELETE FROM dir_activity WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute("DELETE FROM task_due WHERE task_id = ?1", params![task_id])?;
+    conn.execute(
+        "DELETE FROM name_mappings WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    conn.execute(
+        "DELETE FROM locked_files WHERE task_id = ?1",
+        params![task_id],
+    )?;
     conn.execute("DELETE FROM tasks WHERE task_id = ?1", params![task_id])?;
     Ok(())
 }
 
 pub fn upsert_entry(conn: &Connection, entry: &EntryRow) -> Result<()> {
+    upsert_entry_kind(conn, entry, ENTRY_KIND_FILE)
+}
+
+/// 目录记录与文件记录同表，只保存目录的修改时间，内容相关的字段留空。
+pub fn upsert_dir_entry(conn: &Connection, entry: &EntryRow) -> Result<()> {
+    upsert_entry_kind(conn, entry, ENTRY_KIND_DIR)
+}
+
+fn upsert_entry_kind(conn: &Connection, entry: &EntryRow, kind: &str) -> Result<()> {
+    if kind == ENTRY_KIND_FILE {
+        record_entry_version(conn, entry)?;
+    }
     conn.execute(
-        "INSERT INTO entries (task_id, local_relpath, cloud_file_id, cloud_uri, last_local_mtime_ms, last_local_sha256, last_remote_mtime_ms, last_remote_sha256, last_sync_ts_ms, state) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10) ON CONFLICT(task_id, local_relpath) DO UPDATE SET cloud_file_id=excluded.cloud_file_id, cloud_uri=excluded.cloud_uri, last_local_mtime_ms=excluded.last_local_mtime_ms, last_local_sha256=excluded.last_local_sha256, last_remote_mtime_ms=excluded.last_remote_mtime_ms, last_remote_sha256=excluded.last_remote_sha256, last_sync_ts_ms=excluded.last_sync_ts_ms, state=excluded.state",
+        "INSERT INTO entries (task_id, local_relpath, cloud_file_id, cloud_uri, last_local_mtime_ms, last_local_sha256, last_remote_mtime_ms, last_remote_sha256, last_sync_ts_ms, state, kind, last_local_size) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12) ON CONFLICT(task_id, local_relpath) DO UPDATE SET cloud_file_id=excluded.cloud_file_id, cloud_uri=excluded.cloud_uri, last_local_mtime_ms=excluded.last_local_mtime_ms, last_local_sha256=excluded.last_local_sha256, last_local_size=excluded.last_local_size, last_remote_mtime_ms=excluded.last_remote_mtime_ms, last_remote_sha256=excluded.last_remote_sha256, last_sync_ts_ms=excluded.last_sync_ts_ms, state=excluded.state, kind=excluded.kind, cloud_version_id=CASE WHEN entries.last_remote_sha256 = excluded.last_remote_sha256 THEN entries.cloud_version_id ELSE '' END",
         params![
             entry.task_id,
             entry.local_relpath,
             entry.cloud_file_id,
-            entry.cloud_uri,
+            CloudreveClient::without_share_password(&entry.cloud_uri),
             entry.last_local_mtime_ms,
             entry.last_local_sha256,
             entry.last_remote_mtime_ms,
             entry.last_remote_sha256,
             entry.last_sync_ts_ms,
-            entry.state
+            entry.state,
+            kind,
+            entry.last_local_size
         ],
     )?;
     Ok(())
 }
 
-pub fn list_entries_by_task(conn: &Connection, task_id: &str) -> Result<Vec<EntryRow>> {
+/// 记录条目当前对应的远端版本；远端内容再次变化时 `upsert_entry` 会清空。
+/// 只更新条目的同步时间，用于不涉及内容的变更（如权限位）。
+pub fn set_entry_synced_at(
+    conn: &Connection,
+    task_id: &str,
+    relpath: &str,
+    synced_at_ms: i64,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE entries SET last_sync_ts_ms = ?3 WHERE task_id = ?1 AND local_relpath = ?2",
+        params![task_id, relpath, synced_at_ms],
+    )?;
+    Ok(())
+}
+
+pub fn set_entry_version_id(
+    conn: &Connection,
+    task_id: &str,
+    relpath: &str,
+    version_id: &str,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE entries SET cloud_version_id = ?3 WHERE task_id = ?1 AND local_relpath = ?2",
+        params![task_id, relpath, version_id],
+    )?;
+    Ok(())
+}
+
+/// 条目记录的远端版本，未记录时为空字符串。
+pub fn get_entry_version_id(conn: &Connection, task_id: &str, relpath: &str) -> Result<String> {
+    let mut stmt = conn.prepare(
+        "SELECT cloud_version_id FROM entries WHERE task_id = ?1 AND local_relpath = ?2",
+    )?;
+    let mut rows = stmt.query_map(params![task_id, relpath], |row| row.get(0))?;
+    Ok(rows.next().transpose()?.unwrap_or_default())
+}
+
+/// 删除 `relpath` 本身及其下所有条目，用于路径在文件与目录之间切换时。
+pub fn delete_entries_under(conn: &Connection, task_id: &str, relpath: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO entry_versions (task_id, relpath, size, sha256, cloud_file_id, recorded_at_ms, deleted) SELECT task_id, local_relpath, 0, '', '', ?3, 1 FROM entries WHERE task_id = ?1 AND kind = ?4 AND (local_relpath = ?2 OR substr(local_relpath, 1, length(?2) + 1) = ?2 || '/')",
+        params![task_id, relpath, now_ms(), ENTRY_KIND_FILE],
+    )?;
+    conn.execute(
+        "DELETE FROM entries WHERE task_id = ?1 AND (local_relpath = ?2 OR substr(local_relpath, 1, length(?2) + 1) = ?2 || '/')",
+        params![task_id, relpath],
+    )?;
+    Ok(())
+}
+
+pub fn list_remote_dirs(conn: &Connection, task_id: &str) -> Result<Vec<RemoteDirRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, relpath, updated_at, children_json, listed_at_ms FROM remote_dirs WHERE task_id = ?1",
+    )?;
+    let rows = stmt.query_map(params![task_id], |row| {
+        Ok(RemoteDirRow {
+            task_id: row.get(0)?,
+            relpath: row.get(1)?,
+            updated_at: row.get(2)?,
+            children_json: row.get(3)?,
+            listed_at_ms: row.get(4)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+pub fn upsert_remote_dir(conn: &Connection, dir: &RemoteDirRow) -> Result<()> {
+    conn.execute(
+        "INSERT INTO remote_dirs (task_id, relpath, updated_at, children_json, listed_at_ms) VALUES (?1, ?2, ?3, ?4, ?5) ON CONFLICT(task_id, relpath) DO UPDATE SET updated_at=excluded.updated_at, children_json=excluded.children_json, listed_at_ms=excluded.listed_at_ms",
+        params![
+            dir.task_id,
+            dir.relpath,
+            dir.updated_at,
+            dir.children_json,
+            dir.listed_at_ms
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn delete_remote_dir(conn: &Connection, task_id: &str, relpath: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM remote_dirs WHERE task_id = ?1 AND relpath = ?2",
+        params![task_id, relpath],
+    )?;
+    Ok(())
+}
+
+pub fn get_partial_download(
+    conn: &Connection,
+    task_id: &str,
+    relpath: &str,
+) -> Result<Option<PartialDownloadRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, relpath, file_id, sha256, total_bytes, downloaded_bytes, temp_path, updated_at_ms, etag FROM partial_downloads WHERE task_id = ?1 AND relpath = ?2",
+    )?;
+    let mut rows = stmt.query_map(params![task_id, relpath], |row| {
+        Ok(PartialDownloadRow {
+            task_id: row.get(0)?,
+            relpath: row.get(1)?,
+            file_id: row.get(2)?,
+            sha256: row.get(3)?,
+            total_bytes: row.get(4)?,
+            downloaded_bytes: row.get(5)?,
+            temp_path: row.get(6)?,
+            updated_at_ms: row.get(7)?,
+            etag: row.get(8)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
+pub fn upsert_partial_download(conn: &Connection, download: &PartialDownloadRow) -> Result<()> {
+    conn.execute(
+        "INSERT INTO partial_downloads (task_id, relpath, file_id, sha256, total_bytes, downloaded_bytes, temp_path, updated_at_ms, etag) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9) ON CONFLICT(task_id, relpath) DO UPDATE SET file_id=excluded.file_id, sha256=excluded.sha256, total_bytes=excluded.total_bytes, downloaded_bytes=excluded.downloaded_bytes, temp_path=excluded.temp_path, updated_at_ms=excluded.updated_at_ms, etag=excluded.etag",
+        params![
+            download.task_id,
+            download.relpath,
+            download.file_id,
+            download.sha256,
+            download.total_bytes,
+            download.downloaded_bytes,
+            download.temp_path,
+            download.updated_at_ms,
+            download.etag
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn delete_partial_download(conn: &Connection, task_id: &str, relpath: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM partial_downloads WHERE task_id = ?1 AND relpath = ?2",
+        params![task_id, relpath],
+    )?;
+    Ok(())
+}
+
+pub fn list_locked_files(conn: &Connection, task_id: &str) -> Result<Vec<LockedFileRow>> {
     let mut stmt = conn.prepare(
-        "SELECT task_id, local_relpath, cloud_file_id, cloud_uri, last_local_mtime_ms, last_local_sha256, last_remote_mtime_ms, last_remote_sha256, last_sync_ts_ms, state FROM entries WHERE task_id = ?1",
+        "SELECT task_id, relpath, attempts, next_retry_ms, reason FROM locked_files WHERE task_id = ?1",
     )?;
     let rows = stmt.query_map(params![task_id], |row| {
-        Ok(EntryRow {
+        Ok(LockedFileRow {
             task_id: row.get(0)?,
-            local_relpath: row.get(1)?,
-            cloud_file_id: row.get(2)?,
-            cloud_uri: row.get(3)?,
-            last_local_mtime_ms: row.get(4)?,
-            last_local_sha256: row.get(5)?,
-            last_remote_mtime_ms: row.get(6)?,
-            last_remote_sha256: row.get(7)?,
-            last_sync_ts_ms: row.get(8)?,
-            state: row.get(9)?,
+            relpath: row.get(1)?,
+            attempts: row.get(2)?,
+            next_retry_ms: row.get(3)?,
+            reason: row.get(4)?,
         })
     })?;
+    rows.collect()
+}
+
+pub fn upsert_locked_file(conn: &Connection, locked: &LockedFileRow) -> Result<()> {
+    conn.execute(
+        "INSERT INTO locked_files (task_id, relpath, attempts, next_retry_ms, reason) VALUES (?1, ?2, ?3, ?4, ?5) ON CONFLICT(task_id, relpath) DO UPDATE SET attempts=excluded.attempts, next_retry_ms=excluded.next_retry_ms, reason=excluded.reason",
+        params![
+            locked.task_id,
+            locked.relpath,
+            locked.attempts,
+            locked.next_retry_ms,
+            locked.reason
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn delete_locked_file(conn: &Connection, task_id: &str, relpath: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM locked_files WHERE task_id = ?1 AND relpath = ?2",
+        params![task_id, relpath],
+    )?;
+    Ok(())
+}
+
+pub fn list_entries_by_task(conn: &Connection, task_id: &str) -> Result<Vec<EntryRow>> {
+    list_entries_of_kind(conn, task_id, ENTRY_KIND_FILE)
+}
+
+pub fn list_dir_entries(conn: &Connection, task_id: &str) -> Result<Vec<EntryRow>> {
+    list_entries_of_kind(conn, task_id, ENTRY_KIND_DIR)
+}
+
+pub fn delete_dir_entry(conn: &Connection, task_id: &str, relpath: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM entries WHERE task_id = ?1 AND local_relpath = ?2 AND kind = ?3",
+        params![task_id, relpath, ENTRY_KIND_DIR],
+    )?;
+    Ok(())
+}
+
+/// 远端内容摘要为 `sha256` 的已同步文件，上传前用来查找可以在服务端复制的相同内容。
+pub fn list_entries_by_remote_sha256(
+    conn: &Connection,
+    task_id: &str,
+    sha256: &str,
+) -> Result<Vec<EntryRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, local_relpath, cloud_file_id, cloud_uri, last_local_mtime_ms, last_local_sha256, last_remote_mtime_ms, last_remote_sha256, last_sync_ts_ms, state, last_local_size FROM entries WHERE task_id = ?1 AND kind = ?2 AND last_remote_sha256 = ?3 AND state = 'ok'",
+    )?;
+    let rows = stmt.query_map(params![task_id, ENTRY_KIND_FILE, sha256], entry_from_row)?;
+    rows.collect()
+}
+
+fn list_entries_of_kind(conn: &Connection, task_id: &str, kind: &str) -> Result<Vec<EntryRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, local_relpath, cloud_file_id, cloud_uri, last_local_mtime_ms, last_local_sha256, last_remote_mtime_ms, last_remote_sha256, last_sync_ts_ms, state, last_local_size FROM entries WHERE task_id = ?1 AND kind = ?2",
+    )?;
+    let rows = stmt.query_map(params![task_id, kind], entry_from_row)?;
     let mut out = Vec::new();
     for row in rows {
         out.push(row?);
@@ -262,6 +733,22 @@ pub fn list_entries_by_task(conn: &Connection, task_id: &str) -> Result<Vec<Entr
     Ok(out)
 }
 
+fn entry_from_row(row: &rusqlite::Row) -> Result<EntryRow> {
+    Ok(EntryRow {
+        task_id: row.get(0)?,
+        local_relpath: row.get(1)?,
+        cloud_file_id: row.get(2)?,
+        cloud_uri: row.get(3)?,
+        last_local_mtime_ms: row.get(4)?,
+        last_local_sha256: row.get(5)?,
+        last_local_size: row.get(10)?,
+        last_remote_mtime_ms: row.get(6)?,
+        last_remote_sha256: row.get(7)?,
+        last_sync_ts_ms: row.get(8)?,
+        state: row.get(9)?,
+    })
+}
+
 pub fn insert_tombstone(conn: &Connection, tombstone: &TombstoneRow) -> Result<()> {
     conn.execute(
         "INSERT INTO tombstones (task_id, cloud_file_id, local_relpath, deleted_at_ms, origin) VALUES (?1, ?2, ?3, ?4, ?5) ON CONFLICT(task_id, local_relpath) DO UPDATE SET cloud_file_id=excluded.cloud_file_id, deleted_at_ms=excluded.deleted_at_ms, origin=excluded.origin",
@@ -310,14 +797,311 @@ pub fn insert_conflict(conn: &Connection, conflict: &ConflictRow) -> Result<()>
     Ok(())
 }
 
-pub fn delete_conflict(conn: &Connection, task_id: &str, conflict_relpath: &str) -> Result<()> {
-    conn.execute(
-        "DELETE FROM conflicts WHERE task_id = ?1 AND conflict_relpath = ?2",
-        params![task_id, conflict_relpath],
+pub fn count_conflicts(conn: &Connection, task_id: &str, original_relpath: &str) -> Result<u32> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM conflicts WHERE task_id = ?1 AND original_relpath = ?2",
+        params![task_id, original_relpath],
+        |row| row.get(0),
+    )
+}
+
+/// 冲突表当前最大的 rowid，与 [`count_conflicts_after`] 配合统计一轮同步新记录的冲突。
+pub fn last_conflict_rowid(conn: &Connection) -> Result<i64> {
+    conn.query_row("SELECT COALESCE(MAX(rowid), 0) FROM conflicts", [], |row| {
+        row.get(0)
+    })
+}
+
+pub fn count_conflicts_after(conn: &Connection, task_id: &str, rowid: i64) -> Result<u32> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM conflicts WHERE task_id = ?1 AND rowid > ?2",
+        params![task_id, rowid],
+        |row| row.get(0),
+    )
+}
+
+pub fn list_name_mappings(conn: &Connection, task_id: &str) -> Result<Vec<(String, String)>> {
+    let mut stmt =
+        conn.prepare("SELECT remote_relpath, local_relpath FROM name_mappings WHERE task_id = ?1")?;
+    let rows = stmt.query_map(params![task_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+pub fn upsert_name_mapping(
+    conn: &Connection,
+    task_id: &str,
+    remote_relpath: &str,
+    local_relpath: &str,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO name_mappings (task_id, remote_relpath, local_relpath) VALUES (?1, ?2, ?3)
+         ON CONFLICT(task_id, remote_relpath) DO UPDATE SET local_relpath = excluded.local_relpath",
+        params![task_id, remote_relpath, local_relpath],
+    )?;
+    Ok(())
+}
+
+/// 删除远端路径及其下所有路径的名称映射。
+pub fn delete_name_mappings_under(
+    conn: &Connection,
+    task_id: &str,
+    remote_relpath: &str,
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM name_mappings WHERE task_id = ?1 AND (remote_relpath = ?2 OR substr(remote_relpath, 1, length(?2) + 1) = ?2 || '/')",
+        params![task_id, remote_relpath],
+    )?;
+    Ok(())
+}
+
+pub fn has_conflict(conn: &Connection, task_id: &str, conflict_relpath: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM conflicts WHERE task_id = ?1 AND conflict_relpath = ?2)",
+        params![task_id, conflict_relpath],
+        |row| row.get(0),
+    )
+}
+
+pub fn delete_conflict(conn: &Connection, task_id: &str, conflict_relpath: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM conflicts WHERE task_id = ?1 AND conflict_relpath = ?2",
+        params![task_id, conflict_relpath],
+    )?;
+    Ok(())
+}
+
+pub fn insert_conflict_archive(conn: &Connection, archive: &ConflictArchiveRow) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO conflict_archive (task_id, archive_relpath, original_relpath, archived_at_ms, expires_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            archive.task_id,
+            archive.archive_relpath,
+            archive.original_relpath,
+            archive.archived_at_ms,
+            archive.expires_at_ms
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn list_expired_conflict_archives(
+    conn: &Connection,
+    task_id: &str,
+    now_ms: i64,
+) -> Result<Vec<ConflictArchiveRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, archive_relpath, original_relpath, archived_at_ms, expires_at_ms FROM conflict_archive WHERE task_id = ?1 AND expires_at_ms <= ?2 ORDER BY expires_at_ms",
+    )?;
+    let rows = stmt.query_map(params![task_id, now_ms], |row| {
+        Ok(ConflictArchiveRow {
+            task_id: row.get(0)?,
+            archive_relpath: row.get(1)?,
+            original_relpath: row.get(2)?,
+            archived_at_ms: row.get(3)?,
+            expires_at_ms: row.get(4)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+pub fn delete_conflict_archive(
+    conn: &Connection,
+    task_id: &str,
+    archive_relpath: &str,
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM conflict_archive WHERE task_id = ?1 AND archive_relpath = ?2",
+        params![task_id, archive_relpath],
+    )?;
+    Ok(())
+}
+
+/// 选择性同步中不在本地保留的远端目录。
+pub fn list_selective_excludes(conn: &Connection, task_id: &str) -> Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT relpath FROM selective_excludes WHERE task_id = ?1 ORDER BY relpath")?;
+    let rows = stmt.query_map(params![task_id], |row| row.get(0))?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+pub fn set_selective_excludes(conn: &Connection, task_id: &str, relpaths: &[String]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "DELETE FROM selective_excludes WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    for relpath in relpaths {
+        tx.execute(
+            "INSERT OR IGNORE INTO selective_excludes (task_id, relpath) VALUES (?1, ?2)",
+            params![task_id, relpath],
+        )?;
+    }
+    tx.commit()
+}
+
+/// 暂停时尚未处理的路径，按原来的处理顺序返回。
+pub fn list_sync_queue(conn: &Connection, task_id: &str) -> Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT relpath FROM sync_queue WHERE task_id = ?1 ORDER BY position")?;
+    let rows = stmt.query_map(params![task_id], |row| row.get(0))?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+/// 用 `relpaths` 替换任务的待处理队列，传入空列表即清空。
+pub fn set_sync_queue(conn: &Connection, task_id: &str, relpaths: &[String]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "DELETE FROM sync_queue WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    for (position, relpath) in relpaths.iter().enumerate() {
+        tx.execute(
+            "INSERT OR IGNORE INTO sync_queue (task_id, relpath, position) VALUES (?1, ?2, ?3)",
+            params![task_id, relpath, position as i64],
+        )?;
+    }
+    tx.commit()
+}
+
+/// 新一轮同步开始时清空上一轮的队列；暂停时未处理的路径另存于 `sync_queue`。
+pub fn clear_transfer_queue(conn: &Connection, task_id: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM transfer_queue WHERE task_id = ?1",
+        params![task_id],
     )?;
     Ok(())
 }
 
+/// 加入或重新排队；已有的手动优先级保留。`relpath` 为空的项忽略。
+pub fn enqueue_transfers(conn: &Connection, items: &[TransferRow]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    for item in items.iter().filter(|item| !item.relpath.is_empty()) {
+        tx.execute(
+            "INSERT INTO transfer_queue (task_id, relpath, kind, size, status, priority, position, error, updated_at_ms) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, NULL, ?8) ON CONFLICT(task_id, relpath) DO UPDATE SET kind=excluded.kind, size=excluded.size, status=excluded.status, position=excluded.position, error=NULL, updated_at_ms=excluded.updated_at_ms",
+            params![
+                item.task_id,
+                item.relpath,
+                item.kind,
+                item.size,
+                TRANSFER_QUEUED,
+                item.priority,
+                item.position,
+                item.updated_at_ms
+            ],
+        )?;
+    }
+    tx.commit()
+}
+
+/// 取出下一个排队项并标记为进行中；队列为空时返回 None。查询与标记在同一事务中完成。
+pub fn claim_next_transfer(conn: &Connection, task_id: &str) -> Result<Option<String>> {
+    let tx = conn.unchecked_transaction()?;
+    let relpath: Option<String> = tx
+        .query_row(
+            "SELECT relpath FROM transfer_queue WHERE task_id = ?1 AND status = ?2 ORDER BY priority DESC, position LIMIT 1",
+            params![task_id, TRANSFER_QUEUED],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(relpath) = &relpath {
+        tx.execute(
+            "UPDATE transfer_queue SET status = ?3, updated_at_ms = ?4 WHERE task_id = ?1 AND relpath = ?2",
+            params![task_id, relpath, TRANSFER_ACTIVE, now_ms()],
+        )?;
+    }
+    tx.commit()?;
+    Ok(relpath)
+}
+
+/// 在一个事务中记录多项的结束状态，错误为 None 表示成功。
+pub fn finish_transfers(
+    conn: &Connection,
+    task_id: &str,
+    results: &[(String, Option<String>)],
+) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    let updated_at_ms = now_ms();
+    for (relpath, error) in results {
+        let status = if error.is_some() {
+            TRANSFER_FAILED
+        } else {
+            TRANSFER_DONE
+        };
+        tx.execute(
+            "UPDATE transfer_queue SET status = ?3, error = ?4, updated_at_ms = ?5 WHERE task_id = ?1 AND relpath = ?2 AND status = ?6",
+            params![task_id, relpath, status, error, updated_at_ms, TRANSFER_ACTIVE],
+        )?;
+    }
+    tx.commit()
+}
+
+/// 暂停后仍在排队的路径，按取出顺序返回。
+pub fn list_queued_transfers(conn: &Connection, task_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT relpath FROM transfer_queue WHERE task_id = ?1 AND status = ?2 ORDER BY priority DESC, position",
+    )?;
+    let rows = stmt.query_map(params![task_id, TRANSFER_QUEUED], |row| row.get(0))?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+pub fn list_transfers(conn: &Connection, task_id: Option<&str>) -> Result<Vec<TransferRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, relpath, kind, size, status, priority, position, error, updated_at_ms FROM transfer_queue WHERE ?1 IS NULL OR task_id = ?1 ORDER BY task_id, CASE status WHEN 'active' THEN 0 WHEN 'queued' THEN 1 WHEN 'failed' THEN 2 ELSE 3 END, priority DESC, position",
+    )?;
+    let rows = stmt.query_map(params![task_id], |row| {
+        Ok(TransferRow {
+            task_id: row.get(0)?,
+            relpath: row.get(1)?,
+            kind: row.get(2)?,
+            size: row.get(3)?,
+            status: row.get(4)?,
+            priority: row.get(5)?,
+            position: row.get(6)?,
+            error: row.get(7)?,
+            updated_at_ms: row.get(8)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+/// 取消排队中的项，本轮不再处理，下次同步重新排队；返回是否取消成功。
+pub fn cancel_transfer(conn: &Connection, task_id: &str, relpath: &str) -> Result<bool> {
+    let changed = conn.execute(
+        "UPDATE transfer_queue SET status = ?3, updated_at_ms = ?4 WHERE task_id = ?1 AND relpath = ?2 AND status = ?5",
+        params![task_id, relpath, TRANSFER_CANCELLED, now_ms(), TRANSFER_QUEUED],
+    )?;
+    Ok(changed > 0)
+}
+
+/// 把一项提到队首：优先级设为当前任务中最高值加一。返回是否找到该项。
+pub fn bump_transfer(conn: &Connection, task_id: &str, relpath: &str) -> Result<bool> {
+    let changed = conn.execute(
+        "UPDATE transfer_queue SET priority = (SELECT COALESCE(MAX(priority), 0) + 1 FROM transfer_queue WHERE task_id = ?1), updated_at_ms = ?3 WHERE task_id = ?1 AND relpath = ?2",
+        params![task_id, relpath, now_ms()],
+    )?;
+    Ok(changed > 0)
+}
+
 pub fn list_conflicts(conn: &Connection, task_id: Option<&str>) -> Result<Vec<ConflictRow>> {
     let mut out = Vec::new();
     if let Some(task_id) = task_id {
@@ -358,12 +1142,111 @@ pub fn list_conflicts(conn: &Connection, task_id: Option<&str>) -> Result<Vec<Co
 
 pub fn insert_log(conn: &Connection, log: &LogRow) -> Result<()> {
     conn.execute(
-        "INSERT INTO logs (task_id, level, event, detail, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![log.task_id, log.level, log.event, log.detail, log.created_at_ms],
+        "INSERT INTO logs (task_id, level, event, detail, created_at_ms, batch_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            log.task_id,
+            log.level,
+            log.event,
+            CloudreveClient::redact_share_passwords(&log.detail),
+            log.created_at_ms,
+            log.batch_id
+        ],
+    )?;
+    Ok(())
+}
+
+/// 删除早于 `before_ms` 的 `level` 级别日志，返回删除的行数。
+pub fn delete_logs_before(conn: &Connection, level: &str, before_ms: i64) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM logs WHERE level = ?1 AND created_at_ms < ?2",
+        params![level, before_ms],
+    )
+}
+
+/// 只保留最新的 `keep` 条日志，返回删除的行数。
+pub fn trim_logs(conn: &Connection, keep: i64) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM logs WHERE id <= (SELECT id FROM logs ORDER BY id DESC LIMIT 1 OFFSET ?1)",
+        params![keep],
+    )
+}
+
+/// 删除日志已被清理、且 `before_ms` 之后没有再累加的批次汇总。
+pub fn delete_orphan_log_batches(conn: &Connection, before_ms: i64) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM log_batches WHERE updated_at_ms < ?1 AND NOT EXISTS (SELECT 1 FROM logs WHERE logs.batch_id = log_batches.batch_id)",
+        params![before_ms],
+    )
+}
+
+pub fn delete_tombstones_before(conn: &Connection, before_ms: i64) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM tombstones WHERE deleted_at_ms < ?1",
+        params![before_ms],
+    )
+}
+
+/// 每完成一个文件累加一次，批次中途失败时已完成部分的汇总依然准确。
+pub fn record_batch_item(conn: &Connection, batch: &LogBatchRow) -> Result<()> {
+    conn.execute(
+        "INSERT INTO log_batches (batch_id, task_id, event, dir, file_count, total_bytes, updated_at_ms) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) ON CONFLICT(batch_id) DO UPDATE SET file_count=file_count + excluded.file_count, total_bytes=total_bytes + excluded.total_bytes, updated_at_ms=excluded.updated_at_ms",
+        params![
+            batch.batch_id,
+            batch.task_id,
+            batch.event,
+            batch.dir,
+            batch.file_count,
+            batch.total_bytes,
+            batch.updated_at_ms
+        ],
     )?;
     Ok(())
 }
 
+/// 只返回包含多个文件的批次；单个文件的批次在动态中按普通日志显示。
+pub fn list_log_batches(conn: &Connection) -> Result<Vec<LogBatchRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT batch_id, task_id, event, dir, file_count, total_bytes, updated_at_ms FROM log_batches WHERE file_count > 1 ORDER BY updated_at_ms DESC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(LogBatchRow {
+            batch_id: row.get(0)?,
+            task_id: row.get(1)?,
+            event: row.get(2)?,
+            dir: row.get(3)?,
+            file_count: row.get(4)?,
+            total_bytes: row.get(5)?,
+            updated_at_ms: row.get(6)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+pub fn list_batch_logs(conn: &Connection, batch_id: &str) -> Result<Vec<LogRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, level, event, detail, created_at_ms, batch_id FROM logs WHERE batch_id = ?1 ORDER BY created_at_ms DESC",
+    )?;
+    let rows = stmt.query_map(params![batch_id], |row| {
+        Ok(LogRow {
+            task_id: row.get(0)?,
+            level: row.get(1)?,
+            event: row.get(2)?,
+            detail: row.get(3)?,
+            created_at_ms: row.get(4)?,
+            batch_id: row.get(5)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
 pub fn list_logs(
     conn: &Connection,
     task_id: Option<&str>,
@@ -371,7 +1254,8 @@ pub fn list_logs(
     limit: Option<u32>,
     offset: Option<u32>,
 ) -> Result<Vec<LogRow>> {
-    let mut sql = "SELECT task_id, level, event, detail, created_at_ms FROM logs".to_string();
+    let mut sql =
+        "SELECT task_id, level, event, detail, created_at_ms, batch_id FROM logs".to_string();
     let mut filters = Vec::new();
     let mut params_vec: Vec<Value> = Vec::new();
     if task_id.is_some() {
@@ -414,6 +1298,7 @@ pub fn list_logs(
             event: row.get(2)?,
             detail: row.get(3)?,
             created_at_ms: row.get(4)?,
+            batch_id: row.get(5)?,
         })
     })?;
     for row in rows {
@@ -450,6 +1335,284 @@ pub fn count_logs(conn: &Connection, task_id: Option<&str>, level: Option<&str>)
     Ok(count)
 }
 
+pub fn get_scan_schedule(conn: &Connection, task_id: &str) -> Result<Option<ScanScheduleRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, last_quick_scan_ms, last_deep_scan_ms FROM scan_schedule WHERE task_id = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![task_id], |row| {
+        Ok(ScanScheduleRow {
+            task_id: row.get(0)?,
+            last_quick_scan_ms: row.get(1)?,
+            last_deep_scan_ms: row.get(2)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
+pub fn record_scan(conn: &Connection, task_id: &str, deep: bool, at_ms: i64) -> Result<()> {
+    let sql = if deep {
+        "INSERT INTO scan_schedule (task_id, last_quick_scan_ms, last_deep_scan_ms) VALUES (?1, ?2, ?2) ON CONFLICT(task_id) DO UPDATE SET last_quick_scan_ms=excluded.last_quick_scan_ms, last_deep_scan_ms=excluded.last_deep_scan_ms"
+    } else {
+        "INSERT INTO scan_schedule (task_id, last_quick_scan_ms) VALUES (?1, ?2) ON CONFLICT(task_id) DO UPDATE SET last_quick_scan_ms=excluded.last_quick_scan_ms"
+    };
+    conn.execute(sql, params![task_id, at_ms])?;
+    Ok(())
+}
+
+pub fn list_dir_activity(conn: &Connection, task_id: &str) -> Result<Vec<DirActivityRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT relpath, score, skipped_passes FROM dir_activity WHERE task_id = ?1 ORDER BY relpath",
+    )?;
+    let rows = stmt.query_map(params![task_id], |row| {
+        Ok(DirActivityRow {
+            relpath: row.get(0)?,
+            score: row.get(1)?,
+            skipped_passes: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// 一轮快速扫描结束后写入各目录的新分数；本轮已不存在的目录一并删除。
+pub fn save_dir_activity(
+    conn: &Connection,
+    task_id: &str,
+    rows: &[DirActivityRow],
+    removed: &[String],
+) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut upsert = tx.prepare(
+            "INSERT INTO dir_activity (task_id, relpath, score, skipped_passes) VALUES (?1, ?2, ?3, ?4) ON CONFLICT(task_id, relpath) DO UPDATE SET score=excluded.score, skipped_passes=excluded.skipped_passes",
+        )?;
+        for row in rows {
+            upsert.execute(params![task_id, row.relpath, row.score, row.skipped_passes])?;
+        }
+        let mut delete =
+            tx.prepare("DELETE FROM dir_activity WHERE task_id = ?1 AND relpath = ?2")?;
+        for relpath in removed {
+            delete.execute(params![task_id, relpath])?;
+        }
+    }
+    tx.commit()
+}
+
+/// 本地监听到变化的目录直接视为热目录，下一轮快速扫描一定会访问。
+pub fn mark_dirs_changed(conn: &Connection, task_id: &str, relpaths: &[String]) -> Result<()> {
+    for relpath in relpaths {
+        conn.execute(
+            "UPDATE dir_activity SET score = MAX(score, 1.0), skipped_passes = 0 WHERE task_id = ?1 AND relpath = ?2",
+            params![task_id, relpath],
+        )?;
+    }
+    Ok(())
+}
+
+/// 清除上次深度扫描的时间，下一轮同步改为深度扫描。
+pub fn request_deep_scan(conn: &Connection, task_id: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE scan_schedule SET last_deep_scan_ms = NULL WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    Ok(())
+}
+
+pub fn get_task_run(conn: &Connection, task_id: &str) -> Result<Option<TaskRunRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_id, last_attempt_ms, last_success_ms FROM task_runs WHERE task_id = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![task_id], |row| {
+        Ok(TaskRunRow {
+            task_id: row.get(0)?,
+            last_attempt_ms: row.get(1)?,
+            last_success_ms: row.get(2)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
+/// 记录一次中途暂停的同步：只更新最近尝试时间，不写入运行记录，运行记录只对应完整的一轮。
+pub fn record_task_attempt(conn: &Connection, task_id: &str, at_ms: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO task_runs (task_id, last_attempt_ms) VALUES (?1, ?2) ON CONFLICT(task_id) DO UPDATE SET last_attempt_ms=excluded.last_attempt_ms",
+        params![task_id, at_ms],
+    )?;
+    Ok(())
+}
+
+/// 记录一次同步尝试；`success` 为 true 时同时更新最近成功时间。
+/// 每次尝试还会写入运行记录，只保留最近 `RUN_HISTORY_LIMIT` 次。
+pub fn record_task_run(conn: &Connection, task_id: &str, success: bool, at_ms: i64) -> Result<()> {
+    let sql = if success {
+        "INSERT INTO task_runs (task_id, last_attempt_ms, last_success_ms) VALUES (?1, ?2, ?2) ON CONFLICT(task_id) DO UPDATE SET last_attempt_ms=excluded.last_attempt_ms, last_success_ms=excluded.last_success_ms"
+    } else {
+        "INSERT INTO task_runs (task_id, last_attempt_ms) VALUES (?1, ?2) ON CONFLICT(task_id) DO UPDATE SET last_attempt_ms=excluded.last_attempt_ms"
+    };
+    conn.execute(sql, params![task_id, at_ms])?;
+    conn.execute(
+        "INSERT INTO sync_runs (task_id, finished_at_ms, success) VALUES (?1, ?2, ?3)",
+        params![task_id, at_ms, success],
+    )?;
+    prune_sync_runs(conn, task_id)
+}
+
+/// 删除超出保留数量的运行记录，以及最早一次保留的运行之前已被新版本取代的文件版本。
+fn prune_sync_runs(conn: &Connection, task_id: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM sync_runs WHERE task_id = ?1 AND run_id NOT IN (SELECT run_id FROM sync_runs WHERE task_id = ?1 ORDER BY run_id DESC LIMIT ?2)",
+        params![task_id, RUN_HISTORY_LIMIT],
+    )?;
+    let oldest: Option<i64> = conn.query_row(
+        "SELECT MIN(finished_at_ms) FROM sync_runs WHERE task_id = ?1",
+        params![task_id],
+        |row| row.get(0),
+    )?;
+    if let Some(cutoff) = oldest {
+        conn.execute(
+            "DELETE FROM entry_versions WHERE task_id = ?1 AND recorded_at_ms <= ?2 AND EXISTS (SELECT 1 FROM entry_versions newer WHERE newer.task_id = entry_versions.task_id AND newer.relpath = entry_versions.relpath AND newer.recorded_at_ms <= ?2 AND newer.id > entry_versions.id)",
+            params![task_id, cutoff],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn list_sync_runs(conn: &Connection, task_id: &str) -> Result<Vec<SyncRunRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT run_id, task_id, finished_at_ms, success FROM sync_runs WHERE task_id = ?1 ORDER BY run_id DESC",
+    )?;
+    let rows = stmt.query_map(params![task_id], |row| {
+        Ok(SyncRunRow {
+            run_id: row.get(0)?,
+            task_id: row.get(1)?,
+            finished_at_ms: row.get(2)?,
+            success: row.get(3)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+/// 文件内容（大小或哈希）与最近一个版本不同时追加新版本。
+fn record_entry_version(conn: &Connection, entry: &EntryRow) -> Result<()> {
+    let sha256 = if entry.last_local_sha256.is_empty() {
+        &entry.last_remote_sha256
+    } else {
+        &entry.last_local_sha256
+    };
+    conn.execute(
+        "INSERT INTO entry_versions (task_id, relpath, size, sha256, cloud_file_id, recorded_at_ms, deleted) SELECT ?1, ?2, ?3, ?4, ?5, ?6, 0 WHERE NOT EXISTS (SELECT 1 FROM entry_versions WHERE id = (SELECT MAX(id) FROM entry_versions WHERE task_id = ?1 AND relpath = ?2) AND deleted = 0 AND size = ?3 AND sha256 = ?4 AND cloud_file_id = ?5)",
+        params![
+            entry.task_id,
+            entry.local_relpath,
+            entry.last_local_size,
+            sha256,
+            entry.cloud_file_id,
+            now_ms()
+        ],
+    )?;
+    Ok(())
+}
+
+/// 按某次运行结束时的记录还原任务中的文件列表；运行记录不存在时返回 None。
+pub fn tree_at_run(
+    conn: &Connection,
+    task_id: &str,
+    run_id: i64,
+) -> Result<Option<Vec<EntryVersionRow>>> {
+    let mut stmt =
+        conn.prepare("SELECT finished_at_ms FROM sync_runs WHERE task_id = ?1 AND run_id = ?2")?;
+    let mut rows = stmt.query_map(params![task_id, run_id], |row| row.get::<_, i64>(0))?;
+    let Some(finished_at_ms) = rows.next().transpose()? else {
+        return Ok(None);
+    };
+    let mut stmt = conn.prepare(
+        "SELECT relpath, size, sha256, cloud_file_id, recorded_at_ms FROM entry_versions v WHERE task_id = ?1 AND deleted = 0 AND id = (SELECT MAX(id) FROM entry_versions w WHERE w.task_id = v.task_id AND w.relpath = v.relpath AND w.recorded_at_ms <= ?2) ORDER BY relpath",
+    )?;
+    let rows = stmt.query_map(params![task_id, finished_at_ms], |row| {
+        Ok(EntryVersionRow {
+            relpath: row.get(0)?,
+            size: row.get(1)?,
+            sha256: row.get(2)?,
+            cloud_file_id: row.get(3)?,
+            recorded_at_ms: row.get(4)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(Some(out))
+}
+
+pub fn insert_api_token(conn: &Connection, token: &ApiTokenRow) -> Result<()> {
+    conn.execute(
+        "INSERT INTO api_tokens (token_id, name, scope, token_sha256, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            token.token_id,
+            token.name,
+            token.scope,
+            token.token_sha256,
+            token.created_at_ms
+        ],
+    )?;
+    Ok(())
+}
+
+fn api_token_from_row(row: &rusqlite::Row) -> Result<ApiTokenRow> {
+    Ok(ApiTokenRow {
+        token_id: row.get(0)?,
+        name: row.get(1)?,
+        scope: row.get(2)?,
+        token_sha256: row.get(3)?,
+        created_at_ms: row.get(4)?,
+    })
+}
+
+pub fn list_api_tokens(conn: &Connection) -> Result<Vec<ApiTokenRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT token_id, name, scope, token_sha256, created_at_ms FROM api_tokens ORDER BY created_at_ms",
+    )?;
+    let rows = stmt.query_map([], api_token_from_row)?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+pub fn find_api_token(conn: &Connection, token_sha256: &str) -> Result<Option<ApiTokenRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT token_id, name, scope, token_sha256, created_at_ms FROM api_tokens WHERE token_sha256 = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![token_sha256], api_token_from_row)?;
+    rows.next().transpose()
+}
+
+/// 返回被吊销的令牌数量。
+pub fn delete_api_token(conn: &Connection, token_id: &str) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM api_tokens WHERE token_id = ?1",
+        params![token_id],
+    )
+}
+
+pub fn get_next_due(conn: &Connection, task_id: &str) -> Result<Option<i64>> {
+    let mut stmt = conn.prepare("SELECT next_due_at_ms FROM task_due WHERE task_id = ?1")?;
+    let mut rows = stmt.query_map(params![task_id], |row| row.get(0))?;
+    rows.next().transpose()
+}
+
+pub fn set_next_due(conn: &Connection, task_id: &str, next_due_at_ms: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO task_due (task_id, next_due_at_ms) VALUES (?1, ?2) ON CONFLICT(task_id) DO UPDATE SET next_due_at_ms=excluded.next_due_at_ms",
+        params![task_id, next_due_at_ms],
+    )?;
+    Ok(())
+}
+
 pub fn now_ms() -> i64 {
     Utc::now().timestamp_millis()
 }
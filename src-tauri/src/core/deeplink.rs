@@ -0,0 +1,81 @@
+pub const DEEP_LINK_SCHEME: &str = "cloudreve-sync";
+
+/// `cloudreve-sync://` 链接对应的操作。链接可能来自任意网页，因此只打开界面上的对应流程，
+/// 创建分享、处理冲突、立即同步等仍需用户在窗口中确认。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    /// `cloudreve-sync://open`：显示主窗口。
+    Open,
+    /// `cloudreve-sync://share?path=<本地路径>`：打开创建分享链接的对话框。
+    Share { path: String },
+    /// `cloudreve-sync://resolve-conflict?id=<冲突 ID>`：在冲突页选中该冲突。
+    ResolveConflict { id: String },
+    /// `cloudreve-sync://sync?task=<任务 ID>`：请用户确认后同步该任务。
+    Sync { task_id: String },
+}
+
+fn required_param(url: &reqwest::Url, name: &str) -> Result<String, String> {
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| format!("链接缺少参数 {}: {}", name, url))
+}
+
+pub fn parse_deep_link(link: &str) -> Result<DeepLink, String> {
+    let url = reqwest::Url::parse(link.trim()).map_err(|err| format!("链接无效: {}", err))?;
+    if url.scheme() != DEEP_LINK_SCHEME {
+        return Err(format!("不支持的链接协议: {}", url.scheme()));
+    }
+    // `cloudreve-sync://share?...` 的操作名在主机部分，`cloudreve-sync:share?...` 则在路径中。
+    let action = url
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| url.path())
+        .trim_matches('/')
+        .to_ascii_lowercase();
+    match action.as_str() {
+        "" | "open" => Ok(DeepLink::Open),
+        "share" => Ok(DeepLink::Share {
+            path: required_param(&url, "path")?,
+        }),
+        "resolve-conflict" => Ok(DeepLink::ResolveConflict {
+            id: required_param(&url, "id")?,
+        }),
+        "sync" => Ok(DeepLink::Sync {
+            task_id: required_param(&url, "task")?,
+        }),
+        other => Err(format!("未知的链接操作: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_supported_actions() {
+        assert_eq!(
+            parse_deep_link("cloudreve-sync://share?path=%2Fhome%2Fme%2FDocs%2Fa%20b.txt"),
+            Ok(DeepLink::Share {
+                path: "/home/me/Docs/a b.txt".to_string()
+            })
+        );
+        assert_eq!(
+            parse_deep_link("cloudreve-sync://resolve-conflict/?id=42"),
+            Ok(DeepLink::ResolveConflict {
+                id: "42".to_string()
+            })
+        );
+        assert_eq!(
+            parse_deep_link("cloudreve-sync:sync?task=t1"),
+            Ok(DeepLink::Sync {
+                task_id: "t1".to_string()
+            })
+        );
+        assert_eq!(parse_deep_link("cloudreve-sync://"), Ok(DeepLink::Open));
+        assert!(parse_deep_link("cloudreve-sync://share").is_err());
+        assert!(parse_deep_link("cloudreve-sync://format-disk").is_err());
+        assert!(parse_deep_link("https://example.com/share?path=/x").is_err());
+    }
+}
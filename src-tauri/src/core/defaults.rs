@@ -0,0 +1,97 @@
+use serde_json::{Map, Value};
+
+/// 任务专属字段，不会从账户默认值继承。
+const PROTECTED_KEYS: &[&str] = &["name", "account_key", "local_roots"];
+
+pub fn is_inheritable(key: &str) -> bool {
+    !PROTECTED_KEYS.contains(&key)
+}
+
+pub fn parse_defaults(raw: &str) -> Map<String, Value> {
+    match serde_json::from_str::<Value>(raw) {
+        Ok(Value::Object(map)) => sanitize_defaults(map),
+        _ => Map::new(),
+    }
+}
+
+pub fn sanitize_defaults(defaults: Map<String, Value>) -> Map<String, Value> {
+    defaults
+        .into_iter()
+        .filter(|(key, value)| is_inheritable(key) && !value.is_null())
+        .collect()
+}
+
+/// 新任务：显式字段优先，缺失的字段从账户默认值补齐。
+pub fn inherit_defaults(defaults: &Map<String, Value>, settings: &mut Map<String, Value>) {
+    for (key, value) in defaults {
+        if is_inheritable(key) && !settings.contains_key(key) {
+            settings.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// 已有任务：将默认值中选定的字段（为空则全部）覆盖写入，返回是否有变化。
+pub fn apply_defaults(
+    defaults: &Map<String, Value>,
+    settings: &mut Map<String, Value>,
+    keys: &[String],
+) -> bool {
+    let mut changed = false;
+    for (key, value) in defaults {
+        if !is_inheritable(key) || (!keys.is_empty() && !keys.contains(key)) {
+            continue;
+        }
+        if settings.get(key) != Some(value) {
+            settings.insert(key.clone(), value.clone());
+            changed = true;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => panic!("expected object"),
+        }
+    }
+
+    #[test]
+    fn parse_defaults_drops_protected_and_invalid() {
+        let defaults = parse_defaults(r#"{"name":"x","sync_interval_secs":30,"extra":null}"#);
+        assert_eq!(defaults, object(json!({"sync_interval_secs": 30})));
+        assert!(parse_defaults("[1,2]").is_empty());
+    }
+
+    #[test]
+    fn inherit_keeps_explicit_values() {
+        let defaults = object(json!({"sync_interval_secs": 30, "conflict_policy": "keep_both"}));
+        let mut settings = object(json!({"name": "a", "sync_interval_secs": 10}));
+        inherit_defaults(&defaults, &mut settings);
+        assert_eq!(settings["sync_interval_secs"], json!(10));
+        assert_eq!(settings["conflict_policy"], json!("keep_both"));
+    }
+
+    #[test]
+    fn apply_overwrites_selected_keys_only() {
+        let defaults = object(json!({"sync_interval_secs": 30, "conflict_policy": "keep_both"}));
+        let mut settings = object(json!({"sync_interval_secs": 10}));
+        assert!(apply_defaults(
+            &defaults,
+            &mut settings,
+            &["sync_interval_secs".to_string()]
+        ));
+        assert_eq!(settings["sync_interval_secs"], json!(30));
+        assert!(!settings.contains_key("conflict_policy"));
+        assert!(!apply_defaults(
+            &defaults,
+            &mut settings,
+            &["sync_interval_secs".to_string()]
+        ));
+    }
+}
@@ -0,0 +1,351 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::generic_array::GenericArray;
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use uuid::Uuid;
+
+/// 加密文件头：格式标记与流式加密的 19 字节随机数。
+const FILE_MAGIC: &[u8; 4] = b"CRE1";
+const STREAM_NONCE_LEN: usize = 19;
+const HEADER_LEN: u64 = FILE_MAGIC.len() as u64 + STREAM_NONCE_LEN as u64;
+/// 每段明文的长度；每段密文额外带 16 字节认证标签。
+const SEGMENT_LEN: usize = 64 * 1024;
+const TAG_LEN: u64 = 16;
+/// 文件名与摘要使用由内容导出的 16 字节随机数，相同名称总是得到相同密文。
+const NAME_NONCE_LEN: usize = 16;
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// 任务设置中的加密参数，只保存盐与密钥指纹；密钥本身保存在钥匙串中。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptionSettings {
+    pub salt: String,
+    pub fingerprint: String,
+    /// 远端文件名与目录名同样加密。
+    #[serde(default)]
+    pub obfuscate_names: bool,
+}
+
+impl EncryptionSettings {
+    /// 为新任务生成盐并由密码派生密钥，返回设置与密钥。
+    pub fn create(
+        passphrase: &str,
+        obfuscate_names: bool,
+    ) -> Result<(Self, [u8; 32]), Box<dyn Error>> {
+        let salt = to_hex(Uuid::new_v4().as_bytes());
+        let key = derive_key(passphrase, &salt)?;
+        let settings = Self {
+            salt,
+            fingerprint: key_fingerprint(&key),
+            obfuscate_names,
+        };
+        Ok((settings, key))
+    }
+
+    /// 由密码派生密钥并核对指纹，密码错误时返回错误。
+    pub fn unlock(&self, passphrase: &str) -> Result<[u8; 32], Box<dyn Error>> {
+        let key = derive_key(passphrase, &self.salt)?;
+        if key_fingerprint(&key) != self.fingerprint {
+            return Err("加密密码错误".into());
+        }
+        Ok(key)
+    }
+}
+
+/// Argon2id 派生 32 字节密钥，盐为十六进制字符串。
+pub fn derive_key(passphrase: &str, salt: &str) -> Result<[u8; 32], Box<dyn Error>> {
+    if passphrase.is_empty() {
+        return Err("加密密码不能为空".into());
+    }
+    let salt = from_hex(salt).ok_or("加密设置中的盐无效")?;
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|err| format!("密钥派生失败: {}", err))?;
+    Ok(key)
+}
+
+/// 密钥指纹，用于确认各设备输入的密码一致，不能由指纹反推密钥。
+pub fn key_fingerprint(key: &[u8; 32]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"cloudreve-sync-e2e-fingerprint");
+    hasher.update(key);
+    to_hex(&hasher.finalize()[..8])
+}
+
+/// 加密任务使用的密钥。文件内容使用 XChaCha20-Poly1305 分段加密，
+/// 文件名与摘要元数据使用确定性加密，以便按名称查找远端文件。
+#[derive(Clone)]
+pub struct TaskCipher {
+    key: [u8; 32],
+    obfuscate_names: bool,
+}
+
+impl TaskCipher {
+    pub fn new(key: [u8; 32], obfuscate_names: bool) -> Self {
+        Self {
+            key,
+            obfuscate_names,
+        }
+    }
+
+    pub fn obfuscates_names(&self) -> bool {
+        self.obfuscate_names
+    }
+
+    pub fn fingerprint(&self) -> String {
+        key_fingerprint(&self.key)
+    }
+
+    fn aead(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(GenericArray::from_slice(&self.key))
+    }
+
+    /// 加密 `src` 写入 `dst`，返回密文大小。
+    pub fn seal_file(&self, src: &Path, dst: &Path) -> Result<u64, Box<dyn Error>> {
+        let mut input = fs::File::open(src)?;
+        let mut output = fs::File::create(dst)?;
+        let mut nonce = [0u8; STREAM_NONCE_LEN];
+        nonce[..16].copy_from_slice(Uuid::new_v4().as_bytes());
+        nonce[16..].copy_from_slice(&Uuid::new_v4().as_bytes()[..3]);
+        output.write_all(FILE_MAGIC)?;
+        output.write_all(&nonce)?;
+        let mut encryptor = EncryptorBE32::from_aead(self.aead(), GenericArray::from_slice(&nonce));
+        let mut written = HEADER_LEN;
+        let mut current = vec![0u8; SEGMENT_LEN];
+        let mut next = vec![0u8; SEGMENT_LEN];
+        let mut current_len = read_full(&mut input, &mut current)?;
+        loop {
+            let next_len = if current_len == SEGMENT_LEN {
+                read_full(&mut input, &mut next)?
+            } else {
+                0
+            };
+            if next_len == 0 {
+                let sealed = encryptor
+                    .encrypt_last(&current[..current_len])
+                    .map_err(|_| "加密失败")?;
+                output.write_all(&sealed)?;
+                written += sealed.len() as u64;
+                break;
+            }
+            let sealed = encryptor
+                .encrypt_next(&current[..current_len])
+                .map_err(|_| "加密失败")?;
+            output.write_all(&sealed)?;
+            written += sealed.len() as u64;
+            std::mem::swap(&mut current, &mut next);
+            current_len = next_len;
+        }
+        output.sync_all()?;
+        Ok(written)
+    }
+
+    /// 解密 `src` 写入 `dst`，返回明文大小。密钥错误、内容被篡改或截断时返回错误。
+    pub fn open_file(&self, src: &Path, dst: &Path) -> Result<u64, Box<dyn Error>> {
+        const DAMAGED: &str = "解密失败，密钥错误或文件已损坏";
+        let mut input = fs::File::open(src)?;
+        let mut header = [0u8; HEADER_LEN as usize];
+        if read_full(&mut input, &mut header)? != header.len() || &header[..4] != FILE_MAGIC {
+            return Err(DAMAGED.into());
+        }
+        let mut decryptor =
+            DecryptorBE32::from_aead(self.aead(), GenericArray::from_slice(&header[4..]));
+        let mut output = fs::File::create(dst)?;
+        let segment = SEGMENT_LEN + TAG_LEN as usize;
+        let mut current = vec![0u8; segment];
+        let mut next = vec![0u8; segment];
+        let mut current_len = read_full(&mut input, &mut current)?;
+        let mut written = 0;
+        let last = loop {
+            let next_len = if current_len == segment {
+                read_full(&mut input, &mut next)?
+            } else {
+                0
+            };
+            if next_len == 0 {
+                break decryptor.decrypt_last(&current[..current_len]);
+            }
+            match decryptor.decrypt_next(&current[..current_len]) {
+                Ok(plain) => {
+                    output.write_all(&plain)?;
+                    written += plain.len() as u64;
+                }
+                Err(err) => break Err(err),
+            }
+            std::mem::swap(&mut current, &mut next);
+            current_len = next_len;
+        };
+        let Ok(plain) = last else {
+            drop(output);
+            let _ = fs::remove_file(dst);
+            return Err(DAMAGED.into());
+        };
+        output.write_all(&plain)?;
+        output.sync_all()?;
+        Ok(written + plain.len() as u64)
+    }
+
+    /// 加密单个名称，结果为小写 base32，可安全用作文件名。
+    pub fn seal_name(&self, name: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"cloudreve-sync-e2e-name");
+        hasher.update(self.key);
+        hasher.update(name.as_bytes());
+        let mut nonce = [0u8; 24];
+        nonce[..NAME_NONCE_LEN].copy_from_slice(&hasher.finalize()[..NAME_NONCE_LEN]);
+        let sealed = self
+            .aead()
+            .encrypt(XNonce::from_slice(&nonce), name.as_bytes())
+            .unwrap_or_default();
+        let mut bytes = nonce[..NAME_NONCE_LEN].to_vec();
+        bytes.extend_from_slice(&sealed);
+        to_base32(&bytes)
+    }
+
+    /// 解密 `seal_name` 的结果；不是本密钥加密的名称返回 `None`。
+    pub fn open_name(&self, sealed: &str) -> Option<String> {
+        let bytes = from_base32(sealed)?;
+        if bytes.len() < NAME_NONCE_LEN + TAG_LEN as usize {
+            return None;
+        }
+        let mut nonce = [0u8; 24];
+        nonce[..NAME_NONCE_LEN].copy_from_slice(&bytes[..NAME_NONCE_LEN]);
+        let plain = self
+            .aead()
+            .decrypt(XNonce::from_slice(&nonce), &bytes[NAME_NONCE_LEN..])
+            .ok()?;
+        let name = String::from_utf8(plain).ok()?;
+        (self.seal_name(&name) == sealed).then_some(name)
+    }
+}
+
+/// 明文大小对应的密文大小。
+pub fn sealed_len(plain_len: u64) -> u64 {
+    let segments = plain_len.div_ceil(SEGMENT_LEN as u64).max(1);
+    HEADER_LEN + plain_len + segments * TAG_LEN
+}
+
+/// 密文大小对应的明文大小，不足一个文件头与标签时视为空文件。
+pub fn plain_len(sealed_len: u64) -> u64 {
+    let Some(body) = sealed_len.checked_sub(HEADER_LEN + TAG_LEN) else {
+        return 0;
+    };
+    let segments = (body + TAG_LEN).div_ceil(SEGMENT_LEN as u64 + TAG_LEN);
+    body + TAG_LEN - segments * TAG_LEN
+}
+
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            count => filled += count,
+        }
+    }
+    Ok(filled)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || text.is_empty() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+fn to_base32(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn from_base32(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for ch in text.bytes() {
+        let value = BASE32_ALPHABET.iter().position(|item| *item == ch)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn files_and_names_round_trip() {
+        let (settings, key) = EncryptionSettings::create("correct horse", true).expect("create");
+        assert_eq!(settings.unlock("correct horse").expect("unlock"), key);
+        assert!(settings.unlock("wrong").is_err());
+        let cipher = TaskCipher::new(key, true);
+
+        let dir = tempdir().expect("tempdir");
+        for size in [0, 5, SEGMENT_LEN, SEGMENT_LEN * 2 + 7] {
+            let plain = dir.path().join("plain");
+            let sealed = dir.path().join("sealed");
+            let opened = dir.path().join("opened");
+            let content = (0..size)
+                .map(|index| (index % 251) as u8)
+                .collect::<Vec<_>>();
+            fs::write(&plain, &content).expect("write");
+            let sealed_size = cipher.seal_file(&plain, &sealed).expect("seal");
+            assert_eq!(sealed_size, sealed_len(size as u64));
+            assert_eq!(fs::metadata(&sealed).expect("meta").len(), sealed_size);
+            assert_eq!(plain_len(sealed_size), size as u64);
+            assert_eq!(
+                cipher.open_file(&sealed, &opened).expect("open"),
+                size as u64
+            );
+            assert_eq!(fs::read(&opened).expect("read"), content);
+        }
+
+        let sealed = dir.path().join("sealed");
+        let mut bytes = fs::read(&sealed).expect("read");
+        bytes.truncate(bytes.len() - 1);
+        fs::write(&sealed, &bytes).expect("truncate");
+        assert!(cipher
+            .open_file(&sealed, &dir.path().join("opened"))
+            .is_err());
+
+        let name = cipher.seal_name("报告 2024.txt");
+        assert_eq!(name, cipher.seal_name("报告 2024.txt"));
+        assert!(name.bytes().all(|ch| BASE32_ALPHABET.contains(&ch)));
+        assert_eq!(cipher.open_name(&name).as_deref(), Some("报告 2024.txt"));
+        assert_eq!(cipher.open_name("notes.txt"), None);
+        let other = TaskCipher::new([7; 32], true);
+        assert_eq!(other.open_name(&name), None);
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::io;
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 #[repr(u32)]
@@ -232,6 +233,161 @@ impl CloudreveError {
     }
 }
 
+/// 同步失败的分类，调用方据此决定提示内容以及是否值得重试。
+/// 显示文本面向用户；原始错误保留在各变体中，便于写入日志。
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    #[error("网络连接失败: {0}")]
+    Network(String),
+    #[error("登录已失效，请重新登录 ({0})")]
+    Auth(CloudreveError),
+    #[error("云端存储空间或文件数量已达上限 ({0})")]
+    Quota(CloudreveError),
+    #[error("本地文件读写失败: {0}")]
+    Io(#[from] io::Error),
+    #[error("文件被占用或已被其他客户端修改 ({0})")]
+    Conflict(CloudreveError),
+    #[error("本地数据库错误: {0}")]
+    Db(#[from] rusqlite::Error),
+    #[error("服务端返回错误 ({0})")]
+    Remote(CloudreveError),
+    #[error("同步已取消")]
+    Cancelled,
+    /// 未归类的错误，保留原始错误以便调用方按具体类型判断（如本地目录只读）。
+    #[error("{0}")]
+    Other(Box<dyn Error>),
+}
+
+impl SyncError {
+    /// 写入日志与命令行输出的分类名称。
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SyncError::Network(_) => "network",
+            SyncError::Auth(_) => "auth",
+            SyncError::Quota(_) => "quota",
+            SyncError::Io(_) => "io",
+            SyncError::Conflict(_) => "conflict",
+            SyncError::Db(_) => "db",
+            SyncError::Remote(_) => "remote",
+            SyncError::Cancelled => "cancelled",
+            SyncError::Other(_) => "other",
+        }
+    }
+
+    /// 稍后重试可能成功的错误：网络中断、服务端临时故障、锁冲突与数据库忙。
+    /// 登录失效与空间不足需要用户处理，重试只会重复失败。
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SyncError::Network(_) | SyncError::Conflict(_) => true,
+            SyncError::Remote(code) => matches!(
+                code,
+                CloudreveError::DatabaseOperationFailed
+                    | CloudreveError::IOOperationFailed
+                    | CloudreveError::CacheOperationFailed
+                    | CloudreveError::NodeOffline
+                    | CloudreveError::UploadSessionExpired
+            ),
+            SyncError::Io(err) => matches!(
+                err.kind(),
+                io::ErrorKind::Interrupted | io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+            ),
+            SyncError::Db(err) => matches!(
+                err.sqlite_error_code(),
+                Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+            ),
+            SyncError::Auth(_) | SyncError::Quota(_) | SyncError::Cancelled => false,
+            SyncError::Other(_) => false,
+        }
+    }
+
+    /// 查看未归类错误的原始类型。
+    pub fn downcast_ref<T: Error + 'static>(&self) -> Option<&T> {
+        match self {
+            SyncError::Other(err) => err.downcast_ref::<T>(),
+            _ => None,
+        }
+    }
+}
+
+/// 同步被取消时各层返回的错误。
+pub fn is_cancelled(err: &(dyn Error + 'static)) -> bool {
+    err.downcast_ref::<SyncError>()
+        .is_some_and(|err| matches!(err, SyncError::Cancelled))
+}
+
+/// 文件被其他程序独占打开或加锁（Windows 的共享冲突与锁冲突），包括包装在 `SyncError::Io` 中的。
+pub fn is_file_locked(err: &(dyn Error + 'static)) -> bool {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    let io_err =
+        err.downcast_ref::<io::Error>()
+            .or_else(|| match err.downcast_ref::<SyncError>() {
+                Some(SyncError::Io(err)) => Some(err),
+                _ => None,
+            });
+    cfg!(windows)
+        && io_err.is_some_and(|err| {
+            matches!(
+                err.raw_os_error(),
+                Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+            )
+        })
+}
+
+impl From<CloudreveError> for SyncError {
+    fn from(code: CloudreveError) -> Self {
+        match code {
+            CloudreveError::NotLoggedIn
+            | CloudreveError::LoginSessionDoesNotExist
+            | CloudreveError::InvalidCredentials
+            | CloudreveError::UserBanned
+            | CloudreveError::UserNotActivated => SyncError::Auth(code),
+            CloudreveError::InsufficientUserCapacity
+            | CloudreveError::FileCountLimitReached
+            | CloudreveError::InsufficientCredit => SyncError::Quota(code),
+            CloudreveError::ResourceConflict
+            | CloudreveError::LockConflict
+            | CloudreveError::LockTokenExpired
+            | CloudreveError::CurrentVersionIsStale
+            | CloudreveError::FileWithSameNameBeingUploaded => SyncError::Conflict(code),
+            _ => SyncError::Remote(code),
+        }
+    }
+}
+
+/// 按原始错误的类型归类；客户端与引擎内部仍返回 `Box<dyn Error>`，在对外接口处转换。
+impl From<Box<dyn Error>> for SyncError {
+    fn from(err: Box<dyn Error>) -> Self {
+        let err = match err.downcast::<SyncError>() {
+            Ok(err) => return *err,
+            Err(err) => err,
+        };
+        let err = match err.downcast::<io::Error>() {
+            Ok(err) => return SyncError::Io(*err),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<rusqlite::Error>() {
+            Ok(err) => return SyncError::Db(*err),
+            Err(err) => err,
+        };
+        if let Some(code) = err.downcast_ref::<CloudreveError>() {
+            return SyncError::from(*code);
+        }
+        if let Some(request) = err.downcast_ref::<reqwest::Error>() {
+            // 下载中途连接被重置等读取响应体时的错误同样可以重试。
+            if request.is_connect()
+                || request.is_timeout()
+                || request.is_request()
+                || request.is_body()
+                || request.is_decode()
+            {
+                return SyncError::Network(request.to_string());
+            }
+        }
+        SyncError::Other(err)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,4 +418,54 @@ mod tests {
         let text = err.to_string();
         assert_eq!(text, "404: ResourceNotFound");
     }
+
+    #[test]
+    fn sync_error_classifies_boxed_errors() {
+        let boxed: Box<dyn Error> = Box::new(CloudreveError::NotLoggedIn);
+        let err = SyncError::from(boxed);
+        assert_eq!(err.kind(), "auth");
+        assert!(!err.is_retryable());
+
+        let boxed: Box<dyn Error> = Box::new(CloudreveError::InsufficientUserCapacity);
+        assert_eq!(SyncError::from(boxed).kind(), "quota");
+
+        let boxed: Box<dyn Error> = Box::new(CloudreveError::NodeOffline);
+        let err = SyncError::from(boxed);
+        assert_eq!(err.kind(), "remote");
+        assert!(err.is_retryable());
+
+        let boxed: Box<dyn Error> = Box::new(io::Error::from(io::ErrorKind::TimedOut));
+        assert!(SyncError::from(boxed).is_retryable());
+
+        let boxed: Box<dyn Error> = "任务不存在".into();
+        let err = SyncError::from(boxed);
+        assert_eq!(err.kind(), "other");
+        assert_eq!(err.to_string(), "任务不存在");
+
+        let boxed: Box<dyn Error> = Box::new(SyncError::Cancelled);
+        assert!(matches!(SyncError::from(boxed), SyncError::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn truncated_response_body_is_a_network_error() {
+        use std::io::Write;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut request = [0u8; 1024];
+            let _ = io::Read::read(&mut stream, &mut request);
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\nabc");
+        });
+        let err = reqwest::get(format!("http://{}/", addr))
+            .await
+            .expect("headers")
+            .bytes()
+            .await
+            .expect_err("truncated body");
+        let err = SyncError::from(Box::new(err) as Box<dyn Error>);
+        assert_eq!(err.kind(), "network");
+        assert!(err.is_retryable());
+    }
 }
@@ -0,0 +1,248 @@
+use crate::core::logging::{LogEntry, LogStore};
+use crate::core::sync::SyncStats;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// 单个文件传输进度的最短上报间隔，避免大量小块写入时刷屏。
+pub const TRANSFER_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 同步引擎发布的事件；日志、统计、界面推送等各自订阅所需部分。
+#[derive(Debug, Clone)]
+pub enum SyncEvent {
+    Log(LogEntry),
+    Status { task_id: String, status: String },
+    Progress { task_id: String, stats: SyncStats },
+    Transfer(TransferProgress),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// 单个文件的传输进度，速率单位为字节每秒；`done` 为真表示该文件本次传输结束。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransferProgress {
+    pub task_id: String,
+    pub relpath: String,
+    pub direction: TransferDirection,
+    pub transferred: u64,
+    pub total: u64,
+    pub bytes_per_sec: u64,
+    pub eta_secs: Option<u64>,
+    pub done: bool,
+}
+
+/// 跟踪一个文件的传输，按间隔生成进度；续传时起点之前的字节不计入速率。
+pub struct TransferMeter {
+    task_id: String,
+    relpath: String,
+    direction: TransferDirection,
+    total: u64,
+    base: u64,
+    started: Instant,
+    last_report: Option<Instant>,
+}
+
+impl TransferMeter {
+    pub fn new(
+        task_id: &str,
+        relpath: &str,
+        direction: TransferDirection,
+        total: u64,
+        base: u64,
+        now: Instant,
+    ) -> Self {
+        Self {
+            task_id: task_id.to_string(),
+            relpath: relpath.to_string(),
+            direction,
+            total,
+            base,
+            started: now,
+            last_report: None,
+        }
+    }
+
+    /// 距上次上报不足间隔时返回 `None`；首次与结束时总是上报。
+    pub fn update(
+        &mut self,
+        transferred: u64,
+        done: bool,
+        now: Instant,
+    ) -> Option<TransferProgress> {
+        if !done
+            && self
+                .last_report
+                .is_some_and(|last| now.duration_since(last) < TRANSFER_PROGRESS_INTERVAL)
+        {
+            return None;
+        }
+        self.last_report = Some(now);
+        let elapsed_ms = now.duration_since(self.started).as_millis() as u64;
+        let session_bytes = transferred.saturating_sub(self.base);
+        let bytes_per_sec = session_bytes
+            .saturating_mul(1000)
+            .checked_div(elapsed_ms)
+            .unwrap_or(0);
+        let remaining = self.total.saturating_sub(transferred);
+        let eta_secs = if done || remaining == 0 {
+            Some(0)
+        } else if bytes_per_sec == 0 {
+            None
+        } else {
+            Some(remaining.div_ceil(bytes_per_sec))
+        };
+        Some(TransferProgress {
+            task_id: self.task_id.clone(),
+            relpath: self.relpath.clone(),
+            direction: self.direction,
+            transferred,
+            total: self.total,
+            bytes_per_sec,
+            eta_secs,
+            done,
+        })
+    }
+}
+
+type Handler = Arc<dyn Fn(&SyncEvent) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct EventBus {
+    handlers: Arc<RwLock<Vec<Handler>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe<F>(&self, handler: F)
+    where
+        F: Fn(&SyncEvent) + Send + Sync + 'static,
+    {
+        if let Ok(mut handlers) = self.handlers.write() {
+            handlers.push(Arc::new(handler));
+        }
+    }
+
+    /// 将日志事件写入数据库。
+    pub fn subscribe_log_store(&self, store: LogStore) {
+        self.subscribe(move |event| {
+            if let SyncEvent::Log(entry) = event {
+                let _ = store.record(entry);
+            }
+        });
+    }
+
+    /// 按订阅顺序同步分发；处理函数在发布者线程上执行，应保持轻量。
+    pub fn publish(&self, event: SyncEvent) {
+        let handlers = match self.handlers.read() {
+            Ok(handlers) => handlers.clone(),
+            Err(_) => return,
+        };
+        for handler in handlers {
+            handler(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::db::{init_db, list_logs};
+    use crate::core::logging::LogLevel;
+    use rusqlite::Connection;
+    use std::sync::Mutex;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn subscribers_receive_events_independently() {
+        let bus = EventBus::new();
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let progress = Arc::new(Mutex::new(0u32));
+        let statuses_sub = statuses.clone();
+        bus.subscribe(move |event| {
+            if let SyncEvent::Status { status, .. } = event {
+                statuses_sub.lock().unwrap().push(status.clone());
+            }
+        });
+        let progress_sub = progress.clone();
+        bus.subscribe(move |event| {
+            if let SyncEvent::Progress { stats, .. } = event {
+                *progress_sub.lock().unwrap() = stats.operations;
+            }
+        });
+
+        bus.publish(SyncEvent::Status {
+            task_id: "t".to_string(),
+            status: "Hashing".to_string(),
+        });
+        bus.publish(SyncEvent::Progress {
+            task_id: "t".to_string(),
+            stats: SyncStats {
+                operations: 3,
+                ..SyncStats::default()
+            },
+        });
+
+        assert_eq!(*statuses.lock().unwrap(), vec!["Hashing".to_string()]);
+        assert_eq!(*progress.lock().unwrap(), 3);
+    }
+
+    #[test]
+    fn transfer_meter_throttles_and_estimates_remaining_time() {
+        let start = Instant::now();
+        let mut meter = TransferMeter::new(
+            "t",
+            "a.bin",
+            TransferDirection::Download,
+            10_000,
+            2_000,
+            start,
+        );
+
+        let first = meter.update(2_000, false, start).expect("first report");
+        assert_eq!(first.bytes_per_sec, 0);
+        assert_eq!(first.eta_secs, None);
+        assert!(meter
+            .update(3_000, false, start + Duration::from_millis(100))
+            .is_none());
+
+        let second = meter
+            .update(4_000, false, start + Duration::from_secs(1))
+            .expect("after interval");
+        assert_eq!(second.bytes_per_sec, 2_000);
+        assert_eq!(second.eta_secs, Some(3));
+
+        let done = meter
+            .update(10_000, true, start + Duration::from_millis(1_100))
+            .expect("final report");
+        assert!(done.done);
+        assert_eq!(done.eta_secs, Some(0));
+    }
+
+    #[test]
+    fn log_store_subscriber_persists_entries() {
+        let file = NamedTempFile::new().expect("temp db");
+        let conn = Connection::open(file.path()).expect("open db");
+        init_db(&conn).expect("init db");
+        let bus = EventBus::new();
+        bus.subscribe_log_store(LogStore::new(file.path().to_path_buf()));
+
+        bus.publish(SyncEvent::Log(LogEntry::new(
+            "task-1",
+            LogLevel::Info,
+            "upload",
+            "a.txt",
+        )));
+
+        let logs = list_logs(&conn, Some("task-1"), None, None, None).expect("logs");
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].detail, "a.txt");
+    }
+}
@@ -0,0 +1,249 @@
+use crate::core::sync::SyncStats;
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// 计算当前速率时回看的时长。
+pub const RATE_WINDOW_MS: i64 = 5_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AccountStats {
+    pub account_key: String,
+    pub rate_up: u64,
+    pub rate_down: u64,
+    pub active_tasks: u32,
+    pub active_transfers: u32,
+    pub uploaded_today: u64,
+    pub downloaded_today: u64,
+}
+
+/// 所有任务合计的传输情况，速率单位为字节每秒，今日字节数从应用启动后开始累计。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GlobalSnapshot {
+    pub rate_up: u64,
+    pub rate_down: u64,
+    pub active_tasks: u32,
+    pub active_transfers: u32,
+    pub uploaded_today: u64,
+    pub downloaded_today: u64,
+    pub accounts: Vec<AccountStats>,
+}
+
+#[derive(Default)]
+struct PassState {
+    account_key: String,
+    uploaded: u64,
+    downloaded: u64,
+    active_transfers: u32,
+}
+
+struct Sample {
+    at_ms: i64,
+    account_key: String,
+    uploaded: u64,
+    downloaded: u64,
+}
+
+#[derive(Default)]
+struct Totals {
+    day: Option<NaiveDate>,
+    passes: HashMap<String, PassState>,
+    samples: VecDeque<Sample>,
+    today: BTreeMap<String, (u64, u64)>,
+}
+
+impl Totals {
+    /// 跨过本地零点后清空今日累计。
+    fn roll_day(&mut self, now_ms: i64) {
+        let day = Local
+            .timestamp_millis_opt(now_ms)
+            .single()
+            .map(|time| time.date_naive());
+        if day != self.day {
+            self.day = day;
+            self.today.clear();
+        }
+    }
+
+    fn prune(&mut self, now_ms: i64) {
+        while self
+            .samples
+            .front()
+            .is_some_and(|sample| now_ms - sample.at_ms > RATE_WINDOW_MS)
+        {
+            self.samples.pop_front();
+        }
+    }
+}
+
+fn account_entry<'a>(
+    accounts: &'a mut BTreeMap<String, AccountStats>,
+    account_key: &str,
+) -> &'a mut AccountStats {
+    accounts
+        .entry(account_key.to_string())
+        .or_insert_with(|| AccountStats {
+            account_key: account_key.to_string(),
+            ..AccountStats::default()
+        })
+}
+
+/// 汇总各任务每轮同步上报的进度，供仪表盘显示全局速率与今日传输量。
+#[derive(Clone, Default)]
+pub struct GlobalStats {
+    totals: Arc<Mutex<Totals>>,
+}
+
+impl GlobalStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn begin_pass(&self, task_id: &str, account_key: &str) {
+        if let Ok(mut totals) = self.totals.lock() {
+            totals.passes.insert(
+                task_id.to_string(),
+                PassState {
+                    account_key: account_key.to_string(),
+                    ..PassState::default()
+                },
+            );
+        }
+    }
+
+    pub fn end_pass(&self, task_id: &str) {
+        if let Ok(mut totals) = self.totals.lock() {
+            totals.passes.remove(task_id);
+        }
+    }
+
+    /// `stats` 是本轮的累计值，只把与上次上报的差额计入。
+    pub fn record_progress(&self, task_id: &str, stats: &SyncStats, now_ms: i64) {
+        let Ok(mut totals) = self.totals.lock() else {
+            return;
+        };
+        totals.roll_day(now_ms);
+        let Some(pass) = totals.passes.get_mut(task_id) else {
+            return;
+        };
+        let uploaded = stats.uploaded_bytes.saturating_sub(pass.uploaded);
+        let downloaded = stats.downloaded_bytes.saturating_sub(pass.downloaded);
+        pass.uploaded = pass.uploaded.max(stats.uploaded_bytes);
+        pass.downloaded = pass.downloaded.max(stats.downloaded_bytes);
+        pass.active_transfers = stats.active_transfers;
+        if uploaded == 0 && downloaded == 0 {
+            return;
+        }
+        let account_key = pass.account_key.clone();
+        let today = totals.today.entry(account_key.clone()).or_default();
+        today.0 += uploaded;
+        today.1 += downloaded;
+        totals.samples.push_back(Sample {
+            at_ms: now_ms,
+            account_key,
+            uploaded,
+            downloaded,
+        });
+        totals.prune(now_ms);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.totals
+            .lock()
+            .map(|totals| !totals.passes.is_empty())
+            .unwrap_or(false)
+    }
+
+    pub fn snapshot(&self, now_ms: i64) -> GlobalSnapshot {
+        let Ok(mut totals) = self.totals.lock() else {
+            return GlobalSnapshot::default();
+        };
+        totals.roll_day(now_ms);
+        totals.prune(now_ms);
+        let mut accounts: BTreeMap<String, AccountStats> = BTreeMap::new();
+        for pass in totals.passes.values() {
+            let entry = account_entry(&mut accounts, &pass.account_key);
+            entry.active_tasks += 1;
+            entry.active_transfers += pass.active_transfers;
+        }
+        let window_secs = (RATE_WINDOW_MS / 1000) as u64;
+        for sample in &totals.samples {
+            let entry = account_entry(&mut accounts, &sample.account_key);
+            entry.rate_up += sample.uploaded;
+            entry.rate_down += sample.downloaded;
+        }
+        for (key, (uploaded, downloaded)) in &totals.today {
+            let entry = account_entry(&mut accounts, key);
+            entry.uploaded_today = *uploaded;
+            entry.downloaded_today = *downloaded;
+        }
+        let mut snapshot = GlobalSnapshot::default();
+        for mut entry in accounts.into_values() {
+            entry.rate_up /= window_secs;
+            entry.rate_down /= window_secs;
+            snapshot.rate_up += entry.rate_up;
+            snapshot.rate_down += entry.rate_down;
+            snapshot.active_tasks += entry.active_tasks;
+            snapshot.active_transfers += entry.active_transfers;
+            snapshot.uploaded_today += entry.uploaded_today;
+            snapshot.downloaded_today += entry.downloaded_today;
+            snapshot.accounts.push(entry);
+        }
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+    fn progress(uploaded_bytes: u64, downloaded_bytes: u64, active_transfers: u32) -> SyncStats {
+        SyncStats {
+            uploaded_bytes,
+            downloaded_bytes,
+            active_transfers,
+            ..SyncStats::default()
+        }
+    }
+
+    #[test]
+    fn aggregates_rates_and_today_bytes_per_account() {
+        let stats = GlobalStats::new();
+        let start = 10 * DAY_MS + 12 * 60 * 60 * 1000;
+        stats.begin_pass("a", "alice");
+        stats.begin_pass("b", "alice");
+        stats.begin_pass("c", "bob");
+        stats.record_progress("a", &progress(5_000, 0, 2), start);
+        stats.record_progress("a", &progress(10_000, 0, 1), start + 1_000);
+        stats.record_progress("b", &progress(0, 20_000, 1), start + 1_000);
+        stats.record_progress("c", &progress(0, 0, 3), start + 1_000);
+
+        let snapshot = stats.snapshot(start + 2_000);
+        assert_eq!(snapshot.rate_up, 2_000);
+        assert_eq!(snapshot.rate_down, 4_000);
+        assert_eq!(snapshot.active_tasks, 3);
+        assert_eq!(snapshot.active_transfers, 5);
+        assert_eq!(snapshot.uploaded_today, 10_000);
+        assert_eq!(snapshot.downloaded_today, 20_000);
+        let alice = &snapshot.accounts[0];
+        assert_eq!(alice.account_key, "alice");
+        assert_eq!(alice.active_tasks, 2);
+        assert_eq!(alice.uploaded_today, 10_000);
+        assert_eq!(snapshot.accounts[1].active_transfers, 3);
+
+        stats.end_pass("a");
+        stats.end_pass("b");
+        stats.end_pass("c");
+        assert!(!stats.is_active());
+        let idle = stats.snapshot(start + 2_000 + RATE_WINDOW_MS);
+        assert_eq!((idle.rate_up, idle.rate_down), (0, 0));
+        assert_eq!(idle.uploaded_today, 10_000);
+
+        let tomorrow = stats.snapshot(start + DAY_MS);
+        assert_eq!(tomorrow.uploaded_today, 0);
+        assert!(tomorrow.accounts.is_empty());
+    }
+}
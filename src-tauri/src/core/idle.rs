@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+/// 返回用户无输入的时长；无法检测时返回 None。
+pub fn user_idle_duration() -> Option<Duration> {
+    platform_idle_duration()
+}
+
+/// 检测不可用时视为空闲，避免深度扫描被无限推迟。
+pub fn is_idle_for(threshold: Duration, idle: Option<Duration>) -> bool {
+    idle.map(|idle| idle >= threshold).unwrap_or(true)
+}
+
+#[cfg(target_os = "windows")]
+fn platform_idle_duration() -> Option<Duration> {
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    // SAFETY: info 已按 API 要求初始化 cbSize。
+    let ok = unsafe { GetLastInputInfo(&mut info) };
+    if ok == 0 {
+        return None;
+    }
+    let now = unsafe { GetTickCount() };
+    Some(Duration::from_millis(now.wrapping_sub(info.dwTime) as u64))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_idle_duration() -> Option<Duration> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    parse_ioreg_idle(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(target_os = "linux")]
+fn platform_idle_duration() -> Option<Duration> {
+    let xprintidle = std::process::Command::new("xprintidle")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
+        .map(Duration::from_millis);
+    if xprintidle.is_some() {
+        return xprintidle;
+    }
+    let output = std::process::Command::new("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--dest",
+            "org.gnome.Mutter.IdleMonitor",
+            "--object-path",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    parse_gdbus_idle(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn platform_idle_duration() -> Option<Duration> {
+    None
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_gdbus_idle(text: &str) -> Option<Duration> {
+    let value = text
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .trim_end_matches(',')
+        .trim_start_matches("uint64")
+        .trim();
+    value.parse::<u64>().ok().map(Duration::from_millis)
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_ioreg_idle(text: &str) -> Option<Duration> {
+    text.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        if !key.contains("\"HIDIdleTime\"") {
+            return None;
+        }
+        value.trim().parse::<u64>().ok().map(Duration::from_nanos)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_idle_counts_as_idle() {
+        assert!(is_idle_for(Duration::from_secs(60), None));
+        assert!(!is_idle_for(
+            Duration::from_secs(60),
+            Some(Duration::from_secs(5))
+        ));
+        assert!(is_idle_for(
+            Duration::from_secs(60),
+            Some(Duration::from_secs(60))
+        ));
+    }
+
+    #[test]
+    fn parses_gdbus_idle_time() {
+        assert_eq!(
+            parse_gdbus_idle("(uint64 12345,)\n"),
+            Some(Duration::from_millis(12345))
+        );
+        assert_eq!(parse_gdbus_idle("Error"), None);
+    }
+
+    #[test]
+    fn parses_ioreg_idle_time() {
+        let text = "    | |   \"HIDIdleTime\" = 2500000000\n";
+        assert_eq!(parse_ioreg_idle(text), Some(Duration::from_millis(2500)));
+    }
+}
@@ -0,0 +1,197 @@
+use globset::{GlobBuilder, GlobMatcher};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// 本地根目录下的忽略规则文件，语法与 .gitignore 相近。
+pub const IGNORE_FILE: &str = ".cloudreveignore";
+
+#[derive(Debug, Clone)]
+struct Rule {
+    matcher: GlobMatcher,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// 任务的包含/排除规则；路径为相对本地根目录、以 `/` 分隔的路径。
+///
+/// 排除规则按顺序生效，后出现的规则覆盖先出现的，`!` 开头表示重新包含；
+/// 目录被排除后其下所有内容一并排除。包含规则非空时只同步匹配的文件。
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    include: Vec<Rule>,
+    exclude: Vec<Rule>,
+}
+
+/// 不含 `/` 的模式匹配任意层级的同名条目；以 `/` 开头或中间含 `/` 的模式相对根目录；以 `/` 结尾只匹配目录。
+fn parse_rule(pattern: &str) -> Result<Option<Rule>, String> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() || pattern.starts_with('#') {
+        return Ok(None);
+    }
+    let (negated, pattern) = match pattern.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let glob = match pattern.strip_prefix('/') {
+        Some(rest) => rest.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{}", pattern),
+    };
+    if glob.is_empty() || glob == "**/" {
+        return Err(format!("无效的匹配规则: {}", pattern));
+    }
+    let matcher = GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .map_err(|err| format!("无效的匹配规则: {} ({})", pattern, err))?
+        .compile_matcher();
+    Ok(Some(Rule {
+        matcher,
+        negated,
+        dir_only,
+    }))
+}
+
+fn parse_rules(patterns: &[String]) -> Result<Vec<Rule>, String> {
+    let mut rules = Vec::new();
+    for pattern in patterns {
+        rules.extend(parse_rule(pattern)?);
+    }
+    Ok(rules)
+}
+
+pub fn validate_patterns(patterns: &[String]) -> Result<(), String> {
+    parse_rules(patterns).map(|_| ())
+}
+
+/// 最后一条匹配的规则决定结果；没有规则匹配时返回 None。
+fn last_match(rules: &[Rule], relpath: &str, is_dir: bool) -> Option<bool> {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| (is_dir || !rule.dir_only) && rule.matcher.is_match(relpath))
+        .map(|rule| !rule.negated)
+}
+
+fn ancestors(relpath: &str) -> impl Iterator<Item = &str> {
+    relpath
+        .match_indices('/')
+        .map(move |(index, _)| &relpath[..index])
+}
+
+impl IgnoreRules {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            include: parse_rules(include)?,
+            exclude: parse_rules(exclude)?,
+        })
+    }
+
+    /// 追加根目录下 `.cloudreveignore` 中的排除规则；文件不存在时不做处理。
+    pub fn load_ignore_file(&mut self, root: &Path) -> Result<(), Box<dyn Error>> {
+        let path = root.join(IGNORE_FILE);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        for (index, line) in text.lines().enumerate() {
+            let rule = parse_rule(line)
+                .map_err(|err| format!("{} 第 {} 行: {}", path.display(), index + 1, err))?;
+            self.exclude.extend(rule);
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn is_ignored(&self, relpath: &str, is_dir: bool) -> bool {
+        let relpath = relpath.trim_matches('/');
+        if relpath.is_empty() {
+            return false;
+        }
+        if ancestors(relpath).any(|dir| last_match(&self.exclude, dir, true) == Some(true))
+            || last_match(&self.exclude, relpath, is_dir) == Some(true)
+        {
+            return true;
+        }
+        // 目录下可能有需要包含的文件，包含规则只作用于文件。
+        if is_dir || self.include.is_empty() {
+            return false;
+        }
+        let included = ancestors(relpath)
+            .any(|dir| last_match(&self.include, dir, true) == Some(true))
+            || last_match(&self.include, relpath, false) == Some(true);
+        !included
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn patterns(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn excludes_by_name_at_any_depth() {
+        let rules = IgnoreRules::new(&[], &patterns(&["node_modules", "*.tmp"])).expect("rules");
+        assert!(rules.is_ignored("node_modules", true));
+        assert!(rules.is_ignored("web/node_modules/pkg/index.js", false));
+        assert!(rules.is_ignored("a/b/c.tmp", false));
+        assert!(!rules.is_ignored("a/b/c.txt", false));
+        assert!(!rules.is_ignored("", true));
+    }
+
+    #[test]
+    fn anchored_and_dir_only_patterns() {
+        let rules = IgnoreRules::new(&[], &patterns(&["/build", "cache/"])).expect("rules");
+        assert!(rules.is_ignored("build/out.bin", false));
+        assert!(!rules.is_ignored("src/build/out.bin", false));
+        assert!(rules.is_ignored("src/cache/a", false));
+        assert!(!rules.is_ignored("src/cache", false));
+    }
+
+    #[test]
+    fn negation_reincludes_later_match() {
+        let rules = IgnoreRules::new(&[], &patterns(&["*.log", "!keep.log"])).expect("rules");
+        assert!(rules.is_ignored("a/debug.log", false));
+        assert!(!rules.is_ignored("a/keep.log", false));
+    }
+
+    #[test]
+    fn include_limits_files_but_not_dirs() {
+        let rules = IgnoreRules::new(&patterns(&["docs", "*.md"]), &[]).expect("rules");
+        assert!(!rules.is_ignored("docs/a/b.pdf", false));
+        assert!(!rules.is_ignored("src/readme.md", false));
+        assert!(rules.is_ignored("src/main.rs", false));
+        assert!(!rules.is_ignored("src", true));
+    }
+
+    #[test]
+    fn loads_ignore_file_and_reports_bad_line() {
+        let dir = tempdir().expect("tempdir");
+        let mut rules = IgnoreRules::default();
+        rules.load_ignore_file(dir.path()).expect("missing file");
+        assert!(rules.is_empty());
+
+        fs::write(dir.path().join(IGNORE_FILE), "# build output\ntarget/\n\n").expect("write");
+        rules.load_ignore_file(dir.path()).expect("load");
+        assert!(rules.is_ignored("target/debug/app", false));
+
+        fs::write(dir.path().join(IGNORE_FILE), "ok\n[z-a]\n").expect("write");
+        let err = IgnoreRules::default()
+            .load_ignore_file(dir.path())
+            .expect_err("invalid");
+        assert!(err.to_string().contains("第 2 行"));
+    }
+}
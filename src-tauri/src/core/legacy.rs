@@ -0,0 +1,286 @@
+use crate::core::cloudreve::CloudreveClient;
+use crate::core::db::{
+    create_task, list_accounts, upsert_account, upsert_entry, AccountRow, EntryRow, TaskRow,
+};
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub const LEGACY_CONFIG_NAME: &str = "config.json";
+pub const LEGACY_STATE_NAME: &str = "state.json";
+/// 旧版客户端没有保存邮箱时，新建账户使用的占位邮箱。
+const LEGACY_EMAIL: &str = "legacy";
+
+/// 旧版单目录客户端（FLTK 界面）的 config.json。
+#[derive(Debug, Clone, Deserialize)]
+struct LegacyConfig {
+    base_url: String,
+    #[serde(default)]
+    local_root: String,
+    #[serde(default = "default_remote_root")]
+    remote_root: String,
+    #[serde(default = "default_sync_interval_secs")]
+    sync_interval_secs: u64,
+    #[serde(default)]
+    email: String,
+    #[serde(default)]
+    access_token: String,
+    #[serde(default)]
+    refresh_token: String,
+}
+
+fn default_remote_root() -> String {
+    "/".to_string()
+}
+
+fn default_sync_interval_secs() -> u64 {
+    60
+}
+
+/// 旧版 state.json：以相对路径为键记录上次同步时的文件状态。
+#[derive(Debug, Default, Deserialize)]
+struct LegacyState {
+    #[serde(default, alias = "entries")]
+    files: BTreeMap<String, LegacyEntry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct LegacyEntry {
+    #[serde(default)]
+    size: Option<i64>,
+    #[serde(default, alias = "mtime")]
+    mtime_ms: i64,
+    #[serde(default)]
+    sha256: String,
+    #[serde(default, alias = "file_id")]
+    remote_id: String,
+    #[serde(default)]
+    remote_mtime_ms: Option<i64>,
+    #[serde(default)]
+    remote_sha256: Option<String>,
+    #[serde(default)]
+    synced_at_ms: Option<i64>,
+}
+
+/// 一次导入的结果，由调用方写入日志并保存令牌。
+#[derive(Debug)]
+pub struct LegacyImport {
+    /// 旧版配置没有本地目录时不创建任务，只归档文件。
+    pub task_id: Option<String>,
+    pub account_key: String,
+    pub entries: usize,
+    pub archive_dir: PathBuf,
+    /// 旧版配置中保存的令牌，为空表示需要重新登录。
+    pub tokens: Option<(String, String)>,
+}
+
+fn legacy_entry_row(
+    task_id: &str,
+    remote_root: &str,
+    relpath: &str,
+    entry: &LegacyEntry,
+    now_ms: i64,
+) -> EntryRow {
+    let relpath = relpath.replace('\\', "/").trim_matches('/').to_string();
+    EntryRow {
+        task_id: task_id.to_string(),
+        cloud_uri: format!("{}/{}", remote_root.trim_end_matches('/'), relpath),
+        local_relpath: relpath,
+        cloud_file_id: entry.remote_id.clone(),
+        last_local_mtime_ms: entry.mtime_ms,
+        last_local_sha256: entry.sha256.clone(),
+        last_local_size: entry.size.unwrap_or(-1),
+        last_remote_mtime_ms: entry.remote_mtime_ms.unwrap_or(entry.mtime_ms),
+        last_remote_sha256: entry
+            .remote_sha256
+            .clone()
+            .unwrap_or_else(|| entry.sha256.clone()),
+        last_sync_ts_ms: entry.synced_at_ms.unwrap_or(now_ms),
+        state: "ok".to_string(),
+    }
+}
+
+/// 同一服务器上只有一个已登录账户时沿用它，否则按旧版配置新建账户。
+fn legacy_account(
+    conn: &Connection,
+    config: &LegacyConfig,
+    now_ms: i64,
+) -> Result<String, Box<dyn Error>> {
+    let existing = list_accounts(conn)?
+        .into_iter()
+        .filter(|account| {
+            account.base_url == config.base_url
+                && (config.email.is_empty() || account.email == config.email)
+        })
+        .collect::<Vec<_>>();
+    if let [account] = existing.as_slice() {
+        return Ok(account.account_key.clone());
+    }
+    let email = if config.email.is_empty() {
+        LEGACY_EMAIL
+    } else {
+        config.email.as_str()
+    };
+    let account_key = format!("{}|{}", config.base_url, email);
+    upsert_account(
+        conn,
+        &AccountRow {
+            account_key: account_key.clone(),
+            base_url: config.base_url.clone(),
+            email: email.to_string(),
+            created_at_ms: now_ms,
+        },
+    )?;
+    Ok(account_key)
+}
+
+fn archive_legacy_files(dir: &Path, now_ms: i64) -> Result<PathBuf, Box<dyn Error>> {
+    let archive_dir = dir.join(format!("legacy-{}", now_ms));
+    fs::create_dir_all(&archive_dir)?;
+    for name in [LEGACY_CONFIG_NAME, LEGACY_STATE_NAME] {
+        let path = dir.join(name);
+        if path.exists() {
+            fs::rename(&path, archive_dir.join(name))?;
+        }
+    }
+    Ok(archive_dir)
+}
+
+/// 检测配置目录中旧版客户端留下的 config.json/state.json，转换为账户、任务与同步索引，
+/// 然后把旧文件移到 `legacy-<时间戳>` 目录，因此只会导入一次。
+/// `validate` 检查生成的任务设置能否被应用读取。
+pub fn import_legacy(
+    conn: &Connection,
+    dir: &Path,
+    now_ms: i64,
+    validate: impl Fn(&str) -> Result<(), String>,
+) -> Result<Option<LegacyImport>, Box<dyn Error>> {
+    let config_path = dir.join(LEGACY_CONFIG_NAME);
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    let config = serde_json::from_str::<LegacyConfig>(&fs::read_to_string(&config_path)?)
+        .map_err(|err| format!("旧版配置格式错误: {}", err))?;
+    let state_path = dir.join(LEGACY_STATE_NAME);
+    let state = if state_path.exists() {
+        serde_json::from_str::<LegacyState>(&fs::read_to_string(&state_path)?)
+            .map_err(|err| format!("旧版同步状态格式错误: {}", err))?
+    } else {
+        LegacyState::default()
+    };
+
+    let account_key = legacy_account(conn, &config, now_ms)?;
+    let tokens = (!config.access_token.is_empty() || !config.refresh_token.is_empty())
+        .then(|| (config.access_token.clone(), config.refresh_token.clone()));
+    let mut task_id = None;
+    let mut entries = 0;
+    let local_root = config.local_root.trim();
+    if !local_root.is_empty() {
+        let name = Path::new(local_root)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| local_root.to_string());
+        let settings: Value = json!({
+            "name": name,
+            "account_key": account_key,
+            "sync_interval_secs": config.sync_interval_secs.max(1),
+            "enabled": true,
+        });
+        let settings_json = settings.to_string();
+        validate(&settings_json)?;
+        let remote_root = CloudreveClient::build_file_uri(&config.remote_root);
+        let id = Uuid::new_v4().to_string();
+        let tx = conn.unchecked_transaction()?;
+        create_task(
+            &tx,
+            &TaskRow {
+                task_id: id.clone(),
+                base_url: config.base_url.clone(),
+                local_root: local_root.to_string(),
+                remote_root_uri: remote_root.clone(),
+                device_id: Uuid::new_v4().to_string(),
+                mode: "Bidirectional".to_string(),
+                settings_json,
+                created_at_ms: now_ms,
+            },
+        )?;
+        for (relpath, entry) in &state.files {
+            let row = legacy_entry_row(&id, &remote_root, relpath, entry, now_ms);
+            if row.local_relpath.is_empty() {
+                continue;
+            }
+            upsert_entry(&tx, &row)?;
+            entries += 1;
+        }
+        tx.commit()?;
+        task_id = Some(id);
+    }
+
+    let archive_dir = archive_legacy_files(dir, now_ms)?;
+    Ok(Some(LegacyImport {
+        task_id,
+        account_key,
+        entries,
+        archive_dir,
+        tokens,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::db::{init_db, list_entries_by_task, list_tasks};
+    use tempfile::tempdir;
+
+    #[test]
+    fn imports_legacy_config_and_state_once() {
+        let dir = tempdir().expect("tempdir");
+        fs::write(
+            dir.path().join(LEGACY_CONFIG_NAME),
+            r#"{"base_url":"https://cloud.example.com/api/v4","local_root":"/home/me/Cloud","remote_root":"/Backup","sync_interval_secs":120,"api_paths":{}}"#,
+        )
+        .expect("config");
+        fs::write(
+            dir.path().join(LEGACY_STATE_NAME),
+            r#"{"files":{"docs\\a.txt":{"size":5,"mtime_ms":1000,"sha256":"abc","remote_id":"f1"}}}"#,
+        )
+        .expect("state");
+        let conn = Connection::open_in_memory().expect("db");
+        init_db(&conn).expect("init");
+
+        let report = import_legacy(&conn, dir.path(), 42, |_| Ok(()))
+            .expect("import")
+            .expect("legacy files");
+        assert_eq!(report.entries, 1);
+        assert_eq!(
+            report.account_key,
+            "https://cloud.example.com/api/v4|legacy"
+        );
+        assert!(report.tokens.is_none());
+        let task = &list_tasks(&conn).expect("tasks")[0];
+        assert_eq!(Some(&task.task_id), report.task_id.as_ref());
+        assert_eq!(task.local_root, "/home/me/Cloud");
+        assert_eq!(task.remote_root_uri, "cloudreve://my/Backup");
+        let settings = serde_json::from_str::<Value>(&task.settings_json).expect("json");
+        assert_eq!(settings["sync_interval_secs"], 120);
+        assert_eq!(settings["name"], "Cloud");
+        let entries = list_entries_by_task(&conn, &task.task_id).expect("entries");
+        let entry = &entries[0];
+        assert_eq!(entry.local_relpath, "docs/a.txt");
+        assert_eq!(entry.cloud_uri, "cloudreve://my/Backup/docs/a.txt");
+        assert_eq!(entry.cloud_file_id, "f1");
+        assert_eq!(entry.last_local_size, 5);
+        assert_eq!(entry.last_remote_sha256, "abc");
+
+        assert!(!dir.path().join(LEGACY_CONFIG_NAME).exists());
+        assert!(report.archive_dir.join(LEGACY_STATE_NAME).exists());
+        assert!(import_legacy(&conn, dir.path(), 43, |_| Ok(()))
+            .expect("second import")
+            .is_none());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::core::cloudreve::CloudreveClient;
 use crate::core::db::LogRow;
+use crate::core::pool::connect;
 use chrono::Utc;
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
@@ -29,6 +31,8 @@ pub struct LogEntry {
     pub event: String,
     pub detail: String,
     pub created_at_ms: i64,
+    #[serde(default)]
+    pub batch_id: Option<String>,
 }
 
 impl LogEntry {
@@ -39,9 +43,15 @@ impl LogEntry {
             event: event.to_string(),
             detail: detail.to_string(),
             created_at_ms: Utc::now().timestamp_millis(),
+            batch_id: None,
         }
     }
 
+    pub fn with_batch(mut self, batch_id: &str) -> Self {
+        self.batch_id = Some(batch_id.to_string());
+        self
+    }
+
     pub fn to_row(&self) -> LogRow {
         LogRow {
             task_id: self.task_id.clone(),
@@ -49,6 +59,7 @@ impl LogEntry {
             event: self.event.clone(),
             detail: self.detail.clone(),
             created_at_ms: self.created_at_ms,
+            batch_id: self.batch_id.clone(),
         }
     }
 }
@@ -65,17 +76,23 @@ impl LogStore {
 
     pub fn append(&self, conn: &mut Connection, entry: &LogEntry) -> Result<(), Box<dyn Error>> {
         conn.execute(
-            "INSERT INTO logs (task_id, level, event, detail, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO logs (task_id, level, event, detail, created_at_ms, batch_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             (
                 entry.task_id.clone(),
                 entry.level.as_str().to_string(),
                 entry.event.clone(),
-                entry.detail.clone(),
+                CloudreveClient::redact_share_passwords(&entry.detail),
                 entry.created_at_ms,
+                entry.batch_id.clone(),
             ),
         )?;
         Ok(())
     }
+
+    pub fn record(&self, entry: &LogEntry) -> Result<(), Box<dyn Error>> {
+        let mut conn = connect(&self.db_path)?;
+        self.append(&mut conn, entry)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,182 @@
+use crate::core::db::{
+    delete_logs_before, delete_orphan_log_batches, delete_tombstones_before, trim_logs,
+};
+use crate::core::pool::connect;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// 日志与删除记录的保留规则，各项为 0 表示不限制。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_log_rows: u64,
+    pub info_days: u64,
+    pub warn_days: u64,
+    pub error_days: u64,
+    pub tombstone_days: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PruneReport {
+    pub logs_deleted: usize,
+    pub batches_deleted: usize,
+    pub tombstones_deleted: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactReport {
+    #[serde(flatten)]
+    pub pruned: PruneReport,
+    pub size_before: u64,
+    pub size_after: u64,
+    pub reclaimed_bytes: u64,
+}
+
+fn cutoff_ms(now_ms: i64, days: u64) -> Option<i64> {
+    (days > 0).then(|| now_ms - days as i64 * DAY_MS)
+}
+
+/// 按保留规则删除过期日志、超出行数上限的旧日志与过期的删除记录。
+pub fn prune(
+    conn: &Connection,
+    policy: &RetentionPolicy,
+    now_ms: i64,
+) -> Result<PruneReport, Box<dyn Error>> {
+    let mut report = PruneReport::default();
+    for (level, days) in [
+        ("info", policy.info_days),
+        ("warn", policy.warn_days),
+        ("error", policy.error_days),
+    ] {
+        if let Some(before_ms) = cutoff_ms(now_ms, days) {
+            report.logs_deleted += delete_logs_before(conn, level, before_ms)?;
+        }
+    }
+    if policy.max_log_rows > 0 {
+        report.logs_deleted += trim_logs(conn, policy.max_log_rows as i64)?;
+    }
+    report.batches_deleted = delete_orphan_log_batches(conn, now_ms - DAY_MS)?;
+    if let Some(before_ms) = cutoff_ms(now_ms, policy.tombstone_days) {
+        report.tombstones_deleted = delete_tombstones_before(conn, before_ms)?;
+    }
+    Ok(report)
+}
+
+/// 等待写锁超时后仍被占用时，改为提示用户稍后重试。
+fn busy_to_retry(err: Box<dyn Error>) -> Box<dyn Error> {
+    let busy = err
+        .downcast_ref::<rusqlite::Error>()
+        .and_then(|err| err.sqlite_error_code())
+        .is_some_and(|code| {
+            matches!(
+                code,
+                rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+            )
+        });
+    if busy {
+        "数据库正被同步任务使用，请稍后重试压缩".into()
+    } else {
+        err
+    }
+}
+
+/// 清理后执行 VACUUM 归还空闲页，返回清理数量与数据库文件缩小的字节数。
+/// VACUUM 需要独占数据库，调用方应先停下同步任务；仍被占用时返回提示稍后重试的错误。
+pub fn compact_database(
+    db_path: &Path,
+    policy: &RetentionPolicy,
+    now_ms: i64,
+) -> Result<CompactReport, Box<dyn Error>> {
+    let size_before = fs::metadata(db_path)?.len();
+    let conn = connect(db_path)?;
+    let pruned = prune(&conn, policy, now_ms).map_err(busy_to_retry)?;
+    conn.execute_batch("VACUUM;")
+        .map_err(|err| busy_to_retry(err.into()))?;
+    // WAL 模式下整理结果先写入日志文件，检查点之后主文件才会变小。
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    drop(conn);
+    let size_after = fs::metadata(db_path)?.len();
+    Ok(CompactReport {
+        pruned,
+        size_before,
+        size_after,
+        reclaimed_bytes: size_before.saturating_sub(size_after),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::db::{init_db, insert_log, insert_tombstone, LogRow, TombstoneRow};
+    use tempfile::tempdir;
+
+    fn log(level: &str, created_at_ms: i64) -> LogRow {
+        LogRow {
+            task_id: "t".to_string(),
+            level: level.to_string(),
+            event: "sync".to_string(),
+            detail: "x".repeat(512),
+            created_at_ms,
+            batch_id: None,
+        }
+    }
+
+    #[test]
+    fn prunes_by_level_age_and_row_limit_then_vacuums() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("cloudreve.db");
+        let conn = Connection::open(&db_path).expect("db");
+        init_db(&conn).expect("init");
+        let now = 100 * DAY_MS;
+        // 旧的 info 过期，同样旧的 error 仍在保留期内。
+        insert_log(&conn, &log("info", now - 40 * DAY_MS)).expect("old info");
+        insert_log(&conn, &log("error", now - 40 * DAY_MS)).expect("old error");
+        for offset in 0..500 {
+            insert_log(&conn, &log("info", now - offset)).expect("recent");
+        }
+        for (relpath, deleted_at_ms) in [("old.txt", now - 400 * DAY_MS), ("new.txt", now)] {
+            insert_tombstone(
+                &conn,
+                &TombstoneRow {
+                    task_id: "t".to_string(),
+                    cloud_file_id: String::new(),
+                    local_relpath: relpath.to_string(),
+                    deleted_at_ms,
+                    origin: "local".to_string(),
+                },
+            )
+            .expect("tombstone");
+        }
+        drop(conn);
+
+        let policy = RetentionPolicy {
+            max_log_rows: 100,
+            info_days: 30,
+            warn_days: 0,
+            error_days: 180,
+            tombstone_days: 365,
+        };
+        let report = compact_database(&db_path, &policy, now).expect("compact");
+        assert_eq!(report.pruned.logs_deleted, 1 + 401);
+        assert_eq!(report.pruned.tombstones_deleted, 1);
+        assert!(report.reclaimed_bytes > 0);
+        assert_eq!(
+            report.size_before - report.size_after,
+            report.reclaimed_bytes
+        );
+
+        let conn = Connection::open(&db_path).expect("reopen");
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM logs", [], |row| row.get(0))
+            .expect("count");
+        assert_eq!(remaining, 100);
+        assert_eq!(
+            prune(&conn, &policy, now).expect("again"),
+            PruneReport::default()
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// 超过该时长没有成功同步即视为同步停滞。
+pub const STALE_SUCCESS_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// 请求行与请求头合计最多读取的字节数，超出的请求直接断开。
+const MAX_REQUEST_HEAD_BYTES: u64 = 8 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskHealth {
+    pub last_success_ms: Option<i64>,
+    pub queue_depth: u32,
+    pub failed_passes: u64,
+    pub consecutive_failures: u32,
+    pub file_errors: u64,
+}
+
+/// 各任务的运行健康状况，供本地 `/metrics` 与 `/healthz` 读取。
+#[derive(Clone, Default)]
+pub struct HealthRegistry {
+    tasks: Arc<Mutex<BTreeMap<String, TaskHealth>>>,
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, task_id: &str, apply: impl FnOnce(&mut TaskHealth)) {
+        if let Ok(mut tasks) = self.tasks.lock() {
+            apply(tasks.entry(task_id.to_string()).or_default());
+        }
+    }
+
+    pub fn record_success(&self, task_id: &str, now_ms: i64) {
+        self.update(task_id, |health| {
+            health.last_success_ms = Some(now_ms);
+            health.consecutive_failures = 0;
+        });
+    }
+
+    pub fn record_failure(&self, task_id: &str) {
+        self.update(task_id, |health| {
+            health.failed_passes += 1;
+            health.consecutive_failures += 1;
+        });
+    }
+
+    pub fn record_file_error(&self, task_id: &str) {
+        self.update(task_id, |health| health.file_errors += 1);
+    }
+
+    pub fn set_queue_depth(&self, task_id: &str, queue_depth: u32) {
+        self.update(task_id, |health| health.queue_depth = queue_depth);
+    }
+
+    pub fn remove(&self, task_id: &str) {
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.remove(task_id);
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, TaskHealth> {
+        self.tasks
+            .lock()
+            .map(|tasks| tasks.clone())
+            .unwrap_or_default()
+    }
+}
+
+type MetricValue = fn(&TaskHealth, i64) -> Option<String>;
+
+/// 按请求携带的 Bearer 令牌判断是否允许读取；未携带时参数为 None。
+pub type Authorizer = Arc<dyn Fn(Option<&str>) -> bool + Send + Sync>;
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Prometheus 文本格式；尚未成功同步过的任务不输出 last_success_age。
+pub fn render_metrics(tasks: &BTreeMap<String, TaskHealth>, now_ms: i64) -> String {
+    let mut out = String::new();
+    let families: [(&str, &str, &str, MetricValue); 4] = [
+        (
+            "cloudreve_sync_last_success_age_seconds",
+            "gauge",
+            "Seconds since the last successful sync pass.",
+            |health, now_ms| {
+                health
+                    .last_success_ms
+                    .map(|ts| format!("{:.3}", (now_ms - ts).max(0) as f64 / 1000.0))
+            },
+        ),
+        (
+            "cloudreve_sync_queue_depth",
+            "gauge",
+            "Operations in the current sync pass.",
+            |health, _| Some(health.queue_depth.to_string()),
+        ),
+        (
+            "cloudreve_sync_failed_passes_total",
+            "counter",
+            "Sync passes that ended with an error.",
+            |health, _| Some(health.failed_passes.to_string()),
+        ),
+        (
+            "cloudreve_sync_file_errors_total",
+            "counter",
+            "Per-file sync errors.",
+            |health, _| Some(health.file_errors.to_string()),
+        ),
+    ];
+    for (name, kind, help, value) in families {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for (task_id, health) in tasks {
+            if let Some(value) = value(health, now_ms) {
+                let _ = writeln!(
+                    out,
+                    "{}{{task_id=\"{}\"}} {}",
+                    name,
+                    escape_label(task_id),
+                    value
+                );
+            }
+        }
+    }
+    out
+}
+
+pub fn is_stale(last_success_ms: Option<i64>, now_ms: i64) -> bool {
+    last_success_ms.is_some_and(|ts| now_ms - ts > STALE_SUCCESS_MS)
+}
+
+/// 任一任务最近一次同步失败或长时间没有成功同步即视为不健康。
+pub fn is_healthy(tasks: &BTreeMap<String, TaskHealth>, now_ms: i64) -> bool {
+    tasks
+        .values()
+        .all(|health| health.consecutive_failures == 0 && !is_stale(health.last_success_ms, now_ms))
+}
+
+fn bearer_token(header: &str) -> Option<String> {
+    let (name, value) = header.split_once(':')?;
+    if !name.trim().eq_ignore_ascii_case("authorization") {
+        return None;
+    }
+    let (scheme, token) = value.trim().split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| token.trim().to_string())
+}
+
+/// 读取请求行与请求头，返回请求行和其中的 Bearer 令牌。
+/// 请求头没有以空行结束或超过 [`MAX_REQUEST_HEAD_BYTES`] 时返回 `None`。
+fn read_request_head(stream: impl Read) -> Option<(String, Option<String>)> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_HEAD_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    if !request_line.ends_with('\n') {
+        return None;
+    }
+    // 读完请求头再响应，避免未读数据导致连接被重置。
+    let mut header = String::new();
+    let mut token = None;
+    loop {
+        header.clear();
+        reader.read_line(&mut header).ok()?;
+        if !header.ends_with('\n') {
+            return None;
+        }
+        if header.trim_end().is_empty() {
+            return Some((request_line, token));
+        }
+        token = token.or_else(|| bearer_token(&header));
+    }
+}
+
+fn respond(mut stream: TcpStream, registry: &HealthRegistry, now_ms: i64, authorize: &Authorizer) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let Some((request_line, token)) = read_request_head(&stream) else {
+        return;
+    };
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("").split('?').next().unwrap_or("");
+    let tasks = registry.snapshot();
+    let authorized = authorize(token.as_deref());
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics" | "/healthz") if !authorized => (
+            "401 Unauthorized",
+            "text/plain",
+            "unauthorized\n".to_string(),
+        ),
+        ("GET", "/metrics") => (
+            "200 OK",
+            "text/plain; version=0.0.4",
+            render_metrics(&tasks, now_ms),
+        ),
+        ("GET", "/healthz") if is_healthy(&tasks, now_ms) => {
+            ("200 OK", "text/plain", "ok\n".to_string())
+        }
+        ("GET", "/healthz") => (
+            "503 Service Unavailable",
+            "text/plain",
+            "degraded\n".to_string(),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+}
+
+/// 在后台线程上接受连接，只提供只读的 `/metrics` 与 `/healthz`。
+/// 每个连接在各自的线程上处理，慢速或空闲的客户端不会挡住其他请求。
+pub fn serve(
+    listener: TcpListener,
+    registry: HealthRegistry,
+    now_ms: fn() -> i64,
+    authorize: Authorizer,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for stream in listener.incoming().filter_map(Result::ok) {
+            let registry = registry.clone();
+            let authorize = authorize.clone();
+            thread::spawn(move || respond(stream, &registry, now_ms(), &authorize));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn renders_prometheus_text() {
+        let registry = HealthRegistry::new();
+        registry.record_success("a", 1_000);
+        registry.set_queue_depth("a", 4);
+        registry.record_failure("b");
+        registry.record_file_error("b");
+
+        let text = render_metrics(&registry.snapshot(), 3_500);
+        assert!(text.contains("# TYPE cloudreve_sync_last_success_age_seconds gauge"));
+        assert!(text.contains("cloudreve_sync_last_success_age_seconds{task_id=\"a\"} 2.500"));
+        assert!(!text.contains("cloudreve_sync_last_success_age_seconds{task_id=\"b\"}"));
+        assert!(text.contains("cloudreve_sync_queue_depth{task_id=\"a\"} 4"));
+        assert!(text.contains("cloudreve_sync_failed_passes_total{task_id=\"b\"} 1"));
+        assert!(text.contains("cloudreve_sync_file_errors_total{task_id=\"b\"} 1"));
+    }
+
+    #[test]
+    fn health_recovers_after_success() {
+        let registry = HealthRegistry::new();
+        assert!(is_healthy(&registry.snapshot(), 1));
+        registry.record_failure("a");
+        assert!(!is_healthy(&registry.snapshot(), 1));
+        registry.record_success("a", 1);
+        assert!(is_healthy(&registry.snapshot(), 1));
+        assert_eq!(registry.snapshot()["a"].failed_passes, 1);
+    }
+
+    #[test]
+    fn stale_success_is_unhealthy() {
+        let registry = HealthRegistry::new();
+        registry.record_success("a", 1_000);
+        assert!(is_healthy(&registry.snapshot(), 1_000 + STALE_SUCCESS_MS));
+        assert!(!is_healthy(&registry.snapshot(), 1_001 + STALE_SUCCESS_MS));
+        assert!(!is_stale(None, i64::MAX));
+    }
+
+    #[test]
+    fn serves_metrics_and_healthz() {
+        let registry = HealthRegistry::new();
+        registry.record_failure("a");
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr").to_string();
+        serve(listener, registry, || 0, Arc::new(|_| true));
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(&addr).expect("connect");
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).expect("write");
+            let mut response = String::new();
+            stream.read_to_string(&mut response).expect("read");
+            response
+        };
+        assert!(get("/healthz").starts_with("HTTP/1.1 503"));
+        assert!(get("/metrics").contains("cloudreve_sync_failed_passes_total{task_id=\"a\"} 1"));
+        assert!(get("/other").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn idle_connections_do_not_block_other_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr").to_string();
+        serve(listener, HealthRegistry::new(), || 0, Arc::new(|_| true));
+
+        let _idle = TcpStream::connect(&addr).expect("idle connect");
+        let mut stream = TcpStream::connect(&addr).expect("connect");
+        stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .expect("timeout");
+        write!(stream, "GET /healthz HTTP/1.1\r\nHost: localhost\r\n\r\n").expect("write");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("read");
+        assert!(response.starts_with("HTTP/1.1 200"));
+    }
+
+    #[test]
+    fn oversized_request_heads_are_rejected() {
+        let request = |header: &str| {
+            format!("GET /healthz HTTP/1.1\r\nHost: localhost\r\n{}\r\n", header).into_bytes()
+        };
+        let (line, token) =
+            read_request_head(&request("Authorization: Bearer secret\r\n")[..]).expect("head");
+        assert!(line.starts_with("GET /healthz"));
+        assert_eq!(token.as_deref(), Some("secret"));
+
+        let long = format!(
+            "X-Filler: {}\r\n",
+            "a".repeat(MAX_REQUEST_HEAD_BYTES as usize)
+        );
+        assert!(read_request_head(&request(&long)[..]).is_none());
+        // 没有以空行结束的请求头不完整。
+        assert!(read_request_head(&b"GET /healthz HTTP/1.1\r\nHost: localhost\r\n"[..]).is_none());
+    }
+
+    #[test]
+    fn requires_token_when_authorizer_rejects() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr").to_string();
+        serve(
+            listener,
+            HealthRegistry::new(),
+            || 0,
+            Arc::new(|token| token == Some("secret")),
+        );
+
+        let get = |auth: &str| {
+            let mut stream = TcpStream::connect(&addr).expect("connect");
+            write!(
+                stream,
+                "GET /healthz HTTP/1.1\r\nHost: localhost\r\n{}\r\n",
+                auth
+            )
+            .expect("write");
+            let mut response = String::new();
+            stream.read_to_string(&mut response).expect("read");
+            response
+        };
+        assert!(get("").starts_with("HTTP/1.1 401"));
+        assert!(get("Authorization: Bearer wrong\r\n").starts_with("HTTP/1.1 401"));
+        assert!(get("authorization: bearer secret\r\n").starts_with("HTTP/1.1 200"));
+    }
+}
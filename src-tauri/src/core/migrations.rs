@@ -0,0 +1,475 @@
+use crate::core::db::{now_ms, ENTRY_KIND_FILE};
+use rusqlite::{params, Connection, Result};
+use std::error::Error;
+
+type Migration = fn(&Connection) -> Result<()>;
+
+/// 按顺序执行的结构升级，版本号为下标加一。已发布的迁移不能再修改，新结构只能在末尾追加。
+const MIGRATIONS: &[(&str, Migration)] = &[
+    ("baseline", baseline),
+    ("entries.cloud_version_id", entry_version_id),
+    ("logs.created_at_index", logs_created_index),
+    ("entries.remote_sha256_index", entries_remote_sha256_index),
+    ("name_mappings", name_mappings),
+    ("locked_files", locked_files),
+    ("account_status", account_status),
+    ("transfer_queue.claim_index", transfer_queue_claim_index),
+    ("partial_downloads.etag", partial_download_etag),
+];
+
+/// 当前程序的数据库结构版本。
+pub fn latest_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+/// 数据库已执行到的迁移版本；没有 schema_version 表（新建或引入迁移前的数据库）时为 0。
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    let has_table: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+        [],
+        |row| row.get(0),
+    )?;
+    if has_table == 0 {
+        return Ok(0);
+    }
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// 已有数据的数据库还有未执行的迁移时返回 true，此时应先备份；空数据库无需备份。
+pub fn needs_migration(conn: &Connection) -> Result<bool> {
+    let tables: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name NOT IN ('schema_version', 'sqlite_sequence')",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(tables > 0 && schema_version(conn)? < latest_version())
+}
+
+/// 依次执行未执行的迁移，每个迁移与版本记录在同一事务中提交，返回升级后的版本。
+/// 由更新版本的程序升级过的数据库保持不动。
+pub fn migrate(conn: &Connection) -> Result<u32> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at_ms INTEGER NOT NULL
+        );",
+    )?;
+    let current = schema_version(conn)?;
+    for (index, (name, migration)) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        apply(conn, index as u32 + 1, name, *migration)?;
+    }
+    Ok(schema_version(conn)?.max(current))
+}
+
+/// 以 IMMEDIATE 事务执行一个迁移；另一个进程同时升级时，拿到写锁后发现已执行就跳过。
+fn apply(conn: &Connection, version: u32, name: &str, migration: Migration) -> Result<()> {
+    conn.execute_batch("BEGIN IMMEDIATE")?;
+    let result = (|| {
+        if schema_version(conn)? >= version {
+            return Ok(());
+        }
+        migration(conn)?;
+        conn.execute(
+            "INSERT INTO schema_version (version, name, applied_at_ms) VALUES (?1, ?2, ?3)",
+            params![version, name, now_ms()],
+        )?;
+        Ok(())
+    })();
+    match result {
+        Ok(()) => conn.execute_batch("COMMIT"),
+        Err(err) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(err)
+        }
+    }
+}
+
+/// 已有数据库需要升级时先调用 `backup`，备份失败则不升级，避免结构改到一半无法回退。
+pub fn migrate_with_backup(
+    conn: &Connection,
+    backup: impl FnOnce() -> Result<(), Box<dyn Error>>,
+) -> Result<u32, Box<dyn Error>> {
+    if needs_migration(conn)? {
+        backup().map_err(|err| format!("升级数据库前备份失败: {}", err))?;
+    }
+    Ok(migrate(conn)?)
+}
+
+/// 引入迁移之前的完整结构。旧版本创建的数据库可能缺少其中任意部分，所以全部语句都可重复执行。
+fn baseline(conn: &Connection) -> Result<()> {
+    let has_versions: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'entry_versions'",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS tasks (
+            task_id TEXT PRIMARY KEY,
+            base_url TEXT NOT NULL,
+            local_root TEXT NOT NULL,
+            remote_root_uri TEXT NOT NULL,
+            device_id TEXT NOT NULL,
+            mode TEXT NOT NULL,
+            settings_json TEXT NOT NULL,
+            created_at_ms INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS accounts (
+            account_key TEXT PRIMARY KEY,
+            base_url TEXT NOT NULL,
+            email TEXT NOT NULL,
+            created_at_ms INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS entries (
+            task_id TEXT NOT NULL,
+            local_relpath TEXT NOT NULL,
+            cloud_file_id TEXT NOT NULL,
+            cloud_uri TEXT NOT NULL,
+            last_local_mtime_ms INTEGER NOT NULL,
+            last_local_sha256 TEXT NOT NULL,
+            last_remote_mtime_ms INTEGER NOT NULL,
+            last_remote_sha256 TEXT NOT NULL,
+            last_sync_ts_ms INTEGER NOT NULL,
+            state TEXT NOT NULL,
+            PRIMARY KEY (task_id, local_relpath)
+        );
+
+        CREATE TABLE IF NOT EXISTS tombstones (
+            task_id TEXT NOT NULL,
+            cloud_file_id TEXT NOT NULL,
+            local_relpath TEXT NOT NULL,
+            deleted_at_ms INTEGER NOT NULL,
+            origin TEXT NOT NULL,
+            PRIMARY KEY (task_id, local_relpath)
+        );
+
+        CREATE TABLE IF NOT EXISTS conflicts (
+            task_id TEXT NOT NULL,
+            original_relpath TEXT NOT NULL,
+            conflict_relpath TEXT NOT NULL,
+            created_at_ms INTEGER NOT NULL,
+            reason TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id TEXT NOT NULL,
+            level TEXT NOT NULL,
+            event TEXT NOT NULL,
+            detail TEXT NOT NULL,
+            created_at_ms INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS scan_schedule (
+            task_id TEXT PRIMARY KEY,
+            last_quick_scan_ms INTEGER,
+            last_deep_scan_ms INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS account_defaults (
+            account_key TEXT PRIMARY KEY,
+            settings_json TEXT NOT NULL,
+            updated_at_ms INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS task_due (
+            task_id TEXT PRIMARY KEY,
+            next_due_at_ms INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS log_batches (
+            batch_id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            dir TEXT NOT NULL,
+            file_count INTEGER NOT NULL,
+            total_bytes INTEGER NOT NULL,
+            updated_at_ms INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS remote_dirs (
+            task_id TEXT NOT NULL,
+            relpath TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            children_json TEXT NOT NULL,
+            listed_at_ms INTEGER NOT NULL,
+            PRIMARY KEY (task_id, relpath)
+        );
+
+        CREATE TABLE IF NOT EXISTS partial_downloads (
+            task_id TEXT NOT NULL,
+            relpath TEXT NOT NULL,
+            file_id TEXT NOT NULL,
+            sha256 TEXT NOT NULL,
+            total_bytes INTEGER NOT NULL,
+            downloaded_bytes INTEGER NOT NULL,
+            temp_path TEXT NOT NULL,
+            updated_at_ms INTEGER NOT NULL,
+            PRIMARY KEY (task_id, relpath)
+        );
+
+        CREATE TABLE IF NOT EXISTS conflict_archive (
+            task_id TEXT NOT NULL,
+            archive_relpath TEXT NOT NULL,
+            original_relpath TEXT NOT NULL,
+            archived_at_ms INTEGER NOT NULL,
+            expires_at_ms INTEGER NOT NULL,
+            PRIMARY KEY (task_id, archive_relpath)
+        );
+
+        CREATE TABLE IF NOT EXISTS selective_excludes (
+            task_id TEXT NOT NULL,
+            relpath TEXT NOT NULL,
+            PRIMARY KEY (task_id, relpath)
+        );
+
+        CREATE TABLE IF NOT EXISTS task_runs (
+            task_id TEXT PRIMARY KEY,
+            last_attempt_ms INTEGER,
+            last_success_ms INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS sync_queue (
+            task_id TEXT NOT NULL,
+            relpath TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (task_id, relpath)
+        );
+
+        CREATE TABLE IF NOT EXISTS transfer_queue (
+            task_id TEXT NOT NULL,
+            relpath TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            priority INTEGER NOT NULL DEFAULT 0,
+            position INTEGER NOT NULL,
+            error TEXT,
+            updated_at_ms INTEGER NOT NULL,
+            PRIMARY KEY (task_id, relpath)
+        );
+
+        CREATE TABLE IF NOT EXISTS sync_runs (
+            run_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id TEXT NOT NULL,
+            finished_at_ms INTEGER NOT NULL,
+            success INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_versions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id TEXT NOT NULL,
+            relpath TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            sha256 TEXT NOT NULL,
+            cloud_file_id TEXT NOT NULL,
+            recorded_at_ms INTEGER NOT NULL,
+            deleted INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS api_tokens (
+            token_id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            scope TEXT NOT NULL,
+            token_sha256 TEXT NOT NULL UNIQUE,
+            created_at_ms INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS dir_activity (
+            task_id TEXT NOT NULL,
+            relpath TEXT NOT NULL,
+            score REAL NOT NULL,
+            skipped_passes INTEGER NOT NULL,
+            PRIMARY KEY (task_id, relpath)
+        );
+
+        CREATE INDEX IF NOT EXISTS entry_versions_path
+            ON entry_versions (task_id, relpath, recorded_at_ms);
+        "#,
+    )?;
+    ensure_column(conn, "logs", "batch_id", "TEXT")?;
+    ensure_column(conn, "entries", "kind", "TEXT NOT NULL DEFAULT 'file'")?;
+    ensure_column(
+        conn,
+        "entries",
+        "last_local_size",
+        "INTEGER NOT NULL DEFAULT -1",
+    )?;
+    if has_versions == 0 {
+        // 升级前已同步的文件以上次同步时间作为第一个版本。
+        conn.execute(
+            "INSERT INTO entry_versions (task_id, relpath, size, sha256, cloud_file_id, recorded_at_ms, deleted) SELECT task_id, local_relpath, last_local_size, CASE WHEN last_local_sha256 = '' THEN last_remote_sha256 ELSE last_local_sha256 END, cloud_file_id, last_sync_ts_ms, 0 FROM entries WHERE kind = ?1",
+            params![ENTRY_KIND_FILE],
+        )?;
+    }
+    Ok(())
+}
+
+/// 旧数据库的表缺少新列时补上；CREATE TABLE IF NOT EXISTS 不会修改已有表。
+fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn entry_version_id(conn: &Connection) -> Result<()> {
+    ensure_column(
+        conn,
+        "entries",
+        "cloud_version_id",
+        "TEXT NOT NULL DEFAULT ''",
+    )
+}
+
+/// 日志清理按级别与时间删除。
+fn logs_created_index(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS logs_level_created ON logs (level, created_at_ms);",
+    )
+}
+
+/// 上传前按内容摘要查找任务内已有的相同文件。
+fn entries_remote_sha256_index(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS entries_remote_sha256 ON entries (task_id, last_remote_sha256);",
+    )
+}
+
+/// Windows 无法创建的远端名称在本地使用的替代路径。
+fn name_mappings(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS name_mappings (
+            task_id TEXT NOT NULL,
+            remote_relpath TEXT NOT NULL,
+            local_relpath TEXT NOT NULL,
+            PRIMARY KEY (task_id, remote_relpath)
+        );
+        ",
+    )
+}
+
+/// 被其他程序占用、按退避时间暂缓同步的本地文件。
+fn locked_files(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS locked_files (
+            task_id TEXT NOT NULL,
+            relpath TEXT NOT NULL,
+            attempts INTEGER NOT NULL,
+            next_retry_ms INTEGER NOT NULL,
+            reason TEXT NOT NULL,
+            PRIMARY KEY (task_id, relpath)
+        );
+        ",
+    )
+}
+
+/// 账户最近一次成功鉴权的时间，以及刷新令牌失效后是否需要重新登录。
+fn account_status(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS account_status (
+            account_key TEXT PRIMARY KEY,
+            last_auth_ms INTEGER,
+            needs_login INTEGER NOT NULL DEFAULT 0,
+            reason TEXT NOT NULL DEFAULT '',
+            updated_at_ms INTEGER NOT NULL
+        );
+        ",
+    )
+}
+
+/// 按优先级取出下一个排队的传输。
+fn transfer_queue_claim_index(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS transfer_queue_claim ON transfer_queue (task_id, status, priority DESC, position);",
+    )
+}
+
+/// 续传时作为 If-Range 发送，远端内容已变化则从头下载。
+fn partial_download_etag(conn: &Connection) -> Result<()> {
+    ensure_column(
+        conn,
+        "partial_downloads",
+        "etag",
+        "TEXT NOT NULL DEFAULT ''",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_legacy_schema_once_in_order() {
+        let conn = Connection::open_in_memory().expect("db");
+        assert!(!needs_migration(&conn).expect("fresh"));
+        // 引入迁移前创建、缺少后来加入的列的数据库。
+        conn.execute_batch(
+            "CREATE TABLE entries (task_id TEXT NOT NULL, local_relpath TEXT NOT NULL, cloud_file_id TEXT NOT NULL, cloud_uri TEXT NOT NULL, last_local_mtime_ms INTEGER NOT NULL, last_local_sha256 TEXT NOT NULL, last_remote_mtime_ms INTEGER NOT NULL, last_remote_sha256 TEXT NOT NULL, last_sync_ts_ms INTEGER NOT NULL, state TEXT NOT NULL, PRIMARY KEY (task_id, local_relpath));
+             INSERT INTO entries VALUES ('t', 'a.txt', 'f1', 'cloudreve://my/a.txt', 1, 'abc', 1, 'abc', 5, 'ok');",
+        )
+        .expect("legacy");
+        assert!(needs_migration(&conn).expect("legacy"));
+
+        let mut backups = 0;
+        let version = migrate_with_backup(&conn, || {
+            backups += 1;
+            Ok(())
+        })
+        .expect("migrate");
+        assert_eq!(version, latest_version());
+        assert_eq!(backups, 1);
+        let versions = conn
+            .prepare("SELECT version, name FROM schema_version ORDER BY version")
+            .expect("prepare")
+            .query_map([], |row| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?))
+            })
+            .expect("query")
+            .collect::<Result<Vec<_>>>()
+            .expect("rows");
+        assert_eq!(versions.len(), MIGRATIONS.len());
+        assert_eq!(versions[0], (1, "baseline".to_string()));
+        let (kind, version_id): (String, String) = conn
+            .query_row(
+                "SELECT kind, cloud_version_id FROM entries WHERE local_relpath = 'a.txt'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("entry");
+        assert_eq!((kind.as_str(), version_id.as_str()), ("file", ""));
+
+        assert!(!needs_migration(&conn).expect("current"));
+        assert_eq!(
+            migrate_with_backup(&conn, || Err("不应备份".into())).expect("noop"),
+            latest_version()
+        );
+    }
+
+    #[test]
+    fn failed_backup_leaves_schema_untouched() {
+        let conn = Connection::open_in_memory().expect("db");
+        conn.execute_batch("CREATE TABLE tasks (task_id TEXT PRIMARY KEY);")
+            .expect("legacy");
+        assert!(migrate_with_backup(&conn, || Err("磁盘已满".into())).is_err());
+        assert_eq!(schema_version(&conn).expect("version"), 0);
+    }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const BUILTIN_TYPES: &[(&str, &str)] = &[
+    ("7z", "application/x-7z-compressed"),
+    ("aac", "audio/aac"),
+    ("avi", "video/x-msvideo"),
+    ("bmp", "image/bmp"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("epub", "application/epub+zip"),
+    ("flac", "audio/flac"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("heic", "image/heic"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("m4a", "audio/mp4"),
+    ("md", "text/markdown"),
+    ("mkv", "video/x-matroska"),
+    ("mov", "video/quicktime"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ogg", "audio/ogg"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("rar", "application/vnd.rar"),
+    ("rtf", "application/rtf"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("txt", "text/plain"),
+    ("wav", "audio/wav"),
+    ("webm", "video/webm"),
+    ("webp", "image/webp"),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("zip", "application/zip"),
+];
+
+fn normalize_extension(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_lowercase()
+}
+
+/// 按扩展名推断 MIME 类型，用户映射优先；未知类型返回 None，由服务端决定。
+pub fn guess_mime(path: &str, overrides: &BTreeMap<String, String>) -> Option<String> {
+    let ext = normalize_extension(Path::new(path).extension()?.to_str()?);
+    let custom = overrides
+        .iter()
+        .find(|(key, value)| normalize_extension(key) == ext && !value.trim().is_empty())
+        .map(|(_, value)| value.trim().to_string());
+    custom.or_else(|| {
+        BUILTIN_TYPES
+            .binary_search_by(|(key, _)| (*key).cmp(ext.as_str()))
+            .ok()
+            .map(|index| BUILTIN_TYPES[index].1.to_string())
+    })
+}
+
+/// 按类型筛选下载时使用的文件分组。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileTypeGroup {
+    Documents,
+    Images,
+    Videos,
+    Other,
+}
+
+fn is_document(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/pdf" | "application/msword" | "application/rtf" | "application/epub+zip"
+        )
+        || mime.starts_with("application/vnd.ms-")
+        || mime.starts_with("application/vnd.openxmlformats-officedocument.")
+        || mime.starts_with("application/vnd.oasis.opendocument.")
+}
+
+/// 按推断的 MIME 类型分组，无法推断的归入 Other。
+pub fn file_type_group(path: &str, overrides: &BTreeMap<String, String>) -> FileTypeGroup {
+    match guess_mime(path, overrides) {
+        Some(mime) if mime.starts_with("image/") => FileTypeGroup::Images,
+        Some(mime) if mime.starts_with("video/") => FileTypeGroup::Videos,
+        Some(mime) if is_document(&mime) => FileTypeGroup::Documents,
+        _ => FileTypeGroup::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_table_is_sorted() {
+        assert!(BUILTIN_TYPES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn guesses_by_extension_case_insensitively() {
+        let overrides = BTreeMap::new();
+        assert_eq!(
+            guess_mime("docs/Report.PDF", &overrides).as_deref(),
+            Some("application/pdf")
+        );
+        assert_eq!(guess_mime("notes", &overrides), None);
+        assert_eq!(guess_mime("data.unknownext", &overrides), None);
+    }
+
+    #[test]
+    fn user_mapping_overrides_builtin() {
+        let overrides = BTreeMap::from([
+            (".MD".to_string(), "text/plain".to_string()),
+            ("dwg".to_string(), "image/vnd.dwg".to_string()),
+        ]);
+        assert_eq!(
+            guess_mime("readme.md", &overrides).as_deref(),
+            Some("text/plain")
+        );
+        assert_eq!(
+            guess_mime("plan.dwg", &overrides).as_deref(),
+            Some("image/vnd.dwg")
+        );
+    }
+
+    #[test]
+    fn groups_files_by_mime_type() {
+        let overrides = BTreeMap::from([("raw".to_string(), "image/x-raw".to_string())]);
+        assert_eq!(
+            file_type_group("a/report.PDF", &overrides),
+            FileTypeGroup::Documents
+        );
+        assert_eq!(
+            file_type_group("sheet.xlsx", &overrides),
+            FileTypeGroup::Documents
+        );
+        assert_eq!(
+            file_type_group("notes.md", &overrides),
+            FileTypeGroup::Documents
+        );
+        assert_eq!(
+            file_type_group("photo.raw", &overrides),
+            FileTypeGroup::Images
+        );
+        assert_eq!(
+            file_type_group("movie.mkv", &overrides),
+            FileTypeGroup::Videos
+        );
+        assert_eq!(
+            file_type_group("song.mp3", &overrides),
+            FileTypeGroup::Other
+        );
+        assert_eq!(
+            file_type_group("Makefile", &overrides),
+            FileTypeGroup::Other
+        );
+    }
+}
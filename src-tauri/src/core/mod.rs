@@ -1,8 +1,43 @@
+pub mod api_tokens;
+pub mod backup;
 pub mod cloudreve;
+pub mod coalesce;
+pub mod compression;
 pub mod config;
+pub mod consistency;
 pub mod credentials;
 pub mod db;
+pub mod deeplink;
+pub mod defaults;
+pub mod encryption;
 pub mod error;
+pub mod events;
+pub mod global_stats;
+pub mod idle;
+pub mod ignore;
+pub mod legacy;
 pub mod logging;
+pub mod maintenance;
+pub mod metrics;
+pub mod migrations;
+pub mod mime;
+pub mod names;
+pub mod network;
+pub mod notify;
+pub mod placeholders;
+pub mod pool;
+pub mod provision;
+pub mod push;
+pub mod remote_info;
 pub mod requests;
+pub mod roots;
+pub mod runner;
+pub mod scan_hook;
+pub mod schedule;
+pub mod servers;
+pub mod service;
 pub mod sync;
+pub mod system_state;
+pub mod transport;
+pub mod watcher;
+pub mod xattrs;
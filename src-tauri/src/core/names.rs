@@ -0,0 +1,384 @@
+use icu_normalizer::ComposingNormalizerBorrowed;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// 冲突记录的类别：两个远端路径只差大小写。
+pub const CASE_COLLISION: &str = "case_collision";
+/// 冲突记录的类别：两个远端路径只差 Unicode 规范化形式（NFC 与 NFD）。
+pub const UNICODE_COLLISION: &str = "unicode_collision";
+
+/// Windows 保留的设备名，不区分大小写，带任何扩展名同样不能使用。
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+/// Windows 文件名中不允许的字符（控制字符另行处理）。
+const INVALID_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*', '\\'];
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+/// 本地文件系统比较文件名的方式。Cloudreve 区分大小写与规范化形式，
+/// 远端可以同时存在本地视为同一个文件的几个名称。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct NameFolding {
+    #[serde(default)]
+    pub case_insensitive: bool,
+    #[serde(default)]
+    pub normalizes_unicode: bool,
+}
+
+impl NameFolding {
+    /// Windows 与 macOS 默认不区分大小写，macOS 还把 NFC 与 NFD 写法视为同一名称。
+    pub fn native() -> Self {
+        Self {
+            case_insensitive: cfg!(any(windows, target_os = "macos")),
+            normalizes_unicode: cfg!(target_os = "macos"),
+        }
+    }
+
+    pub fn is_exact(self) -> bool {
+        !self.case_insensitive && !self.normalizes_unicode
+    }
+
+    /// 本地视为同一路径的写法得到相同的键，各级目录与文件名分别折叠。
+    pub fn key(self, relpath: &str) -> String {
+        relpath
+            .split('/')
+            .map(|segment| self.fold(segment))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn fold(self, name: &str) -> String {
+        let name = if self.normalizes_unicode {
+            nfc(name)
+        } else {
+            Cow::Borrowed(name)
+        };
+        if self.case_insensitive {
+            name.to_lowercase()
+        } else {
+            name.into_owned()
+        }
+    }
+
+    /// 本地视为同一目录的几种目录写法，按组返回，组内按路径排序。
+    /// 上级目录已经冲突时不再单独报告下级目录，上级改名后下一轮再检查。
+    pub fn dir_collisions<'a>(
+        self,
+        relpaths: impl IntoIterator<Item = &'a String>,
+    ) -> Vec<Vec<String>> {
+        if self.is_exact() {
+            return Vec::new();
+        }
+        let mut groups: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for relpath in relpaths {
+            for (index, _) in relpath.match_indices('/') {
+                let dir = &relpath[..index];
+                groups
+                    .entry(self.key(dir))
+                    .or_default()
+                    .insert(dir.to_string());
+            }
+        }
+        let colliding = groups
+            .iter()
+            .filter(|(_, group)| group.len() > 1)
+            .map(|(key, _)| key.clone())
+            .collect::<HashSet<_>>();
+        groups
+            .into_iter()
+            .filter(|(key, group)| {
+                group.len() > 1
+                    && !key
+                        .match_indices('/')
+                        .any(|(index, _)| colliding.contains(&key[..index]))
+            })
+            .map(|(_, group)| group.into_iter().collect())
+            .collect()
+    }
+
+    /// 按键分组，返回包含多个不同路径的组，组内按路径排序。
+    pub fn collisions<'a>(
+        self,
+        relpaths: impl IntoIterator<Item = &'a String>,
+    ) -> Vec<Vec<String>> {
+        if self.is_exact() {
+            return Vec::new();
+        }
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for relpath in relpaths {
+            let group = groups.entry(self.key(relpath)).or_default();
+            if !group.contains(relpath) {
+                group.push(relpath.clone());
+            }
+        }
+        groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort();
+                group
+            })
+            .collect()
+    }
+}
+
+pub fn nfc(text: &str) -> Cow<'_, str> {
+    ComposingNormalizerBorrowed::new_nfc().normalize(text)
+}
+
+/// 同一组中两个路径冲突的类别。
+pub fn collision_kind(a: &str, b: &str) -> &'static str {
+    if nfc(a) == nfc(b) {
+        UNICODE_COLLISION
+    } else {
+        CASE_COLLISION
+    }
+}
+
+pub fn is_name_collision(reason: &str) -> bool {
+    reason == CASE_COLLISION || reason == UNICODE_COLLISION
+}
+
+/// 文件名加上序号后的路径：`docs/Readme.md` -> `docs/Readme (2).md`。
+pub fn numbered_relpath(relpath: &str, number: u32) -> String {
+    let (dir, name) = match relpath.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, relpath),
+    };
+    let path = Path::new(name);
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(name);
+    let name = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => format!("{} ({}).{}", stem, number, ext),
+        None => format!("{} ({})", stem, number),
+    };
+    match dir {
+        Some(dir) => format!("{}/{}", dir, name),
+        None => name,
+    }
+}
+
+/// Windows 上无法创建的文件名换成可以创建的名称：不允许的字符换成 `_`，
+/// 结尾的点与空格换成 `_`，保留设备名后加 `_`（`aux.txt` -> `aux_.txt`）。名称可用时返回 `None`。
+pub fn windows_safe_name(name: &str) -> Option<String> {
+    let mut safe = name
+        .chars()
+        .map(|ch| {
+            if ch.is_control() || INVALID_CHARS.contains(&ch) {
+                '_'
+            } else {
+                ch
+            }
+        })
+        .collect::<String>();
+    let kept = safe.trim_end_matches(['.', ' ']).len();
+    let trailing = safe.len() - kept;
+    safe.truncate(kept);
+    safe.push_str(&"_".repeat(trailing));
+    let stem_len = safe.find('.').unwrap_or(safe.len());
+    let stem = safe[..stem_len].trim_end_matches(' ');
+    if RESERVED_NAMES
+        .iter()
+        .any(|name| stem.eq_ignore_ascii_case(name))
+    {
+        safe.insert(stem.len(), '_');
+    }
+    (safe != name).then_some(safe)
+}
+
+/// 远端路径与本地使用的安全路径之间的映射，按任务持久化。每一项都是完整的相对路径，
+/// 目录改名后其下的文件随之映射。
+#[derive(Debug, Default)]
+pub struct NameMap {
+    to_local: HashMap<String, String>,
+    to_remote: HashMap<String, String>,
+}
+
+impl NameMap {
+    pub fn new(pairs: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut map = Self::default();
+        for (remote, local) in pairs {
+            map.insert(remote, local);
+        }
+        map
+    }
+
+    pub fn insert(&mut self, remote: String, local: String) {
+        self.to_remote.insert(local.clone(), remote.clone());
+        self.to_local.insert(remote, local);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.to_local.is_empty()
+    }
+
+    /// 去掉远端路径及其下所有路径的映射。
+    pub fn remove_under(&mut self, remote: &str) {
+        let prefix = format!("{}/", remote);
+        let to_remote = &mut self.to_remote;
+        self.to_local.retain(|path, local| {
+            let keep = path != remote && !path.starts_with(&prefix);
+            if !keep {
+                to_remote.remove(local);
+            }
+            keep
+        });
+    }
+
+    /// 已映射的远端路径（目录或文件）在本地的路径。
+    pub fn mapped_local(&self, remote: &str) -> Option<&String> {
+        self.to_local.get(remote)
+    }
+
+    pub fn is_local_taken(&self, local: &str) -> bool {
+        self.to_remote.contains_key(local)
+    }
+
+    /// 远端相对路径对应的本地相对路径。
+    pub fn local_relpath(&self, relpath: &str) -> String {
+        translate(&self.to_local, relpath)
+    }
+
+    /// 本地相对路径对应的远端相对路径。
+    pub fn remote_relpath(&self, relpath: &str) -> String {
+        translate(&self.to_remote, relpath)
+    }
+}
+
+/// 逐级替换已映射的上级路径。
+fn translate(map: &HashMap<String, String>, relpath: &str) -> String {
+    if map.is_empty() {
+        return relpath.to_string();
+    }
+    let mut source = String::new();
+    let mut out = String::new();
+    for segment in relpath.split('/') {
+        source = join_segment(&source, segment);
+        out = match map.get(&source) {
+            Some(mapped) => mapped.clone(),
+            None => join_segment(&out, segment),
+        };
+    }
+    out
+}
+
+pub fn join_segment(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+/// Windows 上超过 MAX_PATH 的绝对路径加 `\\?\` 前缀，绕过长度限制；其他系统原样返回。
+#[cfg(windows)]
+pub fn long_path(path: PathBuf) -> PathBuf {
+    let raw = path.to_string_lossy();
+    if raw.len() < MAX_PATH || raw.starts_with(r"\\?\") || !path.is_absolute() {
+        return path;
+    }
+    let raw = raw.replace('/', "\\");
+    match raw.strip_prefix(r"\\") {
+        Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+        None => PathBuf::from(format!(r"\\?\{}", raw)),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: PathBuf) -> PathBuf {
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_case_and_normalization_variants() {
+        let folding = NameFolding {
+            case_insensitive: true,
+            normalizes_unicode: true,
+        };
+        let nfd = "docs/cafe\u{301}.txt".to_string();
+        let nfc_name = "docs/caf\u{e9}.txt".to_string();
+        let paths = [
+            "docs/Readme.md".to_string(),
+            "docs/README.md".to_string(),
+            nfd.clone(),
+            nfc_name.clone(),
+            "docs/other.md".to_string(),
+        ];
+        let groups = folding.collisions(&paths);
+        assert_eq!(groups.len(), 2);
+        assert!(groups.contains(&vec![
+            "docs/README.md".to_string(),
+            "docs/Readme.md".to_string()
+        ]));
+        assert_eq!(collision_kind(&nfd, &nfc_name), UNICODE_COLLISION);
+        assert_eq!(
+            collision_kind("docs/Readme.md", "docs/README.md"),
+            CASE_COLLISION
+        );
+
+        let case_only = NameFolding {
+            case_insensitive: true,
+            normalizes_unicode: false,
+        };
+        assert_eq!(case_only.collisions(&paths).len(), 1);
+        let exact = NameFolding {
+            case_insensitive: false,
+            normalizes_unicode: false,
+        };
+        assert!(exact.collisions(&paths).is_empty());
+
+        let nested = [
+            "Docs/a.txt".to_string(),
+            "docs/b.txt".to_string(),
+            "docs/Sub/c.txt".to_string(),
+            "Docs/sub/d.txt".to_string(),
+            "other/e.txt".to_string(),
+        ];
+        assert!(folding.collisions(&nested).is_empty());
+        assert_eq!(
+            folding.dir_collisions(&nested),
+            vec![vec!["Docs".to_string(), "docs".to_string()]]
+        );
+        assert!(exact.dir_collisions(&nested).is_empty());
+
+        assert_eq!(numbered_relpath("docs/Readme.md", 2), "docs/Readme (2).md");
+        assert_eq!(numbered_relpath("Makefile", 3), "Makefile (3)");
+    }
+
+    #[test]
+    fn maps_names_windows_cannot_create() {
+        assert_eq!(windows_safe_name("aux.txt").as_deref(), Some("aux_.txt"));
+        assert_eq!(windows_safe_name("CON").as_deref(), Some("CON_"));
+        assert_eq!(
+            windows_safe_name("Com1.tar.gz").as_deref(),
+            Some("Com1_.tar.gz")
+        );
+        assert_eq!(windows_safe_name("notes.").as_deref(), Some("notes_"));
+        assert_eq!(windows_safe_name("a:b?.txt ").as_deref(), Some("a_b_.txt_"));
+        assert_eq!(windows_safe_name("console.txt"), None);
+        assert_eq!(windows_safe_name("report.txt"), None);
+
+        let mut map = NameMap::new([
+            ("con".to_string(), "con_".to_string()),
+            ("con/aux.txt".to_string(), "con_/aux_.txt".to_string()),
+        ]);
+        assert_eq!(map.local_relpath("con/aux.txt"), "con_/aux_.txt");
+        assert_eq!(map.local_relpath("con/b.txt"), "con_/b.txt");
+        assert_eq!(map.remote_relpath("con_/aux_.txt"), "con/aux.txt");
+        assert_eq!(map.remote_relpath("other/aux_.txt"), "other/aux_.txt");
+
+        map.remove_under("con");
+        assert!(map.is_empty());
+        assert!(!map.is_local_taken("con_/aux_.txt"));
+    }
+}
@@ -0,0 +1,462 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    pub name: String,
+    #[serde(default)]
+    pub ssids: Vec<String>,
+    #[serde(default)]
+    pub gateways: Vec<String>,
+    #[serde(default)]
+    pub upload_limit_kbps: Option<u64>,
+    #[serde(default)]
+    pub download_limit_kbps: Option<u64>,
+    #[serde(default)]
+    pub concurrency: Option<u32>,
+    #[serde(default)]
+    pub pause_sync: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NetworkSnapshot {
+    pub ssid: Option<String>,
+    pub gateway: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferLimits {
+    pub upload_bytes_per_sec: Option<u64>,
+    pub download_bytes_per_sec: Option<u64>,
+    pub concurrency: Option<u32>,
+}
+
+/// 单个分片请求至少按该速率估算耗时，慢速上行链路上大分片不会因固定超时失败。
+const CHUNK_MIN_BYTES_PER_SEC: u64 = 16 * 1024;
+
+/// 分片上传参数：期望的最大分片大小与分片请求的基础超时。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSettings {
+    pub max_chunk_bytes: Option<u64>,
+    pub timeout_base: Duration,
+}
+
+impl Default for ChunkSettings {
+    fn default() -> Self {
+        Self {
+            max_chunk_bytes: None,
+            timeout_base: Duration::from_secs(60),
+        }
+    }
+}
+
+impl ChunkSettings {
+    /// 基础超时加上按最低速率传完 `len` 字节所需的时间。
+    pub fn timeout_for(&self, len: u64) -> Duration {
+        self.timeout_base + Duration::from_secs(len / CHUNK_MIN_BYTES_PER_SEC)
+    }
+}
+
+/// 请求超时：`connect` 为建立连接的上限，`read` 为等待响应及每次读取响应体的上限；
+/// `file_deadline` 限制单个文件的一次传输，超时后取消该文件，本轮其余文件继续。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutSettings {
+    pub connect: Duration,
+    pub read: Duration,
+    pub file_deadline: Option<Duration>,
+}
+
+impl Default for TimeoutSettings {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(15),
+            read: Duration::from_secs(60),
+            file_deadline: None,
+        }
+    }
+}
+
+/// 代理设置。`System` 使用系统代理与 `HTTP(S)_PROXY`、`ALL_PROXY` 等环境变量；
+/// `Manual` 的地址支持 http、https、socks5、socks5h 与 socks4，用户名为空时不做代理认证。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ProxySettings {
+    #[default]
+    System,
+    Direct,
+    Manual {
+        url: String,
+        username: String,
+        password: String,
+    },
+}
+
+/// 重试间隔的增长方式，对应设置中的「指数退避 / 线性退避 / 固定间隔」。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    Exponential,
+    Linear,
+    Fixed,
+}
+
+impl Backoff {
+    pub fn from_label(label: &str) -> Self {
+        match label {
+            "线性退避" => Backoff::Linear,
+            "固定间隔" => Backoff::Fixed,
+            _ => Backoff::Exponential,
+        }
+    }
+}
+
+/// 瞬时网络错误的重试策略，`retries` 为首次请求之后最多再试的次数。默认不重试。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub backoff: Backoff,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: Backoff::Exponential,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `attempt` 次重试（从 1 开始）前的等待时间。`jitter` 取 [0, 1)，实际等待为计算值的 50%~100%，
+    /// 避免多个任务在同一时刻一起重试。
+    pub fn delay_for(&self, attempt: u32, jitter: f64) -> Duration {
+        let attempt = attempt.max(1);
+        let delay = match self.backoff {
+            Backoff::Exponential => self
+                .base_delay
+                .saturating_mul(2u32.saturating_pow(attempt - 1)),
+            Backoff::Linear => self.base_delay.saturating_mul(attempt),
+            Backoff::Fixed => self.base_delay,
+        };
+        delay
+            .min(self.max_delay)
+            .mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0)
+    }
+}
+
+/// 取 [0, 1) 的随机数用于重试抖动；标准库的 RandomState 每次创建都带有随机种子。
+pub fn retry_jitter() -> f64 {
+    let seed = RandomState::new().build_hasher().finish();
+    (seed >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl NetworkProfile {
+    pub fn matches(&self, snapshot: &NetworkSnapshot) -> bool {
+        let ssid_match = snapshot
+            .ssid
+            .as_deref()
+            .map(|ssid| self.ssids.iter().any(|item| item == ssid))
+            .unwrap_or(false);
+        let gateway_match = snapshot
+            .gateway
+            .as_deref()
+            .map(|gateway| self.gateways.iter().any(|item| item == gateway))
+            .unwrap_or(false);
+        ssid_match || gateway_match
+    }
+
+    pub fn limits(&self) -> TransferLimits {
+        TransferLimits {
+            upload_bytes_per_sec: self.upload_limit_kbps.filter(|v| *v > 0).map(|v| v * 1024),
+            download_bytes_per_sec: self
+                .download_limit_kbps
+                .filter(|v| *v > 0)
+                .map(|v| v * 1024),
+            concurrency: self.concurrency.filter(|v| *v > 0),
+        }
+    }
+}
+
+/// 手动选择优先；否则按 SSID/网关匹配第一个配置。
+pub fn select_profile<'a>(
+    profiles: &'a [NetworkProfile],
+    manual: &str,
+    snapshot: &NetworkSnapshot,
+) -> Option<&'a NetworkProfile> {
+    let manual = manual.trim();
+    if !manual.is_empty() {
+        return profiles.iter().find(|profile| profile.name == manual);
+    }
+    profiles.iter().find(|profile| profile.matches(snapshot))
+}
+
+/// 令牌桶限速：同一方向的所有传输共用一个桶，并发传输合计不超过速率上限。
+/// 桶容量为一秒的流量，超出的部分记为欠额，由之后的传输等待偿还。
+pub struct Bandwidth {
+    bucket: Mutex<Option<Bucket>>,
+}
+
+struct Bucket {
+    available: f64,
+    updated: Instant,
+}
+
+static UPLOAD_BANDWIDTH: Bandwidth = Bandwidth::new();
+static DOWNLOAD_BANDWIDTH: Bandwidth = Bandwidth::new();
+
+pub fn upload_bandwidth() -> &'static Bandwidth {
+    &UPLOAD_BANDWIDTH
+}
+
+pub fn download_bandwidth() -> &'static Bandwidth {
+    &DOWNLOAD_BANDWIDTH
+}
+
+impl Bandwidth {
+    const fn new() -> Self {
+        Self {
+            bucket: Mutex::new(None),
+        }
+    }
+
+    /// 记入 `bytes` 字节，返回为满足速率上限还需等待的时间；没有上限时不等待。
+    pub fn reserve(&self, bytes: u64, limit_bytes_per_sec: Option<u64>) -> Duration {
+        let Some(rate) = limit_bytes_per_sec.filter(|v| *v > 0).map(|v| v as f64) else {
+            return Duration::ZERO;
+        };
+        let Ok(mut bucket) = self.bucket.lock() else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        let bucket = bucket.get_or_insert(Bucket {
+            available: rate,
+            updated: now,
+        });
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * rate;
+        bucket.available = (bucket.available + refill).min(rate) - bytes as f64;
+        bucket.updated = now;
+        if bucket.available >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.available / rate)
+        }
+    }
+
+    /// 记入 `bytes` 字节并异步等待，不占用运行时的工作线程。
+    pub async fn acquire(&self, bytes: u64, limit_bytes_per_sec: Option<u64>) {
+        let delay = self.reserve(bytes, limit_bytes_per_sec);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+pub fn detect_network() -> NetworkSnapshot {
+    NetworkSnapshot {
+        ssid: detect_ssid(),
+        gateway: detect_gateway(),
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn detect_ssid() -> Option<String> {
+    command_output("iwgetid", &["-r"])
+        .map(|text| text.trim().to_string())
+        .filter(|ssid| !ssid.is_empty())
+}
+
+#[cfg(target_os = "windows")]
+fn detect_ssid() -> Option<String> {
+    let text = command_output("netsh", &["wlan", "show", "interfaces"])?;
+    text.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if key.trim() == "SSID" {
+            Some(value.trim().to_string()).filter(|ssid| !ssid.is_empty())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn detect_ssid() -> Option<String> {
+    let text = command_output("networksetup", &["-getairportnetwork", "en0"])?;
+    text.split_once(": ")
+        .map(|(_, ssid)| ssid.trim().to_string())
+        .filter(|ssid| !ssid.is_empty())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn detect_ssid() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn detect_gateway() -> Option<String> {
+    let text = std::fs::read_to_string("/proc/net/route").ok()?;
+    parse_proc_route_gateway(&text)
+}
+
+#[cfg(target_os = "windows")]
+fn detect_gateway() -> Option<String> {
+    let text = command_output("route", &["print", "0.0.0.0"])?;
+    text.lines().find_map(|line| {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        if parts.len() >= 3 && parts[0] == "0.0.0.0" && parts[1] == "0.0.0.0" {
+            Some(parts[2].to_string())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn detect_gateway() -> Option<String> {
+    let text = command_output("route", &["-n", "get", "default"])?;
+    text.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if key.trim() == "gateway" {
+            Some(value.trim().to_string())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn detect_gateway() -> Option<String> {
+    None
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_route_gateway(text: &str) -> Option<String> {
+    text.lines().skip(1).find_map(|line| {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        if parts.len() < 3 || parts[1] != "00000000" {
+            return None;
+        }
+        let raw = u32::from_str_radix(parts[2], 16).ok()?;
+        let bytes = raw.to_le_bytes();
+        Some(format!(
+            "{}.{}.{}.{}",
+            bytes[0], bytes[1], bytes[2], bytes[3]
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, ssid: &str) -> NetworkProfile {
+        NetworkProfile {
+            name: name.to_string(),
+            ssids: vec![ssid.to_string()],
+            ..NetworkProfile::default()
+        }
+    }
+
+    #[test]
+    fn select_profile_prefers_manual_choice() {
+        let profiles = vec![profile("home", "HomeWifi"), profile("office", "Corp")];
+        let snapshot = NetworkSnapshot {
+            ssid: Some("HomeWifi".to_string()),
+            gateway: None,
+        };
+        assert_eq!(
+            select_profile(&profiles, "", &snapshot).map(|p| p.name.as_str()),
+            Some("home")
+        );
+        assert_eq!(
+            select_profile(&profiles, "office", &snapshot).map(|p| p.name.as_str()),
+            Some("office")
+        );
+        assert!(select_profile(&profiles, "", &NetworkSnapshot::default()).is_none());
+    }
+
+    #[test]
+    fn retry_delays_follow_backoff_and_cap() {
+        let policy = RetryPolicy {
+            retries: 5,
+            backoff: Backoff::from_label("指数退避"),
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+        };
+        assert_eq!(policy.delay_for(2, 0.5), Duration::from_millis(1500));
+        assert_eq!(policy.delay_for(3, 0.0), Duration::from_secs(2));
+        assert_eq!(policy.delay_for(8, 0.0), Duration::from_secs(5));
+        let linear = RetryPolicy {
+            backoff: Backoff::from_label("线性退避"),
+            ..policy
+        };
+        assert_eq!(linear.delay_for(3, 0.0), Duration::from_millis(1500));
+        let fixed = RetryPolicy {
+            backoff: Backoff::from_label("固定间隔"),
+            ..policy
+        };
+        assert_eq!(fixed.delay_for(4, 0.0), Duration::from_millis(500));
+        let jitter = retry_jitter();
+        assert!((0.0..1.0).contains(&jitter));
+    }
+
+    #[test]
+    fn limits_convert_kbps_and_ignore_zero() {
+        let profile = NetworkProfile {
+            upload_limit_kbps: Some(100),
+            download_limit_kbps: Some(0),
+            concurrency: Some(2),
+            ..NetworkProfile::default()
+        };
+        let limits = profile.limits();
+        assert_eq!(limits.upload_bytes_per_sec, Some(102_400));
+        assert_eq!(limits.download_bytes_per_sec, None);
+        assert_eq!(limits.concurrency, Some(2));
+    }
+
+    #[test]
+    fn bandwidth_is_shared_between_transfers() {
+        let bandwidth = Bandwidth::new();
+        assert_eq!(bandwidth.reserve(4096, None), Duration::ZERO);
+        // 首秒的额度可以直接使用，之后的传输共同偿还欠额。
+        assert_eq!(bandwidth.reserve(1024, Some(1024)), Duration::ZERO);
+        let first = bandwidth.reserve(1024, Some(1024));
+        let second = bandwidth.reserve(1024, Some(1024));
+        assert!(first > Duration::from_millis(900) && first <= Duration::from_secs(1));
+        assert!(second > Duration::from_millis(1900) && second <= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn chunk_timeout_grows_with_chunk_size() {
+        let settings = ChunkSettings::default();
+        assert_eq!(settings.timeout_for(0), Duration::from_secs(60));
+        assert_eq!(
+            settings.timeout_for(100 * 1024 * 1024),
+            Duration::from_secs(60 + 6400)
+        );
+    }
+
+    #[test]
+    fn parse_proc_route_reads_default_gateway() {
+        let text = "Iface\tDestination\tGateway\tFlags\n\
+                    eth0\t0000A8C0\t00000000\t0001\n\
+                    eth0\t00000000\t0101A8C0\t0003\n";
+        assert_eq!(
+            parse_proc_route_gateway(text),
+            Some("192.168.1.1".to_string())
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::core::config::AppSettings;
+use crate::core::schedule::in_quiet_hours;
+use crate::core::sync::SyncStats;
+
+/// 系统通知的类别，每类可在设置中单独开关。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyKind {
+    Completed,
+    Error,
+    Conflict,
+    Auth,
+}
+
+impl NotifyKind {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Completed => "同步完成",
+            Self::Error => "同步失败",
+            Self::Conflict => "有冲突需要处理",
+            Self::Auth => "登录已失效",
+        }
+    }
+}
+
+/// 免打扰时段内不发送任何通知；起止小时相同表示不启用。
+pub fn should_notify(settings: &AppSettings, kind: NotifyKind, hour: u32) -> bool {
+    let enabled = match kind {
+        NotifyKind::Completed => settings.notify_completed,
+        NotifyKind::Error => settings.notify_errors,
+        NotifyKind::Conflict => settings.notify_conflicts,
+        NotifyKind::Auth => settings.notify_auth,
+    };
+    enabled && !in_quiet_hours(settings.notify_quiet_start, settings.notify_quiet_end, hour)
+}
+
+/// 没有传输任何内容的一轮同步不值得提醒，返回 `None`。
+pub fn completed_message(stats: &SyncStats) -> Option<String> {
+    if stats.operations == 0 {
+        return None;
+    }
+    Some(format!(
+        "处理 {} 项，上传 {}，下载 {}",
+        stats.operations,
+        format_bytes(stats.uploaded_bytes),
+        format_bytes(stats.downloaded_bytes)
+    ))
+}
+
+/// 本轮产生了新冲突，或达到副本上限的文件比上次多时提醒；`previously_held` 是上一轮的上限文件数。
+pub fn conflicts_message(stats: &SyncStats, previously_held: u32) -> Option<String> {
+    let held = stats.held_conflicts > previously_held;
+    match (stats.new_conflicts, held) {
+        (0, false) => None,
+        (0, true) => Some(format!(
+            "{} 个文件的冲突副本已达上限，需要手动处理",
+            stats.held_conflicts
+        )),
+        (new, false) => Some(format!("产生了 {} 个新冲突，请在冲突中心处理", new)),
+        (new, true) => Some(format!(
+            "产生了 {} 个新冲突，另有 {} 个文件的冲突副本已达上限，需要手动处理",
+            new, stats.held_conflicts
+        )),
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn respects_category_toggles_and_quiet_hours() {
+        let mut settings = AppSettings {
+            notify_completed: false,
+            notify_quiet_start: 22,
+            notify_quiet_end: 7,
+            ..AppSettings::default()
+        };
+        assert!(!should_notify(&settings, NotifyKind::Completed, 12));
+        assert!(should_notify(&settings, NotifyKind::Error, 12));
+        assert!(!should_notify(&settings, NotifyKind::Error, 23));
+        assert!(should_notify(&settings, NotifyKind::Auth, 7));
+        settings.notify_quiet_end = 22;
+        assert!(should_notify(&settings, NotifyKind::Conflict, 23));
+    }
+
+    #[test]
+    fn completed_message_skips_idle_passes() {
+        assert_eq!(completed_message(&SyncStats::default()), None);
+        let stats = SyncStats {
+            operations: 3,
+            uploaded_bytes: 512,
+            downloaded_bytes: 3 * 1024 * 1024 / 2,
+            ..SyncStats::default()
+        };
+        assert_eq!(
+            completed_message(&stats).as_deref(),
+            Some("处理 3 项，上传 512 B，下载 1.5 MB")
+        );
+    }
+
+    #[test]
+    fn conflicts_message_reports_new_conflicts_even_when_the_held_count_is_unchanged() {
+        assert_eq!(conflicts_message(&SyncStats::default(), 0), None);
+        let stats = SyncStats {
+            new_conflicts: 1,
+            held_conflicts: 2,
+            ..SyncStats::default()
+        };
+        assert_eq!(
+            conflicts_message(&stats, 2).as_deref(),
+            Some("产生了 1 个新冲突，请在冲突中心处理")
+        );
+        let held = SyncStats {
+            held_conflicts: 2,
+            ..SyncStats::default()
+        };
+        assert_eq!(conflicts_message(&held, 2), None);
+        assert_eq!(
+            conflicts_message(&held, 1).as_deref(),
+            Some("2 个文件的冲突副本已达上限，需要手动处理")
+        );
+    }
+}
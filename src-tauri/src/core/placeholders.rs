@@ -0,0 +1,686 @@
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_os = "windows"))]
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 占位文件随文件一起保存的身份信息，按需下载时据此找到远端文件；体积受系统限制，只放必要字段。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaceholderIdentity {
+    pub uri: String,
+    pub sha256: String,
+}
+
+impl PlaceholderIdentity {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+}
+
+/// 把身份对应的远端文件完整下载到给定路径，由提供方在系统请求数据时调用。
+pub type Fetcher = Arc<dyn Fn(&PlaceholderIdentity, &Path) -> Result<(), String> + Send + Sync>;
+
+#[cfg(not(target_os = "windows"))]
+const UNSUPPORTED: &str = "当前平台的占位文件不支持打开时自动下载";
+
+/// 当前平台能否创建按需下载的占位文件。
+pub fn supported() -> bool {
+    cfg!(any(target_os = "windows", unix))
+}
+
+/// 占位文件能否在被打开时自动下载内容；不能时需要先在应用中固定文件。
+pub fn hydrates_on_open() -> bool {
+    cfg!(target_os = "windows")
+}
+
+/// 更小的文件直接下载：空文件无需占位；macOS 与 Linux 的占位文件打不开，为几 KB 的文件不值得。
+#[cfg(target_os = "windows")]
+const MIN_PLACEHOLDER_SIZE: u64 = 1;
+#[cfg(not(target_os = "windows"))]
+const MIN_PLACEHOLDER_SIZE: u64 = 64 * 1024;
+
+/// `size` 字节的远端文件能否只创建占位文件。
+pub fn can_placehold(size: u64) -> bool {
+    supported() && size >= MIN_PLACEHOLDER_SIZE
+}
+
+#[cfg(target_os = "windows")]
+pub use cfapi::{
+    connect, create_placeholder, dehydrated_identity, register_sync_root, set_pinned,
+    unregister_sync_root, SyncRootConnection,
+};
+
+#[cfg(not(target_os = "windows"))]
+pub use stub::{create_placeholder, dehydrated_identity, set_pinned};
+
+#[cfg(not(target_os = "windows"))]
+pub struct SyncRootConnection;
+
+#[cfg(not(target_os = "windows"))]
+pub fn register_sync_root(_root: &Path, _task_id: &str) -> Result<(), Box<dyn Error>> {
+    Err(UNSUPPORTED.into())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn unregister_sync_root(_root: &Path) -> Result<(), Box<dyn Error>> {
+    Err(UNSUPPORTED.into())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn connect(_root: &Path, _fetcher: Fetcher) -> Result<SyncRootConnection, Box<dyn Error>> {
+    Err(UNSUPPORTED.into())
+}
+
+/// macOS 与 Linux 上的占位文件：开头写入标记与身份信息，其余部分是稀疏的空洞，
+/// 大小与修改时间和远端一致，不占用实际磁盘空间；打开前需要先固定下载。
+#[cfg(not(target_os = "windows"))]
+mod stub {
+    use super::{Fetcher, PlaceholderIdentity};
+    use crate::core::sync::hash_file;
+    use filetime::FileTime;
+    use std::error::Error;
+    use std::fs::{self, File};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::path::{Path, PathBuf};
+
+    const MAGIC: &[u8] = b"CLOUDREVE-SYNC-STUB\n";
+    const IDENTITY_MAX: u64 = 4 * 1024;
+
+    fn sibling_temp(path: &Path, kind: &str) -> PathBuf {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        path.with_file_name(format!(
+            ".{}.cloudreve-{}-{}",
+            name,
+            kind,
+            uuid::Uuid::new_v4().simple()
+        ))
+    }
+
+    fn set_mtime(path: &Path, mtime_ms: i64) -> std::io::Result<()> {
+        let mtime = FileTime::from_unix_time(
+            mtime_ms.div_euclid(1000),
+            (mtime_ms.rem_euclid(1000) * 1_000_000) as u32,
+        );
+        filetime::set_file_mtime(path, mtime)
+    }
+
+    fn mtime_ms(metadata: &fs::Metadata) -> i64 {
+        let mtime = FileTime::from_last_modification_time(metadata);
+        mtime.unix_seconds() * 1000 + (mtime.nanoseconds() / 1_000_000) as i64
+    }
+
+    /// 先写到同目录的临时文件再改名，扫描时不会看到只写了一半的占位文件。
+    pub fn create_placeholder(
+        target: &Path,
+        size: u64,
+        mtime_ms: i64,
+        identity: &PlaceholderIdentity,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut header = MAGIC.to_vec();
+        header.extend(identity.encode());
+        header.push(b'\n');
+        if header.len() as u64 > size.min(IDENTITY_MAX) {
+            return Err("文件过小或远端路径过长，无法创建占位文件".into());
+        }
+        let temp = sibling_temp(target, "stub");
+        let result = (|| {
+            let mut file = File::create(&temp)?;
+            file.write_all(&header)?;
+            file.set_len(size)?;
+            drop(file);
+            set_mtime(&temp, mtime_ms)?;
+            fs::rename(&temp, target)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        Ok(result?)
+    }
+
+    /// 文件是占位文件时返回其身份信息，只读取开头的标记。
+    pub fn dehydrated_identity(path: &Path) -> Option<PlaceholderIdentity> {
+        let mut file = File::open(path).ok()?;
+        let mut magic = vec![0u8; MAGIC.len()];
+        file.read_exact(&mut magic).ok()?;
+        if magic != MAGIC {
+            return None;
+        }
+        let mut line = Vec::new();
+        BufReader::new(file.take(IDENTITY_MAX))
+            .read_until(b'\n', &mut line)
+            .ok()?;
+        PlaceholderIdentity::decode(line.strip_suffix(b"\n")?)
+    }
+
+    /// 固定时下载完整内容替换占位文件；取消固定时把与远端一致的文件换回占位文件，
+    /// 本地有未同步修改的文件不会被释放。
+    pub fn set_pinned(
+        path: &Path,
+        pinned: bool,
+        identity: &PlaceholderIdentity,
+        fetcher: &Fetcher,
+    ) -> Result<(), Box<dyn Error>> {
+        let metadata = fs::metadata(path)?;
+        let mtime_ms = mtime_ms(&metadata);
+        match (pinned, dehydrated_identity(path)) {
+            (true, Some(stored)) => {
+                let temp = sibling_temp(path, "hydrate");
+                let result = (|| -> Result<(), Box<dyn Error>> {
+                    fetcher(&stored, &temp)?;
+                    let size = fs::metadata(&temp)?.len();
+                    if size != metadata.len() {
+                        return Err(format!(
+                            "远端文件大小已变化 ({} / {})，未下载；请同步任务更新占位文件后再试",
+                            metadata.len(),
+                            size
+                        )
+                        .into());
+                    }
+                    if !stored.sha256.is_empty() && hash_file(&temp)? != stored.sha256 {
+                        return Err("下载内容与记录的 sha256 不一致".into());
+                    }
+                    set_mtime(&temp, mtime_ms)?;
+                    fs::rename(&temp, path)?;
+                    Ok(())
+                })();
+                if result.is_err() {
+                    let _ = fs::remove_file(&temp);
+                }
+                result
+            }
+            (false, None) => {
+                if identity.sha256.is_empty() || hash_file(path)? != identity.sha256 {
+                    return Err("本地有未同步的修改，不能释放空间".into());
+                }
+                create_placeholder(path, metadata.len(), mtime_ms, identity)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 毫秒时间戳换算为 Windows 文件时间（1601 年起的 100 纳秒数）。
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn file_time_from_ms(mtime_ms: i64) -> i64 {
+    (mtime_ms + 11_644_473_600_000) * 10_000
+}
+
+#[cfg(target_os = "windows")]
+mod cfapi {
+    use super::{file_time_from_ms, Fetcher, PlaceholderIdentity};
+    use std::error::Error;
+    use std::ffi::c_void;
+    use std::fs::{File, OpenOptions};
+    use std::io::Read;
+    use std::mem::{offset_of, size_of};
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::{MetadataExt, OpenOptionsExt};
+    use std::os::windows::io::AsRawHandle;
+    use std::path::Path;
+    use std::ptr::{null, null_mut};
+    use windows_sys::core::{GUID, HRESULT};
+    use windows_sys::Win32::Foundation::{HANDLE, NTSTATUS, STATUS_UNSUCCESSFUL};
+    use windows_sys::Win32::Storage::CloudFilters::*;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_ATTRIBUTE_NORMAL, FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS, FILE_BASIC_INFO,
+    };
+
+    const PROVIDER_NAME: &str = "Cloudreve Sync";
+    const PROVIDER_ID: GUID = GUID::from_u128(0x6f1c_2a4e_93b7_4d0a_8c55_1e7f_3b2d_9a61);
+    /// 每次交给系统的数据量，必须是 4 KiB 的整数倍。
+    const TRANSFER_CHUNK: usize = 4 * 1024 * 1024;
+    const IDENTITY_MAX: usize = 4 * 1024;
+
+    fn wide(text: &std::ffi::OsStr) -> Vec<u16> {
+        text.encode_wide().chain(std::iter::once(0)).collect()
+    }
+
+    fn check(result: HRESULT, action: &str) -> Result<(), Box<dyn Error>> {
+        if result < 0 {
+            return Err(format!("{}失败: 0x{:08X}", action, result as u32).into());
+        }
+        Ok(())
+    }
+
+    /// 把本地目录注册为同步根目录，重复注册会更新已有登记。
+    pub fn register_sync_root(root: &Path, task_id: &str) -> Result<(), Box<dyn Error>> {
+        let root = wide(root.as_os_str());
+        let name = wide(PROVIDER_NAME.as_ref());
+        let version = wide(env!("CARGO_PKG_VERSION").as_ref());
+        let registration = CF_SYNC_REGISTRATION {
+            StructSize: size_of::<CF_SYNC_REGISTRATION>() as u32,
+            ProviderName: name.as_ptr(),
+            ProviderVersion: version.as_ptr(),
+            SyncRootIdentity: task_id.as_ptr() as *const c_void,
+            SyncRootIdentityLength: task_id.len() as u32,
+            FileIdentity: null(),
+            FileIdentityLength: 0,
+            ProviderId: PROVIDER_ID,
+        };
+        let policies = CF_SYNC_POLICIES {
+            StructSize: size_of::<CF_SYNC_POLICIES>() as u32,
+            Hydration: CF_HYDRATION_POLICY {
+                Primary: CF_HYDRATION_POLICY_FULL,
+                Modifier: CF_HYDRATION_POLICY_MODIFIER_NONE,
+            },
+            Population: CF_POPULATION_POLICY {
+                Primary: CF_POPULATION_POLICY_ALWAYS_FULL,
+                Modifier: CF_POPULATION_POLICY_MODIFIER_NONE,
+            },
+            InSync: CF_INSYNC_POLICY_TRACK_ALL,
+            HardLink: CF_HARDLINK_POLICY_NONE,
+            PlaceholderManagement: CF_PLACEHOLDER_MANAGEMENT_POLICY_DEFAULT,
+        };
+        // SAFETY: 所有指针在调用期间有效，结构体大小已按 API 要求填写。
+        let result = unsafe {
+            CfRegisterSyncRoot(
+                root.as_ptr(),
+                &registration,
+                &policies,
+                CF_REGISTER_FLAG_UPDATE,
+            )
+        };
+        check(result, "注册同步根目录")
+    }
+
+    pub fn unregister_sync_root(root: &Path) -> Result<(), Box<dyn Error>> {
+        let root = wide(root.as_os_str());
+        // SAFETY: root 是以 0 结尾的宽字符串。
+        check(
+            unsafe { CfUnregisterSyncRoot(root.as_ptr()) },
+            "注销同步根目录",
+        )
+    }
+
+    struct ProviderContext {
+        fetcher: Fetcher,
+        callbacks: [CF_CALLBACK_REGISTRATION; 2],
+    }
+
+    /// 持有期间本进程为同步根目录提供数据，丢弃后断开，占位文件此时无法按需下载。
+    pub struct SyncRootConnection {
+        key: CF_CONNECTION_KEY,
+        _context: Box<ProviderContext>,
+    }
+
+    impl Drop for SyncRootConnection {
+        fn drop(&mut self) {
+            // SAFETY: key 来自成功的 CfConnectSyncRoot，且只断开一次。
+            unsafe {
+                CfDisconnectSyncRoot(self.key);
+            }
+        }
+    }
+
+    pub fn connect(root: &Path, fetcher: Fetcher) -> Result<SyncRootConnection, Box<dyn Error>> {
+        let root = wide(root.as_os_str());
+        let context = Box::new(ProviderContext {
+            fetcher,
+            callbacks: [
+                CF_CALLBACK_REGISTRATION {
+                    Type: CF_CALLBACK_TYPE_FETCH_DATA,
+                    Callback: Some(on_fetch_data),
+                },
+                CF_CALLBACK_REGISTRATION {
+                    Type: CF_CALLBACK_TYPE_NONE,
+                    Callback: None,
+                },
+            ],
+        });
+        let mut key: CF_CONNECTION_KEY = 0;
+        // SAFETY: 回调表与上下文放在 Box 中，连接断开前不会移动或释放。
+        let result = unsafe {
+            CfConnectSyncRoot(
+                root.as_ptr(),
+                context.callbacks.as_ptr(),
+                &*context as *const ProviderContext as *const c_void,
+                CF_CONNECT_FLAG_NONE,
+                &mut key,
+            )
+        };
+        check(result, "连接同步根目录")?;
+        Ok(SyncRootConnection {
+            key,
+            _context: context,
+        })
+    }
+
+    struct FetchRequest {
+        connection_key: CF_CONNECTION_KEY,
+        transfer_key: i64,
+        request_key: i64,
+        file_size: i64,
+        required_offset: i64,
+        required_length: i64,
+        identity: Option<PlaceholderIdentity>,
+    }
+
+    unsafe extern "system" fn on_fetch_data(
+        info: *const CF_CALLBACK_INFO,
+        parameters: *const CF_CALLBACK_PARAMETERS,
+    ) {
+        // SAFETY: 系统保证回调期间两个指针有效，CallbackContext 是 connect 传入的上下文。
+        let (info, fetch) = unsafe { (&*info, (*parameters).Anonymous.FetchData) };
+        let context = unsafe { &*(info.CallbackContext as *const ProviderContext) };
+        let identity = if info.FileIdentity.is_null() {
+            None
+        } else {
+            let bytes = unsafe {
+                std::slice::from_raw_parts(
+                    info.FileIdentity as *const u8,
+                    info.FileIdentityLength as usize,
+                )
+            };
+            PlaceholderIdentity::decode(bytes)
+        };
+        let request = FetchRequest {
+            connection_key: info.ConnectionKey,
+            transfer_key: info.TransferKey,
+            request_key: info.RequestKey,
+            file_size: info.FileSize,
+            required_offset: fetch.RequiredFileOffset,
+            required_length: fetch.RequiredLength,
+            identity,
+        };
+        let fetcher = context.fetcher.clone();
+        // 下载可能很慢，不能占用系统的回调线程。
+        std::thread::spawn(move || serve_fetch(&request, &fetcher));
+    }
+
+    fn serve_fetch(request: &FetchRequest, fetcher: &Fetcher) {
+        let temp = std::env::temp_dir().join(format!(
+            "cloudreve-hydrate-{}",
+            uuid::Uuid::new_v4().simple()
+        ));
+        let result = fetch_into(request, fetcher, &temp);
+        let _ = std::fs::remove_file(&temp);
+        if result.is_err() {
+            let _ = transfer(
+                request,
+                null(),
+                request.required_offset,
+                request.required_length,
+                STATUS_UNSUCCESSFUL,
+            );
+        }
+    }
+
+    fn fetch_into(
+        request: &FetchRequest,
+        fetcher: &Fetcher,
+        temp: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        let identity = request.identity.as_ref().ok_or("占位文件缺少远端信息")?;
+        fetcher(identity, temp)?;
+        let mut file = File::open(temp)?;
+        if file.metadata()?.len() as i64 != request.file_size {
+            return Err("远端文件大小已变化，未下载；请同步任务更新占位文件后再打开".into());
+        }
+        let mut buffer = vec![0u8; TRANSFER_CHUNK];
+        let mut offset = 0i64;
+        while offset < request.file_size {
+            let mut filled = 0;
+            while filled < buffer.len() {
+                let read = file.read(&mut buffer[filled..])?;
+                if read == 0 {
+                    break;
+                }
+                filled += read;
+            }
+            if filled == 0 {
+                break;
+            }
+            check(
+                transfer(
+                    request,
+                    buffer.as_ptr() as *const c_void,
+                    offset,
+                    filled as i64,
+                    0,
+                ),
+                "传输占位文件数据",
+            )?;
+            offset += filled as i64;
+        }
+        Ok(())
+    }
+
+    fn transfer(
+        request: &FetchRequest,
+        buffer: *const c_void,
+        offset: i64,
+        length: i64,
+        status: NTSTATUS,
+    ) -> HRESULT {
+        let info = CF_OPERATION_INFO {
+            StructSize: size_of::<CF_OPERATION_INFO>() as u32,
+            Type: CF_OPERATION_TYPE_TRANSFER_DATA,
+            ConnectionKey: request.connection_key,
+            TransferKey: request.transfer_key,
+            CorrelationVector: null(),
+            SyncStatus: null(),
+            RequestKey: request.request_key,
+        };
+        let mut parameters = CF_OPERATION_PARAMETERS {
+            ParamSize: (offset_of!(CF_OPERATION_PARAMETERS, Anonymous)
+                + size_of::<CF_OPERATION_PARAMETERS_0_0>()) as u32,
+            Anonymous: CF_OPERATION_PARAMETERS_0 {
+                TransferData: CF_OPERATION_PARAMETERS_0_0 {
+                    Flags: CF_OPERATION_TRANSFER_DATA_FLAG_NONE,
+                    CompletionStatus: status,
+                    Buffer: buffer,
+                    Offset: offset,
+                    Length: length,
+                },
+            },
+        };
+        // SAFETY: buffer 在调用期间有效，长度与 length 一致。
+        unsafe { CfExecute(&info, &mut parameters) }
+    }
+
+    /// 在 `target` 处创建不含数据的占位文件，大小与修改时间取自远端。
+    pub fn create_placeholder(
+        target: &Path,
+        size: u64,
+        mtime_ms: i64,
+        identity: &PlaceholderIdentity,
+    ) -> Result<(), Box<dyn Error>> {
+        let parent = target.parent().ok_or("占位文件缺少上级目录")?;
+        let name = target.file_name().ok_or("占位文件缺少文件名")?;
+        let parent = wide(parent.as_os_str());
+        let name = wide(name);
+        let blob = identity.encode();
+        if blob.len() > IDENTITY_MAX {
+            return Err("远端路径过长，无法创建占位文件".into());
+        }
+        let time = file_time_from_ms(mtime_ms);
+        let mut info = CF_PLACEHOLDER_CREATE_INFO {
+            RelativeFileName: name.as_ptr(),
+            FsMetadata: CF_FS_METADATA {
+                BasicInfo: FILE_BASIC_INFO {
+                    CreationTime: time,
+                    LastAccessTime: time,
+                    LastWriteTime: time,
+                    ChangeTime: time,
+                    FileAttributes: FILE_ATTRIBUTE_NORMAL,
+                },
+                FileSize: size as i64,
+            },
+            FileIdentity: blob.as_ptr() as *const c_void,
+            FileIdentityLength: blob.len() as u32,
+            Flags: CF_PLACEHOLDER_CREATE_FLAG_MARK_IN_SYNC,
+            Result: 0,
+            CreateUsn: 0,
+        };
+        let mut created = 0u32;
+        // SAFETY: info 中的指针在调用期间有效，只创建一个占位文件。
+        let result = unsafe {
+            CfCreatePlaceholders(
+                parent.as_ptr(),
+                &mut info,
+                1,
+                CF_CREATE_FLAG_NONE,
+                &mut created,
+            )
+        };
+        check(result, "创建占位文件")?;
+        check(info.Result, "创建占位文件")
+    }
+
+    /// 文件是尚未下载内容的占位文件时返回其身份信息；读取过程不会触发下载。
+    pub fn dehydrated_identity(path: &Path) -> Option<PlaceholderIdentity> {
+        let attributes = std::fs::metadata(path).ok()?.file_attributes();
+        if attributes & FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS == 0 {
+            return None;
+        }
+        // 只申请读取属性的权限，打开占位文件不会下载内容。
+        let file = OpenOptions::new().access_mode(0x80).open(path).ok()?;
+        let header = size_of::<CF_PLACEHOLDER_BASIC_INFO>();
+        let mut buffer = vec![0u64; (header + IDENTITY_MAX).div_ceil(8)];
+        let mut returned = 0u32;
+        // SAFETY: buffer 按 8 字节对齐且足够容纳基本信息与身份数据。
+        let result = unsafe {
+            CfGetPlaceholderInfo(
+                file.as_raw_handle() as HANDLE,
+                CF_PLACEHOLDER_INFO_BASIC,
+                buffer.as_mut_ptr() as *mut c_void,
+                (buffer.len() * 8) as u32,
+                &mut returned,
+            )
+        };
+        if result < 0 {
+            return None;
+        }
+        let info = unsafe { &*(buffer.as_ptr() as *const CF_PLACEHOLDER_BASIC_INFO) };
+        let start = offset_of!(CF_PLACEHOLDER_BASIC_INFO, FileIdentity);
+        let length = (info.FileIdentityLength as usize).min(IDENTITY_MAX);
+        let bytes = unsafe {
+            std::slice::from_raw_parts((buffer.as_ptr() as *const u8).add(start), length)
+        };
+        PlaceholderIdentity::decode(bytes)
+    }
+
+    /// 固定时下载完整内容并始终保留在本机；取消固定时释放本地空间，普通文件先转换为占位文件。
+    pub fn set_pinned(
+        path: &Path,
+        pinned: bool,
+        identity: &PlaceholderIdentity,
+        _fetcher: &Fetcher,
+    ) -> Result<(), Box<dyn Error>> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let handle = file.as_raw_handle() as HANDLE;
+        let blob = identity.encode();
+        if !pinned {
+            // 已经是占位文件时转换会失败，忽略即可。
+            // SAFETY: handle 在 file 存活期间有效。
+            let _ = unsafe {
+                CfConvertToPlaceholder(
+                    handle,
+                    blob.as_ptr() as *const c_void,
+                    blob.len() as u32,
+                    CF_CONVERT_FLAG_MARK_IN_SYNC,
+                    null_mut(),
+                    null_mut(),
+                )
+            };
+        }
+        let state = if pinned {
+            CF_PIN_STATE_PINNED
+        } else {
+            CF_PIN_STATE_UNPINNED
+        };
+        // SAFETY: 同步调用，不使用 OVERLAPPED。
+        check(
+            unsafe { CfSetPinState(handle, state, CF_SET_PIN_FLAG_NONE, null_mut()) },
+            "设置固定状态",
+        )?;
+        let result = if pinned {
+            unsafe { CfHydratePlaceholder(handle, 0, -1, CF_HYDRATE_FLAG_NONE, null_mut()) }
+        } else {
+            unsafe { CfDehydratePlaceholder(handle, 0, -1, CF_DEHYDRATE_FLAG_NONE, null_mut()) }
+        };
+        check(
+            result,
+            if pinned {
+                "下载完整内容"
+            } else {
+                "释放本地空间"
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_round_trips_and_rejects_garbage() {
+        let identity = PlaceholderIdentity {
+            uri: "cloudreve://my/docs/报告.pdf".to_string(),
+            sha256: "ab".repeat(32),
+        };
+        assert_eq!(
+            PlaceholderIdentity::decode(&identity.encode()),
+            Some(identity)
+        );
+        assert_eq!(PlaceholderIdentity::decode(b"\x00\x01"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stubs_keep_size_and_round_trip_through_pinning() {
+        use crate::core::sync::hash_file;
+        use std::fs;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("movie.mkv");
+        let content = vec![7u8; 100 * 1024];
+        fs::write(dir.path().join("source"), &content).expect("source");
+        let identity = PlaceholderIdentity {
+            uri: "cloudreve://my/movie.mkv".to_string(),
+            sha256: hash_file(&dir.path().join("source")).expect("hash"),
+        };
+        assert!(!can_placehold(1024));
+        create_placeholder(&path, content.len() as u64, 1_700_000_000_123, &identity)
+            .expect("stub");
+        let metadata = fs::metadata(&path).expect("metadata");
+        assert_eq!(metadata.len(), content.len() as u64);
+        assert_eq!(
+            filetime::FileTime::from_last_modification_time(&metadata).unix_seconds(),
+            1_700_000_000
+        );
+        assert_eq!(dehydrated_identity(&path), Some(identity.clone()));
+
+        let source = dir.path().join("source");
+        let fetcher: Fetcher = Arc::new(move |_: &PlaceholderIdentity, target: &Path| {
+            fs::copy(&source, target)
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        });
+        set_pinned(&path, true, &identity, &fetcher).expect("pin");
+        assert_eq!(fs::read(&path).expect("read"), content);
+        assert_eq!(dehydrated_identity(&path), None);
+
+        set_pinned(&path, false, &identity, &fetcher).expect("unpin");
+        assert_eq!(dehydrated_identity(&path), Some(identity.clone()));
+
+        // 本地修改过的文件不能释放。
+        set_pinned(&path, true, &identity, &fetcher).expect("pin again");
+        fs::write(&path, vec![1u8; 100 * 1024]).expect("edit");
+        assert!(set_pinned(&path, false, &identity, &fetcher).is_err());
+        assert_eq!(dehydrated_identity(&path), None);
+    }
+
+    #[test]
+    fn file_time_counts_from_1601() {
+        assert_eq!(file_time_from_ms(0), 116_444_736_000_000_000);
+    }
+}
@@ -0,0 +1,105 @@
+use lazy_static::lazy_static;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub type DbPool = r2d2::Pool<SqliteConnectionManager>;
+pub type PooledConn = r2d2::PooledConnection<SqliteConnectionManager>;
+
+/// 写锁被其他连接占用时最多等待的时间，超过后才返回 SQLITE_BUSY。
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+/// 每个常驻任务在一轮同步期间占用一个连接，上限按同时运行的任务数留足余量。
+const MAX_CONNECTIONS: u32 = 32;
+/// 关闭连接池时等待借出的连接归还的上限。
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    static ref POOLS: Mutex<HashMap<PathBuf, DbPool>> = Mutex::new(HashMap::new());
+}
+
+/// 每个新连接启用 WAL 与忙等待：读写可以并发，同时写入的任务排队而不是立即失败。
+pub fn configure(conn: &mut Connection) -> rusqlite::Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+}
+
+/// 按数据库路径共享的连接池，首次使用时创建；连接按需打开，空闲后保留复用。
+pub fn shared_pool(db_path: &Path) -> Result<DbPool, r2d2::Error> {
+    let mut pools = POOLS.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(pool) = pools.get(db_path) {
+        return Ok(pool.clone());
+    }
+    let manager = SqliteConnectionManager::file(db_path).with_init(configure);
+    let pool = r2d2::Pool::builder()
+        .max_size(MAX_CONNECTIONS)
+        .min_idle(Some(0))
+        .build(manager)?;
+    pools.insert(db_path.to_path_buf(), pool.clone());
+    Ok(pool)
+}
+
+/// 从共享连接池取一个连接，用完自动归还。
+pub fn connect(db_path: impl AsRef<Path>) -> Result<PooledConn, r2d2::Error> {
+    shared_pool(db_path.as_ref())?.get()
+}
+
+/// 替换数据库文件（恢复备份、移走损坏的数据库）前调用：移除共享的连接池，等借出的连接全部归还后
+/// 关闭连接，之后的连接重新打开新文件。仍有连接在使用时返回错误，调用方不应替换文件。
+pub fn close(db_path: &Path) -> Result<(), Box<dyn Error>> {
+    close_within(db_path, CLOSE_TIMEOUT)
+}
+
+fn close_within(db_path: &Path, timeout: Duration) -> Result<(), Box<dyn Error>> {
+    let mut pools = POOLS.lock().unwrap_or_else(|err| err.into_inner());
+    let Some(pool) = pools.remove(db_path) else {
+        return Ok(());
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        let state = pool.state();
+        if state.connections == state.idle_connections {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            pools.insert(db_path.to_path_buf(), pool);
+            return Err("数据库仍在使用中，请稍后重试".into());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn pooled_connections_use_wal_and_share_the_file() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("sync.db");
+        let conn = connect(&path).expect("connect");
+        let mode: String = conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .expect("journal mode");
+        assert_eq!(mode, "wal");
+        conn.execute_batch("CREATE TABLE t (v INTEGER); INSERT INTO t VALUES (1);")
+            .expect("write");
+
+        let other = connect(&path).expect("second connection");
+        let count: i64 = other
+            .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .expect("read");
+        assert_eq!(count, 1);
+
+        assert!(close_within(&path, Duration::from_millis(100)).is_err());
+        drop((conn, other));
+        close(&path).expect("close");
+        assert!(connect(&path).is_ok());
+    }
+}
@@ -0,0 +1,425 @@
+use crate::core::cloudreve::CloudreveClient;
+use crate::core::credentials::store_share_password;
+use crate::core::db::{
+    create_task, list_accounts, list_tasks, update_task_settings, upsert_account, AccountRow,
+    TaskRow,
+};
+use crate::core::ignore::validate_patterns;
+use crate::core::roots::{validate_roots, LocalRoot};
+use crate::core::sync::{SymlinkPolicy, SyncDirection};
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub const CONFIG_ARG: &str = "--config";
+pub const CONFIG_ENV: &str = "CLOUDREVE_SYNC_CONFIG";
+/// 未指定路径时读取配置目录下的这个文件。
+pub const DEFAULT_CONFIG_NAME: &str = "provision.toml";
+
+/// 无窗口部署时由配置管理工具维护的账户与任务声明。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProvisionFile {
+    #[serde(default)]
+    pub accounts: Vec<ProvisionAccount>,
+    #[serde(default)]
+    pub tasks: Vec<ProvisionTask>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProvisionAccount {
+    pub base_url: String,
+    pub email: String,
+    /// 保存令牌的环境变量名，读到的令牌只在本次运行中使用；都为空时按通常的顺序读取凭据。
+    #[serde(default)]
+    pub access_token_env: String,
+    #[serde(default)]
+    pub refresh_token_env: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProvisionTask {
+    /// 任务 ID，重启后按它找到数据库中的同一任务。
+    pub id: String,
+    /// 账户邮箱；同一邮箱登录了多个服务器时写 `base_url|email`。
+    pub account: String,
+    pub local_root: String,
+    pub remote_root: String,
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 其余任务设置，键名与界面保存的任务设置相同。
+    #[serde(default)]
+    pub settings: Map<String, Value>,
+}
+
+fn default_mode() -> String {
+    "Bidirectional".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 一次对账的结果，由调用方写入日志。
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ProvisionReport {
+    pub created: Vec<String>,
+    /// 数据库中的设置与配置文件不一致、已按配置文件改回的任务及设置项。
+    pub drifted: Vec<(String, Vec<String>)>,
+    /// 服务器、目录或同步方式与配置文件不同的任务；这些字段决定索引的含义，不会自动修改。
+    pub mismatched: Vec<(String, Vec<String>)>,
+    /// 数据库中有但配置文件没有声明的任务，保持原样。
+    pub unmanaged: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl ProvisionAccount {
+    pub fn account_key(&self) -> String {
+        format!("{}|{}", self.base_url, self.email)
+    }
+
+    /// 从环境变量读取令牌；声明了变量但未设置时报错，避免静默使用旧凭据。
+    pub fn env_tokens(&self) -> Result<Option<(String, String)>, String> {
+        if self.access_token_env.is_empty() && self.refresh_token_env.is_empty() {
+            return Ok(None);
+        }
+        let read = |name: &str| {
+            if name.is_empty() {
+                return Ok(String::new());
+            }
+            std::env::var(name).map_err(|_| format!("环境变量 {} 未设置", name))
+        };
+        Ok(Some((
+            read(&self.access_token_env)?,
+            read(&self.refresh_token_env)?,
+        )))
+    }
+}
+
+/// 命令行 `--config <path>` 优先，其次是环境变量。
+pub fn config_path_from_args(args: &[String]) -> Option<PathBuf> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == CONFIG_ARG {
+            return iter.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg
+            .strip_prefix(CONFIG_ARG)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(PathBuf::from(path));
+        }
+    }
+    std::env::var_os(CONFIG_ENV)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+pub fn load_provision(path: &Path) -> Result<ProvisionFile, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    toml::from_str(&text).map_err(|err| format!("配置文件格式错误: {}", err).into())
+}
+
+fn find_account<'a>(accounts: &'a [AccountRow], reference: &str) -> Result<&'a AccountRow, String> {
+    let matches = accounts
+        .iter()
+        .filter(|account| account.account_key == reference || account.email == reference)
+        .collect::<Vec<_>>();
+    match matches.as_slice() {
+        [account] => Ok(account),
+        [] => Err(format!("账户未声明也未登录: {}", reference)),
+        _ => Err(format!(
+            "多个服务器上有同名账户，请写成 base_url|email: {}",
+            reference
+        )),
+    }
+}
+
+fn remote_root_uri(remote_root: &str) -> String {
+    if remote_root.starts_with("cloudreve://") {
+        remote_root.to_string()
+    } else {
+        CloudreveClient::build_file_uri(remote_root)
+    }
+}
+
+/// 配置文件声明的设置，加上任务必需的名称、账户与同步间隔。
+fn desired_settings(task: &ProvisionTask, account_key: &str) -> Result<Map<String, Value>, String> {
+    for key in ["include_patterns", "exclude_patterns", "priority_patterns"] {
+        if let Some(value) = task.settings.get(key) {
+            let patterns = serde_json::from_value::<Vec<String>>(value.clone())
+                .map_err(|err| format!("{} 格式错误: {}", key, err))?;
+            validate_patterns(&patterns)?;
+        }
+    }
+    if let Some(value) = task.settings.get("symlinks") {
+        serde_json::from_value::<SymlinkPolicy>(value.clone())
+            .map_err(|err| format!("symlinks 格式错误: {}", err))?
+            .check_supported()?;
+    }
+    if let Some(value) = task.settings.get("local_roots") {
+        let roots = serde_json::from_value::<Vec<LocalRoot>>(value.clone())
+            .map_err(|err| format!("local_roots 格式错误: {}", err))?;
+        validate_roots(&roots)?;
+    }
+    if task.local_root.trim().is_empty() {
+        return Err("本地目录不能为空".to_string());
+    }
+    let mut desired = task.settings.clone();
+    desired
+        .entry("name")
+        .or_insert_with(|| Value::from(task.id.clone()));
+    desired
+        .entry("sync_interval_secs")
+        .or_insert_with(|| Value::from(60));
+    desired.insert("account_key".to_string(), Value::from(account_key));
+    desired.insert("enabled".to_string(), Value::from(task.enabled));
+    Ok(desired)
+}
+
+/// 按配置文件更新数据库：账户与缺少的任务直接写入；已有任务的设置与声明不一致时记为漂移并改回。
+/// `validate` 检查合并后的任务设置能否被应用读取。
+pub fn reconcile(
+    conn: &Connection,
+    file: &ProvisionFile,
+    now_ms: i64,
+    validate: impl Fn(&str) -> Result<(), String>,
+) -> Result<ProvisionReport, Box<dyn Error>> {
+    let mut report = ProvisionReport::default();
+    for account in &file.accounts {
+        upsert_account(
+            conn,
+            &AccountRow {
+                account_key: account.account_key(),
+                base_url: account.base_url.clone(),
+                email: account.email.clone(),
+                created_at_ms: now_ms,
+            },
+        )?;
+    }
+    let accounts = list_accounts(conn)?;
+    let existing = list_tasks(conn)?;
+    for task in &file.tasks {
+        if let Err(err) = reconcile_task(
+            conn,
+            task,
+            &accounts,
+            &existing,
+            now_ms,
+            &validate,
+            &mut report,
+        ) {
+            report.errors.push(format!("{}: {}", task.id, err));
+        }
+    }
+    report.unmanaged = existing
+        .iter()
+        .filter(|row| !file.tasks.iter().any(|task| task.id == row.task_id))
+        .map(|row| row.task_id.clone())
+        .collect();
+    Ok(report)
+}
+
+fn reconcile_task(
+    conn: &Connection,
+    task: &ProvisionTask,
+    accounts: &[AccountRow],
+    existing: &[TaskRow],
+    now_ms: i64,
+    validate: &impl Fn(&str) -> Result<(), String>,
+    report: &mut ProvisionReport,
+) -> Result<(), String> {
+    let direction = SyncDirection::from_mode(&task.mode)
+        .ok_or_else(|| format!("未知的同步模式: {}", task.mode))?;
+    let account = find_account(accounts, &task.account)?;
+    let desired = desired_settings(task, &account.account_key)?;
+    let remote_root = remote_root_uri(&task.remote_root);
+    let Some(row) = existing.iter().find(|row| row.task_id == task.id) else {
+        let settings_json = Value::Object(desired).to_string();
+        validate(&settings_json)?;
+        if let Some(password) = CloudreveClient::share_password(&remote_root) {
+            store_share_password(&task.id, password)
+                .map_err(|err| format!("无法保存分享密码: {}", err))?;
+        }
+        let row = TaskRow {
+            task_id: task.id.clone(),
+            base_url: account.base_url.clone(),
+            local_root: task.local_root.clone(),
+            remote_root_uri: CloudreveClient::without_share_password(&remote_root),
+            device_id: Uuid::new_v4().to_string(),
+            mode: task.mode.clone(),
+            settings_json,
+            created_at_ms: now_ms,
+        };
+        create_task(conn, &row).map_err(|err| err.to_string())?;
+        report.created.push(task.id.clone());
+        return Ok(());
+    };
+
+    let mismatched = [
+        ("base_url", row.base_url != account.base_url),
+        ("local_root", row.local_root != task.local_root),
+        (
+            "remote_root",
+            row.remote_root_uri != CloudreveClient::without_share_password(&remote_root),
+        ),
+        (
+            "mode",
+            SyncDirection::from_mode(&row.mode) != Some(direction),
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, differs)| *differs)
+    .map(|(field, _)| field.to_string())
+    .collect::<Vec<_>>();
+    if !mismatched.is_empty() {
+        report.mismatched.push((task.id.clone(), mismatched));
+    }
+
+    let mut current = match serde_json::from_str::<Value>(&row.settings_json) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    let drifted = desired
+        .iter()
+        .filter(|(key, value)| current.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    if drifted.is_empty() {
+        return Ok(());
+    }
+    current.extend(desired);
+    let settings_json = Value::Object(current).to_string();
+    validate(&settings_json)?;
+    update_task_settings(conn, &task.id, &settings_json).map_err(|err| err.to_string())?;
+    report.drifted.push((task.id.clone(), drifted));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::db::init_db;
+
+    const CONFIG: &str = r#"
+[[accounts]]
+base_url = "https://cloud.example.com"
+email = "ops@example.com"
+
+[[tasks]]
+id = "docs"
+account = "ops@example.com"
+local_root = "/srv/docs"
+remote_root = "/Docs"
+mode = "UploadOnly"
+
+[tasks.settings]
+sync_interval_secs = 300
+exclude_patterns = ["*.tmp"]
+"#;
+
+    #[test]
+    fn reconcile_creates_tasks_and_reverts_drift() {
+        let conn = Connection::open_in_memory().expect("db");
+        init_db(&conn).expect("init");
+        let file = toml::from_str::<ProvisionFile>(CONFIG).expect("parse");
+
+        let first = reconcile(&conn, &file, 1, |_| Ok(())).expect("first");
+        assert_eq!(first.created, vec!["docs"]);
+        let task = &list_tasks(&conn).expect("tasks")[0];
+        assert_eq!(task.base_url, "https://cloud.example.com");
+        assert_eq!(
+            task.remote_root_uri,
+            CloudreveClient::build_file_uri("/Docs")
+        );
+        let settings = serde_json::from_str::<Value>(&task.settings_json).expect("json");
+        assert_eq!(
+            settings["account_key"],
+            "https://cloud.example.com|ops@example.com"
+        );
+        assert_eq!(settings["sync_interval_secs"], 300);
+        assert_eq!(settings["name"], "docs");
+
+        // 界面上改过间隔并新增了其他设置：只改回声明过的项。
+        let mut edited = settings.as_object().expect("object").clone();
+        edited.insert("sync_interval_secs".to_string(), Value::from(30));
+        edited.insert("on_demand".to_string(), Value::from(true));
+        update_task_settings(&conn, "docs", &Value::Object(edited).to_string()).expect("edit");
+        create_task(
+            &conn,
+            &TaskRow {
+                task_id: "manual".to_string(),
+                mode: "双向".to_string(),
+                ..task.clone()
+            },
+        )
+        .expect("manual task");
+
+        let second = reconcile(&conn, &file, 2, |_| Ok(())).expect("second");
+        assert!(second.created.is_empty());
+        assert_eq!(
+            second.drifted,
+            vec![("docs".to_string(), vec!["sync_interval_secs".to_string()])]
+        );
+        assert_eq!(second.unmanaged, vec!["manual"]);
+        let task = list_tasks(&conn)
+            .expect("tasks")
+            .into_iter()
+            .find(|task| task.task_id == "docs")
+            .expect("docs");
+        let settings = serde_json::from_str::<Value>(&task.settings_json).expect("json");
+        assert_eq!(settings["sync_interval_secs"], 300);
+        assert_eq!(settings["on_demand"], true);
+
+        let mut moved = file.clone();
+        moved.tasks[0].local_root = "/srv/other".to_string();
+        let third = reconcile(&conn, &moved, 3, |_| Ok(())).expect("third");
+        assert_eq!(
+            third.mismatched,
+            vec![("docs".to_string(), vec!["local_root".to_string()])]
+        );
+        assert!(third.drifted.is_empty());
+    }
+
+    #[test]
+    fn reconcile_reports_unknown_accounts_and_bad_settings() {
+        let conn = Connection::open_in_memory().expect("db");
+        init_db(&conn).expect("init");
+        let mut file = toml::from_str::<ProvisionFile>(CONFIG).expect("parse");
+        file.tasks[0].account = "nobody@example.com".to_string();
+        let report = reconcile(&conn, &file, 1, |_| Ok(())).expect("reconcile");
+        assert_eq!(
+            report.errors,
+            vec!["docs: 账户未声明也未登录: nobody@example.com"]
+        );
+
+        file.tasks[0].account = "ops@example.com".to_string();
+        let report = reconcile(&conn, &file, 1, |_| Err("bad".to_string())).expect("reconcile");
+        assert_eq!(report.errors, vec!["docs: bad"]);
+        assert!(list_tasks(&conn).expect("tasks").is_empty());
+    }
+
+    #[test]
+    fn config_path_prefers_command_line() {
+        let args = ["app", "--headless", "--config", "/etc/cloudreve-sync.toml"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            config_path_from_args(&args),
+            Some(PathBuf::from("/etc/cloudreve-sync.toml"))
+        );
+        let args = ["app", "--config=/tmp/a.toml"].map(String::from).to_vec();
+        assert_eq!(
+            config_path_from_args(&args),
+            Some(PathBuf::from("/tmp/a.toml"))
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+/// 服务端推送的一条事件；多行 data 以换行连接。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushEvent {
+    pub event: String,
+    pub data: String,
+}
+
+impl PushEvent {
+    /// 心跳只用于保持连接，不代表远端有变化。
+    pub fn is_keepalive(&self) -> bool {
+        matches!(self.event.as_str(), "keepalive" | "ping" | "heartbeat")
+    }
+}
+
+/// 服务端没有变更推送接口（接口不存在或返回的不是事件流），调用方应继续定时轮询。
+#[derive(Debug)]
+pub struct PushUnsupported {
+    pub detail: String,
+}
+
+impl fmt::Display for PushUnsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "服务器不支持变更推送: {}", self.detail)
+    }
+}
+
+impl std::error::Error for PushUnsupported {}
+
+/// 按 text/event-stream 格式增量解析，数据块可能在任意字节处切开。
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<PushEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                events.extend(self.dispatch());
+                continue;
+            }
+            // 冒号开头的是注释，服务端常用来保持连接。
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = value.to_string(),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+
+    /// 空行结束一条事件；没有 data 的事件按规范不派发。
+    fn dispatch(&mut self) -> Option<PushEvent> {
+        let event = std::mem::take(&mut self.event);
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(PushEvent {
+            event: if event.is_empty() {
+                "message".to_string()
+            } else {
+                event
+            },
+            data: std::mem::take(&mut self.data).join("\n"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b": hello\n\nevent: file_").is_empty());
+        let events = parser.push(b"changed\ndata: {\"uri\":\r\ndata: \"a\"}\n\ndata: x\n");
+        assert_eq!(
+            events,
+            vec![PushEvent {
+                event: "file_changed".to_string(),
+                data: "{\"uri\":\n\"a\"}".to_string(),
+            }]
+        );
+        let events = parser.push(b"\nevent: ping\n\n");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "message");
+        assert_eq!(events[0].data, "x");
+    }
+
+    #[test]
+    fn keepalive_events_are_recognised() {
+        let mut parser = SseParser::default();
+        let events = parser.push(b"event: keepalive\ndata: 1\n\n");
+        assert!(events[0].is_keepalive());
+    }
+}
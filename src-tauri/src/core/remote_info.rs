@@ -0,0 +1,158 @@
+use crate::core::cloudreve::{CloudreveClient, RemoteFile};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_REMOTE_INFO_TTL: Duration = Duration::from_secs(30);
+
+struct CachedInfo {
+    info: RemoteFile,
+    fetched_at: Instant,
+}
+
+/// 远端文件信息（文件 ID、大小、哈希等）的读穿缓存，按账户隔离，供各个命令共用，
+/// 避免短时间内对同一 URI 重复查询服务器。修改远端文件的命令需调用 `invalidate`。
+pub struct RemoteInfoCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<(String, String), CachedInfo>>,
+}
+
+impl Default for RemoteInfoCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_REMOTE_INFO_TTL)
+    }
+}
+
+impl RemoteInfoCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn key(account: &str, uri: &str) -> (String, String) {
+        (account.to_string(), uri.trim_end_matches('/').to_string())
+    }
+
+    pub fn get(&self, account: &str, uri: &str, now: Instant) -> Option<RemoteFile> {
+        let mut entries = self.entries.lock().ok()?;
+        let key = Self::key(account, uri);
+        let fresh = entries
+            .get(&key)
+            .is_some_and(|cached| now.saturating_duration_since(cached.fetched_at) < self.ttl);
+        if !fresh {
+            entries.remove(&key);
+            return None;
+        }
+        entries.get(&key).map(|cached| cached.info.clone())
+    }
+
+    pub fn insert(&self, account: &str, uri: &str, info: RemoteFile, now: Instant) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                Self::key(account, uri),
+                CachedInfo {
+                    info,
+                    fetched_at: now,
+                },
+            );
+        }
+    }
+
+    /// 移除该 URI 及其下所有条目，目录被删除或改名时子项的信息也随之失效。
+    pub fn invalidate(&self, account: &str, uri: &str) {
+        let (account, uri) = Self::key(account, uri);
+        let prefix = format!("{}/", uri);
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|(cached_account, cached_uri), _| {
+                cached_account != &account
+                    || (cached_uri != &uri && !cached_uri.starts_with(&prefix))
+            });
+        }
+    }
+
+    pub fn clear_account(&self, account: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|(cached_account, _), _| cached_account != account);
+        }
+    }
+
+    /// 缓存未命中或已过期时向服务器查询并写入缓存；查询失败不写入。
+    pub async fn lookup(
+        &self,
+        client: &CloudreveClient,
+        account: &str,
+        uri: &str,
+    ) -> Result<RemoteFile, Box<dyn Error>> {
+        if let Some(info) = self.get(account, uri, Instant::now()) {
+            return Ok(info);
+        }
+        let info = client.get_file_info(uri).await?;
+        self.insert(account, uri, info.clone(), Instant::now());
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(uri: &str) -> RemoteFile {
+        RemoteFile {
+            id: format!("id-{}", uri),
+            name: uri.rsplit('/').next().unwrap_or_default().to_string(),
+            uri: uri.to_string(),
+            size: 1,
+            updated_at: String::new(),
+            metadata: HashMap::new(),
+            is_dir: false,
+            owned: true,
+        }
+    }
+
+    #[test]
+    fn entries_expire_and_are_scoped_by_account() {
+        let cache = RemoteInfoCache::new(Duration::from_secs(30));
+        let start = Instant::now();
+        let uri = "cloudreve://my/Docs/a.txt";
+        cache.insert("alice", uri, info(uri), start);
+        assert!(cache
+            .get("alice", uri, start + Duration::from_secs(29))
+            .is_some());
+        assert!(cache.get("bob", uri, start).is_none());
+        assert!(cache
+            .get("alice", uri, start + Duration::from_secs(30))
+            .is_none());
+        // 过期条目已被移除。
+        assert!(cache.get("alice", uri, start).is_none());
+    }
+
+    #[test]
+    fn invalidate_removes_descendants_only() {
+        let cache = RemoteInfoCache::default();
+        let now = Instant::now();
+        for uri in [
+            "cloudreve://my/Docs",
+            "cloudreve://my/Docs/a.txt",
+            "cloudreve://my/Docs2/b.txt",
+        ] {
+            cache.insert("alice", uri, info(uri), now);
+            cache.insert("bob", uri, info(uri), now);
+        }
+        cache.invalidate("alice", "cloudreve://my/Docs/");
+        assert!(cache.get("alice", "cloudreve://my/Docs", now).is_none());
+        assert!(cache
+            .get("alice", "cloudreve://my/Docs/a.txt", now)
+            .is_none());
+        assert!(cache
+            .get("alice", "cloudreve://my/Docs2/b.txt", now)
+            .is_some());
+        assert!(cache.get("bob", "cloudreve://my/Docs/a.txt", now).is_some());
+        cache.clear_account("bob");
+        assert!(cache
+            .get("bob", "cloudreve://my/Docs2/b.txt", now)
+            .is_none());
+    }
+}
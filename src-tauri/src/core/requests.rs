@@ -1,4 +1,5 @@
 use crate::core::error::CloudreveError;
+use crate::core::transport;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error;
@@ -15,7 +16,7 @@ pub struct Connection {
 
 impl Connection {
     pub fn new(base_url: String) -> Self {
-        let client = reqwest::Client::new();
+        let client = transport::shared_client();
         let access_token = String::new();
         let refresh_token = String::new();
         let base_url = if base_url.ends_with("/api/v4") {
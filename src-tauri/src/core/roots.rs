@@ -0,0 +1,424 @@
+use crate::core::config::config_dir;
+use crate::core::db::TaskRow;
+use crate::core::error::SyncError;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 检查可写性时创建的探测文件的名称前缀。
+const PROBE_PREFIX: &str = ".cloudreve-sync-probe-";
+
+lazy_static! {
+    /// 检查过的本地目录是否可写。每轮同步都创建探测文件会触发本地监听，
+    /// 因此只在任务启动时与真实写入因权限失败后重新检查。
+    static ref ROOT_WRITABLE: Mutex<HashMap<String, bool>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalRoot {
+    pub local_root: String,
+    #[serde(default)]
+    pub remote_prefix: String,
+}
+
+#[derive(Deserialize)]
+struct RootsSettings {
+    #[serde(default)]
+    local_roots: Vec<LocalRoot>,
+}
+
+impl LocalRoot {
+    pub fn new(local_root: &str, remote_prefix: &str) -> Self {
+        Self {
+            local_root: local_root.to_string(),
+            remote_prefix: normalize_prefix(remote_prefix),
+        }
+    }
+
+    pub fn join_relpath(&self, relpath: &str) -> String {
+        let relpath = relpath.trim_start_matches('/');
+        let prefix = normalize_prefix(&self.remote_prefix);
+        if prefix.is_empty() {
+            relpath.to_string()
+        } else if relpath.is_empty() {
+            prefix
+        } else {
+            format!("{}/{}", prefix, relpath)
+        }
+    }
+
+    pub fn strip_relpath<'a>(&self, relpath: &'a str) -> Option<&'a str> {
+        let prefix = normalize_prefix(&self.remote_prefix);
+        if prefix.is_empty() {
+            return Some(relpath);
+        }
+        if relpath == prefix {
+            return Some("");
+        }
+        relpath
+            .strip_prefix(prefix.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+    }
+}
+
+pub fn task_roots(task: &TaskRow) -> Vec<LocalRoot> {
+    let configured = serde_json::from_str::<RootsSettings>(&task.settings_json)
+        .map(|settings| settings.local_roots)
+        .unwrap_or_default();
+    if configured.is_empty() {
+        return vec![LocalRoot::new(&task.local_root, "")];
+    }
+    configured
+        .into_iter()
+        .map(|root| LocalRoot::new(&root.local_root, &root.remote_prefix))
+        .collect()
+}
+
+pub fn validate_roots(roots: &[LocalRoot]) -> Result<(), String> {
+    if roots.len() <= 1 {
+        return Ok(());
+    }
+    for (idx, root) in roots.iter().enumerate() {
+        if root.local_root.trim().is_empty() {
+            return Err("本地目录不能为空".to_string());
+        }
+        let prefix = normalize_prefix(&root.remote_prefix);
+        if prefix.is_empty() {
+            return Err(format!(
+                "多个本地目录时必须指定远端子目录: {}",
+                root.local_root
+            ));
+        }
+        for other in roots.iter().skip(idx + 1) {
+            let other_prefix = normalize_prefix(&other.remote_prefix);
+            if prefix == other_prefix
+                || other_prefix.starts_with(&format!("{}/", prefix))
+                || prefix.starts_with(&format!("{}/", other_prefix))
+            {
+                return Err(format!("远端子目录重叠: {} / {}", prefix, other_prefix));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 通过创建并删除探测文件判断本地目录是否可写；目录不存在时尝试创建。
+pub fn is_root_writable(path: &Path) -> bool {
+    if !path.exists() && fs::create_dir_all(path).is_err() {
+        return false;
+    }
+    let probe = path.join(format!("{}{}", PROBE_PREFIX, uuid::Uuid::new_v4()));
+    match fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// 应用自身的配置目录与数据库文件（含 SQLite 日志文件），同步正在写入的数据库会损坏状态。
+fn app_data_paths(db_path: &Path) -> Vec<PathBuf> {
+    let mut paths = config_dir().map(|dir| vec![dir]).unwrap_or_default();
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let mut name = db_path.as_os_str().to_os_string();
+        name.push(suffix);
+        paths.push(PathBuf::from(name));
+    }
+    paths
+}
+
+/// 返回位于本地目录内、无论规则如何都不参与同步的应用数据路径，按本地目录原样拼接，
+/// 可直接与扫描到的路径比较；本地目录本身就在应用数据目录内时返回错误。
+pub fn protected_paths(roots: &[LocalRoot], db_path: &Path) -> Result<Vec<PathBuf>, String> {
+    paths_inside_roots(roots, &app_data_paths(db_path))
+}
+
+fn paths_inside_roots(roots: &[LocalRoot], targets: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let targets = targets
+        .iter()
+        .filter_map(|path| canonical_path(path))
+        .collect::<Vec<_>>();
+    let mut out = Vec::new();
+    for root in roots {
+        let Ok(canonical_root) = fs::canonicalize(&root.local_root) else {
+            continue;
+        };
+        for target in &targets {
+            if canonical_root.starts_with(target) {
+                return Err(format!(
+                    "本地目录位于应用数据目录内，不能同步: {}",
+                    root.local_root
+                ));
+            }
+            if let Ok(rest) = target.strip_prefix(&canonical_root) {
+                out.push(Path::new(&root.local_root).join(rest));
+            }
+        }
+    }
+    out.sort();
+    out.dedup();
+    Ok(out)
+}
+
+/// 文件可能尚不存在（如 -wal），此时规范化其所在目录。
+fn canonical_path(path: &Path) -> Option<PathBuf> {
+    fs::canonicalize(path).ok().or_else(|| {
+        let parent = fs::canonicalize(path.parent()?).ok()?;
+        Some(parent.join(path.file_name()?))
+    })
+}
+
+/// 本地目录只读且任务未开启审计模式时返回的错误。
+#[derive(Debug)]
+pub struct RootReadOnly {
+    pub roots: Vec<String>,
+}
+
+impl std::fmt::Display for RootReadOnly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "本地目录只读: {}（可切换为审计模式，仅上传并记录需写入本地的文件）",
+            self.roots.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for RootReadOnly {}
+
+pub fn is_probe_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with(PROBE_PREFIX))
+}
+
+/// 只读的本地目录；尚未检查过的目录创建探测文件检查一次，结果保留到 `forget_root_access`。
+pub fn read_only_roots(roots: &[LocalRoot]) -> Vec<String> {
+    let mut known = ROOT_WRITABLE.lock().unwrap_or_else(|err| err.into_inner());
+    roots
+        .iter()
+        .filter(|root| {
+            !*known
+                .entry(root.local_root.clone())
+                .or_insert_with(|| is_root_writable(Path::new(&root.local_root)))
+        })
+        .map(|root| root.local_root.clone())
+        .collect()
+}
+
+/// 已知只读的本地目录，不做任何写入；供预览、路径诊断等只读操作使用。
+pub fn known_read_only_roots(roots: &[LocalRoot]) -> Vec<String> {
+    let known = ROOT_WRITABLE.lock().unwrap_or_else(|err| err.into_inner());
+    roots
+        .iter()
+        .filter(|root| known.get(&root.local_root) == Some(&false))
+        .map(|root| root.local_root.clone())
+        .collect()
+}
+
+/// 丢弃检查结果，下一轮同步前重新检查这些目录是否可写。
+pub fn forget_root_access(roots: &[LocalRoot]) {
+    let mut known = ROOT_WRITABLE.lock().unwrap_or_else(|err| err.into_inner());
+    for root in roots {
+        known.remove(&root.local_root);
+    }
+}
+
+/// 本地写入因权限不足或文件系统只读而失败，包括包装在 `SyncError::Io` 中的。
+pub fn is_read_only_error(err: &(dyn Error + 'static)) -> bool {
+    let io_err =
+        err.downcast_ref::<io::Error>()
+            .or_else(|| match err.downcast_ref::<SyncError>() {
+                Some(SyncError::Io(err)) => Some(err),
+                _ => None,
+            });
+    io_err.is_some_and(|err| {
+        matches!(
+            err.kind(),
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+        )
+    })
+}
+
+pub fn resolve_local_path(roots: &[LocalRoot], relpath: &str) -> Option<PathBuf> {
+    roots.iter().find_map(|root| {
+        root.strip_relpath(relpath).map(|rest| {
+            if rest.is_empty() {
+                PathBuf::from(&root.local_root)
+            } else {
+                Path::new(&root.local_root).join(rest)
+            }
+        })
+    })
+}
+
+fn normalize_prefix(prefix: &str) -> String {
+    prefix
+        .replace('\\', "/")
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_with_settings(settings_json: &str) -> TaskRow {
+        TaskRow {
+            task_id: "task-1".to_string(),
+            base_url: "https://example.com".to_string(),
+            local_root: "/home/user/Sync".to_string(),
+            remote_root_uri: "cloudreve://my/Backup".to_string(),
+            device_id: "device-1".to_string(),
+            mode: "Bidirectional".to_string(),
+            settings_json: settings_json.to_string(),
+            created_at_ms: 0,
+        }
+    }
+
+    #[test]
+    fn task_roots_defaults_to_single_root() {
+        let roots = task_roots(&task_with_settings("{}"));
+        assert_eq!(roots, vec![LocalRoot::new("/home/user/Sync", "")]);
+    }
+
+    #[test]
+    fn task_roots_reads_settings() {
+        let roots = task_roots(&task_with_settings(
+            r#"{"local_roots":[{"local_root":"/home/user/Desktop","remote_prefix":"/Desktop/"},{"local_root":"/home/user/Documents","remote_prefix":"Documents"}]}"#,
+        ));
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].remote_prefix, "Desktop");
+        assert_eq!(roots[0].join_relpath("a.txt"), "Desktop/a.txt");
+    }
+
+    #[test]
+    fn strip_relpath_matches_whole_segments() {
+        let root = LocalRoot::new("/d", "Docs");
+        assert_eq!(root.strip_relpath("Docs/a.txt"), Some("a.txt"));
+        assert_eq!(root.strip_relpath("Docs"), Some(""));
+        assert_eq!(root.strip_relpath("Docsx/a.txt"), None);
+    }
+
+    #[test]
+    fn validate_roots_rejects_overlap() {
+        let ok = vec![LocalRoot::new("/a", "A"), LocalRoot::new("/b", "B")];
+        assert!(validate_roots(&ok).is_ok());
+        let nested = vec![LocalRoot::new("/a", "A"), LocalRoot::new("/b", "A/B")];
+        assert!(validate_roots(&nested).is_err());
+        let empty = vec![LocalRoot::new("/a", ""), LocalRoot::new("/b", "B")];
+        assert!(validate_roots(&empty).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn detects_read_only_roots() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let writable = dir.path().join("rw");
+        let locked = dir.path().join("ro");
+        fs::create_dir_all(&locked).expect("mkdir");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).expect("chmod");
+        let roots = vec![
+            LocalRoot::new(&writable.to_string_lossy(), "A"),
+            LocalRoot::new(&locked.to_string_lossy(), "B"),
+        ];
+        let read_only = read_only_roots(&roots);
+        assert!(fs::read_dir(&writable)
+            .expect("list")
+            .all(|entry| !is_probe_file(&entry.expect("entry").path())));
+        let probe_succeeded = fs::File::create(locked.join("root-check")).is_ok();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).expect("chmod");
+        assert!(writable.is_dir());
+        if probe_succeeded {
+            // 以 root 运行时权限位不生效，无法模拟只读目录。
+            return;
+        }
+        assert_eq!(read_only, vec![locked.to_string_lossy().to_string()]);
+        // 结果被记住，之后不再创建探测文件；丢弃后重新检查。
+        assert_eq!(known_read_only_roots(&roots), read_only);
+        assert_eq!(read_only_roots(&roots), read_only);
+        forget_root_access(&roots);
+        assert!(known_read_only_roots(&roots).is_empty());
+        assert!(read_only_roots(&roots).is_empty());
+    }
+
+    #[test]
+    fn writability_is_checked_once_until_forgotten() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let roots = vec![LocalRoot::new(&dir.path().to_string_lossy(), "")];
+        assert!(known_read_only_roots(&roots).is_empty());
+        assert!(read_only_roots(&roots).is_empty());
+        fs::remove_dir_all(dir.path()).expect("remove root");
+        // 已检查过的目录不再探测，也就不会重新创建。
+        assert!(read_only_roots(&roots).is_empty());
+        assert!(!dir.path().exists());
+        forget_root_access(&roots);
+        assert!(read_only_roots(&roots).is_empty());
+        assert!(dir.path().is_dir());
+
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(is_read_only_error(&SyncError::Io(denied)));
+        assert!(!is_read_only_error(&io::Error::from(
+            io::ErrorKind::NotFound
+        )));
+        assert!(is_probe_file(Path::new("/a/.cloudreve-sync-probe-1")));
+    }
+
+    #[test]
+    fn resolve_local_path_picks_matching_root() {
+        let roots = vec![
+            LocalRoot::new("/home/user/Desktop", "Desktop"),
+            LocalRoot::new("/home/user/Documents", "Documents"),
+        ];
+        assert_eq!(
+            resolve_local_path(&roots, "Documents/x/y.txt"),
+            Some(Path::new("/home/user/Documents").join("x/y.txt"))
+        );
+        assert_eq!(resolve_local_path(&roots, "Other/z.txt"), None);
+    }
+
+    #[test]
+    fn app_data_inside_a_root_is_protected() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let app_dir = dir.path().join(".config/cloudreve-sync");
+        fs::create_dir_all(&app_dir).expect("mkdir");
+        let db = app_dir.join("cloudreve.db");
+        fs::write(&db, b"db").expect("db");
+        let home = dir.path().to_string_lossy().to_string();
+        let targets = vec![
+            app_dir.clone(),
+            db.clone(),
+            app_dir.join("cloudreve.db-wal"),
+        ];
+
+        let protected =
+            paths_inside_roots(&[LocalRoot::new(&home, "")], &targets).expect("protected");
+        assert_eq!(
+            protected,
+            vec![
+                Path::new(&home).join(".config/cloudreve-sync"),
+                Path::new(&home).join(".config/cloudreve-sync/cloudreve.db"),
+                Path::new(&home).join(".config/cloudreve-sync/cloudreve.db-wal"),
+            ]
+        );
+
+        let elsewhere = dir.path().join("Documents");
+        fs::create_dir_all(&elsewhere).expect("mkdir");
+        let roots = [LocalRoot::new(&elsewhere.to_string_lossy(), "")];
+        assert!(paths_inside_roots(&roots, &targets)
+            .expect("unrelated")
+            .is_empty());
+
+        let nested = [LocalRoot::new(&app_dir.to_string_lossy(), "")];
+        assert!(paths_inside_roots(&nested, &targets).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use chrono::{Local, Timelike};
+
+use crate::core::cloudreve::TokenRefresh;
+use crate::core::config::{config_dir, ApiPaths, AppSettings};
+use crate::core::credentials::{
+    load_task_key, load_tokens, store_tokens, ENCRYPTION_PASSPHRASE_ENV,
+};
+use crate::core::db::TaskRow;
+use crate::core::encryption::{EncryptionSettings, TaskCipher};
+use crate::core::events::EventBus;
+use crate::core::idle::{is_idle_for, user_idle_duration};
+use crate::core::network::{detect_network, select_profile, NetworkProfile};
+use crate::core::roots::{read_only_roots, task_roots, RootReadOnly};
+use crate::core::scan_hook::ScanHook;
+use crate::core::schedule::in_quiet_hours;
+use crate::core::sync::SyncEngine;
+use crate::core::system_state::{current_system_state, PauseReason};
+use crate::core::transport::configure_transport;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 构建同步引擎需要的任务设置；桌面端与命令行共用，其余设置由同步引擎自行解析。
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunSettings {
+    pub account_key: String,
+    #[serde(default)]
+    pub audit_when_read_only: bool,
+    #[serde(default)]
+    pub encryption: Option<EncryptionSettings>,
+}
+
+pub fn run_settings(task: &TaskRow) -> Result<RunSettings, Box<dyn Error>> {
+    serde_json::from_str(&task.settings_json)
+        .map_err(|err| format!("任务 {} 的设置无效: {}", task.task_id, err).into())
+}
+
+pub fn active_network_profile() -> Option<NetworkProfile> {
+    let settings = AppSettings::load().ok()?;
+    if settings.network_profiles.is_empty() {
+        return None;
+    }
+    let snapshot = detect_network();
+    select_profile(
+        &settings.network_profiles,
+        &settings.network_profile,
+        &snapshot,
+    )
+    .cloned()
+}
+
+/// 没有启用任何规则时不检测系统状态。
+pub fn system_pause_reason() -> Option<PauseReason> {
+    let rules = AppSettings::load().unwrap_or_default().pause_rules();
+    if rules.is_empty() {
+        return None;
+    }
+    rules.pause_reason(&current_system_state())
+}
+
+pub fn in_quiet_hours_now() -> bool {
+    let settings = AppSettings::load().unwrap_or_default();
+    in_quiet_hours(
+        settings.quiet_hours_start,
+        settings.quiet_hours_end,
+        Local::now().hour(),
+    )
+}
+
+/// 用户活跃时推迟定期深度扫描，手动触发的深度扫描不受影响。
+pub fn deep_scan_allowed() -> bool {
+    let minutes = AppSettings::load().unwrap_or_default().idle_defer_minutes;
+    minutes == 0 || is_idle_for(Duration::from_secs(minutes * 60), user_idle_duration())
+}
+
+/// 任务的跨进程同步锁，桌面端与命令行同步同一任务前都要取得；进程退出时由系统释放。
+pub struct TaskLock {
+    _file: File,
+}
+
+/// 另一个进程正持有任务的同步锁，本轮跳过。
+#[derive(Debug)]
+pub struct TaskLocked;
+
+impl std::fmt::Display for TaskLocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "另一个进程（桌面端或命令行）正在同步此任务，本轮跳过")
+    }
+}
+
+impl std::error::Error for TaskLocked {}
+
+/// 尝试取得任务的同步锁，锁文件放在数据库旁的 `locks` 目录；另一个进程正在同步该任务时返回 `None`。
+pub fn try_lock_task(db_path: &Path, task_id: &str) -> Result<Option<TaskLock>, Box<dyn Error>> {
+    let dir = db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("locks");
+    fs::create_dir_all(&dir)?;
+    let name: String = task_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(format!("{}.lock", name)))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(TaskLock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(err)) => Err(err.into()),
+    }
+}
+
+/// 访问令牌失效时从钥匙串读取最新令牌，仍是失效的那个再用刷新令牌换新，新令牌写回钥匙串。
+pub fn token_refresh(account_key: &str) -> TokenRefresh {
+    let load_key = account_key.to_string();
+    let store_key = account_key.to_string();
+    TokenRefresh::new(
+        move || load_tokens(&load_key).ok(),
+        move |pair| {
+            let _ = store_tokens(&store_key, &pair.access_token, &pair.refresh_token);
+        },
+    )
+}
+
+/// 加密任务的密钥：先读钥匙串，没有时用环境变量中的密码派生。密钥指纹与任务设置不一致时拒绝同步，
+/// 避免用错误的密钥上传其他设备无法解密的内容。
+fn task_cipher(
+    task_id: &str,
+    encryption: &EncryptionSettings,
+) -> Result<TaskCipher, Box<dyn Error>> {
+    let key = match load_task_key(task_id) {
+        Ok(key) => key,
+        Err(_) => match std::env::var(ENCRYPTION_PASSPHRASE_ENV) {
+            Ok(passphrase) if !passphrase.is_empty() => encryption.unlock(&passphrase)?,
+            _ => return Err("任务已加密，请先输入加密密码".into()),
+        },
+    };
+    let cipher = TaskCipher::new(key, encryption.obfuscate_names);
+    if cipher.fingerprint() != encryption.fingerprint {
+        return Err("保存的加密密钥与任务不匹配，请重新输入加密密码".into());
+    }
+    Ok(cipher)
+}
+
+/// 按应用设置（网络配置、分片、并发、超时、重试、扫描钩子等）构建任务的同步引擎。
+/// 本地目录只读且任务未开启审计模式时返回 `RootReadOnly`。
+pub fn build_engine(
+    db_path: &Path,
+    api_paths: &ApiPaths,
+    task: TaskRow,
+    settings: &RunSettings,
+    events: EventBus,
+    pause: Arc<AtomicBool>,
+) -> Result<SyncEngine, Box<dyn Error>> {
+    let read_only = read_only_roots(&task_roots(&task));
+    if !read_only.is_empty() && !settings.audit_when_read_only {
+        return Err(Box::new(RootReadOnly { roots: read_only }));
+    }
+    let cipher = match &settings.encryption {
+        Some(encryption) => Some(task_cipher(&task.task_id, encryption)?),
+        None => None,
+    };
+    let tokens = load_tokens(&settings.account_key)?;
+    let limits = active_network_profile()
+        .map(|profile| profile.limits())
+        .unwrap_or_default();
+    let app_settings = AppSettings::load().unwrap_or_default();
+    let scan_hook = ScanHook::new(
+        &app_settings.scan_command,
+        app_settings.scan_timeout_secs,
+        config_dir()?.join("quarantine"),
+    );
+    let timeouts = app_settings.timeout_settings();
+    configure_transport(timeouts, app_settings.proxy_settings());
+    Ok(SyncEngine::new(
+        task,
+        api_paths.clone(),
+        Some(tokens.access_token),
+        db_path.to_path_buf(),
+        events,
+    )
+    .with_limits(limits)
+    .with_chunking(app_settings.chunk_settings())
+    .with_transfer_concurrency(app_settings.upload, app_settings.download)
+    .with_file_deadline(timeouts.file_deadline)
+    .with_retry(app_settings.retry_policy())
+    .with_token_refresh(Some(token_refresh(&settings.account_key)))
+    .with_pause_flag(pause)
+    .with_mime_types(app_settings.mime_types)
+    .with_scan_hook(scan_hook)
+    .with_lock_pause(app_settings.lock_pause)
+    .with_cipher(cipher)
+    .with_audit_local(!read_only.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("sync.db");
+        let lock = try_lock_task(&db_path, "task/1")
+            .expect("lock")
+            .expect("first lock");
+        assert!(try_lock_task(&db_path, "task/1").expect("lock").is_none());
+        assert!(try_lock_task(&db_path, "task-2").expect("lock").is_some());
+        drop(lock);
+        assert!(try_lock_task(&db_path, "task/1").expect("lock").is_some());
+        assert!(dir.path().join("locks").join("task_1.lock").exists());
+    }
+}
@@ -0,0 +1,229 @@
+use chrono::Local;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+const PATH_PLACEHOLDER: &str = "{path}";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    Rejected(Option<i32>),
+    TimedOut,
+}
+
+/// 下载内容放到目标位置之前调用的外部扫描命令；非零退出、超时或无法执行时文件会被移入隔离目录。
+#[derive(Debug, Clone)]
+pub struct ScanHook {
+    pub command: String,
+    pub timeout: Duration,
+    pub quarantine_dir: PathBuf,
+}
+
+/// 按 shell 的规则拆分命令模板：空白分隔参数，单引号与双引号内的空白保留，
+/// 反斜杠只转义引号与空白（Windows 路径中的反斜杠原样保留）。引号不成对时返回错误。
+pub fn split_command(template: &str) -> Result<Vec<String>, String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_part = false;
+    let mut quote = None;
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (quote, ch) {
+            (Some(open), ch) if ch == open => quote = None,
+            (Some('"'), '\\') if chars.peek() == Some(&'"') => {
+                current.extend(chars.next());
+            }
+            (Some(_), ch) => current.push(ch),
+            (None, '\'' | '"') => {
+                quote = Some(ch);
+                in_part = true;
+            }
+            (None, '\\')
+                if chars
+                    .peek()
+                    .is_some_and(|next| next.is_whitespace() || matches!(next, '\'' | '"')) =>
+            {
+                current.extend(chars.next());
+                in_part = true;
+            }
+            (None, ch) if ch.is_whitespace() => {
+                if in_part {
+                    parts.push(std::mem::take(&mut current));
+                    in_part = false;
+                }
+            }
+            (None, ch) => {
+                current.push(ch);
+                in_part = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err("扫描命令中的引号不成对".to_string());
+    }
+    if in_part {
+        parts.push(current);
+    }
+    Ok(parts)
+}
+
+/// 拆分命令模板并替换 `{path}`；模板中没有占位符时把路径追加为最后一个参数。
+pub fn render_command(template: &str, path: &Path) -> Result<Vec<String>, String> {
+    let path = path.to_string_lossy();
+    let mut parts = split_command(template)?
+        .into_iter()
+        .map(|part| part.replace(PATH_PLACEHOLDER, &path))
+        .collect::<Vec<_>>();
+    if !template.contains(PATH_PLACEHOLDER) {
+        parts.push(path.to_string());
+    }
+    Ok(parts)
+}
+
+impl ScanHook {
+    pub fn new(command: &str, timeout_secs: u64, quarantine_dir: PathBuf) -> Option<Self> {
+        let command = command.trim();
+        if command.is_empty() {
+            return None;
+        }
+        Some(Self {
+            command: command.to_string(),
+            timeout: Duration::from_secs(timeout_secs.max(1)),
+            quarantine_dir,
+        })
+    }
+
+    /// 运行扫描命令并等待结束；命令无法启动时返回错误，由调用方按未通过处理。
+    pub async fn scan(&self, path: &Path) -> Result<ScanVerdict, Box<dyn Error>> {
+        let parts = render_command(&self.command, path)?;
+        let (program, args) = parts.split_first().ok_or("扫描命令为空")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        match tokio::time::timeout(self.timeout, child.wait()).await {
+            Ok(status) => {
+                let status = status?;
+                Ok(if status.success() {
+                    ScanVerdict::Clean
+                } else {
+                    ScanVerdict::Rejected(status.code())
+                })
+            }
+            Err(_) => {
+                let _ = child.kill().await;
+                Ok(ScanVerdict::TimedOut)
+            }
+        }
+    }
+
+    /// 将文件以 `name` 为名移入 `quarantine_dir/<task_id>/`，文件名带时间前缀避免覆盖，返回新路径。
+    pub fn quarantine(
+        &self,
+        task_id: &str,
+        path: &Path,
+        name: &str,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        let dir = self.quarantine_dir.join(task_id);
+        fs::create_dir_all(&dir)?;
+        let target = dir.join(format!("{}-{}", Local::now().format("%Y%m%d-%H%M%S"), name));
+        if fs::rename(path, &target).is_err() {
+            fs::copy(path, &target)?;
+            fs::remove_file(path)?;
+        }
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn render_command_substitutes_or_appends_path() {
+        let path = Path::new("/tmp/my file.txt");
+        assert_eq!(
+            render_command("clamscan --no-summary {path}", path).expect("render"),
+            vec!["clamscan", "--no-summary", "/tmp/my file.txt"]
+        );
+        assert_eq!(
+            render_command("scanner -q", path).expect("render"),
+            vec!["scanner", "-q", "/tmp/my file.txt"]
+        );
+        assert_eq!(
+            render_command(
+                r#""C:\Program Files\Scan\scan.exe" --mode 'deep scan' {path}"#,
+                path
+            )
+            .expect("render"),
+            vec![
+                r"C:\Program Files\Scan\scan.exe",
+                "--mode",
+                "deep scan",
+                "/tmp/my file.txt"
+            ]
+        );
+        assert_eq!(
+            split_command(r#"scan --tag=a\ b "say \"hi\"" ''"#).expect("split"),
+            vec!["scan", "--tag=a b", "say \"hi\"", ""]
+        );
+        assert!(render_command("scan 'unterminated {path}", path).is_err());
+        assert!(ScanHook::new("  ", 10, PathBuf::new()).is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn scan_reports_exit_status_and_timeout() {
+        let dir = tempdir().expect("tempdir");
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "data").expect("write");
+        let hook = |command: &str, timeout: Duration| ScanHook {
+            command: command.to_string(),
+            timeout,
+            quarantine_dir: dir.path().join("quarantine"),
+        };
+        let long = Duration::from_secs(5);
+        assert_eq!(
+            hook("true", long).scan(&file).await.expect("scan"),
+            ScanVerdict::Clean
+        );
+        assert_eq!(
+            hook("false", long).scan(&file).await.expect("scan"),
+            ScanVerdict::Rejected(Some(1))
+        );
+        assert_eq!(
+            hook("tail -f {path}", Duration::from_millis(200))
+                .scan(&file)
+                .await
+                .expect("scan"),
+            ScanVerdict::TimedOut
+        );
+        assert!(hook("/nonexistent/scanner", long)
+            .scan(&file)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn quarantine_moves_file_out_of_sync_root() {
+        let dir = tempdir().expect("tempdir");
+        let file = dir.path().join("bad.exe.cloudreve-part");
+        fs::write(&file, "payload").expect("write");
+        let hook = ScanHook::new("scanner", 10, dir.path().join("quarantine")).expect("hook");
+        let target = hook
+            .quarantine("task-1", &file, "bad.exe")
+            .expect("quarantine");
+        assert!(!file.exists());
+        assert!(target.starts_with(dir.path().join("quarantine").join("task-1")));
+        assert!(target.to_string_lossy().ends_with("-bad.exe"));
+        assert_eq!(fs::read_to_string(target).expect("read"), "payload");
+    }
+}
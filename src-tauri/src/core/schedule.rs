@@ -0,0 +1,480 @@
+use crate::core::db::{DirActivityRow, ScanScheduleRow};
+use crate::core::sync::ScanMode;
+use chrono::{Datelike, Duration, Local, NaiveDateTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_DEEP_SCAN_INTERVAL_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScanSchedule {
+    pub quick_interval_secs: u64,
+    pub deep_interval_secs: u64,
+}
+
+impl ScanSchedule {
+    pub fn new(quick_interval_secs: u64, deep_interval_secs: u64) -> Self {
+        Self {
+            quick_interval_secs: quick_interval_secs.max(5),
+            deep_interval_secs: deep_interval_secs.max(quick_interval_secs),
+        }
+    }
+
+    pub fn next_mode(&self, state: Option<&ScanScheduleRow>, now_ms: i64) -> ScanMode {
+        let last_deep = state.and_then(|row| row.last_deep_scan_ms);
+        match last_deep {
+            Some(last) if now_ms - last < (self.deep_interval_secs as i64) * 1000 => {
+                ScanMode::Quick
+            }
+            _ => ScanMode::Deep,
+        }
+    }
+}
+
+/// 目录的变化分数在每次扫描后衰减一半，本次有变化再加 1。
+const DIR_SCORE_DECAY: f64 = 0.5;
+/// 分数不低于此值的目录每轮快速扫描都访问，更低时按分数的倒数拉长间隔。
+const HOT_DIR_SCORE: f64 = 0.25;
+/// 最冷的目录每隔这么多轮快速扫描访问一次；深度扫描总会访问所有目录。
+const MAX_DIR_SCAN_PASSES: i64 = 16;
+
+/// 自适应扫描中目录每隔几轮快速扫描访问一次。
+pub fn dir_scan_passes(score: f64) -> i64 {
+    if score >= HOT_DIR_SCORE {
+        return 1;
+    }
+    if score <= 0.0 {
+        return MAX_DIR_SCAN_PASSES;
+    }
+    ((HOT_DIR_SCORE / score).ceil() as i64).clamp(1, MAX_DIR_SCAN_PASSES)
+}
+
+/// 没有记录的目录（新目录或首次启用自适应扫描）总是需要扫描。
+pub fn dir_scan_due(row: Option<&DirActivityRow>) -> bool {
+    row.is_none_or(|row| row.skipped_passes + 1 >= dir_scan_passes(row.score))
+}
+
+pub fn scanned_dir_activity(
+    row: Option<&DirActivityRow>,
+    relpath: &str,
+    changed: bool,
+) -> DirActivityRow {
+    let score = row.map(|row| row.score).unwrap_or(1.0) * DIR_SCORE_DECAY;
+    DirActivityRow {
+        relpath: relpath.to_string(),
+        score: if changed { score + 1.0 } else { score },
+        skipped_passes: 0,
+    }
+}
+
+pub fn skipped_dir_activity(row: &DirActivityRow) -> DirActivityRow {
+    DirActivityRow {
+        skipped_passes: row.skipped_passes + 1,
+        ..row.clone()
+    }
+}
+
+/// 启动时距离下次运行还需等待的毫秒数。逾期任务允许补跑时立即运行，
+/// 否则跳过错过的周期，顺延到下一个周期点。
+pub fn launch_delay_ms(
+    next_due_at_ms: Option<i64>,
+    now_ms: i64,
+    interval_secs: u64,
+    catch_up: bool,
+) -> i64 {
+    let Some(due) = next_due_at_ms else {
+        return 0;
+    };
+    if due > now_ms {
+        return due - now_ms;
+    }
+    if catch_up {
+        return 0;
+    }
+    let interval_ms = (interval_secs.max(1) as i64) * 1000;
+    let missed = (now_ms - due) / interval_ms + 1;
+    due + missed * interval_ms - now_ms
+}
+
+/// 开始与结束小时相同表示未启用；支持跨午夜，如 22 点到 7 点。
+pub fn in_quiet_hours(start_hour: u32, end_hour: u32, hour: u32) -> bool {
+    if start_hour == end_hour {
+        return false;
+    }
+    if start_hour < end_hour {
+        hour >= start_hour && hour < end_hour
+    } else {
+        hour >= start_hour || hour < end_hour
+    }
+}
+
+/// 允许定时同步的时段，时间为本地时间的 `HH:MM`。结束早于开始表示跨午夜，
+/// 如 22:00–06:00；开始与结束相同表示全天。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncWindow {
+    pub start: String,
+    pub end: String,
+}
+
+fn parse_clock(value: &str) -> Result<u32, String> {
+    let (hour, minute) = value
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| format!("时间格式应为 HH:MM: {}", value))?;
+    let hour = hour.parse::<u32>().ok().filter(|hour| *hour < 24);
+    let minute = minute.parse::<u32>().ok().filter(|minute| *minute < 60);
+    match (hour, minute) {
+        (Some(hour), Some(minute)) => Ok(hour * 60 + minute),
+        _ => Err(format!("时间格式应为 HH:MM: {}", value)),
+    }
+}
+
+fn parse_cron_field(spec: &str, min: u32, max: u32) -> Result<u64, String> {
+    let invalid = || format!("cron 字段无效: {}", spec);
+    let mut mask = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(invalid)?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start.parse().map_err(|_| invalid())?,
+                end.parse().map_err(|_| invalid())?,
+            )
+        } else {
+            let start = range.parse().map_err(|_| invalid())?;
+            // `5/15` 表示从 5 开始每 15 个单位一次。
+            (start, if part.contains('/') { max } else { start })
+        };
+        if start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// 五段式 cron 表达式（分 时 日 月 周），按本地时间匹配，周日为 0 或 7。
+/// 日与周都有限制时满足其一即可，与常见 cron 实现一致。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 5 {
+            return Err(format!("cron 表达式需要 5 个字段: {}", expr));
+        }
+        let mut weekdays = parse_cron_field(fields[4], 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_cron_field(fields[0], 0, 59)?,
+            hours: parse_cron_field(fields[1], 0, 23)?,
+            days: parse_cron_field(fields[2], 1, 31)?,
+            months: parse_cron_field(fields[3], 1, 12)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    fn matches_day(&self, at: NaiveDateTime) -> bool {
+        let day = self.days & (1 << at.day()) != 0;
+        let weekday = self.weekdays & (1 << at.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => day,
+            (true, false) => weekday,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// `after` 之后（不含）第一个匹配的分钟；四年内都不匹配（如 2 月 30 日）时返回 None。
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut at = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(4 * 366);
+        while at <= limit {
+            if self.months & (1 << at.month()) == 0 || !self.matches_day(at) {
+                at = (at.date() + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << at.hour()) == 0 {
+                at = at.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << at.minute()) == 0 {
+                at += Duration::minutes(1);
+            } else {
+                return Some(at);
+            }
+        }
+        None
+    }
+}
+
+/// 任务的定时规则：固定间隔或 cron 表达式决定下次运行时间，时间窗口限制允许同步的时段。
+#[derive(Debug, Clone)]
+pub struct RunPlan {
+    interval_secs: u64,
+    cron: Option<CronSchedule>,
+    windows: Vec<(u32, u32)>,
+}
+
+impl RunPlan {
+    pub fn new(interval_secs: u64, cron: &str, windows: &[SyncWindow]) -> Result<Self, String> {
+        let cron = match cron.trim() {
+            "" => None,
+            expr => Some(CronSchedule::parse(expr)?),
+        };
+        let windows = windows
+            .iter()
+            .map(|window| Ok((parse_clock(&window.start)?, parse_clock(&window.end)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self {
+            interval_secs: interval_secs.max(1),
+            cron,
+            windows,
+        })
+    }
+
+    pub fn every(interval_secs: u64) -> Self {
+        Self {
+            interval_secs: interval_secs.max(1),
+            cron: None,
+            windows: Vec::new(),
+        }
+    }
+
+    pub fn allows(&self, at: NaiveDateTime) -> bool {
+        let minute = at.hour() * 60 + at.minute();
+        self.windows.is_empty()
+            || self.windows.iter().any(|&(start, end)| {
+                start == end
+                    || (start < end && minute >= start && minute < end)
+                    || (start > end && (minute >= start || minute < end))
+            })
+    }
+
+    fn next_window_start(&self, at: NaiveDateTime) -> NaiveDateTime {
+        self.windows
+            .iter()
+            .filter_map(|&(start, _)| {
+                let today = at.date().and_hms_opt(start / 60, start % 60, 0)?;
+                Some(if today >= at {
+                    today
+                } else {
+                    today + Duration::days(1)
+                })
+            })
+            .min()
+            .unwrap_or(at)
+    }
+
+    /// `after` 之后的下次定时运行时间，落在时间窗口之外时顺延到窗口内。
+    pub fn next_run_after(&self, after: NaiveDateTime) -> NaiveDateTime {
+        let Some(cron) = &self.cron else {
+            let next = after + Duration::seconds(self.interval_secs as i64);
+            return if self.allows(next) {
+                next
+            } else {
+                self.next_window_start(next)
+            };
+        };
+        let mut at = after;
+        // 只检查有限次数，cron 与窗口永不相交时退回 cron 的下一个时间点。
+        for _ in 0..10_000 {
+            match cron.next_after(at) {
+                Some(next) if self.allows(next) => return next,
+                Some(next) => at = next,
+                None => break,
+            }
+        }
+        cron.next_after(after)
+            .unwrap_or(after + Duration::seconds(self.interval_secs as i64))
+    }
+
+    pub fn allows_ms(&self, now_ms: i64) -> bool {
+        local_time(now_ms).is_none_or(|at| self.allows(at))
+    }
+
+    pub fn next_run_ms(&self, now_ms: i64) -> i64 {
+        let fallback = now_ms + (self.interval_secs as i64) * 1000;
+        local_time(now_ms)
+            .and_then(|now| {
+                let next = self.next_run_after(now);
+                // 夏令时切换造成的重复时刻取较早的一个，不存在的时刻按一小时后处理。
+                Local.from_local_datetime(&next).earliest().or_else(|| {
+                    Local
+                        .from_local_datetime(&(next + Duration::hours(1)))
+                        .earliest()
+                })
+            })
+            .map(|next| next.timestamp_millis().max(now_ms))
+            .unwrap_or(fallback)
+    }
+
+    /// 使用 cron 时错过的运行不补跑则等到下一个 cron 时间点，否则与 `launch_delay_ms` 相同。
+    pub fn launch_delay_ms(&self, next_due_at_ms: Option<i64>, now_ms: i64, catch_up: bool) -> i64 {
+        match next_due_at_ms {
+            Some(due) if self.cron.is_some() && due <= now_ms && !catch_up => {
+                self.next_run_ms(now_ms) - now_ms
+            }
+            _ => launch_delay_ms(next_due_at_ms, now_ms, self.interval_secs, catch_up),
+        }
+    }
+}
+
+fn local_time(ms: i64) -> Option<NaiveDateTime> {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|at| at.naive_local())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2024-01-01 是周一。
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .expect("valid time")
+    }
+
+    fn window(start: &str, end: &str) -> SyncWindow {
+        SyncWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    #[test]
+    fn cron_finds_next_matching_minute() {
+        let nightly = CronSchedule::parse("30 2 * * *").expect("parse");
+        assert_eq!(nightly.next_after(at(1, 1, 0)), Some(at(1, 2, 30)));
+        assert_eq!(nightly.next_after(at(1, 2, 30)), Some(at(2, 2, 30)));
+
+        let quarter = CronSchedule::parse("*/15 9-17 * * 1-5").expect("parse");
+        assert_eq!(quarter.next_after(at(1, 9, 7)), Some(at(1, 9, 15)));
+        assert_eq!(quarter.next_after(at(1, 17, 45)), Some(at(2, 9, 0)));
+        // 1 月 6 日是周六，顺延到周一。
+        assert_eq!(quarter.next_after(at(5, 18, 0)), Some(at(8, 9, 0)));
+
+        let sunday = CronSchedule::parse("0 0 * * 7").expect("parse");
+        assert_eq!(sunday.next_after(at(1, 0, 0)), Some(at(7, 0, 0)));
+        assert_eq!(
+            CronSchedule::parse("0 0 30 2 *")
+                .expect("parse")
+                .next_after(at(1, 0, 0)),
+            None
+        );
+        assert!(CronSchedule::parse("0 0 * *").is_err());
+        assert!(CronSchedule::parse("61 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+    }
+
+    #[test]
+    fn windows_defer_runs_until_they_open() {
+        let plan = RunPlan::new(600, "", &[window("22:00", "06:00")]).expect("plan");
+        assert!(plan.allows(at(1, 23, 0)));
+        assert!(plan.allows(at(2, 5, 59)));
+        assert!(!plan.allows(at(2, 6, 0)));
+        assert_eq!(plan.next_run_after(at(1, 23, 0)), at(1, 23, 10));
+        assert_eq!(plan.next_run_after(at(2, 5, 55)), at(2, 22, 0));
+        assert_eq!(plan.next_run_after(at(2, 12, 0)), at(2, 22, 0));
+
+        let cron = RunPlan::new(600, "0 * * * *", &[window("22:00", "06:00")]).expect("plan");
+        assert_eq!(cron.next_run_after(at(2, 6, 30)), at(2, 22, 0));
+        assert_eq!(cron.next_run_after(at(2, 22, 0)), at(2, 23, 0));
+
+        assert!(RunPlan::new(60, "", &[window("25:00", "06:00")]).is_err());
+        assert!(RunPlan::every(60).allows(at(1, 12, 0)));
+    }
+
+    #[test]
+    fn cold_dirs_are_visited_less_often_until_they_change() {
+        let mut row = scanned_dir_activity(None, "photos", false);
+        let mut visits = Vec::new();
+        for pass in 0..40 {
+            if dir_scan_due(Some(&row)) {
+                visits.push(pass);
+                row = scanned_dir_activity(Some(&row), "photos", false);
+            } else {
+                row = skipped_dir_activity(&row);
+            }
+        }
+        assert_eq!(&visits[..6], &[0, 1, 3, 7, 15, 31]);
+        assert_eq!(dir_scan_passes(row.score), MAX_DIR_SCAN_PASSES);
+
+        let row = scanned_dir_activity(Some(&row), "photos", true);
+        assert_eq!(dir_scan_passes(row.score), 1);
+        assert!(dir_scan_due(Some(&row)));
+        assert!(dir_scan_due(None));
+    }
+
+    fn row(last_deep_scan_ms: Option<i64>) -> ScanScheduleRow {
+        ScanScheduleRow {
+            task_id: "task-1".to_string(),
+            last_quick_scan_ms: None,
+            last_deep_scan_ms,
+        }
+    }
+
+    #[test]
+    fn first_pass_is_deep() {
+        let schedule = ScanSchedule::new(60, 3600);
+        assert_eq!(schedule.next_mode(None, 1_000), ScanMode::Deep);
+        assert_eq!(schedule.next_mode(Some(&row(None)), 1_000), ScanMode::Deep);
+    }
+
+    #[test]
+    fn quick_until_deep_interval_elapses() {
+        let schedule = ScanSchedule::new(60, 3600);
+        let state = row(Some(0));
+        assert_eq!(schedule.next_mode(Some(&state), 3_599_999), ScanMode::Quick);
+        assert_eq!(schedule.next_mode(Some(&state), 3_600_000), ScanMode::Deep);
+    }
+
+    #[test]
+    fn launch_delay_waits_for_future_runs_and_catches_up_overdue() {
+        assert_eq!(launch_delay_ms(None, 1_000, 60, true), 0);
+        assert_eq!(launch_delay_ms(Some(5_000), 1_000, 60, true), 4_000);
+        assert_eq!(launch_delay_ms(Some(1_000), 50_000, 60, true), 0);
+        // 错过一个周期且不补跑：顺延到 1_000 + 60_000。
+        assert_eq!(launch_delay_ms(Some(1_000), 50_000, 60, false), 11_000);
+    }
+
+    #[test]
+    fn quiet_hours_wrap_midnight() {
+        assert!(!in_quiet_hours(0, 0, 3));
+        assert!(in_quiet_hours(1, 6, 3));
+        assert!(!in_quiet_hours(1, 6, 6));
+        assert!(in_quiet_hours(22, 7, 23));
+        assert!(in_quiet_hours(22, 7, 2));
+        assert!(!in_quiet_hours(22, 7, 12));
+    }
+
+    #[test]
+    fn deep_interval_never_shorter_than_quick() {
+        let schedule = ScanSchedule::new(600, 60);
+        assert_eq!(schedule.deep_interval_secs, 600);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::core::db::AccountRow;
+use crate::core::metrics::{is_stale, TaskHealth};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerStatus {
+    /// 没有启用的任务。
+    #[default]
+    Idle,
+    Ok,
+    Running,
+    Stale,
+    Failing,
+    /// 有账户的登录已失效，相关任务暂停到重新登录为止。
+    NeedsLogin,
+}
+
+impl ServerStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Idle => "未启用",
+            Self::Ok => "正常",
+            Self::Running => "运行中",
+            Self::Stale => "长时间未成功同步",
+            Self::Failing => "同步失败",
+            Self::NeedsLogin => "需要重新登录",
+        }
+    }
+}
+
+/// 汇总时需要的任务信息。
+#[derive(Debug, Clone)]
+pub struct ServerTask {
+    pub task_id: String,
+    pub base_url: String,
+    pub enabled: bool,
+    pub running: bool,
+}
+
+/// 同一服务器下所有账户与任务的合计状态。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ServerSummary {
+    pub base_url: String,
+    pub accounts: Vec<String>,
+    /// 需要重新登录的账户数。
+    pub needs_login: u32,
+    pub tasks: u32,
+    pub running: u32,
+    pub failing: u32,
+    pub stale: u32,
+    pub file_errors: u64,
+    pub last_success_ms: Option<i64>,
+    pub status: ServerStatus,
+}
+
+/// 同一服务器的地址可能带或不带末尾的斜杠，按去掉斜杠后的地址分组。
+pub fn server_key(base_url: &str) -> String {
+    base_url.trim().trim_end_matches('/').to_string()
+}
+
+fn server_entry<'a>(
+    servers: &'a mut BTreeMap<String, ServerSummary>,
+    base_url: &str,
+) -> &'a mut ServerSummary {
+    let key = server_key(base_url);
+    servers.entry(key.clone()).or_insert_with(|| ServerSummary {
+        base_url: key,
+        ..ServerSummary::default()
+    })
+}
+
+/// 按服务器分组账户与任务；`health` 缺少的任务视为尚无运行记录，
+/// `needs_login` 为登录已失效的账户。
+pub fn summarize_servers(
+    accounts: &[AccountRow],
+    needs_login: &BTreeSet<String>,
+    tasks: &[ServerTask],
+    health: &BTreeMap<String, TaskHealth>,
+    now_ms: i64,
+) -> Vec<ServerSummary> {
+    let mut servers = BTreeMap::new();
+    for account in accounts {
+        let summary = server_entry(&mut servers, &account.base_url);
+        summary.accounts.push(account.email.clone());
+        summary.needs_login += u32::from(needs_login.contains(&account.account_key));
+    }
+    for task in tasks {
+        let summary = server_entry(&mut servers, &task.base_url);
+        if !task.enabled {
+            continue;
+        }
+        summary.tasks += 1;
+        summary.running += u32::from(task.running);
+        let Some(health) = health.get(&task.task_id) else {
+            continue;
+        };
+        if health.consecutive_failures > 0 {
+            summary.failing += 1;
+        }
+        if is_stale(health.last_success_ms, now_ms) {
+            summary.stale += 1;
+        }
+        summary.file_errors += health.file_errors;
+        summary.last_success_ms = summary.last_success_ms.max(health.last_success_ms);
+    }
+    servers
+        .into_values()
+        .map(|mut summary| {
+            summary.status = if summary.needs_login > 0 {
+                ServerStatus::NeedsLogin
+            } else if summary.tasks == 0 {
+                ServerStatus::Idle
+            } else if summary.failing > 0 {
+                ServerStatus::Failing
+            } else if summary.stale > 0 {
+                ServerStatus::Stale
+            } else if summary.running > 0 {
+                ServerStatus::Running
+            } else {
+                ServerStatus::Ok
+            };
+            summary
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::metrics::STALE_SUCCESS_MS;
+
+    fn account(base_url: &str, email: &str) -> AccountRow {
+        AccountRow {
+            account_key: format!("{}|{}", base_url, email),
+            base_url: base_url.to_string(),
+            email: email.to_string(),
+            created_at_ms: 0,
+        }
+    }
+
+    fn task(task_id: &str, base_url: &str, running: bool) -> ServerTask {
+        ServerTask {
+            task_id: task_id.to_string(),
+            base_url: base_url.to_string(),
+            enabled: true,
+            running,
+        }
+    }
+
+    #[test]
+    fn groups_accounts_and_tasks_by_server() {
+        let now = 10 * STALE_SUCCESS_MS;
+        let accounts = [
+            account("https://a.example/", "alice@a"),
+            account("https://a.example", "bob@a"),
+            account("https://b.example", "carol@b"),
+            account("https://c.example", "dave@c"),
+        ];
+        let tasks = [
+            task("t1", "https://a.example", true),
+            task("t2", "https://a.example/", false),
+            task("t3", "https://b.example", false),
+        ];
+        let health = BTreeMap::from([
+            (
+                "t2".to_string(),
+                TaskHealth {
+                    last_success_ms: Some(now - 1_000),
+                    file_errors: 2,
+                    ..TaskHealth::default()
+                },
+            ),
+            (
+                "t3".to_string(),
+                TaskHealth {
+                    last_success_ms: Some(now - STALE_SUCCESS_MS - 1),
+                    consecutive_failures: 3,
+                    ..TaskHealth::default()
+                },
+            ),
+        ]);
+
+        let servers = summarize_servers(&accounts, &BTreeSet::new(), &tasks, &health, now);
+        assert_eq!(servers.len(), 3);
+        let a = &servers[0];
+        assert_eq!(a.base_url, "https://a.example");
+        assert_eq!(a.accounts, vec!["alice@a", "bob@a"]);
+        assert_eq!((a.tasks, a.running, a.file_errors), (2, 1, 2));
+        assert_eq!(a.last_success_ms, Some(now - 1_000));
+        assert_eq!(a.status, ServerStatus::Running);
+        let b = &servers[1];
+        assert_eq!((b.failing, b.stale), (1, 1));
+        assert_eq!(b.status, ServerStatus::Failing);
+        assert_eq!(servers[2].status, ServerStatus::Idle);
+
+        let expired = BTreeSet::from([accounts[2].account_key.clone()]);
+        let servers = summarize_servers(&accounts, &expired, &tasks, &health, now);
+        assert_eq!(servers[1].needs_login, 1);
+        assert_eq!(servers[1].status, ServerStatus::NeedsLogin);
+        assert_eq!(servers[0].status, ServerStatus::Running);
+    }
+}
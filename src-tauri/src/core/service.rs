@@ -0,0 +1,443 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const HEADLESS_ARG: &str = "--headless";
+/// 登录时自启动附带的参数：不显示主窗口，只在托盘中运行。
+pub const MINIMIZED_ARG: &str = "--minimized";
+/// 计划任务无法重定向输出，无窗口实例据此参数自行把标准输出与标准错误追加到服务日志。
+pub const LOG_FILE_ARG: &str = "--log-file";
+const SYSTEMD_UNIT_NAME: &str = "cloudreve-sync.service";
+const WINDOWS_TASK_NAME: &str = "CloudreveSync";
+const AUTOSTART_DESKTOP_NAME: &str = "cloudreve-sync.desktop";
+const LAUNCH_AGENT_LABEL: &str = "cn.mikesolar.cloudreve-sync";
+const WINDOWS_RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+const WINDOWS_RUN_VALUE: &str = "CloudreveSync";
+
+/// 登录项、后台服务与文件管理器菜单中登记的启动路径。以 AppImage 运行时可执行文件位于每次
+/// 启动临时挂载的目录中，应登记 `$APPIMAGE` 指向的 AppImage 文件本身。
+pub fn launcher_path() -> std::io::Result<PathBuf> {
+    let exe_path = std::env::current_exe()?;
+    if !cfg!(target_os = "linux") {
+        return Ok(exe_path);
+    }
+    Ok(appimage_launcher(std::env::var_os("APPIMAGE"), exe_path))
+}
+
+fn appimage_launcher(appimage: Option<std::ffi::OsString>, exe_path: PathBuf) -> PathBuf {
+    appimage
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute() && path.is_file())
+        .unwrap_or(exe_path)
+}
+
+/// 后台常驻运行所需的信息：可执行文件与服务输出日志的位置。
+#[derive(Debug, Clone)]
+pub struct ServiceSpec {
+    pub exe_path: PathBuf,
+    pub log_path: PathBuf,
+}
+
+fn systemd_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+}
+
+/// systemd 用户单元；程序仍需图形会话，因此挂在 graphical-session.target 上，异常退出后自动重启。
+pub fn systemd_unit(spec: &ServiceSpec) -> String {
+    let log_path = systemd_escape(&spec.log_path.to_string_lossy());
+    format!(
+        "[Unit]\n\
+         Description=Cloudreve Sync\n\
+         After=graphical-session.target network-online.target\n\
+         PartOf=graphical-session.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart=\"{}\" {}\n\
+         Restart=on-failure\n\
+         RestartSec=10\n\
+         StandardOutput=append:{}\n\
+         StandardError=append:{}\n\
+         \n\
+         [Install]\n\
+         WantedBy=graphical-session.target\n",
+        systemd_escape(&spec.exe_path.to_string_lossy()),
+        HEADLESS_ARG,
+        log_path,
+        log_path
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Windows 计划任务定义：登录时启动、隐藏运行、失败后每分钟重试。
+/// 普通桌面程序无法作为 SCM 服务运行，因此使用计划任务代替服务。
+pub fn windows_task_xml(spec: &ServiceSpec) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>Cloudreve Sync</Description>
+  </RegistrationInfo>
+  <Triggers>
+    <LogonTrigger>
+      <Enabled>true</Enabled>
+    </LogonTrigger>
+  </Triggers>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <RestartOnFailure>
+      <Interval>PT1M</Interval>
+      <Count>999</Count>
+    </RestartOnFailure>
+    <Hidden>true</Hidden>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>{}</Command>
+      <Arguments>{} {} "{}"</Arguments>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        xml_escape(&spec.exe_path.to_string_lossy()),
+        HEADLESS_ARG,
+        LOG_FILE_ARG,
+        xml_escape(&spec.log_path.to_string_lossy())
+    )
+}
+
+/// 取出命令行中 `--log-file <路径>` 指定的日志文件。
+pub fn log_file_from_args(args: impl IntoIterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.into_iter();
+    args.find(|arg| arg == LOG_FILE_ARG)?;
+    args.next().map(PathBuf::from)
+}
+
+/// 把本进程的标准输出与标准错误改为追加写入日志文件；句柄在进程退出前一直保留。
+#[cfg(windows)]
+pub fn redirect_output(path: &Path) -> Result<(), Box<dyn Error>> {
+    use std::os::windows::io::IntoRawHandle;
+    use windows_sys::Win32::System::Console::{SetStdHandle, STD_ERROR_HANDLE, STD_OUTPUT_HANDLE};
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let handle = file.into_raw_handle();
+    // SAFETY: 句柄来自刚打开的文件且不再关闭，标准库每次写入都会重新读取标准句柄。
+    let ok = unsafe {
+        SetStdHandle(STD_OUTPUT_HANDLE, handle) != 0 && SetStdHandle(STD_ERROR_HANDLE, handle) != 0
+    };
+    if !ok {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// 其他平台由 systemd 等服务管理器负责重定向输出。
+#[cfg(not(windows))]
+pub fn redirect_output(_path: &Path) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+/// Exec 中带引号的参数需转义 `"`、`` ` ``、`$` 与 `\`，整个值再按桌面文件的字符串规则转义反斜杠。
+fn desktop_exec_escape(value: &str) -> String {
+    let mut quoted = String::new();
+    for ch in value.chars() {
+        if matches!(ch, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(ch);
+    }
+    quoted.replace('\\', "\\\\").replace('%', "%%")
+}
+
+/// XDG autostart 条目，桌面环境在用户登录后启动。
+pub fn autostart_desktop_entry(exe_path: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=Cloudreve Sync\n\
+         Exec=\"{}\" {}\n\
+         Terminal=false\n\
+         X-GNOME-Autostart-enabled=true\n",
+        desktop_exec_escape(&exe_path.to_string_lossy()),
+        MINIMIZED_ARG
+    )
+}
+
+/// macOS 登录项使用的 LaunchAgent，只在登录时启动一次，退出后不自动拉起。
+pub fn launch_agent_plist(exe_path: &Path) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{}</string>
+  <key>ProgramArguments</key>
+  <array>
+    <string>{}</string>
+    <string>{}</string>
+  </array>
+  <key>RunAtLoad</key>
+  <true/>
+</dict>
+</plist>
+"#,
+        LAUNCH_AGENT_LABEL,
+        xml_escape(&exe_path.to_string_lossy()),
+        MINIMIZED_ARG
+    )
+}
+
+/// 写入注册表 Run 键的命令行。
+pub fn windows_run_command(exe_path: &Path) -> String {
+    format!("\"{}\" {}", exe_path.to_string_lossy(), MINIMIZED_ARG)
+}
+
+fn run(program: &str, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let output = Command::new(program).args(args).output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(format!(
+        "{} {} 失败: {}",
+        program,
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    )
+    .into())
+}
+
+fn systemd_unit_path() -> Result<PathBuf, Box<dyn Error>> {
+    let base = directories::BaseDirs::new().ok_or("failed to locate config dir")?;
+    Ok(base
+        .config_dir()
+        .join("systemd/user")
+        .join(SYSTEMD_UNIT_NAME))
+}
+
+fn write_utf16(path: &Path, text: &str) -> Result<(), Box<dyn Error>> {
+    let mut bytes = vec![0xFF, 0xFE];
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_le_bytes());
+    }
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// 注册开机（登录）自启的后台实例；为避免与当前窗口重复同步，下次登录时才启动。
+pub fn install_service(spec: &ServiceSpec, work_dir: &Path) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = spec.log_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if cfg!(target_os = "linux") {
+        let unit_path = systemd_unit_path()?;
+        if let Some(parent) = unit_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&unit_path, systemd_unit(spec))?;
+        run("systemctl", &["--user", "daemon-reload"])?;
+        run("systemctl", &["--user", "enable", SYSTEMD_UNIT_NAME])
+    } else if cfg!(target_os = "windows") {
+        fs::create_dir_all(work_dir)?;
+        let xml_path = work_dir.join("service-task.xml");
+        write_utf16(&xml_path, &windows_task_xml(spec))?;
+        let result = run(
+            "schtasks",
+            &[
+                "/Create",
+                "/TN",
+                WINDOWS_TASK_NAME,
+                "/XML",
+                &xml_path.to_string_lossy(),
+                "/F",
+            ],
+        );
+        let _ = fs::remove_file(&xml_path);
+        result
+    } else {
+        Err("当前系统不支持安装后台服务".into())
+    }
+}
+
+fn autostart_path() -> Result<PathBuf, Box<dyn Error>> {
+    let base = directories::BaseDirs::new().ok_or("failed to locate config dir")?;
+    if cfg!(target_os = "macos") {
+        Ok(base
+            .home_dir()
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", LAUNCH_AGENT_LABEL)))
+    } else {
+        Ok(base
+            .config_dir()
+            .join("autostart")
+            .join(AUTOSTART_DESKTOP_NAME))
+    }
+}
+
+/// 按设置注册或移除登录时自启动；已是目标状态时不做任何修改。
+pub fn set_autostart(exe_path: &Path, enabled: bool) -> Result<(), Box<dyn Error>> {
+    if cfg!(target_os = "windows") {
+        let registered = run("reg", &["query", WINDOWS_RUN_KEY, "/v", WINDOWS_RUN_VALUE]).is_ok();
+        return match (enabled, registered) {
+            (true, _) => run(
+                "reg",
+                &[
+                    "add",
+                    WINDOWS_RUN_KEY,
+                    "/v",
+                    WINDOWS_RUN_VALUE,
+                    "/t",
+                    "REG_SZ",
+                    "/d",
+                    &windows_run_command(exe_path),
+                    "/f",
+                ],
+            ),
+            (false, true) => run(
+                "reg",
+                &["delete", WINDOWS_RUN_KEY, "/v", WINDOWS_RUN_VALUE, "/f"],
+            ),
+            (false, false) => Ok(()),
+        };
+    }
+    if !cfg!(any(target_os = "linux", target_os = "macos")) {
+        return Err("当前系统不支持开机自启动".into());
+    }
+    let path = autostart_path()?;
+    if !enabled {
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    let content = if cfg!(target_os = "macos") {
+        launch_agent_plist(exe_path)
+    } else {
+        autostart_desktop_entry(exe_path)
+    };
+    if fs::read_to_string(&path).is_ok_and(|current| current == content) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, content)?;
+    Ok(())
+}
+
+pub fn uninstall_service() -> Result<(), Box<dyn Error>> {
+    if cfg!(target_os = "linux") {
+        let unit_path = systemd_unit_path()?;
+        let _ = run(
+            "systemctl",
+            &["--user", "disable", "--now", SYSTEMD_UNIT_NAME],
+        );
+        if unit_path.exists() {
+            fs::remove_file(&unit_path)?;
+        }
+        run("systemctl", &["--user", "daemon-reload"])
+    } else if cfg!(target_os = "windows") {
+        run("schtasks", &["/Delete", "/TN", WINDOWS_TASK_NAME, "/F"])
+    } else {
+        Err("当前系统不支持安装后台服务".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ServiceSpec {
+        ServiceSpec {
+            exe_path: PathBuf::from("/opt/Cloudreve Sync/app"),
+            log_path: PathBuf::from("/home/u/.config/sync/logs/100%.log"),
+        }
+    }
+
+    #[test]
+    fn systemd_unit_restarts_and_routes_logs() {
+        let unit = systemd_unit(&spec());
+        assert!(unit.contains("ExecStart=\"/opt/Cloudreve Sync/app\" --headless\n"));
+        assert!(unit.contains("Restart=on-failure\n"));
+        assert!(unit.contains("StandardOutput=append:/home/u/.config/sync/logs/100%%.log\n"));
+        assert!(unit.contains("StandardError=append:/home/u/.config/sync/logs/100%%.log\n"));
+        assert!(unit.contains("WantedBy=graphical-session.target\n"));
+    }
+
+    #[test]
+    fn windows_task_escapes_command() {
+        let xml = windows_task_xml(&ServiceSpec {
+            exe_path: PathBuf::from(r"C:\Apps\R&D\app.exe"),
+            log_path: PathBuf::from(r"C:\logs\R&D\service.log"),
+        });
+        assert!(xml.contains(r"<Command>C:\Apps\R&amp;D\app.exe</Command>"));
+        assert!(xml.contains(
+            r#"<Arguments>--headless --log-file "C:\logs\R&amp;D\service.log"</Arguments>"#
+        ));
+        assert!(xml.contains("<RestartOnFailure>"));
+    }
+
+    #[test]
+    fn log_file_is_read_from_args() {
+        let args = ["app", "--headless", "--log-file", r"C:\logs\service.log"].map(String::from);
+        assert_eq!(
+            log_file_from_args(args),
+            Some(PathBuf::from(r"C:\logs\service.log"))
+        );
+        assert_eq!(
+            log_file_from_args(["app", "--log-file"].map(String::from)),
+            None
+        );
+        assert_eq!(log_file_from_args(["app"].map(String::from)), None);
+    }
+
+    #[test]
+    fn appimage_launcher_prefers_the_appimage_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let appimage = dir.path().join("Cloudreve_Sync.AppImage");
+        fs::write(&appimage, "").expect("write");
+        let exe = PathBuf::from("/tmp/.mount_Cloudr/usr/bin/app");
+        assert_eq!(
+            appimage_launcher(Some(appimage.clone().into()), exe.clone()),
+            appimage
+        );
+        assert_eq!(appimage_launcher(None, exe.clone()), exe);
+        assert_eq!(
+            appimage_launcher(Some(dir.path().join("missing").into()), exe.clone()),
+            exe
+        );
+    }
+
+    #[test]
+    fn autostart_entries_launch_minimized() {
+        let entry = autostart_desktop_entry(Path::new("/opt/My $App/run\"me\""));
+        assert!(entry.contains("Exec=\"/opt/My \\\\$App/run\\\\\"me\\\\\"\" --minimized\n"));
+        let plist = launch_agent_plist(Path::new("/Applications/R&D.app/Contents/MacOS/app"));
+        assert!(plist.contains("<string>/Applications/R&amp;D.app/Contents/MacOS/app</string>"));
+        assert!(plist.contains("<string>--minimized</string>"));
+        assert_eq!(
+            windows_run_command(Path::new(r"C:\Apps\app.exe")),
+            r#""C:\Apps\app.exe" --minimized"#
+        );
+    }
+}
@@ -0,0 +1,447 @@
+use lazy_static::lazy_static;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 检测结果的缓存时长；各任务的同步循环共用一次检测，避免频繁启动外部命令。
+const STATE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    static ref CACHED_STATE: Mutex<Option<(Instant, SystemState)>> = Mutex::new(None);
+}
+
+/// 电池状态；没有电池的设备为 None。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryState {
+    pub discharging: bool,
+    pub percent: Option<u8>,
+    /// 系统的省电模式已开启。
+    pub saver: bool,
+}
+
+/// 影响是否同步的系统状态，无法检测的项为 None 或空。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemState {
+    pub metered: Option<bool>,
+    pub battery: Option<BatteryState>,
+    /// 已连接的网络接口名称。
+    pub interfaces: Vec<String>,
+}
+
+/// 按系统状态暂停同步的规则，来自应用设置。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PauseRules {
+    pub metered: bool,
+    pub on_battery: bool,
+    /// 电量低于该百分比时暂停，0 表示不检查。
+    pub low_battery_percent: u8,
+    /// 这些网络接口已连接时暂停，如 VPN 的 `tun0`；以 `*` 结尾表示前缀匹配。
+    pub interfaces: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PauseReason {
+    Metered,
+    OnBattery,
+    LowBattery(u8),
+    BatterySaver,
+    Interface(String),
+}
+
+impl PauseReason {
+    /// 报告给界面的任务状态。
+    pub fn status(&self) -> &'static str {
+        match self {
+            PauseReason::Metered => "PausedMetered",
+            PauseReason::OnBattery | PauseReason::LowBattery(_) | PauseReason::BatterySaver => {
+                "PausedBattery"
+            }
+            PauseReason::Interface(_) => "PausedInterface",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            PauseReason::Metered => "当前网络按流量计费".to_string(),
+            PauseReason::OnBattery => "正在使用电池供电".to_string(),
+            PauseReason::LowBattery(percent) => format!("电池电量低 ({}%)", percent),
+            PauseReason::BatterySaver => "系统已开启省电模式".to_string(),
+            PauseReason::Interface(name) => format!("已连接网络接口 {}", name),
+        }
+    }
+}
+
+fn interface_matches(rule: &str, name: &str) -> bool {
+    let rule = rule.trim();
+    match rule.strip_suffix('*') {
+        Some(prefix) => name
+            .to_ascii_lowercase()
+            .starts_with(&prefix.to_ascii_lowercase()),
+        None => !rule.is_empty() && name.eq_ignore_ascii_case(rule),
+    }
+}
+
+impl PauseRules {
+    pub fn is_empty(&self) -> bool {
+        !self.metered
+            && !self.on_battery
+            && self.low_battery_percent == 0
+            && self.interfaces.iter().all(|rule| rule.trim().is_empty())
+    }
+
+    /// 返回第一条命中的暂停原因；检测不到的状态不会导致暂停。
+    pub fn pause_reason(&self, state: &SystemState) -> Option<PauseReason> {
+        if self.metered && state.metered == Some(true) {
+            return Some(PauseReason::Metered);
+        }
+        // 省电模式在接通电源时也可能开启，与是否放电无关。
+        if self.on_battery && state.battery.is_some_and(|battery| battery.saver) {
+            return Some(PauseReason::BatterySaver);
+        }
+        if let Some(battery) = state.battery.filter(|battery| battery.discharging) {
+            if self.on_battery {
+                return Some(PauseReason::OnBattery);
+            }
+            if let Some(percent) = battery
+                .percent
+                .filter(|percent| *percent < self.low_battery_percent)
+            {
+                return Some(PauseReason::LowBattery(percent));
+            }
+        }
+        state
+            .interfaces
+            .iter()
+            .find(|name| {
+                self.interfaces
+                    .iter()
+                    .any(|rule| interface_matches(rule, name))
+            })
+            .map(|name| PauseReason::Interface(name.clone()))
+    }
+}
+
+/// 读取系统状态，30 秒内重复调用直接返回缓存。
+pub fn current_system_state() -> SystemState {
+    if let Ok(cached) = CACHED_STATE.lock() {
+        if let Some((at, state)) = cached.as_ref() {
+            if at.elapsed() < STATE_CACHE_TTL {
+                return state.clone();
+            }
+        }
+    }
+    let state = SystemState {
+        metered: detect_metered(),
+        battery: detect_battery(),
+        interfaces: detect_interfaces(),
+    };
+    if let Ok(mut cached) = CACHED_STATE.lock() {
+        *cached = Some((Instant::now(), state.clone()));
+    }
+    state
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn detect_metered() -> Option<bool> {
+    let script = "[void][Windows.Networking.Connectivity.NetworkInformation,Windows.Networking.Connectivity,ContentType=WindowsRuntime]; \
+        $profile = [Windows.Networking.Connectivity.NetworkInformation]::GetInternetConnectionProfile(); \
+        if ($profile) { $profile.GetConnectionCost().NetworkCostType }";
+    let text = command_output(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", script],
+    )?;
+    parse_network_cost(&text)
+}
+
+/// NetworkManager 各设备的 GENERAL.METERED，任一设备为 `yes`（含 `yes (guessed)`）即视为按流量计费。
+#[cfg(target_os = "linux")]
+fn detect_metered() -> Option<bool> {
+    let text = command_output("nmcli", &["-t", "-f", "GENERAL.METERED", "device", "show"])?;
+    parse_nmcli_metered(&text)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn detect_metered() -> Option<bool> {
+    None
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_network_cost(text: &str) -> Option<bool> {
+    match text.trim() {
+        "Unrestricted" => Some(false),
+        "Fixed" | "Variable" => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_nmcli_metered(text: &str) -> Option<bool> {
+    let values = text
+        .lines()
+        .filter_map(|line| line.rsplit(':').next())
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>();
+    if values.iter().any(|value| value.starts_with("yes")) {
+        Some(true)
+    } else if values.iter().any(|value| value.starts_with("no")) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn detect_battery() -> Option<BatteryState> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS {
+        ACLineStatus: 0,
+        BatteryFlag: 0,
+        BatteryLifePercent: 0,
+        SystemStatusFlag: 0,
+        BatteryLifeTime: 0,
+        BatteryFullLifeTime: 0,
+    };
+    // SAFETY: status 是有效的可写结构体。
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    // 128 表示没有电池，255 表示未知。
+    if status.BatteryFlag == 128 || status.BatteryFlag == 255 {
+        return None;
+    }
+    Some(BatteryState {
+        discharging: status.ACLineStatus == 0,
+        percent: Some(status.BatteryLifePercent).filter(|percent| *percent <= 100),
+        saver: status.SystemStatusFlag == 1,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn detect_battery() -> Option<BatteryState> {
+    let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    let read = |path: &std::path::Path, name: &str| {
+        std::fs::read_to_string(path.join(name))
+            .map(|text| text.trim().to_string())
+            .ok()
+    };
+    let saver = command_output("powerprofilesctl", &["get"])
+        .is_some_and(|profile| profile.trim() == "power-saver");
+    entries.filter_map(Result::ok).find_map(|entry| {
+        let path = entry.path();
+        if read(&path, "type").as_deref() != Some("Battery") {
+            return None;
+        }
+        Some(BatteryState {
+            discharging: read(&path, "status").as_deref() == Some("Discharging"),
+            percent: read(&path, "capacity").and_then(|text| text.parse().ok()),
+            saver,
+        })
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn detect_battery() -> Option<BatteryState> {
+    let battery = parse_pmset_battery(&command_output("pmset", &["-g", "batt"])?)?;
+    Some(BatteryState {
+        saver: command_output("pmset", &["-g"]).is_some_and(|text| parse_low_power_mode(&text)),
+        ..battery
+    })
+}
+
+/// `pmset -g` 中的低电量模式，如 ` lowpowermode         1`。
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_low_power_mode(text: &str) -> bool {
+    text.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("lowpowermode") && words.next() == Some("1")
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn detect_battery() -> Option<BatteryState> {
+    None
+}
+
+/// `pmset -g batt` 的输出，如 `Now drawing from 'Battery Power'` 与 `85%; discharging;`。
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_pmset_battery(text: &str) -> Option<BatteryState> {
+    let line = text.lines().find(|line| line.contains('%'))?;
+    let percent = line
+        .split_whitespace()
+        .find_map(|word| word.split('%').next()?.parse::<u8>().ok());
+    Some(BatteryState {
+        discharging: text.contains("'Battery Power'"),
+        percent,
+        saver: false,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn detect_interfaces() -> Vec<String> {
+    // VPN 的 tun 设备的 operstate 通常为 unknown，因此只排除明确为 down 的接口。
+    std::fs::read_dir("/sys/class/net")
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| {
+                    std::fs::read_to_string(entry.path().join("operstate"))
+                        .map(|state| state.trim() != "down")
+                        .unwrap_or(false)
+                })
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn detect_interfaces() -> Vec<String> {
+    command_output("netsh", &["interface", "show", "interface"])
+        .map(|text| parse_netsh_interfaces(&text))
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+fn detect_interfaces() -> Vec<String> {
+    command_output("ifconfig", &["-lu"])
+        .map(|text| text.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn detect_interfaces() -> Vec<String> {
+    Vec::new()
+}
+
+/// `netsh interface show interface` 的表格：管理状态、连接状态、类型、接口名称（可含空格）。
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_netsh_interfaces(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let _admin = parts.next()?;
+            let state = parts.next()?;
+            let _kind = parts.next()?;
+            let name = parts.collect::<Vec<_>>().join(" ");
+            (state == "Connected" && !name.is_empty()).then_some(name)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn battery(discharging: bool, percent: u8) -> Option<BatteryState> {
+        Some(BatteryState {
+            discharging,
+            percent: Some(percent),
+            saver: false,
+        })
+    }
+
+    #[test]
+    fn pause_reason_follows_enabled_rules() {
+        let state = SystemState {
+            metered: Some(true),
+            battery: battery(true, 15),
+            interfaces: vec!["eth0".to_string(), "tun0".to_string()],
+        };
+        assert!(PauseRules::default().is_empty());
+        assert_eq!(PauseRules::default().pause_reason(&state), None);
+
+        let metered = PauseRules {
+            metered: true,
+            ..PauseRules::default()
+        };
+        assert_eq!(metered.pause_reason(&state), Some(PauseReason::Metered));
+        assert_eq!(PauseReason::Metered.status(), "PausedMetered");
+
+        let low = PauseRules {
+            low_battery_percent: 20,
+            ..PauseRules::default()
+        };
+        assert_eq!(low.pause_reason(&state), Some(PauseReason::LowBattery(15)));
+        let charging = SystemState {
+            battery: battery(false, 15),
+            ..state.clone()
+        };
+        assert_eq!(low.pause_reason(&charging), None);
+
+        let on_battery = PauseRules {
+            on_battery: true,
+            ..PauseRules::default()
+        };
+        assert_eq!(on_battery.pause_reason(&charging), None);
+        let saver = SystemState {
+            battery: Some(BatteryState {
+                saver: true,
+                ..charging.battery.expect("battery")
+            }),
+            ..charging.clone()
+        };
+        assert_eq!(
+            on_battery.pause_reason(&saver),
+            Some(PauseReason::BatterySaver)
+        );
+        assert_eq!(low.pause_reason(&saver), None);
+
+        let vpn = PauseRules {
+            interfaces: vec!["TUN*".to_string()],
+            ..PauseRules::default()
+        };
+        assert_eq!(
+            vpn.pause_reason(&state),
+            Some(PauseReason::Interface("tun0".to_string()))
+        );
+        let exact = PauseRules {
+            interfaces: vec!["tun".to_string(), " ".to_string()],
+            ..PauseRules::default()
+        };
+        assert_eq!(exact.pause_reason(&state), None);
+    }
+
+    #[test]
+    fn parses_platform_outputs() {
+        assert_eq!(parse_network_cost("Variable\r\n"), Some(true));
+        assert_eq!(parse_network_cost("Unrestricted"), Some(false));
+        assert_eq!(parse_network_cost(""), None);
+        assert_eq!(
+            parse_nmcli_metered("GENERAL.METERED:yes (guessed)\n"),
+            Some(true)
+        );
+        assert_eq!(
+            parse_nmcli_metered("GENERAL.METERED:no (guessed)\nGENERAL.METERED:unknown\n"),
+            Some(false)
+        );
+        assert_eq!(
+            parse_pmset_battery(
+                "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1)\t42%; discharging; 3:10 remaining present: true\n"
+            ),
+            Some(BatteryState {
+                discharging: true,
+                percent: Some(42),
+                saver: false,
+            })
+        );
+        assert!(parse_low_power_mode(
+            " standby              1\n lowpowermode         1\n"
+        ));
+        assert!(!parse_low_power_mode(" lowpowermode         0\n"));
+        let netsh = "\nAdmin State    State          Type             Interface Name\n-------------------------------------------------------------------------\nEnabled        Connected      Dedicated        Ethernet 2\nEnabled        Disconnected   Dedicated        Wi-Fi\n";
+        assert_eq!(
+            parse_netsh_interfaces(netsh),
+            vec!["Ethernet 2".to_string()]
+        );
+    }
+}
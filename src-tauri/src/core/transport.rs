@@ -0,0 +1,310 @@
+use crate::core::network::{ChunkSettings, ProxySettings, TimeoutSettings};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::error::Error;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower_layer::Layer;
+use tower_service::Service;
+
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+const HTTP2_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+const HTTP2_KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct TransportCounters {
+    connections: AtomicU64,
+    requests: AtomicU64,
+    http2_requests: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TransportStats {
+    pub connections_opened: u64,
+    pub requests: u64,
+    pub http2_requests: u64,
+    pub reused_requests: u64,
+}
+
+impl TransportCounters {
+    pub fn record_connection(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_response(&self, version: reqwest::Version) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if version == reqwest::Version::HTTP_2 {
+            self.http2_requests.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 未新建连接的请求即视为复用了连接池中的连接（HTTP/2 下多个请求共用一条连接）。
+    pub fn snapshot(&self) -> TransportStats {
+        let connections_opened = self.connections.load(Ordering::Relaxed);
+        let requests = self.requests.load(Ordering::Relaxed);
+        TransportStats {
+            connections_opened,
+            requests,
+            http2_requests: self.http2_requests.load(Ordering::Relaxed),
+            reused_requests: requests.saturating_sub(connections_opened),
+        }
+    }
+}
+
+/// 按同一超时与代理设置构建的一组客户端。
+struct Clients {
+    timeouts: TimeoutSettings,
+    proxy: ProxySettings,
+    api: reqwest::Client,
+    upload: reqwest::Client,
+}
+
+impl Clients {
+    fn new(timeouts: TimeoutSettings, proxy: ProxySettings) -> Self {
+        Self {
+            api: build_client(timeouts.connect, Some(timeouts.read), &proxy),
+            upload: build_client(timeouts.connect, None, &proxy),
+            timeouts,
+            proxy,
+        }
+    }
+}
+
+lazy_static! {
+    static ref COUNTERS: TransportCounters = TransportCounters::default();
+    static ref CLIENTS: RwLock<Clients> = RwLock::new(Clients::new(
+        TimeoutSettings::default(),
+        ProxySettings::default()
+    ));
+}
+
+pub fn counters() -> &'static TransportCounters {
+    &COUNTERS
+}
+
+/// 进程内共享的 HTTP 客户端，所有 CloudreveClient 共用同一连接池。
+pub fn shared_client() -> reqwest::Client {
+    match CLIENTS.read() {
+        Ok(clients) => clients.api.clone(),
+        Err(poisoned) => poisoned.into_inner().api.clone(),
+    }
+}
+
+/// 上传请求专用的客户端。reqwest 的读取超时从发出请求起计时，大文件上传期间收不到响应，
+/// 因此上传不设读取超时，由按大小估算的请求超时（见 `upload_timeout`）与单文件截止时间限制。
+pub fn upload_client() -> reqwest::Client {
+    match CLIENTS.read() {
+        Ok(clients) => clients.upload.clone(),
+        Err(poisoned) => poisoned.into_inner().upload.clone(),
+    }
+}
+
+/// 整体上传 `len` 字节的请求超时：读取超时加上按最低速率传完的时间。
+/// 上传客户端没有读取超时，服务端停止响应时由该超时结束请求。
+pub fn upload_timeout(len: u64) -> Duration {
+    let read = match CLIENTS.read() {
+        Ok(clients) => clients.timeouts.read,
+        Err(poisoned) => poisoned.into_inner().timeouts.read,
+    };
+    ChunkSettings {
+        max_chunk_bytes: None,
+        timeout_base: read,
+    }
+    .timeout_for(len)
+}
+
+/// 应用新的超时与代理设置；与当前设置不同时重建客户端，之后创建的 CloudreveClient 使用新设置。
+pub fn configure_transport(timeouts: TimeoutSettings, proxy: ProxySettings) {
+    let mut clients = match CLIENTS.write() {
+        Ok(clients) => clients,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if clients.timeouts.connect != timeouts.connect
+        || clients.timeouts.read != timeouts.read
+        || clients.proxy != proxy
+    {
+        *clients = Clients::new(timeouts, proxy);
+    }
+}
+
+fn manual_proxy(url: &str, username: &str, password: &str) -> Result<reqwest::Proxy, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|err| format!("代理地址无效: {}", err))?;
+    if !matches!(
+        parsed.scheme(),
+        "http" | "https" | "socks5" | "socks5h" | "socks4"
+    ) {
+        return Err(format!("不支持的代理协议: {}", parsed.scheme()));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err("代理地址缺少主机名".to_string());
+    }
+    let proxy = reqwest::Proxy::all(parsed).map_err(|err| format!("代理地址无效: {}", err))?;
+    Ok(if username.is_empty() {
+        proxy
+    } else {
+        proxy.basic_auth(username, password)
+    })
+}
+
+/// 保存设置前检查手动代理的地址。
+pub fn validate_proxy(proxy: &ProxySettings) -> Result<(), String> {
+    match proxy {
+        ProxySettings::Manual {
+            url,
+            username,
+            password,
+        } => manual_proxy(url, username, password).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+fn apply_proxy(
+    builder: reqwest::ClientBuilder,
+    proxy: &ProxySettings,
+) -> Result<reqwest::ClientBuilder, String> {
+    Ok(match proxy {
+        ProxySettings::System => builder,
+        ProxySettings::Direct => builder.no_proxy(),
+        ProxySettings::Manual {
+            url,
+            username,
+            password,
+        } => builder.proxy(manual_proxy(url, username, password)?),
+    })
+}
+
+/// 用给定的代理设置单独建立客户端请求 `url`，返回收到响应的耗时（毫秒），不影响共享客户端。
+pub async fn probe_proxy(
+    proxy: &ProxySettings,
+    timeouts: TimeoutSettings,
+    url: &str,
+) -> Result<u64, Box<dyn Error>> {
+    let builder = reqwest::Client::builder()
+        .connect_timeout(timeouts.connect)
+        .timeout(timeouts.read);
+    let client = apply_proxy(builder, proxy)?.build()?;
+    let started = Instant::now();
+    let response = client.get(url).send().await?;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    if response.status() == reqwest::StatusCode::PROXY_AUTHENTICATION_REQUIRED {
+        return Err("代理需要认证或用户名密码错误".into());
+    }
+    Ok(latency_ms)
+}
+
+/// 每次建立新连接时计数，供诊断页计算连接复用率。
+#[derive(Clone)]
+struct CountConnections;
+
+#[derive(Clone)]
+struct CountedConnector<S> {
+    inner: S,
+}
+
+impl<S> Layer<S> for CountConnections {
+    type Service = CountedConnector<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CountedConnector { inner }
+    }
+}
+
+impl<S: Service<R>, R> Service<R> for CountedConnector<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        counters().record_connection();
+        self.inner.call(request)
+    }
+}
+
+/// TLS 下通过 ALPN 协商 HTTP/2，服务端只支持 HTTP/1.1 或未启用 TLS 时自动回落到 HTTP/1.1 长连接。
+/// 代理地址无效时不回落到直连，以免绕过代理访问服务器；保存设置时已校验过地址。
+fn build_client(
+    connect_timeout: Duration,
+    read_timeout: Option<Duration>,
+    proxy: &ProxySettings,
+) -> reqwest::Client {
+    let builder = match apply_proxy(tuned_builder(connect_timeout, read_timeout), proxy) {
+        Ok(builder) => builder,
+        Err(err) => {
+            eprintln!("invalid proxy settings: {}", err);
+            // 指向不可用的地址，使请求明确失败；其余设置与正常客户端相同。
+            tuned_builder(connect_timeout, read_timeout).proxy(
+                reqwest::Proxy::all("http://invalid-proxy.invalid").expect("static proxy url"),
+            )
+        }
+    };
+    builder.build().unwrap_or_else(|err| {
+        eprintln!("failed to build tuned http client: {}", err);
+        reqwest::Client::new()
+    })
+}
+
+fn tuned_builder(
+    connect_timeout: Duration,
+    read_timeout: Option<Duration>,
+) -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(connect_timeout)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+        .tcp_keepalive(TCP_KEEPALIVE)
+        .http2_adaptive_window(true)
+        .http2_keep_alive_interval(HTTP2_KEEPALIVE_INTERVAL)
+        .http2_keep_alive_timeout(HTTP2_KEEPALIVE_TIMEOUT)
+        .http2_keep_alive_while_idle(true)
+        .connector_layer(CountConnections);
+    if let Some(read_timeout) = read_timeout {
+        builder = builder.read_timeout(read_timeout);
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_counts_reused_requests() {
+        let counters = TransportCounters::default();
+        counters.record_connection();
+        counters.record_response(reqwest::Version::HTTP_11);
+        counters.record_response(reqwest::Version::HTTP_11);
+        counters.record_response(reqwest::Version::HTTP_2);
+        assert_eq!(
+            counters.snapshot(),
+            TransportStats {
+                connections_opened: 1,
+                requests: 3,
+                http2_requests: 1,
+                reused_requests: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn validates_manual_proxy_urls() {
+        let manual = |url: &str| ProxySettings::Manual {
+            url: url.to_string(),
+            username: "user".to_string(),
+            password: "secret".to_string(),
+        };
+        assert!(validate_proxy(&manual("http://127.0.0.1:8080")).is_ok());
+        assert!(validate_proxy(&manual("socks5h://proxy.example.com:1080")).is_ok());
+        assert!(validate_proxy(&manual("ftp://proxy.example.com")).is_err());
+        assert!(validate_proxy(&manual("proxy.example.com:8080")).is_err());
+        assert!(validate_proxy(&manual("")).is_err());
+        assert!(validate_proxy(&ProxySettings::System).is_ok());
+    }
+}
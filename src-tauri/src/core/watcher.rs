@@ -0,0 +1,252 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const WATCH_QUIET: Duration = Duration::from_secs(2);
+pub const WATCH_MAX_WAIT: Duration = Duration::from_secs(10);
+const IDLE_WAIT: Duration = Duration::from_secs(3600);
+/// 同步引擎写完一个路径后，这段时间内收到的该路径事件仍视为引擎自己的写入。
+const OWN_WRITE_GRACE: Duration = Duration::from_secs(5);
+
+/// 同步引擎正在或刚刚写入的本地路径。监听线程忽略这些路径上的事件，
+/// 否则下载、删除等本地写入会触发下一轮同步，任务永远停不下来。
+#[derive(Clone, Default)]
+pub struct LocalWrites {
+    paths: Arc<Mutex<HashMap<PathBuf, OwnWrite>>>,
+}
+
+#[derive(Clone, Copy)]
+struct OwnWrite {
+    active: usize,
+    finished_at: Instant,
+}
+
+/// 持有期间 `path` 处于写入中，丢弃后再过一小段时间才恢复监听。
+pub struct OwnWriteGuard {
+    writes: LocalWrites,
+    path: PathBuf,
+}
+
+impl LocalWrites {
+    pub fn begin(&self, path: &Path) -> OwnWriteGuard {
+        let now = Instant::now();
+        if let Ok(mut paths) = self.paths.lock() {
+            paths.retain(|_, write| write.active > 0 || now - write.finished_at < OWN_WRITE_GRACE);
+            paths
+                .entry(path.to_path_buf())
+                .or_insert(OwnWrite {
+                    active: 0,
+                    finished_at: now,
+                })
+                .active += 1;
+        }
+        OwnWriteGuard {
+            writes: self.clone(),
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// `path` 本身、其下的路径或其所在的目录正在或刚刚被引擎写入。
+    pub fn is_own(&self, path: &Path, now: Instant) -> bool {
+        let Ok(paths) = self.paths.lock() else {
+            return false;
+        };
+        paths.iter().any(|(written, write)| {
+            (write.active > 0 || now - write.finished_at < OWN_WRITE_GRACE)
+                && (written.starts_with(path) || path.starts_with(written))
+        })
+    }
+}
+
+impl Drop for OwnWriteGuard {
+    fn drop(&mut self) {
+        if let Ok(mut paths) = self.writes.paths.lock() {
+            if let Some(write) = paths.get_mut(&self.path) {
+                write.active = write.active.saturating_sub(1);
+                write.finished_at = Instant::now();
+            }
+        }
+    }
+}
+
+/// 合并连续的本地修改：静默 `quiet` 后触发；持续修改时最迟 `max_wait` 触发一次。
+#[derive(Debug)]
+pub struct Debouncer {
+    quiet: Duration,
+    max_wait: Duration,
+    pending: BTreeSet<PathBuf>,
+    first_at: Option<Instant>,
+    last_at: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(quiet: Duration, max_wait: Duration) -> Self {
+        Self {
+            quiet,
+            max_wait,
+            pending: BTreeSet::new(),
+            first_at: None,
+            last_at: None,
+        }
+    }
+
+    pub fn push(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path);
+        self.first_at.get_or_insert(now);
+        self.last_at = Some(now);
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        let first_at = self.first_at?;
+        let last_at = self.last_at?;
+        Some((last_at + self.quiet).min(first_at + self.max_wait))
+    }
+
+    pub fn take_ready(&mut self, now: Instant) -> Option<Vec<PathBuf>> {
+        if self.deadline()? > now {
+            return None;
+        }
+        self.first_at = None;
+        self.last_at = None;
+        Some(std::mem::take(&mut self.pending).into_iter().collect())
+    }
+}
+
+/// 持有期间监听各根目录；丢弃后停止监听，后台线程随之退出。
+pub struct LocalWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+fn is_relevant(kind: &EventKind) -> bool {
+    !matches!(kind, EventKind::Access(_) | EventKind::Other)
+}
+
+/// 递归监听本地根目录，合并后的变更路径交给 `on_change`；回调在监听线程上执行。
+/// `ignore` 在收到事件时判断，返回 true 的路径（如引擎自己的写入）不计入变更。
+pub fn watch_roots<F, I>(
+    roots: &[PathBuf],
+    quiet: Duration,
+    max_wait: Duration,
+    ignore: I,
+    on_change: F,
+) -> Result<LocalWatcher, Box<dyn Error>>
+where
+    F: Fn(Vec<PathBuf>) + Send + 'static,
+    I: Fn(&Path, Instant) -> bool + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for root in roots {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+    }
+    thread::spawn(move || {
+        let mut debouncer = Debouncer::new(quiet, max_wait);
+        loop {
+            let timeout = debouncer
+                .deadline()
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                .unwrap_or(IDLE_WAIT);
+            match receiver.recv_timeout(timeout) {
+                Ok(Ok(event)) if is_relevant(&event.kind) => {
+                    let now = Instant::now();
+                    for path in event.paths {
+                        if !ignore(&path, now) {
+                            debouncer.push(path, now);
+                        }
+                    }
+                }
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if let Some(paths) = debouncer.take_ready(Instant::now()) {
+                on_change(paths);
+            }
+        }
+    });
+    Ok(LocalWatcher { _watcher: watcher })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::mpsc::channel;
+    use tempfile::tempdir;
+
+    #[test]
+    fn debouncer_waits_for_quiet_period() {
+        let start = Instant::now();
+        let quiet = Duration::from_secs(2);
+        let mut debouncer = Debouncer::new(quiet, Duration::from_secs(10));
+        assert!(debouncer.take_ready(start).is_none());
+        debouncer.push(PathBuf::from("a"), start);
+        debouncer.push(PathBuf::from("a"), start + Duration::from_secs(1));
+        assert!(debouncer
+            .take_ready(start + Duration::from_secs(2))
+            .is_none());
+        assert_eq!(
+            debouncer.take_ready(start + Duration::from_secs(3)),
+            Some(vec![PathBuf::from("a")])
+        );
+        assert!(debouncer.deadline().is_none());
+    }
+
+    #[test]
+    fn debouncer_flushes_continuous_edits_after_max_wait() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_secs(2), Duration::from_secs(5));
+        for secs in 0..6 {
+            debouncer.push(
+                PathBuf::from(format!("f{}", secs)),
+                start + Duration::from_secs(secs),
+            );
+        }
+        let ready = debouncer
+            .take_ready(start + Duration::from_secs(5))
+            .expect("flushed");
+        assert_eq!(ready.len(), 6);
+    }
+
+    #[test]
+    fn own_writes_are_ignored_until_the_grace_period_ends() {
+        let writes = LocalWrites::default();
+        let target = PathBuf::from("/root/docs/a.txt");
+        let guard = writes.begin(&target);
+        let start = Instant::now();
+        assert!(writes.is_own(&target, start + Duration::from_secs(60)));
+        assert!(writes.is_own(Path::new("/root/docs"), start));
+        assert!(!writes.is_own(Path::new("/root/docs/b.txt"), start));
+        drop(guard);
+        let now = Instant::now();
+        assert!(writes.is_own(&target, now));
+        assert!(!writes.is_own(&target, now + OWN_WRITE_GRACE));
+    }
+
+    #[test]
+    fn watcher_reports_new_file() {
+        let dir = tempdir().expect("tempdir");
+        let (sender, receiver) = channel();
+        let _watcher = watch_roots(
+            &[dir.path().to_path_buf()],
+            Duration::from_millis(100),
+            Duration::from_secs(1),
+            |path, _| path.ends_with("ignored.txt"),
+            move |paths| {
+                let _ = sender.send(paths);
+            },
+        )
+        .expect("watch");
+        fs::write(dir.path().join("ignored.txt"), "data").expect("write");
+        fs::write(dir.path().join("new.txt"), "data").expect("write");
+        let paths = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("change reported");
+        assert!(paths.iter().any(|path| path.ends_with("new.txt")));
+        assert!(!paths.iter().any(|path| path.ends_with("ignored.txt")));
+    }
+}
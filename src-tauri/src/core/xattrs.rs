@@ -0,0 +1,342 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// 扩展属性（macOS/Linux 的 xattr、Windows 的备用数据流）的保存位置。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum XattrStorage {
+    /// 不读取也不恢复扩展属性。
+    #[default]
+    Off,
+    /// 编码后写入远端文件的元数据，适合标签、隔离标记等少量数据。
+    Metadata,
+    /// 写入远端根目录下隐藏目录中的附属文件，不受元数据长度限制。
+    Sidecar,
+}
+
+/// 当前平台能否读写名为 `name` 的扩展属性。Linux 只同步 `user.` 命名空间，
+/// 安全标签与 ACL 不随文件迁移；Windows 的数据流名称不能包含冒号。
+pub fn is_portable(name: &str) -> bool {
+    if cfg!(target_os = "linux") {
+        name.starts_with("user.")
+    } else if cfg!(windows) {
+        !name.is_empty() && !name.contains(':')
+    } else {
+        cfg!(target_os = "macos")
+    }
+}
+
+/// 读取文件的扩展属性；文件系统不支持扩展属性时返回空表。
+pub fn read_xattrs(path: &Path) -> io::Result<BTreeMap<String, Vec<u8>>> {
+    let mut attrs = BTreeMap::new();
+    let names = match sys::list(path) {
+        Ok(names) => names,
+        Err(err) if sys::is_unsupported(&err) => return Ok(attrs),
+        Err(err) => return Err(err),
+    };
+    for name in names.into_iter().filter(|name| is_portable(name)) {
+        match sys::get(path, &name) {
+            Ok(value) => {
+                attrs.insert(name, value);
+            }
+            // 列出后被删除的属性跳过。
+            Err(err) if sys::is_missing(&err) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(attrs)
+}
+
+/// 写回扩展属性；当前平台无法表示的名称跳过，文件上已有的其他属性保持不变。
+pub fn write_xattrs(path: &Path, attrs: &BTreeMap<String, Vec<u8>>) -> io::Result<()> {
+    for (name, value) in attrs.iter().filter(|(name, _)| is_portable(name)) {
+        sys::set(path, name, value)?;
+    }
+    Ok(())
+}
+
+/// 编码为 JSON 对象：属性名到十六进制内容。
+pub fn encode_xattrs(attrs: &BTreeMap<String, Vec<u8>>) -> String {
+    let hex = attrs
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>();
+            (name.clone(), value)
+        })
+        .collect::<BTreeMap<_, _>>();
+    serde_json::to_string(&hex).unwrap_or_default()
+}
+
+pub fn decode_xattrs(encoded: &str) -> Option<BTreeMap<String, Vec<u8>>> {
+    let hex = serde_json::from_str::<BTreeMap<String, String>>(encoded).ok()?;
+    hex.into_iter()
+        .map(|(name, value)| {
+            if value.len() % 2 != 0 || !value.is_ascii() {
+                return None;
+            }
+            let bytes = (0..value.len())
+                .step_by(2)
+                .map(|index| u8::from_str_radix(&value[index..index + 2], 16).ok())
+                .collect::<Option<Vec<_>>>()?;
+            Some((name, bytes))
+        })
+        .collect()
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod sys {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    fn c_string(bytes: &[u8]) -> io::Result<CString> {
+        CString::new(bytes).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))
+    }
+
+    /// 先查询长度再读取；两次调用之间内容变长时重试。
+    fn read_sized(mut call: impl FnMut(*mut libc::c_void, usize) -> isize) -> io::Result<Vec<u8>> {
+        loop {
+            let size = call(std::ptr::null_mut(), 0);
+            if size < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut buf = vec![0u8; size as usize];
+            let read = call(buf.as_mut_ptr().cast(), buf.len());
+            if read < 0 {
+                let err = io::Error::last_os_error();
+                if err.raw_os_error() == Some(libc::ERANGE) {
+                    continue;
+                }
+                return Err(err);
+            }
+            buf.truncate(read as usize);
+            return Ok(buf);
+        }
+    }
+
+    pub fn list(path: &Path) -> io::Result<Vec<String>> {
+        let path = c_string(path.as_os_str().as_bytes())?;
+        let names = read_sized(|buf, size| unsafe {
+            #[cfg(target_os = "linux")]
+            let read = libc::listxattr(path.as_ptr(), buf.cast(), size);
+            #[cfg(target_os = "macos")]
+            let read = libc::listxattr(path.as_ptr(), buf.cast(), size, 0);
+            read
+        })?;
+        Ok(names
+            .split(|byte| *byte == 0)
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| String::from_utf8(name.to_vec()).ok())
+            .collect())
+    }
+
+    pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        let path = c_string(path.as_os_str().as_bytes())?;
+        let name = c_string(name.as_bytes())?;
+        read_sized(|buf, size| unsafe {
+            #[cfg(target_os = "linux")]
+            let read = libc::getxattr(path.as_ptr(), name.as_ptr(), buf, size);
+            #[cfg(target_os = "macos")]
+            let read = libc::getxattr(path.as_ptr(), name.as_ptr(), buf, size, 0, 0);
+            read
+        })
+    }
+
+    pub fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let path = c_string(path.as_os_str().as_bytes())?;
+        let name = c_string(name.as_bytes())?;
+        let result = unsafe {
+            #[cfg(target_os = "linux")]
+            let result = libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+            );
+            #[cfg(target_os = "macos")]
+            let result = libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+                0,
+            );
+            result
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn is_unsupported(err: &io::Error) -> bool {
+        err.raw_os_error() == Some(libc::ENOTSUP)
+    }
+
+    pub fn is_missing(err: &io::Error) -> bool {
+        #[cfg(target_os = "linux")]
+        let missing = libc::ENODATA;
+        #[cfg(target_os = "macos")]
+        let missing = libc::ENOATTR;
+        err.raw_os_error() == Some(missing)
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::OsString;
+    use std::fs;
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FindClose, FindFirstStreamW, FindNextStreamW, FindStreamInfoStandard,
+        WIN32_FIND_STREAM_DATA,
+    };
+
+    /// 没有更多数据流（ERROR_HANDLE_EOF）。
+    const NO_MORE_STREAMS: i32 = 38;
+
+    fn stream_path(path: &Path, name: &str) -> PathBuf {
+        let mut stream = OsString::from(path.as_os_str());
+        stream.push(":");
+        stream.push(name);
+        PathBuf::from(stream)
+    }
+
+    /// 列出备用数据流，流名形如 `:名称:$DATA`，默认数据流 `::$DATA` 不在其中。
+    pub fn list(path: &Path) -> io::Result<Vec<String>> {
+        let wide = path
+            .as_os_str()
+            .encode_wide()
+            .chain(Some(0))
+            .collect::<Vec<_>>();
+        let mut data: WIN32_FIND_STREAM_DATA = unsafe { std::mem::zeroed() };
+        let handle = unsafe {
+            FindFirstStreamW(
+                wide.as_ptr(),
+                FindStreamInfoStandard,
+                (&mut data as *mut WIN32_FIND_STREAM_DATA).cast(),
+                0,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            let err = io::Error::last_os_error();
+            return match err.raw_os_error() {
+                Some(NO_MORE_STREAMS) => Ok(Vec::new()),
+                _ => Err(err),
+            };
+        }
+        let mut names = Vec::new();
+        loop {
+            let len = data
+                .cStreamName
+                .iter()
+                .position(|unit| *unit == 0)
+                .unwrap_or(data.cStreamName.len());
+            let name = String::from_utf16_lossy(&data.cStreamName[..len]);
+            if let Some(stream) = name
+                .strip_prefix(':')
+                .and_then(|name| name.strip_suffix(":$DATA"))
+                .filter(|stream| !stream.is_empty())
+            {
+                names.push(stream.to_string());
+            }
+            let more = unsafe {
+                FindNextStreamW(handle, (&mut data as *mut WIN32_FIND_STREAM_DATA).cast())
+            };
+            if more == 0 {
+                break;
+            }
+        }
+        unsafe { FindClose(handle) };
+        Ok(names)
+    }
+
+    pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        fs::read(stream_path(path, name))
+    }
+
+    pub fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        fs::write(stream_path(path, name), value)
+    }
+
+    /// FAT 等不支持数据流的文件系统。
+    pub fn is_unsupported(err: &io::Error) -> bool {
+        err.raw_os_error() == Some(50)
+    }
+
+    pub fn is_missing(err: &io::Error) -> bool {
+        err.kind() == io::ErrorKind::NotFound
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod sys {
+    use std::io;
+    use std::path::Path;
+
+    pub fn list(_path: &Path) -> io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    pub fn get(_path: &Path, _name: &str) -> io::Result<Vec<u8>> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    pub fn set(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    pub fn is_unsupported(_err: &io::Error) -> bool {
+        true
+    }
+
+    pub fn is_missing(_err: &io::Error) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn encodes_attributes_and_restores_them_on_another_file() {
+        let attrs = BTreeMap::from([
+            ("user.tags".to_string(), b"red\0blue".to_vec()),
+            ("user.empty".to_string(), Vec::new()),
+        ]);
+        let encoded = encode_xattrs(&attrs);
+        assert_eq!(decode_xattrs(&encoded), Some(attrs.clone()));
+        assert_eq!(decode_xattrs(r#"{"user.bad":"abc"}"#), None);
+        assert_eq!(decode_xattrs("not json"), None);
+        assert!(!is_portable("security.selinux") || !cfg!(target_os = "linux"));
+
+        let dir = tempdir().expect("tempdir");
+        let source = dir.path().join("a.txt");
+        let copy = dir.path().join("b.txt");
+        std::fs::write(&source, b"a").expect("write a");
+        std::fs::write(&copy, b"b").expect("write b");
+        if write_xattrs(&source, &attrs).is_err() {
+            // 临时目录所在的文件系统不支持扩展属性。
+            return;
+        }
+        let read = read_xattrs(&source).expect("read");
+        if cfg!(any(target_os = "linux", target_os = "macos")) {
+            assert_eq!(read, attrs);
+        }
+        write_xattrs(&copy, &read).expect("restore");
+        assert_eq!(read_xattrs(&copy).expect("read copy"), read);
+    }
+}
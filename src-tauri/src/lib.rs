@@ -1 +1,3 @@
 pub mod core;
+#[cfg(feature = "testsupport")]
+pub mod testsupport;
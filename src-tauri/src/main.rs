@@ -1,22 +1,90 @@
 mod core;
 
-use chrono::{Local, TimeZone};
+use chrono::{Local, TimeZone, Timelike};
+use core::api_tokens::{authorize as authorize_api_token, create_api_token, ApiScope};
+use core::backup::{
+    backups_dir, create_backup, latest_backup_age, list_backups, recover_corrupted_db,
+    restore_backup, BackupInfo, RecoveryReport,
+};
 use core::cloudreve::{
-    finish_sign_in_with_2fa, get_captcha, password_sign_in, refresh_token, CloudreveClient,
-    SignInResult,
+    finish_sign_in_with_2fa, get_captcha, password_sign_in, Capacity, CloudreveClient,
+    RemoteEntryPage, ServerReport, SignInResult,
+};
+use core::compression::CompressionSettings;
+use core::config::{config_dir, db_path, ensure_dir, settings_path, ApiPaths, AppSettings};
+use core::consistency::check_entries;
+use core::credentials::{
+    clear_share_password, clear_task_key, inject_tokens, load_tokens, store_share_password,
+    store_task_key, store_tokens, with_saved_share_password,
 };
-use core::config::{config_dir, ensure_dir, ApiPaths, AppSettings};
-use core::credentials::{load_tokens, store_tokens};
 use core::db::{
-    count_logs, create_task, delete_all_accounts, delete_conflict, delete_task, init_db,
-    list_accounts, list_conflicts, list_logs, list_tasks, now_ms, upsert_account, AccountRow,
-    TaskRow,
+    bump_transfer, cancel_transfer, count_logs, create_task, delete_all_accounts, delete_api_token,
+    delete_conflict, delete_task, get_account_defaults, get_account_status, get_entry_version_id,
+    get_next_due, get_scan_schedule, get_task_run, init_db, list_account_statuses, list_accounts,
+    list_api_tokens, list_batch_logs, list_conflicts, list_entries_by_task, list_log_batches,
+    list_logs, list_name_mappings, list_sync_runs, list_tasks, list_transfers,
+    mark_account_needs_login, mark_dirs_changed, now_ms, record_account_auth, record_scan,
+    record_task_attempt, record_task_run, replace_task_remote_root, request_deep_scan,
+    set_next_due, set_selective_excludes, tree_at_run, update_task_settings, upsert_account,
+    upsert_account_defaults, AccountDefaultsRow, AccountRow, ApiTokenRow, EntryVersionRow,
+    LogBatchRow, LogRow, SyncRunRow, TaskRow, TransferRow,
+};
+use core::deeplink::{parse_deep_link, DeepLink};
+use core::defaults::{apply_defaults, inherit_defaults, parse_defaults, sanitize_defaults};
+use core::encryption::EncryptionSettings;
+use core::error::SyncError;
+use core::events::{EventBus, SyncEvent};
+use core::global_stats::{GlobalSnapshot, GlobalStats};
+use core::ignore::validate_patterns;
+use core::legacy::import_legacy;
+use core::logging::{LogLevel, LogStore};
+use core::maintenance::{compact_database, prune, CompactReport};
+use core::metrics::{is_stale, serve as serve_metrics, Authorizer, HealthRegistry};
+use core::migrations::migrate_with_backup;
+use core::mime::FileTypeGroup;
+use core::names::NameMap;
+use core::network::{detect_network, select_profile, NetworkSnapshot};
+use core::notify::{completed_message, conflicts_message, should_notify, NotifyKind};
+use core::placeholders::{
+    self, register_sync_root, set_pinned, unregister_sync_root, PlaceholderIdentity,
+    SyncRootConnection,
+};
+use core::pool::{connect, PooledConn};
+use core::provision::{config_path_from_args, load_provision, reconcile, DEFAULT_CONFIG_NAME};
+use core::push::PushUnsupported;
+use core::remote_info::RemoteInfoCache;
+use core::roots::{
+    forget_root_access, known_read_only_roots, protected_paths, resolve_local_path, task_roots,
+    validate_roots, LocalRoot, RootReadOnly,
+};
+use core::runner::{
+    active_network_profile, build_engine, deep_scan_allowed, in_quiet_hours_now, run_settings,
+    system_pause_reason, token_refresh, try_lock_task, TaskLocked,
+};
+use core::schedule::{RunPlan, ScanSchedule, SyncWindow, DEFAULT_DEEP_SCAN_INTERVAL_SECS};
+use core::servers::{server_key, summarize_servers, ServerStatus, ServerSummary, ServerTask};
+use core::service::{
+    install_service, launcher_path, log_file_from_args, redirect_output, set_autostart,
+    uninstall_service, ServiceSpec, HEADLESS_ARG, MINIMIZED_ARG,
+};
+use core::sync::{
+    changed_dir_relpaths, default_conflict_retention_days, default_max_conflict_copies,
+    is_engine_scratch, normalize_selection, partial_download_dir, priority_relpaths,
+    purge_local_trash, read_encryption_marker, validate_custom_metadata, write_encryption_marker,
+    ConflictKeep, FirstSyncPolicy, PathExplanation, ScanLimits, ScanMode, SelectiveNode,
+    SymlinkPolicy, SyncDirection, SyncEngine, SyncPreview, SyncStats, TransferOrder,
+};
+use core::system_state::PauseReason;
+use core::transport::{
+    configure_transport, counters as transport_counters, probe_proxy, validate_proxy,
+    TransportStats,
 };
-use core::sync::{SyncEngine, SyncStats};
+use core::watcher::{watch_roots, LocalWatcher, LocalWrites, WATCH_MAX_WAIT, WATCH_QUIET};
+use core::xattrs::XattrStorage;
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::error::Error;
 use std::fs;
 use std::io::Write;
@@ -27,30 +95,76 @@ use std::thread;
 use std::time::{Duration, Instant};
 use tauri::Emitter;
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{IsMenuItem, Menu, MenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Manager, WindowEvent,
+    AppHandle, Manager, WindowEvent, Wry,
 };
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_notification::NotificationExt;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 #[cfg(target_os = "linux")]
 use std::os::unix::fs::PermissionsExt;
 
 const TASK_RUNTIME_EVENT: &str = "task-runtime";
+const GLOBAL_STATS_EVENT: &str = "global-stats";
+const TRANSFER_PROGRESS_EVENT: &str = "transfer-progress";
+const SERVER_SELECTED_EVENT: &str = "server-selected";
+const CONFLICT_SELECTED_EVENT: &str = "conflict-selected";
+const ACCOUNT_NEEDS_LOGIN_EVENT: &str = "account-needs-login";
+const SYNC_REQUESTED_EVENT: &str = "sync-requested";
+const TRAY_ID: &str = "main";
+const TRAY_SERVER_PREFIX: &str = "server:";
 
-#[derive(Clone)]
 struct RunnerHandle {
     stop: Arc<AtomicBool>,
+    deep_requested: Arc<AtomicBool>,
+    run_now: Arc<AtomicBool>,
+    /// 用户暂停：当前文件传完后停下，恢复后从待处理队列继续。
+    paused: Arc<AtomicBool>,
+    /// 停止任务时取消正在进行的一轮，传输中的文件立即中止。
+    cancel: CancellationToken,
+    thread: thread::JoinHandle<()>,
 }
 
 struct AppState {
     db_path: PathBuf,
     api_paths: ApiPaths,
     runners: Mutex<HashMap<String, RunnerHandle>>,
+    /// 没有常驻线程、正在单独跑一轮的任务。
+    one_shots: Arc<Mutex<HashSet<String>>>,
     stats: Arc<Mutex<HashMap<String, TaskStats>>>,
+    health: HealthRegistry,
+    global_stats: GlobalStats,
+    recovery: Mutex<Option<RecoveryReport>>,
+    remote_info: RemoteInfoCache,
+}
+
+impl AppState {
+    /// 从共享连接池取一个连接；表结构在启动时已迁移，不再重复初始化。
+    fn db(&self) -> Result<PooledConn, String> {
+        connect(&self.db_path).map_err(|err| err.to_string())
+    }
 }
 
 const TOKEN_REFRESH_INTERVAL_SECS: u64 = 20 * 60;
+const BACKUP_CHECK_INTERVAL_SECS: u64 = 60 * 60;
+/// 恢复备份前等待同步任务停下的上限。
+const RUNNER_STOP_TIMEOUT: Duration = Duration::from_secs(60);
+const QUIET_HOURS_CHECK_SECS: u64 = 60;
+const PUSH_RECONNECT_SECS: u64 = 30;
+/// 推送连接连续失败时重连间隔逐次翻倍的上限。
+const PUSH_RECONNECT_MAX_SECS: u64 = 30 * 60;
+const GLOBAL_STATS_EMIT_SECS: u64 = 2;
+const TRAY_REFRESH_SECS: u64 = 30;
+
+/// 批量操作的结果：实际处理的任务数与跳过的任务数。
+#[derive(Serialize, Default)]
+struct BulkTaskSummary {
+    affected: u32,
+    skipped: u32,
+}
 
 #[derive(Serialize)]
 struct DashboardCard {
@@ -63,7 +177,10 @@ struct DashboardCard {
 struct TaskItem {
     id: String,
     name: String,
+    /// 按服务器分组用的地址，见 `server_key`。
+    base_url: String,
     mode: String,
+    direction: SyncDirection,
     local_path: String,
     remote_path: String,
     status: String,
@@ -72,6 +189,16 @@ struct TaskItem {
     rate_down: String,
     queue: u32,
     last_sync: String,
+    last_attempt: String,
+    next_run: String,
+    sync_interval_secs: u64,
+    sync_cron: String,
+    sync_windows: Vec<SyncWindow>,
+    enabled: bool,
+    paused: bool,
+    /// 距最近一次成功同步已超过告警阈值。
+    stale: bool,
+    encrypted: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -90,6 +217,14 @@ struct TaskRuntimePayload {
     rate_down: String,
     queue: u32,
     last_sync: String,
+    last_attempt: String,
+}
+
+/// 一轮同步结束的时间与结果。
+#[derive(Clone, Copy)]
+struct RunOutcome {
+    at_ms: i64,
+    success: bool,
 }
 
 #[derive(Serialize)]
@@ -98,6 +233,8 @@ struct AccountItem {
     base_url: String,
     email: String,
     created_at_ms: i64,
+    last_auth_ms: Option<i64>,
+    needs_login: bool,
 }
 
 #[derive(Serialize)]
@@ -106,6 +243,42 @@ struct ActivityItem {
     event: String,
     detail: String,
     level: String,
+    /// 仅批次汇总行带批次号，可展开为逐个文件的日志。
+    batch_id: Option<String>,
+}
+
+impl ActivityItem {
+    fn from_log(log: LogRow) -> Self {
+        Self {
+            timestamp: format_time(log.created_at_ms),
+            event: log.event,
+            detail: log.detail,
+            level: log.level,
+            batch_id: None,
+        }
+    }
+
+    fn from_batch(batch: &LogBatchRow) -> Self {
+        let size = format_size(batch.total_bytes as f64);
+        let detail = if batch.event == "download" {
+            format!(
+                "从 {} 下载 {} 个文件（{}）",
+                batch.dir, batch.file_count, size
+            )
+        } else {
+            format!(
+                "上传 {} 个文件（{}）到 {}",
+                batch.file_count, size, batch.dir
+            )
+        };
+        Self {
+            timestamp: format_time(batch.updated_at_ms),
+            event: batch.event.clone(),
+            detail,
+            level: "info".to_string(),
+            batch_id: Some(batch.batch_id.clone()),
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -122,6 +295,18 @@ struct ConflictItem {
     device: String,
     time: String,
     status: String,
+    /// 同一文件未处理的冲突副本数。
+    copies: u32,
+    /// 副本数已达任务上限，新的冲突不再生成副本。
+    at_limit: bool,
+    /// 冲突类别，名称冲突（只差大小写或 Unicode 形式）只能改名保留。
+    reason: String,
+}
+
+#[derive(Serialize)]
+struct NetworkStatus {
+    snapshot: NetworkSnapshot,
+    active_profile: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -133,6 +318,7 @@ struct DiagnosticInfo {
     config_dir: String,
     accounts: usize,
     tasks: usize,
+    transport: TransportStats,
 }
 
 #[derive(Serialize)]
@@ -175,7 +361,127 @@ struct CreateTaskRequest {
     local_root: String,
     remote_root_uri: String,
     mode: String,
+    #[serde(default)]
+    sync_interval_secs: Option<u64>,
+    #[serde(default)]
+    deep_scan_interval_secs: Option<u64>,
+    #[serde(default)]
+    local_roots: Vec<LocalRoot>,
+    #[serde(default)]
+    first_sync_policy: Option<FirstSyncPolicy>,
+    #[serde(default)]
+    catch_up_missed_runs: Option<bool>,
+    #[serde(default)]
+    sync_cron: String,
+    #[serde(default)]
+    sync_windows: Vec<SyncWindow>,
+    #[serde(default)]
+    scan_limits: Option<ScanLimits>,
+    #[serde(default)]
+    remote_delta: Option<bool>,
+    #[serde(default)]
+    custom_metadata: BTreeMap<String, String>,
+    #[serde(default)]
+    annotate_conflicts: Option<bool>,
+    #[serde(default)]
+    conflict_retention_days: Option<u64>,
+    #[serde(default)]
+    max_conflict_copies: Option<u32>,
+    #[serde(default)]
+    local_trash_days: Option<u64>,
+    #[serde(default)]
+    include_patterns: Vec<String>,
+    #[serde(default)]
+    exclude_patterns: Vec<String>,
+    #[serde(default)]
+    transfer_order: Option<TransferOrder>,
+    #[serde(default)]
+    skip_download_types: Vec<FileTypeGroup>,
+    #[serde(default)]
+    priority_patterns: Vec<String>,
+    #[serde(default)]
+    preserve_dir_times: Option<bool>,
+    #[serde(default)]
+    preserve_permissions: Option<bool>,
+    #[serde(default)]
+    xattrs: Option<XattrStorage>,
+    #[serde(default)]
+    on_demand: Option<bool>,
+    #[serde(default)]
+    verify_writes: Option<bool>,
+    #[serde(default)]
+    skip_uploads_over_quota: Option<bool>,
+    #[serde(default)]
+    dedupe_uploads: Option<bool>,
+    #[serde(default)]
+    compression: Option<CompressionSettings>,
+    #[serde(default)]
+    symlinks: Option<SymlinkPolicy>,
+    /// 非空时创建加密任务，密码只用于派生密钥，不会保存。
+    #[serde(default)]
+    encryption_passphrase: String,
+    #[serde(default)]
+    obfuscate_names: bool,
+}
+
+#[derive(Deserialize)]
+struct SelectiveSyncRequest {
+    task_id: String,
+    #[serde(default)]
+    relpath: String,
+}
+
+#[derive(Deserialize)]
+struct ExplainPathRequest {
+    task_id: String,
+    relpath: String,
+}
+
+#[derive(Deserialize)]
+struct SetPinStateRequest {
+    task_id: String,
+    relpath: String,
+    pinned: bool,
+}
+
+#[derive(Deserialize)]
+struct TransferItemRequest {
+    task_id: String,
+    relpath: String,
+}
+
+#[derive(Deserialize)]
+struct SetSelectiveSyncRequest {
+    task_id: String,
+    excluded: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SetTaskAuditModeRequest {
+    task_id: String,
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct SetTaskScheduleRequest {
+    task_id: String,
     sync_interval_secs: u64,
+    #[serde(default)]
+    sync_cron: String,
+    #[serde(default)]
+    sync_windows: Vec<SyncWindow>,
+}
+
+#[derive(Deserialize)]
+struct UnlockTaskEncryptionRequest {
+    task_id: String,
+    passphrase: String,
+}
+
+#[derive(Deserialize)]
+struct SetTaskEnabledRequest {
+    task_id: String,
+    enabled: bool,
 }
 
 #[derive(Deserialize)]
@@ -191,6 +497,31 @@ struct SyncRequest {
     task_id: String,
 }
 
+#[derive(Deserialize)]
+struct AccountDefaultsQuery {
+    account_key: String,
+}
+
+#[derive(Deserialize)]
+struct SaveAccountDefaultsRequest {
+    account_key: String,
+    defaults: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct ApplyAccountDefaultsRequest {
+    account_key: String,
+    #[serde(default)]
+    task_ids: Vec<String>,
+    #[serde(default)]
+    keys: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RestoreBackupRequest {
+    name: String,
+}
+
 #[derive(Deserialize)]
 struct DeleteTaskRequest {
     task_id: String,
@@ -201,6 +532,49 @@ struct ListRemoteEntriesRequest {
     account_key: String,
     base_url: String,
     uri: String,
+    #[serde(default)]
+    page_token: Option<String>,
+    /// 非空时在 `uri` 下按名称搜索目录。
+    #[serde(default)]
+    search: String,
+}
+
+#[derive(Deserialize)]
+struct DeleteRemoteEntriesRequest {
+    account_key: String,
+    base_url: String,
+    uris: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RenameRemoteEntryRequest {
+    account_key: String,
+    base_url: String,
+    uri: String,
+    new_name: String,
+}
+
+#[derive(Deserialize)]
+struct CreateRemoteFolderRequest {
+    account_key: String,
+    base_url: String,
+    parent_uri: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum ManageApiTokensRequest {
+    List,
+    Create { name: String, scope: ApiScope },
+    Revoke { token_id: String },
+}
+
+#[derive(Serialize)]
+struct ManageApiTokensResult {
+    tokens: Vec<ApiTokenRow>,
+    /// 新建令牌的明文，只在创建时返回这一次。
+    created_token: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -215,6 +589,83 @@ struct TaskSettings {
     name: String,
     account_key: String,
     sync_interval_secs: u64,
+    #[serde(default = "default_deep_scan_interval_secs")]
+    deep_scan_interval_secs: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    local_roots: Vec<LocalRoot>,
+    #[serde(default)]
+    first_sync_policy: FirstSyncPolicy,
+    #[serde(default = "default_catch_up_missed_runs")]
+    catch_up_missed_runs: bool,
+    /// 非空时按 cron 表达式定时运行，取代固定间隔。
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    sync_cron: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sync_windows: Vec<SyncWindow>,
+    #[serde(default)]
+    audit_when_read_only: bool,
+    #[serde(default, skip_serializing_if = "ScanLimits::is_unlimited")]
+    scan_limits: ScanLimits,
+    #[serde(default)]
+    remote_delta: bool,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    custom_metadata: BTreeMap<String, String>,
+    #[serde(default)]
+    annotate_conflicts: bool,
+    #[serde(default = "default_conflict_retention_days")]
+    conflict_retention_days: u64,
+    #[serde(default = "default_max_conflict_copies")]
+    max_conflict_copies: u32,
+    #[serde(default)]
+    local_trash_days: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include_patterns: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude_patterns: Vec<String>,
+    #[serde(default)]
+    transfer_order: TransferOrder,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    skip_download_types: Vec<FileTypeGroup>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    priority_patterns: Vec<String>,
+    #[serde(default)]
+    preserve_dir_times: bool,
+    #[serde(default)]
+    preserve_permissions: bool,
+    #[serde(default)]
+    xattrs: XattrStorage,
+    #[serde(default)]
+    on_demand: bool,
+    #[serde(default)]
+    verify_writes: bool,
+    #[serde(default)]
+    skip_uploads_over_quota: bool,
+    #[serde(default = "default_dedupe_uploads")]
+    dedupe_uploads: bool,
+    #[serde(default, skip_serializing_if = "CompressionSettings::is_disabled")]
+    compression: CompressionSettings,
+    #[serde(default)]
+    symlinks: SymlinkPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encryption: Option<EncryptionSettings>,
+    #[serde(default = "default_task_enabled")]
+    enabled: bool,
+}
+
+fn default_deep_scan_interval_secs() -> u64 {
+    DEFAULT_DEEP_SCAN_INTERVAL_SECS
+}
+
+fn default_catch_up_missed_runs() -> bool {
+    true
+}
+
+fn default_dedupe_uploads() -> bool {
+    true
+}
+
+fn default_task_enabled() -> bool {
+    true
 }
 
 #[derive(Serialize, Clone)]
@@ -222,6 +673,28 @@ struct ShareRequestPayload {
     path: String,
 }
 
+#[derive(Serialize, Clone)]
+struct ServerSelectedPayload {
+    base_url: String,
+}
+
+#[derive(Serialize, Clone)]
+struct ConflictSelectedPayload {
+    id: String,
+}
+
+#[derive(Serialize, Clone)]
+struct SyncRequestedPayload {
+    task_id: String,
+    name: String,
+}
+
+#[derive(Serialize, Clone)]
+struct AccountNeedsLoginPayload {
+    account_key: String,
+    reason: String,
+}
+
 #[tauri::command]
 fn login(
     state: tauri::State<AppState>,
@@ -246,8 +719,7 @@ fn login(
             )
             .map_err(|err| err.to_string())?;
 
-            let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-            init_db(&conn).map_err(|err| err.to_string())?;
+            let conn = state.db()?;
             upsert_account(
                 &conn,
                 &AccountRow {
@@ -258,6 +730,7 @@ fn login(
                 },
             )
             .map_err(|err| err.to_string())?;
+            resume_account_tasks(&state, &account_key)?;
 
             Ok(LoginCommandResult::Success { account_key })
         }
@@ -287,8 +760,7 @@ fn finish_sign_in_with_2fa_command(
     )
     .map_err(|err| err.to_string())?;
 
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    init_db(&conn).map_err(|err| err.to_string())?;
+    let conn = state.db()?;
     upsert_account(
         &conn,
         &AccountRow {
@@ -299,6 +771,7 @@ fn finish_sign_in_with_2fa_command(
         },
     )
     .map_err(|err| err.to_string())?;
+    resume_account_tasks(&state, &account_key)?;
 
     Ok(LoginCommandResult::Success { account_key })
 }
@@ -313,10 +786,52 @@ fn test_connection(
     state: tauri::State<AppState>,
     account_key: String,
     base_url: String,
-) -> Result<(), String> {
-    let tokens = load_tokens(&account_key).map_err(|err| err.to_string())?;
-    let client = CloudreveClient::new(base_url, Some(tokens.access_token), state.api_paths.clone());
-    tauri::async_runtime::block_on(client.ping()).map_err(|err| err.to_string())
+) -> Result<ServerReport, String> {
+    let client = remote_client(&state, &account_key, base_url)?;
+    tauri::async_runtime::block_on(client.diagnose()).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn get_account_capacity_command(
+    state: tauri::State<AppState>,
+    account_key: String,
+    base_url: String,
+) -> Result<Capacity, String> {
+    let client = remote_client(&state, &account_key, base_url)?;
+    tauri::async_runtime::block_on(client.get_capacity()).map_err(|err| err.to_string())
+}
+
+/// 远端目录已有加密标记时沿用其中的盐并核对密码，没有时为新任务生成并写入标记。
+/// 远端已加密而本任务未填密码时拒绝，避免明文与密文混在同一目录。
+fn task_encryption(
+    client: &CloudreveClient,
+    remote_root: &str,
+    scratch_dir: &Path,
+    passphrase: &str,
+    obfuscate_names: bool,
+) -> Result<Option<(EncryptionSettings, [u8; 32])>, String> {
+    let marker =
+        tauri::async_runtime::block_on(read_encryption_marker(client, remote_root, scratch_dir))
+            .map_err(|err| format!("无法读取远端目录的加密设置: {}", err))?;
+    match marker {
+        None if passphrase.is_empty() => Ok(None),
+        Some(_) if passphrase.is_empty() => {
+            Err("远端目录已开启端到端加密，请填写加密密码".to_string())
+        }
+        Some(marker) => {
+            let key = marker
+                .unlock(passphrase)
+                .map_err(|_| "加密密码与远端目录已有的加密设置不一致".to_string())?;
+            Ok(Some((marker, key)))
+        }
+        None => {
+            let (settings, key) = EncryptionSettings::create(passphrase, obfuscate_names)
+                .map_err(|err| err.to_string())?;
+            tauri::async_runtime::block_on(write_encryption_marker(client, remote_root, &settings))
+                .map_err(|err| format!("无法在远端目录写入加密标记: {}", err))?;
+            Ok(Some((settings, key)))
+        }
+    }
 }
 
 #[tauri::command]
@@ -324,8 +839,16 @@ fn create_task_command(
     state: tauri::State<AppState>,
     payload: CreateTaskRequest,
 ) -> Result<String, String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    init_db(&conn).map_err(|err| err.to_string())?;
+    validate_roots(&payload.local_roots)?;
+    let direction = SyncDirection::from_mode(&payload.mode)
+        .ok_or_else(|| format!("未知的同步模式: {}", payload.mode))?;
+    validate_custom_metadata(&payload.custom_metadata)?;
+    validate_patterns(&payload.include_patterns)?;
+    validate_patterns(&payload.exclude_patterns)?;
+    validate_patterns(&payload.priority_patterns)?;
+    payload.symlinks.unwrap_or_default().check_supported()?;
+    RunPlan::new(60, &payload.sync_cron, &payload.sync_windows)?;
+    let conn = state.db()?;
 
     let task_id = Uuid::new_v4().to_string();
     let device_id = Uuid::new_v4().to_string();
@@ -335,139 +858,645 @@ fn create_task_command(
     } else {
         CloudreveClient::build_file_uri(&remote_root_raw)
     };
+    let client = remote_client(&state, &payload.account_key, payload.base_url.clone())?;
+    // 没有修改权限的分享目录只能读取，任务改为仅下载。
+    let read_only_share = CloudreveClient::is_share_uri(&remote_root)
+        && direction.writes_remote()
+        && !tauri::async_runtime::block_on(client.share_writable(&remote_root))
+            .map_err(|err| format!("无法访问分享的目录: {}", err))?;
+    let encryption = task_encryption(
+        &client,
+        &remote_root,
+        &partial_download_dir(&state.db_path, &task_id),
+        &payload.encryption_passphrase,
+        payload.obfuscate_names,
+    )?;
+    let mode = if read_only_share {
+        "DownloadOnly".to_string()
+    } else {
+        payload.mode.clone()
+    };
+    let defaults = get_account_defaults(&conn, &payload.account_key)
+        .map_err(|err| err.to_string())?
+        .map(|row| parse_defaults(&row.settings_json))
+        .unwrap_or_default();
+    let default_u64 = |key: &str| defaults.get(key).and_then(|value| value.as_u64());
     let settings = TaskSettings {
         name: payload.name.clone(),
         account_key: payload.account_key.clone(),
-        sync_interval_secs: payload.sync_interval_secs,
+        sync_interval_secs: payload
+            .sync_interval_secs
+            .or_else(|| default_u64("sync_interval_secs"))
+            .unwrap_or(60),
+        deep_scan_interval_secs: payload
+            .deep_scan_interval_secs
+            .or_else(|| default_u64("deep_scan_interval_secs"))
+            .unwrap_or(DEFAULT_DEEP_SCAN_INTERVAL_SECS),
+        local_roots: payload.local_roots.clone(),
+        first_sync_policy: payload
+            .first_sync_policy
+            .or_else(|| {
+                defaults
+                    .get("first_sync_policy")
+                    .and_then(|value| serde_json::from_value(value.clone()).ok())
+            })
+            .unwrap_or_default(),
+        catch_up_missed_runs: payload
+            .catch_up_missed_runs
+            .or_else(|| {
+                defaults
+                    .get("catch_up_missed_runs")
+                    .and_then(|value| value.as_bool())
+            })
+            .unwrap_or_else(default_catch_up_missed_runs),
+        sync_cron: payload.sync_cron.trim().to_string(),
+        sync_windows: payload.sync_windows.clone(),
+        audit_when_read_only: false,
+        scan_limits: payload.scan_limits.clone().unwrap_or_default(),
+        remote_delta: payload.remote_delta.unwrap_or(false),
+        custom_metadata: payload.custom_metadata.clone(),
+        annotate_conflicts: payload.annotate_conflicts.unwrap_or_default(),
+        conflict_retention_days: payload
+            .conflict_retention_days
+            .unwrap_or_else(default_conflict_retention_days),
+        max_conflict_copies: payload
+            .max_conflict_copies
+            .unwrap_or_else(default_max_conflict_copies),
+        local_trash_days: payload.local_trash_days.unwrap_or(0),
+        include_patterns: payload.include_patterns.clone(),
+        exclude_patterns: payload.exclude_patterns.clone(),
+        transfer_order: payload.transfer_order.unwrap_or_default(),
+        skip_download_types: payload.skip_download_types.clone(),
+        priority_patterns: payload.priority_patterns.clone(),
+        preserve_dir_times: payload.preserve_dir_times.unwrap_or(false),
+        preserve_permissions: payload.preserve_permissions.unwrap_or(false),
+        xattrs: payload.xattrs.unwrap_or_default(),
+        on_demand: payload.on_demand.unwrap_or(false),
+        verify_writes: payload.verify_writes.unwrap_or(false),
+        skip_uploads_over_quota: payload.skip_uploads_over_quota.unwrap_or(false),
+        dedupe_uploads: payload
+            .dedupe_uploads
+            .unwrap_or_else(default_dedupe_uploads),
+        compression: payload.compression.clone().unwrap_or_default(),
+        symlinks: payload.symlinks.unwrap_or_default(),
+        encryption: encryption.as_ref().map(|(settings, _)| settings.clone()),
+        enabled: true,
+    };
+    let mut settings_map = match serde_json::to_value(&settings).map_err(|err| err.to_string())? {
+        serde_json::Value::Object(map) => map,
+        _ => return Err("任务设置格式错误".to_string()),
+    };
+    inherit_defaults(&defaults, &mut settings_map);
+    let local_root = match payload.local_roots.first() {
+        Some(root) if payload.local_root.trim().is_empty() => root.local_root.clone(),
+        _ => payload.local_root,
     };
     let task = TaskRow {
         task_id: task_id.clone(),
         base_url: payload.base_url,
-        local_root: payload.local_root,
+        local_root,
         remote_root_uri: remote_root,
         device_id,
-        mode: payload.mode,
-        settings_json: serde_json::to_string(&settings).map_err(|err| err.to_string())?,
+        mode,
+        settings_json: serde_json::Value::Object(settings_map).to_string(),
         created_at_ms: now_ms(),
     };
+    let protected = protected_paths(&task_roots(&task), &state.db_path)?;
+    if let Some(password) = CloudreveClient::share_password(&task.remote_root_uri) {
+        store_share_password(&task_id, password)
+            .map_err(|err| format!("无法保存分享密码: {}", err))?;
+    }
+    let task = TaskRow {
+        remote_root_uri: CloudreveClient::without_share_password(&task.remote_root_uri),
+        ..task
+    };
+    if let Some((settings, key)) = &encryption {
+        store_task_key(&task_id, key).map_err(|err| format!("无法保存加密密钥: {}", err))?;
+        log_info(
+            &state.db_path,
+            &task_id,
+            "settings",
+            &format!("已开启端到端加密，密钥指纹 {}", settings.fingerprint),
+        );
+    }
     create_task(&conn, &task).map_err(|err| err.to_string())?;
+    if read_only_share {
+        log_info(
+            &state.db_path,
+            &task_id,
+            "settings",
+            "分享的目录只读，任务已改为仅下载",
+        );
+    }
+    if !protected.is_empty() {
+        log_info(
+            &state.db_path,
+            &task_id,
+            "settings",
+            &format!(
+                "本地目录包含应用自身的数据，同步时将始终排除: {}",
+                protected
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        );
+    }
     Ok(task_id)
 }
 
 #[tauri::command]
-fn list_tasks_command(state: tauri::State<AppState>) -> Result<Vec<TaskItem>, String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    build_task_items(&state, &conn).map_err(|err| err.to_string())
+fn get_account_defaults_command(
+    state: tauri::State<AppState>,
+    payload: AccountDefaultsQuery,
+) -> Result<serde_json::Value, String> {
+    let conn = state.db()?;
+    let defaults = get_account_defaults(&conn, &payload.account_key)
+        .map_err(|err| err.to_string())?
+        .map(|row| parse_defaults(&row.settings_json))
+        .unwrap_or_default();
+    Ok(serde_json::Value::Object(defaults))
 }
 
 #[tauri::command]
-fn list_accounts_command(state: tauri::State<AppState>) -> Result<Vec<AccountItem>, String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    init_db(&conn).map_err(|err| err.to_string())?;
-    let accounts = list_accounts(&conn).map_err(|err| err.to_string())?;
-    Ok(accounts
+fn save_account_defaults_command(
+    state: tauri::State<AppState>,
+    payload: SaveAccountDefaultsRequest,
+) -> Result<(), String> {
+    let defaults = match payload.defaults {
+        serde_json::Value::Object(map) => sanitize_defaults(map),
+        _ => return Err("默认设置必须是对象".to_string()),
+    };
+    let conn = state.db()?;
+    upsert_account_defaults(
+        &conn,
+        &AccountDefaultsRow {
+            account_key: payload.account_key,
+            settings_json: serde_json::Value::Object(defaults).to_string(),
+            updated_at_ms: now_ms(),
+        },
+    )
+    .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn set_task_audit_mode_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    payload: SetTaskAuditModeRequest,
+) -> Result<(), String> {
+    let task = set_task_flag(
+        &state.db_path,
+        &payload.task_id,
+        "audit_when_read_only",
+        payload.enabled,
+    )?;
+    let detail = if payload.enabled {
+        "本地目录只读时切换为审计模式"
+    } else {
+        "本地目录只读时停止同步"
+    };
+    log_info(&state.db_path, &task.task_id, "settings", detail);
+
+    let was_running = state
+        .runners
+        .lock()
+        .map_err(|_| "runner lock error".to_string())?
+        .remove(&task.task_id)
+        .map(|handle| handle.stop.store(true, Ordering::SeqCst))
+        .is_some();
+    if was_running {
+        start_sync_task(&app, &state, &task.task_id, false)?;
+    }
+    Ok(())
+}
+
+/// 修改任务设置中的单个布尔开关，返回修改前的任务记录。
+fn set_task_flag(
+    db_path: &PathBuf,
+    task_id: &str,
+    key: &str,
+    value: bool,
+) -> Result<TaskRow, String> {
+    let conn = connect(db_path).map_err(|err| err.to_string())?;
+    let task = list_tasks(&conn)
+        .map_err(|err| err.to_string())?
         .into_iter()
-        .map(|item| AccountItem {
-            account_key: item.account_key,
-            base_url: item.base_url,
-            email: item.email,
-            created_at_ms: item.created_at_ms,
-        })
-        .collect())
+        .find(|item| item.task_id == task_id)
+        .ok_or_else(|| "任务不存在".to_string())?;
+    let mut settings_map = match serde_json::from_str(&task.settings_json) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => return Err("任务设置格式错误".to_string()),
+    };
+    settings_map.insert(key.to_string(), serde_json::Value::Bool(value));
+    update_task_settings(
+        &conn,
+        &task.task_id,
+        &serde_json::Value::Object(settings_map).to_string(),
+    )
+    .map_err(|err| err.to_string())?;
+    Ok(task)
 }
 
+/// 停用的任务保留同步记录，但不参与启动、批量操作与定时同步，也不计入健康检查。
 #[tauri::command]
-fn list_remote_entries_command(
+fn set_task_enabled_command(
+    app: AppHandle,
     state: tauri::State<AppState>,
-    payload: ListRemoteEntriesRequest,
-) -> Result<Vec<core::cloudreve::RemoteEntry>, String> {
-    let tokens = load_tokens(&payload.account_key).map_err(|err| err.to_string())?;
-    let client = CloudreveClient::new(
-        payload.base_url,
-        Some(tokens.access_token),
-        state.api_paths.clone(),
-    );
-    let uri = decode_uri(&payload.uri);
-    tauri::async_runtime::block_on(client.list_directory_entries(&uri))
-        .map_err(|err| err.to_string())
+    payload: SetTaskEnabledRequest,
+) -> Result<(), String> {
+    let task = set_task_flag(&state.db_path, &payload.task_id, "enabled", payload.enabled)?;
+    if payload.enabled {
+        log_info(&state.db_path, &task.task_id, "settings", "任务已启用");
+        return Ok(());
+    }
+    stop_sync_task(&app, &state, &task.task_id)?;
+    state.health.remove(&task.task_id);
+    log_info(&state.db_path, &task.task_id, "settings", "任务已停用");
+    Ok(())
 }
 
+/// 修改任务的同步间隔、cron 表达式与时间窗口，并按新规则重新计算下次运行时间。
+/// 运行中的任务会重启以应用新规则。
 #[tauri::command]
-fn create_share_link_command(
+fn set_task_schedule_command(
+    app: AppHandle,
     state: tauri::State<AppState>,
-    payload: CreateShareLinkRequest,
+    payload: SetTaskScheduleRequest,
 ) -> Result<String, String> {
-    let local_path = PathBuf::from(&payload.local_path);
-    let metadata = local_path.metadata().map_err(|err| err.to_string())?;
-    let is_dir = metadata.is_dir();
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    init_db(&conn).map_err(|err| err.to_string())?;
-    let tasks = list_tasks(&conn).map_err(|err| err.to_string())?;
-    let task = find_task_for_local_path(&tasks, &local_path)
-        .ok_or_else(|| "未找到匹配的同步任务".to_string())?;
-    let settings = parse_settings(&task.settings_json);
-    let tokens = load_tokens(&settings.account_key).map_err(|err| err.to_string())?;
-    let relpath = relpath_from_local(&task.local_root, &local_path)?;
-    let uri = if relpath.is_empty() {
-        task.remote_root_uri.clone()
-    } else {
-        build_remote_uri(&task.remote_root_uri, &relpath)
+    let sync_cron = payload.sync_cron.trim().to_string();
+    let plan = RunPlan::new(
+        payload.sync_interval_secs.max(5),
+        &sync_cron,
+        &payload.sync_windows,
+    )?;
+    let conn = state.db()?;
+    let task = list_tasks(&conn)
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .find(|item| item.task_id == payload.task_id)
+        .ok_or_else(|| "任务不存在".to_string())?;
+    let mut settings_map = match serde_json::from_str(&task.settings_json) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => return Err("任务设置格式错误".to_string()),
     };
-    let password = payload
-        .password
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty());
-    let expire_seconds = payload.expire_seconds.filter(|value| *value > 0);
-    let client = CloudreveClient::new(
-        task.base_url.clone(),
-        Some(tokens.access_token),
-        state.api_paths.clone(),
+    settings_map.insert(
+        "sync_interval_secs".to_string(),
+        payload.sync_interval_secs.max(5).into(),
     );
-    let link = tauri::async_runtime::block_on(client.create_share_link(
-        &uri,
-        password,
-        expire_seconds,
-        Some(is_dir),
-    ))
+    settings_map.insert("sync_cron".to_string(), sync_cron.into());
+    settings_map.insert(
+        "sync_windows".to_string(),
+        serde_json::to_value(&payload.sync_windows).map_err(|err| err.to_string())?,
+    );
+    update_task_settings(
+        &conn,
+        &task.task_id,
+        &serde_json::Value::Object(settings_map).to_string(),
+    )
     .map_err(|err| err.to_string())?;
+    let next_due = plan.next_run_ms(now_ms());
+    set_next_due(&conn, &task.task_id, next_due).map_err(|err| err.to_string())?;
     log_info(
         &state.db_path,
         &task.task_id,
-        "share",
-        &format!("{} -> {}", payload.local_path, link),
+        "settings",
+        &format!("定时规则已更新，下次运行: {}", format_time(next_due)),
     );
-    Ok(link)
+    let was_running = state
+        .runners
+        .lock()
+        .map_err(|_| "runner lock error".to_string())?
+        .remove(&task.task_id)
+        .map(|handle| handle.stop.store(true, Ordering::SeqCst))
+        .is_some();
+    if was_running {
+        start_sync_task(&app, &state, &task.task_id, true)?;
+    }
+    Ok(format_time(next_due))
 }
+
+/// 在新设备上或钥匙串丢失后输入加密任务的密码：派生的密钥与任务记录的指纹一致才保存。
 #[tauri::command]
-fn list_conflicts_command(
+fn unlock_task_encryption_command(
     state: tauri::State<AppState>,
-    task_id: Option<String>,
-) -> Result<Vec<ConflictItem>, String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    let conflicts = list_conflicts(&conn, task_id.as_deref()).map_err(|err| err.to_string())?;
-    let tasks = list_tasks(&conn).map_err(|err| err.to_string())?;
-    let task_map = tasks
+    payload: UnlockTaskEncryptionRequest,
+) -> Result<(), String> {
+    let (task, settings) =
+        load_task_settings(&state.db_path, &payload.task_id).map_err(|err| err.to_string())?;
+    let encryption = settings
+        .encryption
+        .ok_or_else(|| "任务未开启加密".to_string())?;
+    let key = encryption
+        .unlock(&payload.passphrase)
+        .map_err(|err| err.to_string())?;
+    store_task_key(&task.task_id, &key).map_err(|err| format!("无法保存加密密钥: {}", err))?;
+    log_info(&state.db_path, &task.task_id, "settings", "已保存加密密钥");
+    Ok(())
+}
+
+fn ensure_task_enabled(state: &AppState, task_id: &str) -> Result<(), String> {
+    let (_, settings) =
+        load_task_settings(&state.db_path, task_id).map_err(|err| err.to_string())?;
+    if settings.enabled {
+        Ok(())
+    } else {
+        Err("任务已停用".to_string())
+    }
+}
+
+#[tauri::command]
+fn apply_account_defaults_command(
+    state: tauri::State<AppState>,
+    payload: ApplyAccountDefaultsRequest,
+) -> Result<u32, String> {
+    let conn = state.db()?;
+    let defaults = get_account_defaults(&conn, &payload.account_key)
+        .map_err(|err| err.to_string())?
+        .map(|row| parse_defaults(&row.settings_json))
+        .ok_or_else(|| "该账户尚未设置默认值".to_string())?;
+    let tasks = list_tasks(&conn).map_err(|err| err.to_string())?;
+    let mut updated = 0;
+    for task in tasks {
+        if !payload.task_ids.is_empty() && !payload.task_ids.contains(&task.task_id) {
+            continue;
+        }
+        if parse_settings(&task.settings_json).account_key != payload.account_key {
+            continue;
+        }
+        let mut settings_map = match serde_json::from_str(&task.settings_json) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => continue,
+        };
+        if !apply_defaults(&defaults, &mut settings_map, &payload.keys) {
+            continue;
+        }
+        let settings_value = serde_json::Value::Object(settings_map);
+        serde_json::from_value::<TaskSettings>(settings_value.clone())
+            .map_err(|err| format!("默认设置无效: {}", err))?
+            .symlinks
+            .check_supported()?;
+        update_task_settings(&conn, &task.task_id, &settings_value.to_string())
+            .map_err(|err| err.to_string())?;
+        log_info(
+            &state.db_path,
+            &task.task_id,
+            "settings",
+            "已应用账户默认设置，重新启动同步后生效",
+        );
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+#[tauri::command]
+fn list_tasks_command(state: tauri::State<AppState>) -> Result<Vec<TaskItem>, String> {
+    let conn = state.db()?;
+    build_task_items(&state, &conn).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn list_accounts_command(state: tauri::State<AppState>) -> Result<Vec<AccountItem>, String> {
+    let conn = state.db()?;
+    let accounts = list_accounts(&conn).map_err(|err| err.to_string())?;
+    let statuses = list_account_statuses(&conn).map_err(|err| err.to_string())?;
+    Ok(accounts
+        .into_iter()
+        .map(|item| {
+            let status = statuses
+                .iter()
+                .find(|status| status.account_key == item.account_key)
+                .cloned()
+                .unwrap_or_default();
+            AccountItem {
+                account_key: item.account_key,
+                base_url: item.base_url,
+                email: item.email,
+                created_at_ms: item.created_at_ms,
+                last_auth_ms: status.last_auth_ms,
+                needs_login: status.needs_login,
+            }
+        })
+        .collect())
+}
+
+#[tauri::command]
+fn list_remote_entries_command(
+    state: tauri::State<AppState>,
+    payload: ListRemoteEntriesRequest,
+) -> Result<Vec<core::cloudreve::RemoteEntry>, String> {
+    let client = remote_client(&state, &payload.account_key, payload.base_url)?;
+    let uri = decode_uri(&payload.uri);
+    tauri::async_runtime::block_on(client.list_directory_entries(&uri))
+        .map_err(|err| err.to_string())
+}
+
+/// 目录选择器按页加载：每次只返回一页，超大目录不必一次列完。
+#[tauri::command]
+fn list_remote_page_command(
+    state: tauri::State<AppState>,
+    payload: ListRemoteEntriesRequest,
+) -> Result<RemoteEntryPage, String> {
+    let client = remote_client(&state, &payload.account_key, payload.base_url)?;
+    let uri = decode_uri(&payload.uri);
+    let page_token = payload.page_token.as_deref();
+    let search = payload.search.trim();
+    let page = if search.is_empty() {
+        tauri::async_runtime::block_on(client.list_directory_page(&uri, page_token))
+    } else {
+        tauri::async_runtime::block_on(client.search_directories(&uri, search, page_token))
+    };
+    page.map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn delete_remote_entries_command(
+    state: tauri::State<AppState>,
+    payload: DeleteRemoteEntriesRequest,
+) -> Result<(), String> {
+    let uris = payload
+        .uris
+        .iter()
+        .map(|uri| decode_uri(uri))
+        .collect::<Vec<_>>();
+    for uri in &uris {
+        ensure_not_task_root(&state.db_path, uri)?;
+    }
+    let client = remote_client(&state, &payload.account_key, payload.base_url)?;
+    tauri::async_runtime::block_on(client.delete_files(uris.clone(), false))
+        .map_err(|err| err.to_string())?;
+    for uri in &uris {
+        state.remote_info.invalidate(&payload.account_key, uri);
+        log_info(&state.db_path, "", "remote", &format!("远端删除: {}", uri));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn rename_remote_entry_command(
+    state: tauri::State<AppState>,
+    payload: RenameRemoteEntryRequest,
+) -> Result<(), String> {
+    let new_name = validate_remote_name(&payload.new_name)?;
+    let uri = decode_uri(&payload.uri);
+    ensure_not_task_root(&state.db_path, &uri)?;
+    let client = remote_client(&state, &payload.account_key, payload.base_url)?;
+    tauri::async_runtime::block_on(client.rename_file(&uri, new_name))
+        .map_err(|err| err.to_string())?;
+    state.remote_info.invalidate(&payload.account_key, &uri);
+    log_info(
+        &state.db_path,
+        "",
+        "remote",
+        &format!("远端重命名: {} -> {}", uri, new_name),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn create_remote_folder_command(
+    state: tauri::State<AppState>,
+    payload: CreateRemoteFolderRequest,
+) -> Result<String, String> {
+    let name = validate_remote_name(&payload.name)?;
+    let parent = decode_uri(&payload.parent_uri);
+    let uri = format!("{}/{}", parent.trim_end_matches('/'), name);
+    let client = remote_client(&state, &payload.account_key, payload.base_url)?;
+    tauri::async_runtime::block_on(client.create_directory(&uri)).map_err(|err| err.to_string())?;
+    state.remote_info.invalidate(&payload.account_key, &uri);
+    log_info(
+        &state.db_path,
+        "",
+        "remote",
+        &format!("远端新建目录: {}", uri),
+    );
+    Ok(uri)
+}
+
+fn remote_client(
+    state: &AppState,
+    account_key: &str,
+    base_url: String,
+) -> Result<CloudreveClient, String> {
+    let tokens = load_tokens(account_key).map_err(|err| err.to_string())?;
+    let mut client =
+        CloudreveClient::new(base_url, Some(tokens.access_token), state.api_paths.clone());
+    client.set_token_refresh(Some(token_refresh(account_key)));
+    Ok(client)
+}
+
+fn validate_remote_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err("名称无效".to_string());
+    }
+    Ok(name)
+}
+
+/// 同步任务的远端根目录及其上级目录不允许在浏览器中删除或重命名。
+fn ensure_not_task_root(db_path: &PathBuf, uri: &str) -> Result<(), String> {
+    let conn = connect(db_path).map_err(|err| err.to_string())?;
+    let tasks = list_tasks(&conn).map_err(|err| err.to_string())?;
+    let target = uri.trim_end_matches('/');
+    for task in tasks {
+        let root = decode_uri(&task.remote_root_uri);
+        let root = root.trim_end_matches('/');
+        if root == target || root.starts_with(&format!("{}/", target)) {
+            let settings = parse_settings(&task.settings_json);
+            return Err(format!("该目录被同步任务使用: {}", settings.name));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn create_share_link_command(
+    state: tauri::State<AppState>,
+    payload: CreateShareLinkRequest,
+) -> Result<String, String> {
+    let local_path = PathBuf::from(&payload.local_path);
+    local_path.metadata().map_err(|err| err.to_string())?;
+    let conn = state.db()?;
+    let tasks = list_tasks(&conn).map_err(|err| err.to_string())?;
+    let (task, root) = find_task_for_local_path(&tasks, &local_path)
+        .ok_or_else(|| "未找到匹配的同步任务".to_string())?;
+    let settings = parse_settings(&task.settings_json);
+    if settings.encryption.is_some() {
+        return Err("加密任务在远端只有密文，无法创建分享链接".to_string());
+    }
+    let relpath = root.join_relpath(&relpath_from_local(&root.local_root, &local_path)?);
+    let remote_root = with_saved_share_password(&task.task_id, &task.remote_root_uri);
+    let uri = if relpath.is_empty() {
+        remote_root
+    } else {
+        build_remote_uri(&remote_root, &relpath)
+    };
+    let password = payload
+        .password
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    let expire_seconds = payload.expire_seconds.filter(|value| *value > 0);
+    let client = remote_client(&state, &settings.account_key, task.base_url.clone())?;
+    let link = tauri::async_runtime::block_on(async {
+        // 本地已有但尚未上传的文件无法分享，先确认远端存在并以远端类型为准。
+        let info = state
+            .remote_info
+            .lookup(&client, &settings.account_key, &uri)
+            .await
+            .map_err(|err| format!("远端尚无该文件，请等待同步完成: {}", err))?;
+        client
+            .create_share_link(&uri, password, expire_seconds, Some(info.is_dir))
+            .await
+            .map_err(|err| err.to_string())
+    })?;
+    log_info(
+        &state.db_path,
+        &task.task_id,
+        "share",
+        &format!("{} -> {}", payload.local_path, link),
+    );
+    Ok(link)
+}
+#[tauri::command]
+fn list_conflicts_command(
+    state: tauri::State<AppState>,
+    task_id: Option<String>,
+) -> Result<Vec<ConflictItem>, String> {
+    let conn = state.db()?;
+    let conflicts = list_conflicts(&conn, task_id.as_deref()).map_err(|err| err.to_string())?;
+    let tasks = list_tasks(&conn).map_err(|err| err.to_string())?;
+    let task_map = tasks
         .into_iter()
         .map(|task| {
             let settings = parse_settings(&task.settings_json);
-            (task.task_id, (settings.name, task.local_root))
+            let roots = task_roots(&task);
+            (
+                task.task_id,
+                (settings.name, roots, settings.max_conflict_copies),
+            )
         })
         .collect::<HashMap<_, _>>();
+    let mut copies: HashMap<(String, String), u32> = HashMap::new();
+    for item in &conflicts {
+        *copies
+            .entry((item.task_id.clone(), item.original_relpath.clone()))
+            .or_default() += 1;
+    }
     Ok(conflicts
         .into_iter()
         .map(|item| {
-            let (task_name, local_root) = task_map
+            let (task_name, roots, limit) = task_map
                 .get(&item.task_id)
                 .cloned()
-                .unwrap_or_else(|| (item.task_id.clone(), String::new()));
-            let local_path = if local_root.is_empty() {
-                item.conflict_relpath.clone()
-            } else {
-                PathBuf::from(&local_root)
-                    .join(&item.conflict_relpath)
-                    .to_string_lossy()
-                    .to_string()
-            };
+                .unwrap_or_else(|| (item.task_id.clone(), Vec::new(), 0));
+            let count = copies
+                .get(&(item.task_id.clone(), item.original_relpath.clone()))
+                .copied()
+                .unwrap_or(0);
+            let local_path = resolve_local_path(&roots, &item.conflict_relpath)
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_else(|| item.conflict_relpath.clone());
             let local_dir = parent_path(&local_path);
             ConflictItem {
                 id: format!("{}:{}", item.task_id, item.conflict_relpath),
@@ -482,6 +1511,9 @@ fn list_conflicts_command(
                 device: "".to_string(),
                 time: format_time(item.created_at_ms),
                 status: "未处理".to_string(),
+                copies: count,
+                at_limit: limit > 0 && count >= limit,
+                reason: item.reason,
             }
         })
         .collect())
@@ -492,18 +1524,66 @@ fn get_settings_command() -> Result<AppSettings, String> {
     AppSettings::load().map_err(|err| err.to_string())
 }
 
+/// 前端不知道钥匙串中是否已有代理密码，以已保存的设置为准。
+fn with_saved_proxy_password(mut payload: AppSettings) -> AppSettings {
+    payload.proxy_password_saved = AppSettings::load()
+        .map(|settings| settings.proxy_password_saved)
+        .unwrap_or(false);
+    payload
+}
+
 #[tauri::command]
 fn save_settings_command(payload: AppSettings) -> Result<(), String> {
-    payload.save().map_err(|err| err.to_string())
+    let mut payload = with_saved_proxy_password(payload);
+    validate_proxy(&payload.proxy_settings())?;
+    payload.save().map_err(|err| err.to_string())?;
+    configure_transport(payload.timeout_settings(), payload.proxy_settings());
+    let exe_path = launcher_path().map_err(|err| err.to_string())?;
+    set_autostart(&exe_path, payload.autostart)
+        .map_err(|err| format!("设置开机自启动失败: {}", err))
+}
+
+/// 用尚未保存的代理设置访问第一个账户所在服务器的 ping 接口，返回耗时（毫秒）。
+#[tauri::command]
+fn test_proxy_command(state: tauri::State<AppState>, payload: AppSettings) -> Result<u64, String> {
+    let payload = with_saved_proxy_password(payload);
+    let proxy = payload.proxy_settings();
+    validate_proxy(&proxy)?;
+    let conn = state.db()?;
+    let account = list_accounts(&conn)
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "请先添加账户，再测试代理".to_string())?;
+    let base_url = server_key(&account.base_url);
+    let url = format!("{}/api/v4/site/ping", base_url.trim_end_matches("/api/v4"));
+    tauri::async_runtime::block_on(probe_proxy(&proxy, payload.timeout_settings(), &url))
+        .map_err(|err| format!("无法通过代理访问 {}: {}", account.base_url, err))
+}
+
+#[tauri::command]
+fn get_network_status_command() -> Result<NetworkStatus, String> {
+    let settings = AppSettings::load().map_err(|err| err.to_string())?;
+    let snapshot = detect_network();
+    let active_profile = select_profile(
+        &settings.network_profiles,
+        &settings.network_profile,
+        &snapshot,
+    )
+    .map(|profile| profile.name.clone());
+    Ok(NetworkStatus {
+        snapshot,
+        active_profile,
+    })
 }
 
 #[tauri::command]
 fn clear_credentials_command(state: tauri::State<AppState>) -> Result<(), String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    init_db(&conn).map_err(|err| err.to_string())?;
+    let conn = state.db()?;
     let accounts = list_accounts(&conn).map_err(|err| err.to_string())?;
     for account in &accounts {
         let _ = core::credentials::clear_tokens(&account.account_key);
+        state.remote_info.clear_account(&account.account_key);
     }
     delete_all_accounts(&conn).map_err(|err| err.to_string())?;
     Ok(())
@@ -571,8 +1651,7 @@ fn export_logs_command(
     task_id: Option<String>,
     level: Option<String>,
 ) -> Result<String, String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    init_db(&conn).map_err(|err| err.to_string())?;
+    let conn = state.db()?;
     let logs = list_logs(&conn, task_id.as_deref(), level.as_deref(), None, None)
         .map_err(|err| err.to_string())?;
     let base_dir = config_dir().map_err(|err| err.to_string())?;
@@ -590,10 +1669,31 @@ fn export_logs_command(
     Ok(path.to_string_lossy().to_string())
 }
 
+#[tauri::command]
+fn get_global_stats_command(state: tauri::State<AppState>) -> GlobalSnapshot {
+    state.global_stats.snapshot(now_ms())
+}
+
+/// 有任务在同步时定期推送全局统计，全部结束后再推送一次归零的结果。
+fn start_global_stats_emitter(app: &AppHandle, global_stats: &GlobalStats) {
+    let app = app.clone();
+    let global_stats = global_stats.clone();
+    thread::spawn(move || {
+        let mut was_active = false;
+        loop {
+            let active = global_stats.is_active();
+            if active || was_active {
+                let _ = app.emit(GLOBAL_STATS_EVENT, global_stats.snapshot(now_ms()));
+            }
+            was_active = active;
+            thread::sleep(Duration::from_secs(GLOBAL_STATS_EMIT_SECS));
+        }
+    });
+}
+
 #[tauri::command]
 fn get_diagnostics_command(state: tauri::State<AppState>) -> Result<DiagnosticInfo, String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    init_db(&conn).map_err(|err| err.to_string())?;
+    let conn = state.db()?;
     let accounts = list_accounts(&conn).map_err(|err| err.to_string())?;
     let tasks = list_tasks(&conn).map_err(|err| err.to_string())?;
     let cfg_dir = config_dir().map_err(|err| err.to_string())?;
@@ -605,106 +1705,438 @@ fn get_diagnostics_command(state: tauri::State<AppState>) -> Result<DiagnosticIn
         config_dir: cfg_dir.to_string_lossy().to_string(),
         accounts: accounts.len(),
         tasks: tasks.len(),
+        transport: transport_counters().snapshot(),
     })
 }
 
+/// 未指定 `keep` 时只标记为已处理；指定时保留一方，另一方按任务设置归档或删除。
 #[tauri::command]
 fn mark_conflict_resolved(
     state: tauri::State<AppState>,
     task_id: String,
     conflict_relpath: String,
+    keep: Option<ConflictKeep>,
 ) -> Result<(), String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    delete_conflict(&conn, &task_id, &conflict_relpath).map_err(|err| err.to_string())
+    let conn = state.db()?;
+    let Some(keep) = keep else {
+        return delete_conflict(&conn, &task_id, &conflict_relpath).map_err(|err| err.to_string());
+    };
+    let conflict = list_conflicts(&conn, Some(&task_id))
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .find(|item| item.conflict_relpath == conflict_relpath)
+        .ok_or_else(|| "冲突记录不存在".to_string())?;
+    let engine = task_engine(&state, &task_id)?;
+    tauri::async_runtime::block_on(engine.resolve_conflict(&conflict, keep))
+        .map_err(|err| err.to_string())
 }
 
-#[tauri::command]
-fn download_conflict_remote(
-    state: tauri::State<AppState>,
-    task_id: String,
-    original_relpath: String,
-) -> Result<(), String> {
+/// 供界面操作使用的同步引擎，事件只写入日志。
+fn task_engine(state: &AppState, task_id: &str) -> Result<SyncEngine, String> {
     let (task, settings) =
-        load_task_settings(&state.db_path, &task_id).map_err(|err| err.to_string())?;
+        load_task_settings(&state.db_path, task_id).map_err(|err| err.to_string())?;
     let tokens = load_tokens(&settings.account_key).map_err(|err| err.to_string())?;
-    let uri = build_remote_uri(&task.remote_root_uri, &original_relpath);
-    let client = CloudreveClient::new(
-        task.base_url,
-        Some(tokens.access_token),
+    Ok(SyncEngine::new(
+        task,
         state.api_paths.clone(),
-    );
-    let result = tauri::async_runtime::block_on(client.create_download_urls(vec![uri], true))
-        .map_err(|err| err.to_string())?;
-    let url = result
-        .urls
-        .first()
-        .map(|item| item.url.clone())
-        .ok_or_else(|| "download url missing".to_string())?;
-    open_external(url)
+        Some(tokens.access_token),
+        state.db_path.clone(),
+        log_event_bus(&state.db_path),
+    )
+    .with_token_refresh(Some(token_refresh(&settings.account_key))))
 }
 
 #[tauri::command]
-fn hash_local_file(path: String) -> Result<String, String> {
-    let mut file = std::fs::File::open(&path).map_err(|err| err.to_string())?;
-    let mut hasher = Sha256::new();
-    let mut buffer = [0u8; 1024 * 512];
-    loop {
-        let count = std::io::Read::read(&mut file, &mut buffer).map_err(|err| err.to_string())?;
-        if count == 0 {
-            break;
-        }
-        hasher.update(&buffer[..count]);
-    }
-    Ok(format!("{:x}", hasher.finalize()))
+fn list_selective_sync_command(
+    state: tauri::State<AppState>,
+    payload: SelectiveSyncRequest,
+) -> Result<Vec<SelectiveNode>, String> {
+    let engine = task_engine(&state, &payload.task_id)?;
+    tauri::async_runtime::block_on(engine.list_selective_children(&payload.relpath))
+        .map_err(|err| err.to_string())
 }
 
-fn build_remote_uri(root_uri: &str, relpath: &str) -> String {
-    let root = root_uri.trim_end_matches('/');
-    let rel = relpath.trim_start_matches('/');
-    format!("{}/{}", root, rel)
+#[tauri::command]
+fn set_selective_sync_command(
+    state: tauri::State<AppState>,
+    payload: SetSelectiveSyncRequest,
+) -> Result<(), String> {
+    let excluded = normalize_selection(&payload.excluded);
+    let conn = state.db()?;
+    set_selective_excludes(&conn, &payload.task_id, &excluded).map_err(|err| err.to_string())?;
+    log_info(
+        &state.db_path,
+        &payload.task_id,
+        "settings",
+        &format!("选择性同步：{} 个目录不在本地保留", excluded.len()),
+    );
+    if let Some(handle) = state
+        .runners
+        .lock()
+        .map_err(|_| "runner lock error".to_string())?
+        .get(&payload.task_id)
+    {
+        handle.run_now.store(true, Ordering::SeqCst);
+    }
+    Ok(())
 }
 
-fn decode_uri(value: &str) -> String {
-    urlencoding::decode(value)
-        .map(|v| v.into_owned())
-        .unwrap_or_else(|_| value.to_string())
+/// 说明某个路径下次同步时会怎样处理以及原因，不执行任何操作。
+#[tauri::command]
+fn explain_path_command(
+    state: tauri::State<AppState>,
+    payload: ExplainPathRequest,
+) -> Result<PathExplanation, String> {
+    let (task, _) =
+        load_task_settings(&state.db_path, &payload.task_id).map_err(|err| err.to_string())?;
+    let read_only = !known_read_only_roots(&task_roots(&task)).is_empty();
+    let engine = task_engine(&state, &payload.task_id)?.with_audit_local(read_only);
+    tauri::async_runtime::block_on(engine.explain_path(&payload.relpath))
+        .map_err(|err| err.to_string())
 }
 
-fn normalize_path_for_match(path: &Path) -> String {
-    let mut normalized = path.to_string_lossy().replace('\\', "/");
-    while normalized.ends_with('/') {
-        normalized.pop();
-    }
-    if cfg!(target_os = "windows") {
-        normalized = normalized.to_lowercase();
+/// 固定时下载完整内容并始终保留在本机，取消固定时释放本地空间、只保留占位文件。
+/// `relpath` 为目录时处理其下所有已同步的文件，返回处理的文件数。
+#[tauri::command(async)]
+fn set_pin_state_command(
+    state: tauri::State<AppState>,
+    payload: SetPinStateRequest,
+) -> Result<usize, String> {
+    let (task, settings) =
+        load_task_settings(&state.db_path, &payload.task_id).map_err(|err| err.to_string())?;
+    let roots = task_roots(&task);
+    let relpath = payload.relpath.trim_matches('/');
+    let conn = state.db()?;
+    let entries = list_entries_by_task(&conn, &payload.task_id)
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .filter(|entry| {
+            relpath.is_empty()
+                || entry.local_relpath == relpath
+                || entry
+                    .local_relpath
+                    .strip_prefix(relpath)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        return Err(format!("文件尚未同步: {}", payload.relpath));
     }
-    normalized
-}
-
-fn find_task_for_local_path(tasks: &[TaskRow], local_path: &Path) -> Option<TaskRow> {
-    let target = normalize_path_for_match(local_path);
-    let mut best: Option<(usize, TaskRow)> = None;
-    for task in tasks {
-        if task.local_root.trim().is_empty() {
-            continue;
-        }
-        let root = normalize_path_for_match(Path::new(&task.local_root));
-        if root.is_empty() {
+    let fetcher = placeholder_fetcher(
+        &state.db_path,
+        &state.api_paths,
+        &task,
+        &settings.account_key,
+    );
+    let names =
+        NameMap::new(list_name_mappings(&conn, &payload.task_id).map_err(|err| err.to_string())?);
+    let mut changed = 0;
+    let mut last_error = None;
+    for entry in entries {
+        let Some(path) = resolve_local_path(&roots, &names.local_relpath(&entry.local_relpath))
+        else {
             continue;
-        }
-        let is_match = target == root || target.starts_with(&format!("{}/", root));
-        if !is_match {
+        };
+        if !path.is_file() {
             continue;
         }
-        let score = root.len();
-        if best.as_ref().map(|(len, _)| score > *len).unwrap_or(true) {
-            best = Some((score, task.clone()));
+        let identity = PlaceholderIdentity {
+            uri: entry.cloud_uri,
+            sha256: entry.last_remote_sha256,
+        };
+        match set_pinned(&path, payload.pinned, &identity, &fetcher) {
+            Ok(()) => changed += 1,
+            Err(err) => {
+                let detail = format!("{} ({})", entry.local_relpath, err);
+                log_error(&state.db_path, &payload.task_id, &detail);
+                last_error = Some(detail);
+            }
         }
     }
-    best.map(|(_, task)| task)
+    log_info(
+        &state.db_path,
+        &payload.task_id,
+        "placeholder",
+        &format!(
+            "{}: {} ({} 个文件)",
+            if payload.pinned {
+                "始终保留在此设备"
+            } else {
+                "释放空间"
+            },
+            payload.relpath,
+            changed
+        ),
+    );
+    match last_error {
+        Some(err) if changed == 0 => Err(err),
+        _ => Ok(changed),
+    }
 }
 
-fn relpath_from_local(local_root: &str, local_path: &Path) -> Result<String, String> {
+#[tauri::command]
+fn list_transfers_command(
+    state: tauri::State<AppState>,
+    task_id: Option<String>,
+) -> Result<Vec<TransferRow>, String> {
+    let conn = state.db()?;
+    list_transfers(&conn, task_id.as_deref()).map_err(|err| err.to_string())
+}
+
+/// 列出、创建与吊销本地接口令牌，每次都返回最新的令牌列表。
+#[tauri::command]
+fn manage_api_tokens_command(
+    state: tauri::State<AppState>,
+    payload: ManageApiTokensRequest,
+) -> Result<ManageApiTokensResult, String> {
+    let conn = state.db()?;
+    let created_token = match payload {
+        ManageApiTokensRequest::List => None,
+        ManageApiTokensRequest::Create { name, scope } => {
+            let (_, token) =
+                create_api_token(&conn, &name, scope).map_err(|err| err.to_string())?;
+            Some(token)
+        }
+        ManageApiTokensRequest::Revoke { token_id } => {
+            if delete_api_token(&conn, &token_id).map_err(|err| err.to_string())? == 0 {
+                return Err(format!("令牌不存在: {}", token_id));
+            }
+            None
+        }
+    };
+    Ok(ManageApiTokensResult {
+        tokens: list_api_tokens(&conn).map_err(|err| err.to_string())?,
+        created_token,
+    })
+}
+
+#[tauri::command]
+fn list_sync_runs_command(
+    state: tauri::State<AppState>,
+    task_id: String,
+) -> Result<Vec<SyncRunRow>, String> {
+    let conn = state.db()?;
+    list_sync_runs(&conn, &task_id).map_err(|err| err.to_string())
+}
+
+/// 还原某次运行结束时任务中的文件（路径、大小、哈希），用于查看同步出错前的状态。
+#[tauri::command]
+fn get_tree_at_run_command(
+    state: tauri::State<AppState>,
+    task_id: String,
+    run_id: i64,
+) -> Result<Vec<EntryVersionRow>, String> {
+    let conn = state.db()?;
+    tree_at_run(&conn, &task_id, run_id)
+        .map_err(|err| err.to_string())?
+        .ok_or_else(|| format!("运行记录不存在: {}", run_id))
+}
+
+/// 只能取消尚在排队的项；被取消的文件本轮不再同步，下次同步重新排队。
+#[tauri::command]
+fn cancel_transfer_command(
+    state: tauri::State<AppState>,
+    payload: TransferItemRequest,
+) -> Result<(), String> {
+    let conn = state.db()?;
+    if !cancel_transfer(&conn, &payload.task_id, &payload.relpath).map_err(|err| err.to_string())? {
+        return Err(format!("只能取消排队中的传输: {}", payload.relpath));
+    }
+    log_info(
+        &state.db_path,
+        &payload.task_id,
+        "queue",
+        &format!("已取消传输: {}", payload.relpath),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn bump_transfer_command(
+    state: tauri::State<AppState>,
+    payload: TransferItemRequest,
+) -> Result<(), String> {
+    let conn = state.db()?;
+    if !bump_transfer(&conn, &payload.task_id, &payload.relpath).map_err(|err| err.to_string())? {
+        return Err(format!("传输队列中没有该文件: {}", payload.relpath));
+    }
+    Ok(())
+}
+
+/// 清空任务各本地根目录下的回收站，返回删除的批次数。
+#[tauri::command]
+fn purge_local_trash_command(
+    state: tauri::State<AppState>,
+    payload: SyncRequest,
+) -> Result<usize, String> {
+    let (task, _) =
+        load_task_settings(&state.db_path, &payload.task_id).map_err(|err| err.to_string())?;
+    let removed = purge_local_trash(&task_roots(&task), None).map_err(|err| err.to_string())?;
+    log_info(
+        &state.db_path,
+        &payload.task_id,
+        "trash",
+        &format!("清空回收站: {} 批", removed),
+    );
+    Ok(removed)
+}
+
+/// 预览任务下次同步将执行的上传、下载、删除与冲突处理，不执行任何操作。
+#[tauri::command]
+fn preview_sync_command(
+    state: tauri::State<AppState>,
+    payload: SyncRequest,
+) -> Result<SyncPreview, String> {
+    let (task, _) =
+        load_task_settings(&state.db_path, &payload.task_id).map_err(|err| err.to_string())?;
+    let read_only = !known_read_only_roots(&task_roots(&task)).is_empty();
+    let engine = task_engine(&state, &payload.task_id)?.with_audit_local(read_only);
+    tauri::async_runtime::block_on(engine.preview()).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn download_conflict_remote(
+    state: tauri::State<AppState>,
+    task_id: String,
+    original_relpath: String,
+) -> Result<(), String> {
+    let (task, settings) =
+        load_task_settings(&state.db_path, &task_id).map_err(|err| err.to_string())?;
+    if settings.encryption.is_some() {
+        return Err("加密任务在远端只有密文，请在冲突中选择保留远端版本后查看".to_string());
+    }
+    let uri = build_remote_uri(
+        &with_saved_share_password(&task.task_id, &task.remote_root_uri),
+        &original_relpath,
+    );
+    let client = remote_client(&state, &settings.account_key, task.base_url)?;
+    let result = tauri::async_runtime::block_on(async {
+        let info = state
+            .remote_info
+            .lookup(&client, &settings.account_key, &uri)
+            .await
+            .map_err(|err| format!("远端文件不存在或无法访问: {}", err))?;
+        if info.is_dir {
+            return Err(format!("远端是目录，无法下载: {}", uri));
+        }
+        client
+            .create_download_urls(vec![uri], true)
+            .await
+            .map_err(|err| err.to_string())
+    })?;
+    let url = result
+        .urls
+        .first()
+        .map(|item| item.url.clone())
+        .ok_or_else(|| "download url missing".to_string())?;
+    open_external(url)
+}
+
+/// 远端文件的历史版本，以及上次从历史版本恢复时记录的版本。
+#[derive(Serialize)]
+struct FileVersionsView {
+    versions: Vec<core::cloudreve::FileVersion>,
+    restored_version_id: String,
+}
+
+#[tauri::command]
+fn list_file_versions_command(
+    state: tauri::State<AppState>,
+    task_id: String,
+    relpath: String,
+) -> Result<FileVersionsView, String> {
+    let engine = task_engine(&state, &task_id)?;
+    let versions = tauri::async_runtime::block_on(engine.list_versions(&relpath))
+        .map_err(|err| err.to_string())?;
+    let conn = state.db()?;
+    let restored_version_id =
+        get_entry_version_id(&conn, &task_id, &relpath).map_err(|err| err.to_string())?;
+    Ok(FileVersionsView {
+        versions,
+        restored_version_id,
+    })
+}
+
+#[tauri::command]
+fn restore_version_command(
+    state: tauri::State<AppState>,
+    task_id: String,
+    relpath: String,
+    version_id: String,
+) -> Result<(), String> {
+    let engine = task_engine(&state, &task_id)?;
+    tauri::async_runtime::block_on(engine.restore_version(&relpath, &version_id))
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn hash_local_file(path: String) -> Result<String, String> {
+    let mut file = std::fs::File::open(&path).map_err(|err| err.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 1024 * 512];
+    loop {
+        let count = std::io::Read::read(&mut file, &mut buffer).map_err(|err| err.to_string())?;
+        if count == 0 {
+            break;
+        }
+        hasher.update(&buffer[..count]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn build_remote_uri(root_uri: &str, relpath: &str) -> String {
+    let root = root_uri.trim_end_matches('/');
+    let rel = relpath.trim_start_matches('/');
+    format!("{}/{}", root, rel)
+}
+
+fn decode_uri(value: &str) -> String {
+    urlencoding::decode(value)
+        .map(|v| v.into_owned())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn normalize_path_for_match(path: &Path) -> String {
+    let mut normalized = path.to_string_lossy().replace('\\', "/");
+    while normalized.ends_with('/') {
+        normalized.pop();
+    }
+    if cfg!(target_os = "windows") {
+        normalized = normalized.to_lowercase();
+    }
+    normalized
+}
+
+fn find_task_for_local_path(tasks: &[TaskRow], local_path: &Path) -> Option<(TaskRow, LocalRoot)> {
+    let target = normalize_path_for_match(local_path);
+    let mut best: Option<(usize, TaskRow, LocalRoot)> = None;
+    for task in tasks {
+        for local_root in task_roots(task) {
+            if local_root.local_root.trim().is_empty() {
+                continue;
+            }
+            let root = normalize_path_for_match(Path::new(&local_root.local_root));
+            if root.is_empty() {
+                continue;
+            }
+            let is_match = target == root || target.starts_with(&format!("{}/", root));
+            if !is_match {
+                continue;
+            }
+            let score = root.len();
+            if best
+                .as_ref()
+                .map(|(len, _, _)| score > *len)
+                .unwrap_or(true)
+            {
+                best = Some((score, task.clone(), local_root));
+            }
+        }
+    }
+    best.map(|(_, task, root)| (task, root))
+}
+
+fn relpath_from_local(local_root: &str, local_path: &Path) -> Result<String, String> {
     let root = Path::new(local_root);
     let rel = local_path
         .strip_prefix(root)
@@ -720,7 +2152,7 @@ fn relpath_from_local(local_root: &str, local_path: &Path) -> Result<String, Str
 
 #[tauri::command]
 fn list_logs_command(state: tauri::State<AppState>, query: LogsQuery) -> Result<LogsPage, String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
+    let conn = state.db()?;
     let page = query.page.unwrap_or(1).max(1);
     let page_size = query.page_size.unwrap_or(50).clamp(10, 200);
     let offset = (page - 1) * page_size;
@@ -736,173 +2168,962 @@ fn list_logs_command(state: tauri::State<AppState>, query: LogsQuery) -> Result<
     .map_err(|err| err.to_string())?;
     Ok(LogsPage {
         total,
-        items: logs
-            .into_iter()
-            .map(|log| ActivityItem {
-                timestamp: format_time(log.created_at_ms),
-                event: log.event,
-                detail: log.detail,
-                level: log.level,
-            })
-            .collect(),
+        items: logs.into_iter().map(ActivityItem::from_log).collect(),
     })
 }
 
+#[tauri::command]
+fn list_batch_logs_command(
+    state: tauri::State<AppState>,
+    batch_id: String,
+) -> Result<Vec<ActivityItem>, String> {
+    let conn = state.db()?;
+    let logs = list_batch_logs(&conn, &batch_id).map_err(|err| err.to_string())?;
+    Ok(logs.into_iter().map(ActivityItem::from_log).collect())
+}
+
 #[tauri::command]
 fn run_sync_command(
     app: AppHandle,
     state: tauri::State<AppState>,
     payload: SyncRequest,
 ) -> Result<(), String> {
-    start_sync_task(&app, &state, &payload.task_id)
+    start_sync_task(&app, &state, &payload.task_id, false).map(|_| ())
 }
 
-fn start_sync_task(app: &AppHandle, state: &AppState, task_id: &str) -> Result<(), String> {
+/// `wait_until_due` 为 true 时（应用启动）按记录的下次运行时间等待，逾期任务立即补跑。
+/// 任务已在运行时返回 false。
+fn start_sync_task(
+    app: &AppHandle,
+    state: &AppState,
+    task_id: &str,
+    wait_until_due: bool,
+) -> Result<bool, String> {
     let mut runners = state
         .runners
         .lock()
         .map_err(|_| "runner lock error".to_string())?;
     if runners.contains_key(task_id) {
-        return Ok(());
+        return Ok(false);
     }
+    ensure_task_enabled(state, task_id)?;
     let stop_flag = Arc::new(AtomicBool::new(false));
+    let deep_flag = Arc::new(AtomicBool::new(false));
+    let run_now_flag = Arc::new(AtomicBool::new(false));
+    let paused_flag = Arc::new(AtomicBool::new(false));
+    let cancel = CancellationToken::new();
     let task_id = task_id.to_string();
     let task_id_for_thread = task_id.clone();
     let task_id_for_emit = task_id.clone();
     let db_path = state.db_path.clone();
     let api_paths = state.api_paths.clone();
     let stats_map = state.stats.clone();
+    let health = state.health.clone();
+    let global_stats = state.global_stats.clone();
+    let one_shots = state.one_shots.clone();
     let app_handle = app.clone();
     let stop_for_thread = stop_flag.clone();
-    thread::spawn(move || {
-        let settings = match load_task_settings(&db_path, &task_id_for_thread) {
-            Ok((_, settings)) => settings,
+    let deep_for_thread = deep_flag.clone();
+    let run_now_for_thread = run_now_flag.clone();
+    let paused_for_thread = paused_flag.clone();
+    let control = RunControl {
+        pause: paused_flag.clone(),
+        cancel: cancel.clone(),
+        local_writes: LocalWrites::default(),
+    };
+    let thread = thread::spawn(move || {
+        let (task, settings) = match load_task_settings(&db_path, &task_id_for_thread) {
+            Ok(loaded) => loaded,
             Err(err) => {
                 let detail = err.to_string();
                 log_error(&db_path, &task_id_for_thread, &detail);
                 return;
             }
         };
-        let interval = settings.sync_interval_secs.max(5);
+        let schedule = ScanSchedule::new(
+            settings.sync_interval_secs,
+            settings.deep_scan_interval_secs,
+        );
+        let plan = RunPlan::new(
+            schedule.quick_interval_secs,
+            &settings.sync_cron,
+            &settings.sync_windows,
+        )
+        .unwrap_or_else(|err| {
+            log_error(&db_path, &task_id_for_thread, &err);
+            RunPlan::every(schedule.quick_interval_secs)
+        });
+        // 任务启动时重新检查本地目录是否可写，之后只在写入因权限失败时再检查。
+        forget_root_access(&task_roots(&task));
+        let local_changed = Arc::new(AtomicBool::new(false));
+        let saved_files = SavedFiles::default();
+        let _watcher = start_local_watcher(
+            &db_path,
+            &task,
+            &settings.priority_patterns,
+            &local_changed,
+            &saved_files,
+            &control.local_writes,
+        );
+        let _placeholders = connect_placeholders(&db_path, &api_paths, &task, &settings);
+        let remote_changed = Arc::new(AtomicBool::new(false));
+        let _subscriber = start_remote_subscriber(
+            &db_path,
+            &api_paths,
+            &task,
+            &settings.account_key,
+            &remote_changed,
+        );
+        if wait_until_due && AppSettings::load().unwrap_or_default().startup_check {
+            run_startup_check(&db_path, &task);
+        }
+        if wait_until_due {
+            let delay_ms = plan.launch_delay_ms(
+                load_next_due(&db_path, &task_id_for_thread),
+                now_ms(),
+                settings.catch_up_missed_runs,
+            );
+            if delay_ms > 0 {
+                wait_next_cycle(
+                    &stop_for_thread,
+                    &[&deep_for_thread, &local_changed, &remote_changed],
+                    (delay_ms as u64).div_ceil(1000),
+                );
+            }
+        }
+        let mut last_profile: Option<String> = None;
+        let mut last_pause_reason: Option<PauseReason> = None;
+        let mut read_only_reported = false;
+        // 同一问题持续存在时只通知一次，成功同步后重新计数。
+        let mut error_notified = false;
+        let mut last_conflicts = 0;
         loop {
             if stop_for_thread.load(Ordering::SeqCst) {
                 break;
             }
-            let start = Instant::now();
-            let progress_task_id = task_id_for_thread.clone();
-            let progress_stats_map = stats_map.clone();
-            let progress_app = app_handle.clone();
-            let progress_started = start;
-            let progress_notifier: Arc<dyn Fn(SyncStats) + Send + Sync> = Arc::new(move |stats| {
-                update_task_stats(
-                    &progress_stats_map,
-                    &progress_task_id,
-                    stats,
-                    progress_started.elapsed(),
+            if paused_for_thread.load(Ordering::SeqCst) {
+                emit_task_runtime(
+                    &app_handle,
+                    &stats_map,
+                    &task_id_for_thread,
+                    "UserPaused",
+                    None,
+                );
+                // 暂停期间忽略立即同步的请求，恢复时会重新置位。
+                run_now_for_thread.store(false, Ordering::SeqCst);
+                wait_next_cycle(
+                    &stop_for_thread,
+                    &[&run_now_for_thread],
+                    QUIET_HOURS_CHECK_SECS,
+                );
+                continue;
+            }
+            // 登录失效后不再请求服务器，重新登录时会置位立即同步。
+            if account_needs_login(&db_path, &settings.account_key) {
+                emit_task_runtime(
+                    &app_handle,
+                    &stats_map,
+                    &task_id_for_thread,
+                    "NeedsLogin",
+                    None,
+                );
+                run_now_for_thread.store(false, Ordering::SeqCst);
+                wait_next_cycle(
+                    &stop_for_thread,
+                    &[&run_now_for_thread],
+                    QUIET_HOURS_CHECK_SECS,
+                );
+                continue;
+            }
+            let profile = active_network_profile();
+            let profile_name = profile.as_ref().map(|profile| profile.name.clone());
+            if profile_name != last_profile {
+                if let Some(name) = &profile_name {
+                    log_info(
+                        &db_path,
+                        &task_id_for_thread,
+                        "network",
+                        &format!("切换网络配置: {}", name),
+                    );
+                }
+                last_profile = profile_name;
+            }
+            if profile.map(|profile| profile.pause_sync).unwrap_or(false) {
+                emit_task_runtime(&app_handle, &stats_map, &task_id_for_thread, "Paused", None);
+                wait_next_cycle(
+                    &stop_for_thread,
+                    &[&deep_for_thread],
+                    schedule.quick_interval_secs,
+                );
+                continue;
+            }
+            let pause_reason = system_pause_reason();
+            if pause_reason != last_pause_reason {
+                let detail = match &pause_reason {
+                    Some(reason) => format!("同步已暂停: {}", reason.describe()),
+                    None => "系统状态已恢复，继续同步".to_string(),
+                };
+                log_info(&db_path, &task_id_for_thread, "system", &detail);
+                last_pause_reason = pause_reason.clone();
+            }
+            if let Some(reason) = &pause_reason {
+                emit_task_runtime(
+                    &app_handle,
+                    &stats_map,
+                    &task_id_for_thread,
+                    reason.status(),
+                    None,
+                );
+                wait_next_cycle(
+                    &stop_for_thread,
+                    &[&deep_for_thread],
+                    QUIET_HOURS_CHECK_SECS,
+                );
+                continue;
+            }
+            if !deep_for_thread.load(Ordering::SeqCst) && in_quiet_hours_now() {
+                emit_task_runtime(&app_handle, &stats_map, &task_id_for_thread, "Quiet", None);
+                wait_next_cycle(
+                    &stop_for_thread,
+                    &[&deep_for_thread],
+                    QUIET_HOURS_CHECK_SECS,
+                );
+                continue;
+            }
+            // 时间窗口之外只响应手动触发的同步与深度扫描。
+            if !deep_for_thread.load(Ordering::SeqCst)
+                && !run_now_for_thread.load(Ordering::SeqCst)
+                && !plan.allows_ms(now_ms())
+            {
+                emit_task_runtime(
+                    &app_handle,
+                    &stats_map,
+                    &task_id_for_thread,
+                    "OutsideWindow",
+                    None,
+                );
+                wait_next_cycle(
+                    &stop_for_thread,
+                    &[&deep_for_thread, &run_now_for_thread],
+                    QUIET_HOURS_CHECK_SECS,
+                );
+                continue;
+            }
+            // 启动前触发的单次同步尚未结束时等待，避免同一任务并发同步。
+            if one_shot_active(&one_shots, &task_id_for_thread) {
+                wait_next_cycle(&stop_for_thread, &[], 1);
+                continue;
+            }
+            // 优先文件保存后立即单独同步；没有其他变化时等到下次定时扫描，不提前跑完整一轮。
+            let saved = saved_files.take();
+            if !saved.is_empty() {
+                log_info(
+                    &db_path,
+                    &task_id_for_thread,
+                    "saved",
+                    &format!("优先文件已保存，立即同步: {}", saved.join(", ")),
                 );
                 emit_task_runtime(
-                    &progress_app,
-                    &progress_stats_map,
-                    &progress_task_id,
+                    &app_handle,
+                    &stats_map,
+                    &task_id_for_thread,
                     "Syncing",
-                    Some(now_ms()),
+                    None,
                 );
-            });
-
-            let status_task_id = task_id_for_thread.clone();
-            let status_stats_map = stats_map.clone();
-            let status_app = app_handle.clone();
-            let status_notifier: Arc<dyn Fn(String) + Send + Sync> = Arc::new(move |status| {
+                let start = Instant::now();
+                let events = runner_event_bus(&db_path, &app_handle, &stats_map, &health, start);
+                match run_sync_paths(
+                    &db_path,
+                    &api_paths,
+                    &task_id_for_thread,
+                    &saved,
+                    events,
+                    control.clone(),
+                    &global_stats,
+                ) {
+                    Ok(stats) => {
+                        update_task_stats(&stats_map, &task_id_for_thread, stats, start.elapsed())
+                    }
+                    Err(err) if err.downcast_ref::<TaskLocked>().is_some() => {
+                        // 优先文件留到下一轮完整同步时一并处理。
+                    }
+                    Err(err) => {
+                        let detail = err.to_string();
+                        log_sync_failure(&db_path, &task_id_for_thread, &err);
+                        record_auth_failure(
+                            &app_handle,
+                            &db_path,
+                            &task_id_for_thread,
+                            &settings.account_key,
+                            &err,
+                        );
+                        if !error_notified {
+                            let (kind, body) = failure_notification(&err, &detail);
+                            notify_task(&app_handle, &db_path, &task_id_for_thread, kind, body);
+                            error_notified = true;
+                        }
+                    }
+                }
+                set_zero_rates(&stats_map, &task_id_for_thread);
                 emit_task_runtime(
-                    &status_app,
-                    &status_stats_map,
-                    &status_task_id,
-                    &status,
-                    Some(now_ms()),
+                    &app_handle,
+                    &stats_map,
+                    &task_id_for_thread,
+                    "Syncing",
+                    None,
                 );
-            });
-
-            match run_sync_once(
+                let wake: [&AtomicBool; 6] = [
+                    &deep_for_thread,
+                    &local_changed,
+                    &remote_changed,
+                    &run_now_for_thread,
+                    &paused_for_thread,
+                    &saved_files.pending,
+                ];
+                if !wake.iter().any(|flag| flag.load(Ordering::SeqCst)) {
+                    let wait_ms = load_next_due(&db_path, &task_id_for_thread)
+                        .map(|due| due - now_ms())
+                        .unwrap_or(0);
+                    wait_next_cycle(
+                        &stop_for_thread,
+                        &wake,
+                        (wait_ms.max(0) as u64).div_ceil(1000),
+                    );
+                }
+                continue;
+            }
+            let mode = if deep_for_thread.swap(false, Ordering::SeqCst) {
+                ScanMode::Deep
+            } else {
+                match next_scan_mode(&db_path, &task_id_for_thread, &schedule) {
+                    ScanMode::Deep if !deep_scan_allowed() => ScanMode::Quick,
+                    mode => mode,
+                }
+            };
+            // 同步过程中发生的本地修改会重新置位，结束后立即再跑一轮。
+            local_changed.store(false, Ordering::SeqCst);
+            remote_changed.store(false, Ordering::SeqCst);
+            run_now_for_thread.store(false, Ordering::SeqCst);
+            let start = Instant::now();
+            let events = runner_event_bus(&db_path, &app_handle, &stats_map, &health, start);
+            let mut status = "Syncing";
+            let success = match run_sync_once(
                 &db_path,
                 &api_paths,
                 &task_id_for_thread,
-                Some(progress_notifier),
-                Some(status_notifier),
+                mode,
+                events,
+                control.clone(),
+                &global_stats,
             ) {
+                // 中途暂停的一轮没有处理完，不算成功也不算失败：不更新健康状态、扫描记录与运行记录。
+                Ok(stats) if stats.incomplete => {
+                    status = "UserPaused";
+                    update_task_stats(&stats_map, &task_id_for_thread, stats, start.elapsed());
+                    None
+                }
                 Ok(stats) => {
-                    update_task_stats(&stats_map, &task_id_for_thread, stats, start.elapsed())
+                    read_only_reported = false;
+                    error_notified = false;
+                    if stats.held_conflicts > 0 {
+                        status = "NeedsAttention";
+                    }
+                    if let Some(body) = conflicts_message(&stats, last_conflicts) {
+                        notify_task(
+                            &app_handle,
+                            &db_path,
+                            &task_id_for_thread,
+                            NotifyKind::Conflict,
+                            &body,
+                        );
+                    }
+                    last_conflicts = stats.held_conflicts;
+                    if let Some(body) = completed_message(&stats) {
+                        notify_task(
+                            &app_handle,
+                            &db_path,
+                            &task_id_for_thread,
+                            NotifyKind::Completed,
+                            &body,
+                        );
+                    }
+                    health.record_success(&task_id_for_thread, now_ms());
+                    record_auth_success(&db_path, &settings.account_key);
+                    record_scan_finished(&db_path, &task_id_for_thread, mode);
+                    update_task_stats(&stats_map, &task_id_for_thread, stats, start.elapsed());
+                    Some(true)
+                }
+                Err(SyncError::Cancelled) => {
+                    status = "Cancelled";
+                    Some(false)
+                }
+                // 另一个进程正在同步，不算失败，等下一轮再试。
+                Err(err) if err.downcast_ref::<TaskLocked>().is_some() => {
+                    status = "LockedElsewhere";
+                    None
+                }
+                Err(err) if err.downcast_ref::<RootReadOnly>().is_some() => {
+                    status = "RootReadOnly";
+                    health.record_failure(&task_id_for_thread);
+                    if !read_only_reported {
+                        log_error(&db_path, &task_id_for_thread, &err.to_string());
+                        read_only_reported = true;
+                    }
+                    Some(false)
                 }
                 Err(err) => {
+                    health.record_failure(&task_id_for_thread);
                     let detail = err.to_string();
-                    log_error(&db_path, &task_id_for_thread, &detail);
+                    log_sync_failure(&db_path, &task_id_for_thread, &err);
+                    if record_auth_failure(
+                        &app_handle,
+                        &db_path,
+                        &task_id_for_thread,
+                        &settings.account_key,
+                        &err,
+                    ) {
+                        status = "NeedsLogin";
+                    }
+                    if !error_notified {
+                        let (kind, body) = failure_notification(&err, &detail);
+                        notify_task(&app_handle, &db_path, &task_id_for_thread, kind, body);
+                        error_notified = true;
+                    }
+                    Some(false)
                 }
+            };
+            let outcome = success.map(|success| RunOutcome {
+                at_ms: now_ms(),
+                success,
+            });
+            match outcome {
+                Some(outcome) => record_run_finished(&db_path, &task_id_for_thread, outcome),
+                None => record_run_paused(&db_path, &task_id_for_thread),
             }
+            let next_due = store_next_due(&db_path, &task_id_for_thread, &plan);
+            health.set_queue_depth(&task_id_for_thread, 0);
             set_zero_rates(&stats_map, &task_id_for_thread);
             emit_task_runtime(
                 &app_handle,
                 &stats_map,
                 &task_id_for_thread,
-                "Syncing",
-                Some(now_ms()),
+                status,
+                outcome,
+            );
+            wait_next_cycle(
+                &stop_for_thread,
+                &[
+                    &deep_for_thread,
+                    &local_changed,
+                    &remote_changed,
+                    &run_now_for_thread,
+                    &paused_for_thread,
+                    &saved_files.pending,
+                ],
+                ((next_due - now_ms()).max(0) as u64).div_ceil(1000),
             );
-            thread::sleep(Duration::from_secs(interval));
         }
     });
-    runners.insert(task_id, RunnerHandle { stop: stop_flag });
+    runners.insert(
+        task_id,
+        RunnerHandle {
+            stop: stop_flag,
+            deep_requested: deep_flag,
+            run_now: run_now_flag,
+            paused: paused_flag,
+            cancel,
+            thread,
+        },
+    );
     emit_task_runtime(&app, &state.stats, &task_id_for_emit, "Syncing", None);
-    Ok(())
+    Ok(true)
 }
 
-#[tauri::command]
-fn stop_sync_command(
-    app: AppHandle,
-    state: tauri::State<AppState>,
-    payload: SyncRequest,
-) -> Result<(), String> {
-    let mut runners = state
+/// 停止任务的常驻线程；任务未在运行时返回 false。
+fn stop_sync_task(app: &AppHandle, state: &AppState, task_id: &str) -> Result<bool, String> {
+    let handle = state
         .runners
         .lock()
-        .map_err(|_| "runner lock error".to_string())?;
-    if let Some(handle) = runners.remove(&payload.task_id) {
+        .map_err(|_| "runner lock error".to_string())?
+        .remove(task_id);
+    if let Some(handle) = &handle {
         handle.stop.store(true, Ordering::SeqCst);
+        handle.cancel.cancel();
     }
-    set_zero_rates(&state.stats, &payload.task_id);
-    emit_task_runtime(&app, &state.stats, &payload.task_id, "Idle", None);
-    Ok(())
+    set_zero_rates(&state.stats, task_id);
+    emit_task_runtime(app, &state.stats, task_id, "Idle", None);
+    Ok(handle.is_some())
 }
 
-#[tauri::command]
-fn delete_task_command(
-    state: tauri::State<AppState>,
-    payload: DeleteTaskRequest,
+/// 暂停或恢复常驻任务；恢复时立即开始新一轮，先处理暂停时留下的队列。
+fn set_task_paused(
+    app: &AppHandle,
+    state: &AppState,
+    task_id: &str,
+    paused: bool,
 ) -> Result<(), String> {
     {
-        let mut runners = state
+        let runners = state
             .runners
             .lock()
             .map_err(|_| "runner lock error".to_string())?;
-        if let Some(handle) = runners.remove(&payload.task_id) {
-            handle.stop.store(true, Ordering::SeqCst);
+        let handle = runners.get(task_id).ok_or("任务未在运行")?;
+        handle.paused.store(paused, Ordering::SeqCst);
+        if !paused {
+            handle.run_now.store(true, Ordering::SeqCst);
         }
     }
-    if let Ok(mut stats) = state.stats.lock() {
-        stats.remove(&payload.task_id);
-    }
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    delete_task(&conn, &payload.task_id).map_err(|err| err.to_string())?;
+    let (status, detail) = if paused {
+        ("UserPaused", "任务已暂停")
+    } else {
+        ("Syncing", "任务已恢复")
+    };
+    log_info(&state.db_path, task_id, "pause", detail);
+    emit_task_runtime(app, &state.stats, task_id, status, None);
     Ok(())
 }
 
 #[tauri::command]
-fn bootstrap(state: tauri::State<AppState>) -> Result<BootstrapPayload, String> {
-    let conn = Connection::open(&state.db_path).map_err(|err| err.to_string())?;
-    let tasks = build_task_items(&state, &conn).map_err(|err| err.to_string())?;
-    let conflicts = list_conflicts(&conn, None).map_err(|err| err.to_string())?;
-    let logs = list_logs(&conn, None, None, None, None).map_err(|err| err.to_string())?;
+fn pause_sync_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    payload: SyncRequest,
+) -> Result<(), String> {
+    set_task_paused(&app, &state, &payload.task_id, true)
+}
 
-    let today = Local::now().date_naive();
-    let mut upload_count = 0;
-    let mut download_count = 0;
+#[tauri::command]
+fn resume_sync_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    payload: SyncRequest,
+) -> Result<(), String> {
+    set_task_paused(&app, &state, &payload.task_id, false)
+}
+
+#[tauri::command]
+fn stop_sync_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    payload: SyncRequest,
+) -> Result<(), String> {
+    stop_sync_task(&app, &state, &payload.task_id).map(|_| ())
+}
+
+/// 批量操作涉及的任务，不含已停用的任务。
+fn all_task_ids(state: &AppState) -> Result<Vec<String>, String> {
+    let conn = state.db()?;
+    Ok(list_tasks(&conn)
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .filter(|task| parse_settings(&task.settings_json).enabled)
+        .map(|task| task.task_id)
+        .collect())
+}
+
+#[tauri::command]
+fn start_all_tasks_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<BulkTaskSummary, String> {
+    let mut summary = BulkTaskSummary::default();
+    for task_id in all_task_ids(&state)? {
+        if start_sync_task(&app, &state, &task_id, false)? {
+            summary.affected += 1;
+        } else {
+            summary.skipped += 1;
+        }
+    }
+    Ok(summary)
+}
+
+#[tauri::command]
+fn stop_all_tasks_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<BulkTaskSummary, String> {
+    let mut summary = BulkTaskSummary::default();
+    for task_id in all_task_ids(&state)? {
+        if stop_sync_task(&app, &state, &task_id)? {
+            summary.affected += 1;
+        } else {
+            summary.skipped += 1;
+        }
+    }
+    Ok(summary)
+}
+
+#[tauri::command]
+fn run_all_once_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<BulkTaskSummary, String> {
+    run_all_tasks_once(&app, &state)
+}
+
+/// 立即同步所有任务：常驻任务提前开始下一轮，未运行的任务单独跑一轮；静默时段内全部跳过。
+fn run_all_tasks_once(app: &AppHandle, state: &AppState) -> Result<BulkTaskSummary, String> {
+    let task_ids = all_task_ids(state)?;
+    if in_quiet_hours_now() {
+        return Ok(BulkTaskSummary {
+            affected: 0,
+            skipped: task_ids.len() as u32,
+        });
+    }
+    let mut summary = BulkTaskSummary::default();
+    for task_id in task_ids {
+        let woken = state
+            .runners
+            .lock()
+            .map_err(|_| "runner lock error".to_string())?
+            .get(&task_id)
+            .map(|handle| handle.run_now.store(true, Ordering::SeqCst))
+            .is_some();
+        if woken || spawn_one_shot(app, state, &task_id, ScanMode::Quick)? {
+            summary.affected += 1;
+        } else {
+            summary.skipped += 1;
+        }
+    }
+    Ok(summary)
+}
+
+fn one_shot_active(one_shots: &Mutex<HashSet<String>>, task_id: &str) -> bool {
+    one_shots
+        .lock()
+        .map(|one_shots| one_shots.contains(task_id))
+        .unwrap_or(false)
+}
+
+/// 在后台单独跑一轮同步；任务已有常驻线程或上一轮单次同步未结束时返回 false。
+fn spawn_one_shot(
+    app: &AppHandle,
+    state: &AppState,
+    task_id: &str,
+    mode: ScanMode,
+) -> Result<bool, String> {
+    {
+        let runners = state
+            .runners
+            .lock()
+            .map_err(|_| "runner lock error".to_string())?;
+        if runners.contains_key(task_id) {
+            return Ok(false);
+        }
+        ensure_task_enabled(state, task_id)?;
+        let mut one_shots = state
+            .one_shots
+            .lock()
+            .map_err(|_| "runner lock error".to_string())?;
+        if !one_shots.insert(task_id.to_string()) {
+            return Ok(false);
+        }
+    }
+    let app = app.clone();
+    let db_path = state.db_path.clone();
+    let api_paths = state.api_paths.clone();
+    let stats_map = state.stats.clone();
+    let health = state.health.clone();
+    let global_stats = state.global_stats.clone();
+    let one_shots = state.one_shots.clone();
+    let task_id = task_id.to_string();
+    thread::spawn(move || {
+        let start = Instant::now();
+        let status = match mode {
+            ScanMode::Deep => "Verifying",
+            ScanMode::Quick => "Syncing",
+        };
+        emit_task_runtime(&app, &stats_map, &task_id, status, None);
+        let events = log_event_bus(&db_path);
+        let account_key = load_task_settings(&db_path, &task_id)
+            .map(|(_, settings)| settings.account_key)
+            .unwrap_or_default();
+        let mut final_status = "Idle";
+        let success = match run_sync_once(
+            &db_path,
+            &api_paths,
+            &task_id,
+            mode,
+            events,
+            RunControl::default(),
+            &global_stats,
+        ) {
+            Ok(stats) => {
+                if stats.held_conflicts > 0 {
+                    final_status = "NeedsAttention";
+                }
+                if let Some(body) = conflicts_message(&stats, 0) {
+                    notify_task(&app, &db_path, &task_id, NotifyKind::Conflict, &body);
+                }
+                if let Some(body) = completed_message(&stats) {
+                    notify_task(&app, &db_path, &task_id, NotifyKind::Completed, &body);
+                }
+                health.record_success(&task_id, now_ms());
+                record_auth_success(&db_path, &account_key);
+                record_scan_finished(&db_path, &task_id, mode);
+                update_task_stats(&stats_map, &task_id, stats, start.elapsed());
+                true
+            }
+            Err(err) => {
+                health.record_failure(&task_id);
+                let detail = err.to_string();
+                log_sync_failure(&db_path, &task_id, &err);
+                if record_auth_failure(&app, &db_path, &task_id, &account_key, &err) {
+                    final_status = "NeedsLogin";
+                }
+                let (kind, body) = failure_notification(&err, &detail);
+                notify_task(&app, &db_path, &task_id, kind, body);
+                false
+            }
+        };
+        let outcome = RunOutcome {
+            at_ms: now_ms(),
+            success,
+        };
+        record_run_finished(&db_path, &task_id, outcome);
+        set_zero_rates(&stats_map, &task_id);
+        emit_task_runtime(&app, &stats_map, &task_id, final_status, Some(outcome));
+        if let Ok(mut one_shots) = one_shots.lock() {
+            one_shots.remove(&task_id);
+        }
+    });
+    Ok(true)
+}
+
+#[tauri::command]
+fn run_deep_scan_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    payload: SyncRequest,
+) -> Result<(), String> {
+    {
+        let runners = state
+            .runners
+            .lock()
+            .map_err(|_| "runner lock error".to_string())?;
+        if let Some(handle) = runners.get(&payload.task_id) {
+            handle.deep_requested.store(true, Ordering::SeqCst);
+            return Ok(());
+        }
+    }
+    spawn_one_shot(&app, &state, &payload.task_id, ScanMode::Deep).map(|_| ())
+}
+
+#[tauri::command]
+fn delete_task_command(
+    state: tauri::State<AppState>,
+    payload: DeleteTaskRequest,
+) -> Result<(), String> {
+    let (roots, encrypted, shared) = load_task_settings(&state.db_path, &payload.task_id)
+        .map(|(task, settings)| {
+            (
+                task_roots(&task),
+                settings.encryption.is_some(),
+                CloudreveClient::is_share_uri(&task.remote_root_uri),
+            )
+        })
+        .unwrap_or_default();
+    {
+        let mut runners = state
+            .runners
+            .lock()
+            .map_err(|_| "runner lock error".to_string())?;
+        if let Some(handle) = runners.remove(&payload.task_id) {
+            handle.stop.store(true, Ordering::SeqCst);
+        }
+    }
+    if let Ok(mut stats) = state.stats.lock() {
+        stats.remove(&payload.task_id);
+    }
+    state.health.remove(&payload.task_id);
+    if let Err(err) = backup_now(&state.db_path, "pre-delete") {
+        eprintln!("failed to backup before deleting task: {}", err);
+    }
+    let conn = state.db()?;
+    delete_task(&conn, &payload.task_id).map_err(|err| err.to_string())?;
+    let partial_dir = partial_download_dir(&state.db_path, &payload.task_id);
+    if partial_dir.exists() {
+        let _ = fs::remove_dir_all(partial_dir);
+    }
+    if encrypted {
+        let _ = clear_task_key(&payload.task_id);
+    }
+    if shared {
+        let _ = clear_share_password(&payload.task_id);
+    }
+    if placeholders::hydrates_on_open() {
+        for root in roots {
+            let _ = unregister_sync_root(Path::new(&root.local_root));
+        }
+    }
+    Ok(())
+}
+
+/// 返回启动时的数据库恢复报告，只返回一次。
+#[tauri::command]
+fn take_recovery_report_command(state: tauri::State<AppState>) -> Option<RecoveryReport> {
+    state
+        .recovery
+        .lock()
+        .ok()
+        .and_then(|mut report| report.take())
+}
+
+/// 注册登录后自动启动的无窗口实例（Linux 为 systemd 用户单元，Windows 为计划任务）。
+#[tauri::command]
+fn install_service_command() -> Result<(), String> {
+    let dir = config_dir().map_err(|err| err.to_string())?;
+    let spec = ServiceSpec {
+        exe_path: launcher_path().map_err(|err| err.to_string())?,
+        log_path: dir.join("logs").join("service.log"),
+    };
+    install_service(&spec, &dir).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn uninstall_service_command() -> Result<(), String> {
+    uninstall_service().map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn list_backups_command() -> Result<Vec<BackupInfo>, String> {
+    let root = backups_dir().map_err(|err| err.to_string())?;
+    list_backups(&root).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn create_backup_command(state: tauri::State<AppState>) -> Result<BackupInfo, String> {
+    backup_now(&state.db_path, "manual").map_err(|err| err.to_string())
+}
+
+/// 立即清理日志与删除记录并压缩数据库，返回清理数量与回收的空间。
+#[tauri::command]
+fn compact_database_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<CompactReport, String> {
+    let policy = AppSettings::load().unwrap_or_default().retention_policy();
+    // 压缩期间停下同步任务，结束后无论成败都重新启动原先在运行的任务。
+    let running = stop_runners_and_wait(&state)?;
+    let result = compact_database(&state.db_path, &policy, now_ms());
+    for task_id in &running {
+        if let Err(err) = start_sync_task(&app, &state, task_id, false) {
+            eprintln!("failed to restart task {}: {}", task_id, err);
+        }
+    }
+    let report = result.map_err(|err| err.to_string())?;
+    log_info(
+        &state.db_path,
+        "",
+        "maintenance",
+        &format!(
+            "数据库已压缩: 删除日志 {} 条、删除记录 {} 条，回收 {} 字节",
+            report.pruned.logs_deleted, report.pruned.tombstones_deleted, report.reclaimed_bytes
+        ),
+    );
+    Ok(report)
+}
+
+/// 后台按保留规则清理，不执行 VACUUM；释放的页会被后续写入复用。
+fn run_scheduled_cleanup(db_path: &Path) {
+    let policy = AppSettings::load().unwrap_or_default().retention_policy();
+    let result = connect(db_path)
+        .map_err(|err| err.into())
+        .and_then(|conn| prune(&conn, &policy, now_ms()));
+    if let Err(err) = result {
+        eprintln!("scheduled cleanup failed: {}", err);
+    }
+}
+
+#[tauri::command]
+fn restore_backup_command(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    payload: RestoreBackupRequest,
+) -> Result<(), String> {
+    let running = stop_runners_and_wait(&state)?;
+    if let Ok(mut stats) = state.stats.lock() {
+        stats.clear();
+    }
+    let root = backups_dir().map_err(|err| err.to_string())?;
+    let settings = settings_path().map_err(|err| err.to_string())?;
+    backup_now(&state.db_path, "pre-restore").map_err(|err| err.to_string())?;
+    restore_backup(&root, &payload.name, &state.db_path, &settings)
+        .map_err(|err| err.to_string())?;
+
+    let conn = state.db()?;
+    init_db(&conn).map_err(|err| err.to_string())?;
+    // 只重新启动恢复前在运行、且备份中仍然存在的任务。
+    let tasks = list_tasks(&conn).map_err(|err| err.to_string())?;
+    for task in tasks.iter().filter(|task| running.contains(&task.task_id)) {
+        if let Err(err) = start_sync_task(&app, &state, &task.task_id, false) {
+            eprintln!("failed to restart task {}: {}", task.task_id, err);
+        }
+    }
+    Ok(())
+}
+
+/// 停止所有常驻任务，等待其线程退出、单独运行的一轮结束，返回原先在运行的任务。
+fn stop_runners_and_wait(state: &AppState) -> Result<Vec<String>, String> {
+    let handles = state
+        .runners
+        .lock()
+        .map_err(|_| "runner lock error".to_string())?
+        .drain()
+        .collect::<Vec<_>>();
+    for (_, handle) in &handles {
+        handle.stop.store(true, Ordering::SeqCst);
+        handle.cancel.cancel();
+    }
+    let deadline = Instant::now() + RUNNER_STOP_TIMEOUT;
+    while !handles
+        .iter()
+        .all(|(_, handle)| handle.thread.is_finished())
+        || state.one_shots.lock().is_ok_and(|runs| !runs.is_empty())
+    {
+        if Instant::now() >= deadline {
+            return Err("仍有同步任务未停止，请稍后重试".to_string());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Ok(handles.into_iter().map(|(task_id, _)| task_id).collect())
+}
+
+fn backup_now(db_path: &Path, reason: &str) -> Result<BackupInfo, Box<dyn Error>> {
+    let keep = AppSettings::load().unwrap_or_default().backup_keep as usize;
+    create_backup(&backups_dir()?, db_path, &settings_path()?, reason, keep)
+}
+
+fn run_scheduled_backup(db_path: &Path) {
+    let interval_hours = AppSettings::load()
+        .unwrap_or_default()
+        .backup_interval_hours;
+    if interval_hours == 0 {
+        return;
+    }
+    let interval = Duration::from_secs(interval_hours * 60 * 60);
+    let due = backups_dir()
+        .ok()
+        .and_then(|root| latest_backup_age(&root))
+        .map(|age| age >= interval)
+        .unwrap_or(true);
+    if due {
+        if let Err(err) = backup_now(db_path, "scheduled") {
+            eprintln!("scheduled backup failed: {}", err);
+        }
+    }
+}
+
+#[tauri::command]
+fn bootstrap(state: tauri::State<AppState>) -> Result<BootstrapPayload, String> {
+    let conn = state.db()?;
+    let tasks = build_task_items(&state, &conn).map_err(|err| err.to_string())?;
+    let conflicts = list_conflicts(&conn, None).map_err(|err| err.to_string())?;
+    let logs = list_logs(&conn, None, None, None, None).map_err(|err| err.to_string())?;
+
+    let today = Local::now().date_naive();
+    let mut upload_count = 0;
+    let mut download_count = 0;
     for log in &logs {
         let dt = Local.timestamp_millis_opt(log.created_at_ms).single();
         if let Some(dt) = dt {
@@ -917,79 +3138,709 @@ fn bootstrap(state: tauri::State<AppState>) -> Result<BootstrapPayload, String>
         }
     }
 
-    let cards = vec![
-        DashboardCard {
-            label: "同步状态".to_string(),
-            value: if tasks.iter().any(|t| t.status == "Syncing") {
-                "运行中".to_string()
-            } else {
-                "已暂停".to_string()
-            },
-            tone: if tasks.iter().any(|t| t.status == "Syncing") {
-                "success".to_string()
-            } else {
-                "warn".to_string()
-            },
-        },
-        DashboardCard {
-            label: "今日上传".to_string(),
-            value: format!("{} 文件", upload_count),
-            tone: "info".to_string(),
-        },
-        DashboardCard {
-            label: "今日下载".to_string(),
-            value: format!("{} 文件", download_count),
-            tone: "info".to_string(),
-        },
-        DashboardCard {
-            label: "未处理冲突".to_string(),
-            value: conflicts.len().to_string(),
-            tone: if conflicts.is_empty() {
-                "info".to_string()
-            } else {
-                "danger".to_string()
-            },
-        },
-    ];
-
-    let activities = logs
-        .into_iter()
-        .map(|log| ActivityItem {
-            timestamp: format_time(log.created_at_ms),
-            event: log.event,
-            detail: log.detail,
-            level: log.level,
-        })
-        .collect();
+    let cards = vec![
+        DashboardCard {
+            label: "同步状态".to_string(),
+            value: if tasks.iter().any(|t| t.status == "Syncing") {
+                "运行中".to_string()
+            } else {
+                "已暂停".to_string()
+            },
+            tone: if tasks.iter().any(|t| t.status == "Syncing") {
+                "success".to_string()
+            } else {
+                "warn".to_string()
+            },
+        },
+        DashboardCard {
+            label: "今日上传".to_string(),
+            value: format!("{} 文件", upload_count),
+            tone: "info".to_string(),
+        },
+        DashboardCard {
+            label: "今日下载".to_string(),
+            value: format!("{} 文件", download_count),
+            tone: "info".to_string(),
+        },
+        DashboardCard {
+            label: "未处理冲突".to_string(),
+            value: conflicts.len().to_string(),
+            tone: if conflicts.is_empty() {
+                "info".to_string()
+            } else {
+                "danger".to_string()
+            },
+        },
+    ];
+
+    // 多文件批次在动态中只显示一行汇总，位置取批次中最新的一条日志。
+    let batches = list_log_batches(&conn)
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(|batch| (batch.batch_id.clone(), batch))
+        .collect::<HashMap<_, _>>();
+    let mut shown_batches = HashSet::new();
+    let activities = logs
+        .into_iter()
+        .filter_map(
+            |log| match log.batch_id.as_ref().and_then(|id| batches.get(id)) {
+                Some(batch) => shown_batches
+                    .insert(batch.batch_id.clone())
+                    .then(|| ActivityItem::from_batch(batch)),
+                None => Some(ActivityItem::from_log(log)),
+            },
+        )
+        .collect();
+
+    let conflict_items = list_conflicts_command(state, None)?;
+
+    Ok(BootstrapPayload {
+        cards,
+        tasks,
+        activities,
+        conflicts: conflict_items,
+    })
+}
+
+/// 一轮同步的暂停与取消开关，以及与本地监听共用的引擎写入记录。
+#[derive(Clone, Default)]
+struct RunControl {
+    pause: Arc<AtomicBool>,
+    cancel: CancellationToken,
+    local_writes: LocalWrites,
+}
+
+fn run_sync_once(
+    db_path: &PathBuf,
+    api_paths: &ApiPaths,
+    task_id: &str,
+    mode: ScanMode,
+    events: EventBus,
+    control: RunControl,
+    global_stats: &GlobalStats,
+) -> Result<SyncStats, SyncError> {
+    run_engine(
+        db_path,
+        api_paths,
+        task_id,
+        events,
+        control,
+        global_stats,
+        |engine| tauri::async_runtime::block_on(engine.sync_with_mode(mode)),
+    )
+}
+
+/// 只同步刚保存的优先文件，不扫描整个任务。
+fn run_sync_paths(
+    db_path: &PathBuf,
+    api_paths: &ApiPaths,
+    task_id: &str,
+    relpaths: &[String],
+    events: EventBus,
+    control: RunControl,
+    global_stats: &GlobalStats,
+) -> Result<SyncStats, SyncError> {
+    run_engine(
+        db_path,
+        api_paths,
+        task_id,
+        events,
+        control,
+        global_stats,
+        |engine| tauri::async_runtime::block_on(engine.sync_paths(relpaths)),
+    )
+}
+
+fn run_engine(
+    db_path: &PathBuf,
+    api_paths: &ApiPaths,
+    task_id: &str,
+    events: EventBus,
+    control: RunControl,
+    global_stats: &GlobalStats,
+    run: impl FnOnce(&SyncEngine) -> Result<SyncStats, SyncError>,
+) -> Result<SyncStats, SyncError> {
+    let (task, _) = load_task_settings(db_path, task_id)?;
+    let settings = run_settings(&task)?;
+    // 命令行守护进程可能正在同步同一任务；锁文件无法创建时照常同步，不因此停掉任务。
+    let _lock = match try_lock_task(db_path, task_id) {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) => return Err(SyncError::Other(Box::new(TaskLocked))),
+        Err(err) => {
+            eprintln!("failed to lock task {}: {}", task_id, err);
+            None
+        }
+    };
+    let global_for_progress = global_stats.clone();
+    events.subscribe(move |event| {
+        if let SyncEvent::Progress { task_id, stats } = event {
+            global_for_progress.record_progress(task_id, stats, now_ms());
+        }
+    });
+    let engine = build_engine(db_path, api_paths, task, &settings, events, control.pause)?
+        .with_cancel_token(control.cancel)
+        .with_local_writes(control.local_writes);
+    global_stats.begin_pass(task_id, &settings.account_key);
+    let result = run(&engine);
+    global_stats.end_pass(task_id);
+    result
+}
+
+fn log_event_bus(db_path: &Path) -> EventBus {
+    let events = EventBus::new();
+    events.subscribe_log_store(LogStore::new(db_path.to_path_buf()));
+    events
+}
+
+/// 常驻任务的事件总线：写日志、累计速率并推送到前端。
+fn runner_event_bus(
+    db_path: &Path,
+    app: &AppHandle,
+    stats_map: &Arc<Mutex<HashMap<String, TaskStats>>>,
+    health: &HealthRegistry,
+    started: Instant,
+) -> EventBus {
+    let events = log_event_bus(db_path);
+    let health = health.clone();
+    events.subscribe(move |event| match event {
+        SyncEvent::Progress { task_id, stats } => {
+            health.set_queue_depth(task_id, stats.operations);
+        }
+        SyncEvent::Log(entry) if matches!(entry.level, LogLevel::Error) => {
+            health.record_file_error(&entry.task_id);
+        }
+        _ => {}
+    });
+    let stats_for_progress = stats_map.clone();
+    events.subscribe(move |event| {
+        if let SyncEvent::Progress { task_id, stats } = event {
+            update_task_stats(
+                &stats_for_progress,
+                task_id,
+                stats.clone(),
+                started.elapsed(),
+            );
+        }
+    });
+    let app = app.clone();
+    let stats_for_emit = stats_map.clone();
+    events.subscribe(move |event| match event {
+        SyncEvent::Progress { task_id, .. } => {
+            emit_task_runtime(&app, &stats_for_emit, task_id, "Syncing", None);
+        }
+        SyncEvent::Status { task_id, status } => {
+            emit_task_runtime(&app, &stats_for_emit, task_id, status, None);
+        }
+        SyncEvent::Transfer(progress) => {
+            let _ = app.emit(TRANSFER_PROGRESS_EVENT, progress);
+        }
+        SyncEvent::Log(_) => {}
+    });
+    events
+}
+
+/// 只监听回环地址，供本机的监控程序抓取；端口修改后重启应用生效。
+/// 创建本地接口令牌后需携带状态或完全控制权限的令牌才能读取。
+fn start_metrics_server(health: &HealthRegistry, db_path: &Path) {
+    let port = AppSettings::load().unwrap_or_default().metrics_port;
+    if port == 0 {
+        return;
+    }
+    let db_path = db_path.to_path_buf();
+    let authorize: Authorizer = Arc::new(move |token| {
+        connect(&db_path)
+            .ok()
+            .and_then(|conn| authorize_api_token(&conn, token, ApiScope::Status).ok())
+            .unwrap_or(false)
+    });
+    match std::net::TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => {
+            serve_metrics(listener, health.clone(), now_ms, authorize);
+        }
+        Err(err) => eprintln!("failed to start metrics server on port {}: {}", port, err),
+    }
+}
+
+/// 程序关闭期间的本地修改：索引中的文件缺失或大小变化时记录摘要，并让下一轮改为深度扫描。
+fn run_startup_check(db_path: &PathBuf, task: &TaskRow) {
+    let Ok(conn) = connect(db_path) else {
+        return;
+    };
+    match check_entries(&conn, task) {
+        Ok(report) => {
+            if !report.is_consistent() {
+                let _ = request_deep_scan(&conn, &task.task_id);
+            }
+            log_info(db_path, &task.task_id, "startup_check", &report.summary());
+        }
+        Err(err) => log_error(db_path, &task.task_id, &format!("启动检查失败: {}", err)),
+    }
+}
+
+fn next_scan_mode(db_path: &PathBuf, task_id: &str, schedule: &ScanSchedule) -> ScanMode {
+    let state = connect(db_path)
+        .ok()
+        .and_then(|conn| get_scan_schedule(&conn, task_id).ok().flatten());
+    schedule.next_mode(state.as_ref(), now_ms())
+}
+
+fn load_next_due(db_path: &PathBuf, task_id: &str) -> Option<i64> {
+    connect(db_path)
+        .ok()
+        .and_then(|conn| get_next_due(&conn, task_id).ok().flatten())
+}
+
+fn store_next_due(db_path: &PathBuf, task_id: &str, plan: &RunPlan) -> i64 {
+    let next_due = plan.next_run_ms(now_ms());
+    if let Ok(conn) = connect(db_path) {
+        let _ = set_next_due(&conn, task_id, next_due);
+    }
+    next_due
+}
+
+/// 按设置发送系统通知，正文带上任务的本地目录以便区分任务。
+fn notify_task(app: &AppHandle, db_path: &PathBuf, task_id: &str, kind: NotifyKind, body: &str) {
+    let settings = AppSettings::load().unwrap_or_default();
+    if !should_notify(&settings, kind, Local::now().hour()) {
+        return;
+    }
+    let root = load_task_settings(db_path, task_id)
+        .map(|(task, _)| task.local_root)
+        .unwrap_or_else(|_| task_id.to_string());
+    let shown = app
+        .notification()
+        .builder()
+        .title(kind.title())
+        .body(format!("{}\n{}", root, body))
+        .show();
+    if let Err(err) = shown {
+        log_error(db_path, task_id, &format!("发送系统通知失败: {}", err));
+    }
+}
+
+/// 登录失效单独归类，提醒用户重新登录而不是等待重试。
+fn failure_notification<'a>(err: &SyncError, detail: &'a str) -> (NotifyKind, &'a str) {
+    match err {
+        SyncError::Auth(_) => (NotifyKind::Auth, "请在账户页面重新登录"),
+        SyncError::Quota(_) => (NotifyKind::Error, "云端空间已满，请清理文件或扩容后重试"),
+        _ => (NotifyKind::Error, detail),
+    }
+}
+
+/// 刷新令牌也失效时标记账户需要重新登录：使用该账户的任务暂停，界面提示重新登录。
+/// 返回失败是否因为登录失效。
+fn record_auth_failure(
+    app: &AppHandle,
+    db_path: &PathBuf,
+    task_id: &str,
+    account_key: &str,
+    err: &SyncError,
+) -> bool {
+    let SyncError::Auth(code) = err else {
+        return false;
+    };
+    let Ok(conn) = connect(db_path) else {
+        return true;
+    };
+    let reason = code.to_string();
+    if let Ok(true) = mark_account_needs_login(&conn, account_key, &reason, now_ms()) {
+        log_warn(
+            db_path,
+            task_id,
+            "account",
+            "账户登录已失效，使用该账户的任务已暂停，重新登录后自动恢复",
+        );
+        let _ = app.emit(
+            ACCOUNT_NEEDS_LOGIN_EVENT,
+            AccountNeedsLoginPayload {
+                account_key: account_key.to_string(),
+                reason,
+            },
+        );
+    }
+    true
+}
+
+/// 定期为每个账户换新访问令牌，空闲很久后的第一次请求不必先碰到令牌过期。
+/// 已标记需要重新登录的账户跳过，刷新失败留给请求时的内联刷新处理。
+fn refresh_tokens_once(db_path: &PathBuf, api_paths: &ApiPaths) -> Result<(), Box<dyn Error>> {
+    let accounts = list_accounts(&*connect(db_path)?)?;
+    for account in accounts {
+        if account_needs_login(db_path, &account.account_key) {
+            continue;
+        }
+        let Ok(tokens) = load_tokens(&account.account_key) else {
+            continue;
+        };
+        if tokens.refresh_token.is_empty() {
+            continue;
+        }
+        let mut client = CloudreveClient::new(
+            account.base_url,
+            Some(tokens.access_token),
+            api_paths.clone(),
+        );
+        client.set_token_refresh(Some(token_refresh(&account.account_key)));
+        let _ = tauri::async_runtime::block_on(client.renew_tokens());
+    }
+    Ok(())
+}
+
+fn record_auth_success(db_path: &PathBuf, account_key: &str) {
+    if let Ok(conn) = connect(db_path) {
+        let _ = record_account_auth(&conn, account_key, now_ms());
+    }
+}
+
+fn account_needs_login(db_path: &PathBuf, account_key: &str) -> bool {
+    connect(db_path)
+        .ok()
+        .and_then(|conn| get_account_status(&conn, account_key).ok().flatten())
+        .is_some_and(|status| status.needs_login)
+}
+
+/// 登录成功后记录鉴权时间；此前登录已失效时唤醒使用该账户的常驻任务。
+fn resume_account_tasks(state: &AppState, account_key: &str) -> Result<(), String> {
+    let conn = state.db()?;
+    let expired = get_account_status(&conn, account_key)
+        .map_err(|err| err.to_string())?
+        .is_some_and(|status| status.needs_login);
+    record_account_auth(&conn, account_key, now_ms()).map_err(|err| err.to_string())?;
+    if !expired {
+        return Ok(());
+    }
+    let tasks = list_tasks(&conn).map_err(|err| err.to_string())?;
+    let runners = state
+        .runners
+        .lock()
+        .map_err(|_| "runner lock error".to_string())?;
+    for task in tasks {
+        if parse_settings(&task.settings_json).account_key != account_key {
+            continue;
+        }
+        if let Some(handle) = runners.get(&task.task_id) {
+            handle.run_now.store(true, Ordering::SeqCst);
+        }
+        log_info(
+            &state.db_path,
+            &task.task_id,
+            "account",
+            "账户已重新登录，任务恢复同步",
+        );
+    }
+    Ok(())
+}
+
+fn record_scan_finished(db_path: &PathBuf, task_id: &str, mode: ScanMode) {
+    if let Ok(conn) = connect(db_path) {
+        let _ = record_scan(&conn, task_id, mode == ScanMode::Deep, now_ms());
+    }
+}
+
+fn record_run_finished(db_path: &PathBuf, task_id: &str, outcome: RunOutcome) {
+    if let Ok(conn) = connect(db_path) {
+        let _ = record_task_run(&conn, task_id, outcome.success, outcome.at_ms);
+    }
+}
+
+fn record_run_paused(db_path: &PathBuf, task_id: &str) {
+    if let Ok(conn) = connect(db_path) {
+        let _ = record_task_attempt(&conn, task_id, now_ms());
+    }
+}
+
+fn wait_next_cycle(stop: &AtomicBool, wake: &[&AtomicBool], interval_secs: u64) {
+    let deadline = Instant::now() + Duration::from_secs(interval_secs);
+    while Instant::now() < deadline {
+        if stop.load(Ordering::SeqCst) || wake.iter().any(|flag| flag.load(Ordering::SeqCst)) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+/// 监听线程记下的已保存优先文件，置位 `pending` 唤醒同步线程。
+#[derive(Clone, Default)]
+struct SavedFiles {
+    relpaths: Arc<Mutex<BTreeSet<String>>>,
+    pending: Arc<AtomicBool>,
+}
+
+impl SavedFiles {
+    fn push(&self, relpaths: Vec<String>) {
+        if let Ok(mut saved) = self.relpaths.lock() {
+            saved.extend(relpaths);
+        }
+        self.pending.store(true, Ordering::SeqCst);
+    }
+
+    fn take(&self) -> Vec<String> {
+        if !self.pending.swap(false, Ordering::SeqCst) {
+            return Vec::new();
+        }
+        self.relpaths
+            .lock()
+            .map(|mut saved| std::mem::take(&mut *saved).into_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// 按设置监听任务的本地根目录，有变更时置位 `changed` 唤醒同步线程；监听失败时只依赖定时扫描。
+/// 匹配优先规则的文件另记入 `saved`，同步线程先单独同步这些文件，不必等待整轮同步。
+/// 同步引擎自己的写入（登记在 `local_writes` 中）与暂存文件不算作变更。
+fn start_local_watcher(
+    db_path: &PathBuf,
+    task: &TaskRow,
+    priority_patterns: &[String],
+    changed: &Arc<AtomicBool>,
+    saved: &SavedFiles,
+    local_writes: &LocalWrites,
+) -> Option<LocalWatcher> {
+    if !AppSettings::load()
+        .map(|settings| settings.watch_local)
+        .unwrap_or(true)
+    {
+        return None;
+    }
+    let task_roots = task_roots(task);
+    // 本地目录包含应用数据目录时，数据库的写入不能反复触发同步。
+    let protected = protected_paths(&task_roots, db_path).unwrap_or_default();
+    let roots = task_roots
+        .iter()
+        .map(|root| PathBuf::from(&root.local_root))
+        .filter(|root| root.is_dir())
+        .collect::<Vec<_>>();
+    let changed = changed.clone();
+    let saved = saved.clone();
+    let priority_patterns = priority_patterns.to_vec();
+    let watch_db_path = db_path.clone();
+    let watch_task_id = task.task_id.clone();
+    let local_writes = local_writes.clone();
+    let ignore =
+        move |path: &Path, now: Instant| is_engine_scratch(path) || local_writes.is_own(path, now);
+    match watch_roots(&roots, WATCH_QUIET, WATCH_MAX_WAIT, ignore, move |paths| {
+        let paths = paths
+            .into_iter()
+            .filter(|path| !protected.iter().any(|guarded| path.starts_with(guarded)))
+            .collect::<Vec<_>>();
+        if let Ok(conn) = connect(&watch_db_path) {
+            let _ = mark_dirs_changed(
+                &conn,
+                &watch_task_id,
+                &changed_dir_relpaths(&task_roots, &paths),
+            );
+        }
+        let priority =
+            priority_relpaths(&task_roots, &priority_patterns, &paths).unwrap_or_default();
+        // 同时有其他路径变化（包括编辑器的临时文件）时仍需要完整的一轮。
+        if priority.len() < paths.len() {
+            changed.store(true, Ordering::SeqCst);
+        }
+        if !priority.is_empty() {
+            saved.push(priority);
+        }
+    }) {
+        Ok(watcher) => Some(watcher),
+        Err(err) => {
+            log_info(
+                db_path,
+                &task.task_id,
+                "watch",
+                &format!("无法监听本地目录，改为定时扫描: {}", err),
+            );
+            None
+        }
+    }
+}
+
+/// 按需下载的任务把各本地根目录注册为同步根目录并连接，系统请求占位文件内容时下载远端文件。
+/// 占位文件不能在打开时下载的平台无需连接，由用户在应用中固定需要的文件。
+fn connect_placeholders(
+    db_path: &PathBuf,
+    api_paths: &ApiPaths,
+    task: &TaskRow,
+    settings: &TaskSettings,
+) -> Vec<SyncRootConnection> {
+    if !settings.on_demand {
+        return Vec::new();
+    }
+    if !placeholders::supported() {
+        log_info(
+            db_path,
+            &task.task_id,
+            "placeholder",
+            "当前平台不支持按需下载，远端文件将完整下载",
+        );
+        return Vec::new();
+    }
+    if !placeholders::hydrates_on_open() {
+        log_info(
+            db_path,
+            &task.task_id,
+            "placeholder",
+            "占位文件需要先在应用中选择“始终保留在此设备上”才能打开",
+        );
+        return Vec::new();
+    }
+    let fetcher = placeholder_fetcher(db_path, api_paths, task, &settings.account_key);
+    let mut connections = Vec::new();
+    for root in task_roots(task) {
+        let path = PathBuf::from(&root.local_root);
+        let connected = register_sync_root(&path, &task.task_id)
+            .and_then(|_| placeholders::connect(&path, fetcher.clone()));
+        match connected {
+            Ok(connection) => connections.push(connection),
+            Err(err) => log_error(
+                db_path,
+                &task.task_id,
+                &format!("无法启用按需下载: {} ({})", root.local_root, err),
+            ),
+        }
+    }
+    connections
+}
+
+/// 把占位文件对应的远端文件下载到指定路径，下载结果写入任务日志。
+fn placeholder_fetcher(
+    db_path: &Path,
+    api_paths: &ApiPaths,
+    task: &TaskRow,
+    account_key: &str,
+) -> placeholders::Fetcher {
+    let db_path = db_path.to_path_buf();
+    let api_paths = api_paths.clone();
+    let base_url = task.base_url.clone();
+    let task_id = task.task_id.clone();
+    let account_key = account_key.to_string();
+    Arc::new(move |identity: &PlaceholderIdentity, target: &Path| {
+        let access_token = load_tokens(&account_key)
+            .ok()
+            .map(|tokens| tokens.access_token);
+        let mut client = CloudreveClient::new(base_url.clone(), access_token, api_paths.clone());
+        client.set_token_refresh(Some(token_refresh(&account_key)));
+        let mut file = fs::File::create(target).map_err(|err| err.to_string())?;
+        let uri = with_saved_share_password(&task_id, &identity.uri);
+        let result =
+            tauri::async_runtime::block_on(client.download_to_file(&uri, &mut file, 0, |_, _| {}));
+        match result {
+            Ok(size) => {
+                log_info(
+                    &db_path,
+                    &task_id,
+                    "placeholder",
+                    &format!("按需下载: {} ({} 字节)", identity.uri, size),
+                );
+                Ok(())
+            }
+            Err(err) => {
+                let detail = format!("按需下载失败: {} ({})", identity.uri, err);
+                log_error(&db_path, &task_id, &detail);
+                Err(detail)
+            }
+        }
+    })
+}
 
-    let conflict_items = list_conflicts_command(state, None)?;
+/// 持有期间保持远端变更订阅，丢弃后断开。
+struct RemoteSubscriber {
+    handle: tauri::async_runtime::JoinHandle<()>,
+}
 
-    Ok(BootstrapPayload {
-        cards,
-        tasks,
-        activities,
-        conflicts: conflict_items,
-    })
+impl Drop for RemoteSubscriber {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
 }
 
-fn run_sync_once(
-    db_path: &PathBuf,
+/// 订阅任务远端目录的变更推送，收到变更后置位 `changed` 立即触发同步；连接断开后自动重连，
+/// 连续失败时重连间隔逐次翻倍，相同的错误只记录一次。
+/// 服务器不支持推送时记录一次日志后停止订阅，任务继续按间隔轮询。仅上传的任务不关心远端变化。
+fn start_remote_subscriber(
+    db_path: &Path,
     api_paths: &ApiPaths,
-    task_id: &str,
-    progress_notifier: Option<Arc<dyn Fn(SyncStats) + Send + Sync>>,
-    status_notifier: Option<Arc<dyn Fn(String) + Send + Sync>>,
-) -> Result<SyncStats, Box<dyn Error>> {
-    let (task, settings) = load_task_settings(db_path, task_id)?;
-    let tokens = load_tokens(&settings.account_key)?;
-    let engine = SyncEngine::new(
-        task,
-        api_paths.clone(),
-        Some(tokens.access_token),
-        db_path.clone(),
-        progress_notifier,
-        status_notifier,
-    );
-    tauri::async_runtime::block_on(engine.sync_once())
+    task: &TaskRow,
+    account_key: &str,
+    changed: &Arc<AtomicBool>,
+) -> Option<RemoteSubscriber> {
+    let writes_local = SyncDirection::from_mode(&task.mode)
+        .unwrap_or_default()
+        .writes_local();
+    if !writes_local
+        || !AppSettings::load()
+            .map(|settings| settings.watch_remote)
+            .unwrap_or(true)
+    {
+        return None;
+    }
+    let db_path = db_path.to_path_buf();
+    let api_paths = api_paths.clone();
+    let task = task.clone();
+    let account_key = account_key.to_string();
+    let changed = changed.clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut delay = PUSH_RECONNECT_SECS;
+        let mut last_error = None;
+        loop {
+            // 每次连接前重新读取令牌，断线期间令牌可能已刷新。
+            let access_token = load_tokens(&account_key)
+                .ok()
+                .map(|tokens| tokens.access_token);
+            let mut client =
+                CloudreveClient::new(task.base_url.clone(), access_token, api_paths.clone());
+            client.set_token_refresh(Some(token_refresh(&account_key)));
+            let flag = changed.clone();
+            let remote_root = with_saved_share_password(&task.task_id, &task.remote_root_uri);
+            // 错误不能跨越之后的等待，先转换成是否支持推送与错误描述。
+            let started = Instant::now();
+            let result = client
+                .subscribe_changes(&remote_root, move |_| {
+                    flag.store(true, Ordering::SeqCst);
+                })
+                .await
+                .map_err(|err| {
+                    (
+                        err.downcast_ref::<PushUnsupported>().is_some(),
+                        err.to_string(),
+                    )
+                });
+            match result {
+                Err((true, err)) => {
+                    log_info(
+                        &db_path,
+                        &task.task_id,
+                        "push",
+                        &format!("{}，继续定时轮询", err),
+                    );
+                    return;
+                }
+                Err((false, err)) => {
+                    // 连接保持过一段时间才断开的，视为已恢复，重新从最短间隔开始。
+                    if started.elapsed() >= Duration::from_secs(PUSH_RECONNECT_SECS) {
+                        delay = PUSH_RECONNECT_SECS;
+                        last_error = None;
+                    }
+                    if last_error.as_ref() != Some(&err) {
+                        log_info(
+                            &db_path,
+                            &task.task_id,
+                            "push",
+                            &format!("变更推送连接断开，{} 秒后重连: {}", delay, err),
+                        );
+                        last_error = Some(err);
+                    }
+                }
+                Ok(()) => {
+                    delay = PUSH_RECONNECT_SECS;
+                    last_error = None;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(delay)).await;
+            if last_error.is_some() {
+                delay = (delay * 2).min(PUSH_RECONNECT_MAX_SECS);
+            }
+        }
+    });
+    Some(RemoteSubscriber { handle })
 }
 
 fn update_task_stats(
@@ -1029,7 +3880,7 @@ fn emit_task_runtime(
     stats_map: &Arc<Mutex<HashMap<String, TaskStats>>>,
     task_id: &str,
     status: &str,
-    last_sync_ms: Option<i64>,
+    outcome: Option<RunOutcome>,
 ) {
     let stats = stats_map
         .lock()
@@ -1047,9 +3898,14 @@ fn emit_task_runtime(
         rate_up: stats.rate_up,
         rate_down: stats.rate_down,
         queue: stats.queue,
-        last_sync: last_sync_ms
-            .map(format_time)
-            .unwrap_or_else(|| "--".to_string()),
+        // 空字符串表示时间未变化，前端保留原值。
+        last_sync: outcome
+            .filter(|outcome| outcome.success)
+            .map(|outcome| format_time(outcome.at_ms))
+            .unwrap_or_default(),
+        last_attempt: outcome
+            .map(|outcome| format_time(outcome.at_ms))
+            .unwrap_or_default(),
     };
     let _ = app.emit(TASK_RUNTIME_EVENT, payload);
 }
@@ -1058,22 +3914,163 @@ fn progress_text_for_status(status: &str) -> String {
     match status {
         "Hashing" => "正在计算本地文件 SHA256...".to_string(),
         "ListingRemote" => "正在拉取远程目录...".to_string(),
+        "Verifying" => "正在校验远端元数据...".to_string(),
+        "Paused" => "当前网络配置已暂停同步".to_string(),
+        "Quiet" => "静默时段，结束后继续同步".to_string(),
+        "OutsideWindow" => "不在允许同步的时间窗口内".to_string(),
+        "PausedMetered" => "按流量计费的网络，同步已暂停".to_string(),
+        "PausedBattery" => "正在使用电池供电，同步已暂停".to_string(),
+        "PausedInterface" => "已连接指定的网络接口，同步已暂停".to_string(),
+        "UserPaused" => "已暂停，恢复后从中断处继续".to_string(),
+        "RootReadOnly" => "本地目录只读，同步已停止".to_string(),
+        "LockedElsewhere" => "另一个进程正在同步此任务，稍后再试".to_string(),
+        "NeedsAttention" => "冲突副本过多，请到冲突中心处理".to_string(),
+        "NeedsLogin" => "账户登录已失效，请重新登录".to_string(),
         "Syncing" => "正在同步文件...".to_string(),
         "Idle" => "空闲".to_string(),
+        "Disabled" => "任务已停用".to_string(),
         "Error" => "同步异常，请查看日志".to_string(),
         _ => "处理中...".to_string(),
     }
 }
 
+/// 无窗口启动时按声明式配置文件对账账户与任务；未指定路径且默认文件不存在时不做任何事。
+fn apply_provision_file(db_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let path = match config_path_from_args(&args) {
+        Some(path) => path,
+        None => {
+            let path = config_dir()?.join(DEFAULT_CONFIG_NAME);
+            if !path.exists() {
+                return Ok(());
+            }
+            path
+        }
+    };
+    let file = load_provision(&path)?;
+    for account in &file.accounts {
+        if let Some((access_token, refresh_token)) = account.env_tokens()? {
+            inject_tokens(&account.account_key(), &access_token, &refresh_token);
+        }
+    }
+    let conn = connect(db_path)?;
+    init_db(&conn)?;
+    let report = reconcile(&conn, &file, now_ms(), |settings_json| {
+        serde_json::from_str::<TaskSettings>(settings_json)
+            .map(|_| ())
+            .map_err(|err| format!("任务设置格式错误: {}", err))
+    })?;
+    for task_id in &report.created {
+        log_info(db_path, task_id, "provision", "已按配置文件创建任务");
+    }
+    for (task_id, keys) in &report.drifted {
+        log_info(
+            db_path,
+            task_id,
+            "provision",
+            &format!(
+                "设置与配置文件不一致，已按配置文件更新: {}",
+                keys.join(", ")
+            ),
+        );
+    }
+    for (task_id, fields) in &report.mismatched {
+        let detail = format!(
+            "以下字段与配置文件不同，需要删除任务后重新创建: {}",
+            fields.join(", ")
+        );
+        eprintln!("{}: {}", task_id, detail);
+        log_error(db_path, task_id, &detail);
+    }
+    if !report.unmanaged.is_empty() {
+        log_info(
+            db_path,
+            "",
+            "provision",
+            &format!(
+                "配置文件未声明的任务保持不变: {}",
+                report.unmanaged.join(", ")
+            ),
+        );
+    }
+    for err in &report.errors {
+        eprintln!("配置文件中的任务无法应用: {}", err);
+        log_error(db_path, "", &format!("配置文件中的任务无法应用: {}", err));
+    }
+    Ok(())
+}
+
+/// 从旧版单目录客户端升级时导入它的配置与同步状态；导入后旧文件被归档，之后的启动不再处理。
+fn import_legacy_files(db_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let conn = connect(db_path)?;
+    let Some(report) = import_legacy(&conn, &config_dir()?, now_ms(), |settings_json| {
+        serde_json::from_str::<TaskSettings>(settings_json)
+            .map(|_| ())
+            .map_err(|err| format!("任务设置格式错误: {}", err))
+    })?
+    else {
+        return Ok(());
+    };
+    if let Some((access_token, refresh_token)) = &report.tokens {
+        store_tokens(&report.account_key, access_token, refresh_token)?;
+    }
+    let task_id = report.task_id.as_deref().unwrap_or("");
+    let mut detail = match &report.task_id {
+        Some(_) => format!(
+            "已导入旧版客户端的配置与 {} 条同步记录，旧文件已移至 {}",
+            report.entries,
+            report.archive_dir.display()
+        ),
+        None => format!(
+            "旧版客户端的配置没有本地目录，未创建任务，旧文件已移至 {}",
+            report.archive_dir.display()
+        ),
+    };
+    if report.tokens.is_none() {
+        detail.push_str("；请重新登录账户");
+    }
+    log_info(db_path, task_id, "migration", &detail);
+    Ok(())
+}
+
 fn log_error(db_path: &PathBuf, task_id: &str, detail: &str) {
-    if let Ok(conn) = Connection::open(db_path) {
+    if let Ok(conn) = connect(db_path) {
         let _ = conn.execute(
             "INSERT INTO logs (task_id, level, event, detail, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
             (
                 task_id.to_string(),
                 "error",
                 "sync",
-                detail.to_string(),
+                CloudreveClient::redact_share_passwords(detail),
+                now_ms(),
+            ),
+        );
+    }
+}
+
+/// 可重试的失败（网络中断、锁冲突等）记为警告，下一轮会自动重试。
+fn log_sync_failure(db_path: &PathBuf, task_id: &str, err: &SyncError) {
+    if !err.is_retryable() {
+        log_error(db_path, task_id, &err.to_string());
+        return;
+    }
+    log_warn(
+        db_path,
+        task_id,
+        "sync",
+        &format!("{}，将在下一轮重试", err),
+    );
+}
+
+fn log_warn(db_path: &PathBuf, task_id: &str, event: &str, detail: &str) {
+    if let Ok(conn) = connect(db_path) {
+        let _ = conn.execute(
+            "INSERT INTO logs (task_id, level, event, detail, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                task_id.to_string(),
+                "warn",
+                event.to_string(),
+                CloudreveClient::redact_share_passwords(detail),
                 now_ms(),
             ),
         );
@@ -1081,14 +4078,14 @@ fn log_error(db_path: &PathBuf, task_id: &str, detail: &str) {
 }
 
 fn log_info(db_path: &PathBuf, task_id: &str, event: &str, detail: &str) {
-    if let Ok(conn) = Connection::open(db_path) {
+    if let Ok(conn) = connect(db_path) {
         let _ = conn.execute(
             "INSERT INTO logs (task_id, level, event, detail, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
             (
                 task_id.to_string(),
                 "info",
                 event.to_string(),
-                detail.to_string(),
+                CloudreveClient::redact_share_passwords(detail),
                 now_ms(),
             ),
         );
@@ -1100,6 +4097,36 @@ fn parse_settings(raw: &str) -> TaskSettings {
         name: "未命名任务".to_string(),
         account_key: "".to_string(),
         sync_interval_secs: 60,
+        deep_scan_interval_secs: DEFAULT_DEEP_SCAN_INTERVAL_SECS,
+        local_roots: Vec::new(),
+        first_sync_policy: FirstSyncPolicy::default(),
+        catch_up_missed_runs: default_catch_up_missed_runs(),
+        sync_cron: String::new(),
+        sync_windows: Vec::new(),
+        audit_when_read_only: false,
+        scan_limits: ScanLimits::default(),
+        remote_delta: false,
+        custom_metadata: BTreeMap::new(),
+        annotate_conflicts: false,
+        conflict_retention_days: default_conflict_retention_days(),
+        max_conflict_copies: default_max_conflict_copies(),
+        local_trash_days: 0,
+        include_patterns: Vec::new(),
+        exclude_patterns: Vec::new(),
+        transfer_order: TransferOrder::default(),
+        skip_download_types: Vec::new(),
+        priority_patterns: Vec::new(),
+        preserve_dir_times: false,
+        preserve_permissions: false,
+        xattrs: XattrStorage::default(),
+        on_demand: false,
+        verify_writes: false,
+        skip_uploads_over_quota: false,
+        dedupe_uploads: default_dedupe_uploads(),
+        compression: CompressionSettings::default(),
+        symlinks: SymlinkPolicy::default(),
+        encryption: None,
+        enabled: default_task_enabled(),
     })
 }
 
@@ -1107,7 +4134,7 @@ fn load_task_settings(
     db_path: &PathBuf,
     task_id: &str,
 ) -> Result<(TaskRow, TaskSettings), Box<dyn Error>> {
-    let conn = Connection::open(db_path)?;
+    let conn = connect(db_path)?;
     let tasks = list_tasks(&conn)?;
     let task = tasks
         .into_iter()
@@ -1117,11 +4144,6 @@ fn load_task_settings(
     Ok((task, settings))
 }
 
-fn latest_log_time(conn: &Connection, task_id: &str) -> Option<i64> {
-    let logs = list_logs(conn, Some(task_id), None, None, None).ok()?;
-    logs.first().map(|log| log.created_at_ms)
-}
-
 fn format_time(timestamp_ms: i64) -> String {
     let dt = Local.timestamp_millis_opt(timestamp_ms).single();
     dt.map(|t| t.format("%Y-%m-%d %H:%M").to_string())
@@ -1144,11 +4166,15 @@ fn file_name(path: &str) -> String {
 }
 
 fn format_rate(bytes_per_sec: f64) -> String {
-    if bytes_per_sec <= 0.0 {
-        return "0 B/s".to_string();
+    format!("{}/s", format_size(bytes_per_sec))
+}
+
+fn format_size(bytes: f64) -> String {
+    if bytes <= 0.0 {
+        return "0 B".to_string();
     }
-    let units = ["B/s", "KB/s", "MB/s", "GB/s"];
-    let mut value = bytes_per_sec;
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes;
     let mut idx = 0;
     while value >= 1024.0 && idx < units.len() - 1 {
         value /= 1024.0;
@@ -1169,18 +4195,45 @@ fn is_running(state: &AppState, task_id: &str) -> bool {
         .unwrap_or(false)
 }
 
+fn is_paused(state: &AppState, task_id: &str) -> bool {
+    state
+        .runners
+        .lock()
+        .map(|r| {
+            r.get(task_id)
+                .is_some_and(|handle| handle.paused.load(Ordering::SeqCst))
+        })
+        .unwrap_or(false)
+}
+
 fn build_task_items(state: &AppState, conn: &Connection) -> Result<Vec<TaskItem>, Box<dyn Error>> {
     let tasks = list_tasks(conn)?;
     let stats_map = state.stats.lock().map_err(|_| "stats lock error")?;
     let mut output = Vec::new();
     for task in tasks {
         let settings = parse_settings(&task.settings_json);
-        let status = if is_running(state, &task.task_id) {
+        let paused = is_paused(state, &task.task_id);
+        let status = if !settings.enabled {
+            "Disabled".to_string()
+        } else if paused {
+            "UserPaused".to_string()
+        } else if is_running(state, &task.task_id) {
             "Syncing".to_string()
         } else {
             "Idle".to_string()
         };
-        let last_sync = latest_log_time(conn, &task.task_id)
+        let run = get_task_run(conn, &task.task_id)?;
+        let last_success_ms = run.as_ref().and_then(|run| run.last_success_ms);
+        let last_sync = last_success_ms
+            .map(format_time)
+            .unwrap_or_else(|| "--".to_string());
+        let last_attempt = run
+            .and_then(|run| run.last_attempt_ms)
+            .map(format_time)
+            .unwrap_or_else(|| "--".to_string());
+        let next_run = get_next_due(conn, &task.task_id)
+            .ok()
+            .flatten()
             .map(format_time)
             .unwrap_or_else(|| "--".to_string());
         let stats = stats_map.get(&task.task_id).cloned().unwrap_or(TaskStats {
@@ -1191,8 +4244,14 @@ fn build_task_items(state: &AppState, conn: &Connection) -> Result<Vec<TaskItem>
         output.push(TaskItem {
             id: task.task_id.clone(),
             name: settings.name,
+            base_url: server_key(&task.base_url),
+            direction: SyncDirection::from_mode(&task.mode).unwrap_or_default(),
             mode: task.mode.clone(),
-            local_path: task.local_root.clone(),
+            local_path: task_roots(&task)
+                .into_iter()
+                .map(|root| root.local_root)
+                .collect::<Vec<_>>()
+                .join("; "),
             remote_path: decode_uri(&task.remote_root_uri),
             progress_text: progress_text_for_status(&status),
             status,
@@ -1200,32 +4259,207 @@ fn build_task_items(state: &AppState, conn: &Connection) -> Result<Vec<TaskItem>
             rate_down: stats.rate_down,
             queue: stats.queue,
             last_sync,
+            last_attempt,
+            next_run,
+            sync_interval_secs: settings.sync_interval_secs,
+            sync_cron: settings.sync_cron.clone(),
+            sync_windows: settings.sync_windows.clone(),
+            stale: settings.enabled && is_stale(last_success_ms, now_ms()),
+            encrypted: settings.encryption.is_some(),
+            enabled: settings.enabled,
+            paused,
         });
     }
     Ok(output)
 }
 
-fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn Error>> {
+/// 按服务器汇总账户与任务；本次启动后还没运行过的任务取数据库中的上次成功时间。
+fn server_summaries(state: &AppState) -> Result<Vec<ServerSummary>, Box<dyn Error>> {
+    let conn = connect(&state.db_path)?;
+    let accounts = list_accounts(&conn)?;
+    let needs_login = list_account_statuses(&conn)?
+        .into_iter()
+        .filter(|status| status.needs_login)
+        .map(|status| status.account_key)
+        .collect::<BTreeSet<_>>();
+    let mut health = state.health.snapshot();
+    let mut tasks = Vec::new();
+    for task in list_tasks(&conn)? {
+        let entry = health.entry(task.task_id.clone()).or_default();
+        if entry.last_success_ms.is_none() {
+            entry.last_success_ms =
+                get_task_run(&conn, &task.task_id)?.and_then(|run| run.last_success_ms);
+        }
+        tasks.push(ServerTask {
+            running: is_running(state, &task.task_id),
+            enabled: parse_settings(&task.settings_json).enabled,
+            task_id: task.task_id,
+            base_url: task.base_url,
+        });
+    }
+    Ok(summarize_servers(
+        &accounts,
+        &needs_login,
+        &tasks,
+        &health,
+        now_ms(),
+    ))
+}
+
+#[tauri::command]
+fn get_server_summaries_command(
+    state: tauri::State<AppState>,
+) -> Result<Vec<ServerSummary>, String> {
+    server_summaries(&state).map_err(|err| err.to_string())
+}
+
+fn server_menu_label(server: &ServerSummary) -> String {
+    let host = server
+        .base_url
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(&server.base_url);
+    let mark = match server.status {
+        ServerStatus::NeedsLogin => "⚠",
+        ServerStatus::Failing => "✕",
+        ServerStatus::Stale => "!",
+        ServerStatus::Running | ServerStatus::Ok => "✓",
+        ServerStatus::Idle => "–",
+    };
+    match server.status {
+        ServerStatus::NeedsLogin => format!(
+            "{} {} · {} ({} 个账户)",
+            mark,
+            host,
+            server.status.label(),
+            server.needs_login
+        ),
+        ServerStatus::Failing => format!(
+            "{} {} · {} ({}/{} 个任务)",
+            mark,
+            host,
+            server.status.label(),
+            server.failing,
+            server.tasks
+        ),
+        ServerStatus::Stale => format!(
+            "{} {} · {} ({}/{} 个任务)",
+            mark,
+            host,
+            server.status.label(),
+            server.stale,
+            server.tasks
+        ),
+        _ => format!(
+            "{} {} · {} ({} 个任务)",
+            mark,
+            host,
+            server.status.label(),
+            server.tasks
+        ),
+    }
+}
+
+fn tray_tooltip(servers: &[ServerSummary]) -> String {
+    let needs_login = servers.iter().map(|server| server.needs_login).sum::<u32>();
+    if needs_login > 0 {
+        return format!("Cloudreve Sync：{} 个账户需要重新登录", needs_login);
+    }
+    let failing = servers
+        .iter()
+        .filter(|server| server.status == ServerStatus::Failing)
+        .count();
+    if failing > 0 {
+        format!("Cloudreve Sync：{} 个服务器同步失败", failing)
+    } else {
+        "Cloudreve Sync".to_string()
+    }
+}
+
+/// 托盘菜单：常用操作加一个按服务器列出状态的子菜单，点击服务器打开窗口并筛选其任务。
+fn tray_menu(app: &AppHandle, servers: &[ServerSummary]) -> tauri::Result<Menu<Wry>> {
     let show = MenuItem::with_id(app, "show", "显示窗口", true, None::<&str>)?;
     let hide = MenuItem::with_id(app, "hide", "隐藏窗口", true, None::<&str>)?;
     let sync = MenuItem::with_id(app, "sync", "立即同步", true, None::<&str>)?;
     let quit = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-    let menu = Menu::with_items(app, &[&show, &hide, &sync, &quit])?;
-    let _tray = TrayIconBuilder::new()
+    let mut server_items = servers
+        .iter()
+        .map(|server| {
+            MenuItem::with_id(
+                app,
+                format!("{}{}", TRAY_SERVER_PREFIX, server.base_url),
+                server_menu_label(server),
+                true,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    if server_items.is_empty() {
+        server_items.push(MenuItem::with_id(
+            app,
+            "servers-empty",
+            "尚未登录任何服务器",
+            false,
+            None::<&str>,
+        )?);
+    }
+    let server_refs = server_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<Wry>)
+        .collect::<Vec<_>>();
+    let servers_menu = Submenu::with_items(app, "服务器状态", true, &server_refs)?;
+    Menu::with_items(app, &[&show, &hide, &sync, &servers_menu, &quit])
+}
+
+/// 定期刷新托盘中的服务器状态，只在汇总结果变化时重建菜单。
+fn start_tray_refresher(app: &AppHandle, mut last: Vec<ServerSummary>) {
+    let app = app.clone();
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(TRAY_REFRESH_SECS));
+        let Ok(servers) = server_summaries(&app.state::<AppState>()) else {
+            continue;
+        };
+        if servers == last {
+            continue;
+        }
+        if let (Some(tray), Ok(menu)) = (app.tray_by_id(TRAY_ID), tray_menu(&app, &servers)) {
+            let _ = tray.set_menu(Some(menu));
+            let _ = tray.set_tooltip(Some(tray_tooltip(&servers)));
+        }
+        last = servers;
+    });
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn Error>> {
+    let servers = server_summaries(&app.state::<AppState>()).unwrap_or_default();
+    let menu = tray_menu(app, &servers)?;
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
         .icon(
             app.default_window_icon()
                 .ok_or("missing default window icon")?
                 .clone(),
         )
+        .tooltip(tray_tooltip(&servers))
         .menu(&menu)
         .show_menu_on_left_click(false)
         .on_menu_event(|app, event| match event.id.as_ref() {
-            "show" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.unminimize();
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
+            "show" => show_main_window(app),
+            id if id.starts_with(TRAY_SERVER_PREFIX) => {
+                show_main_window(app);
+                let _ = app.emit(
+                    SERVER_SELECTED_EVENT,
+                    ServerSelectedPayload {
+                        base_url: id[TRAY_SERVER_PREFIX.len()..].to_string(),
+                    },
+                );
             }
             "hide" => {
                 if let Some(window) = app.get_webview_window("main") {
@@ -1233,29 +4467,9 @@ fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn Error>> {
                 }
             }
             "sync" => {
-                let state = app.state::<AppState>();
-                let db_path = state.db_path.clone();
-                let api_paths = state.api_paths.clone();
-                let stats_map = state.stats.clone();
-                thread::spawn(move || {
-                    if let Ok(conn) = Connection::open(&db_path) {
-                        if let Ok(tasks) = list_tasks(&conn) {
-                            for task in tasks {
-                                let start = Instant::now();
-                                if let Ok(stats) =
-                                    run_sync_once(&db_path, &api_paths, &task.task_id, None, None)
-                                {
-                                    update_task_stats(
-                                        &stats_map,
-                                        &task.task_id,
-                                        stats,
-                                        start.elapsed(),
-                                    );
-                                }
-                            }
-                        }
-                    }
-                });
+                if let Err(err) = run_all_tasks_once(app, &app.state::<AppState>()) {
+                    eprintln!("failed to run all tasks: {}", err);
+                }
             }
             "quit" => {
                 app.exit(0);
@@ -1267,17 +4481,11 @@ fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn Error>> {
                 button: MouseButton::Left,
                 button_state: MouseButtonState::Up,
                 ..
-            } => {
-                let app = tray.app_handle();
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.unminimize();
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
-            }
+            } => show_main_window(tray.app_handle()),
             _ => {}
         })
         .build(app)?;
+    start_tray_refresher(app, servers);
     Ok(())
 }
 
@@ -1299,16 +4507,8 @@ fn setup_window_events(app: &AppHandle) {
     }
 }
 
-fn db_path() -> Result<PathBuf, Box<dyn Error>> {
-    let path = config_dir()?.join("cloudreve.db");
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    Ok(path)
-}
-
-fn collect_share_paths_from_args() -> Vec<String> {
-    let mut args = std::env::args().skip(1);
+fn collect_share_paths_from_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut args = args.into_iter().skip(1);
     let mut paths = Vec::new();
     let mut collect_all = false;
     while let Some(arg) = args.next() {
@@ -1342,9 +4542,56 @@ fn emit_share_requests(app: &AppHandle, paths: Vec<String>) {
     }
 }
 
+fn handle_deep_link(app: &AppHandle, link: &str) {
+    let state = app.state::<AppState>();
+    let link = match parse_deep_link(link) {
+        Ok(link) => link,
+        Err(err) => {
+            log_error(&state.db_path, "", &format!("无法处理链接: {}", err));
+            return;
+        }
+    };
+    show_main_window(app);
+    match link {
+        DeepLink::Open => {}
+        DeepLink::Share { path } => emit_share_requests(app, vec![path]),
+        DeepLink::ResolveConflict { id } => {
+            let _ = app.emit(CONFLICT_SELECTED_EVENT, ConflictSelectedPayload { id });
+        }
+        // 链接可能来自任意网页，由界面请用户确认后再同步。
+        DeepLink::Sync { task_id } => match load_task_settings(&state.db_path, &task_id) {
+            Ok((_, settings)) => {
+                let _ = app.emit(
+                    SYNC_REQUESTED_EVENT,
+                    SyncRequestedPayload {
+                        task_id,
+                        name: settings.name,
+                    },
+                );
+            }
+            Err(err) => log_error(
+                &state.db_path,
+                &task_id,
+                &format!("链接启动同步失败: {}", err),
+            ),
+        },
+    }
+}
+
+/// 已有实例在运行时，新进程的参数转发到这里；深度链接由 deep-link 插件另行分发。
+fn handle_second_instance(app: &AppHandle, args: Vec<String>) {
+    if !args
+        .iter()
+        .any(|arg| arg == HEADLESS_ARG || arg == MINIMIZED_ARG)
+    {
+        show_main_window(app);
+    }
+    emit_share_requests(app, collect_share_paths_from_args(args));
+}
+
 #[cfg(target_os = "linux")]
 fn install_linux_share_menus() -> Result<(), Box<dyn Error>> {
-    let exe_path = std::env::current_exe()?.to_string_lossy().to_string();
+    let exe_path = launcher_path()?.to_string_lossy().to_string();
     let base = directories::BaseDirs::new().ok_or("failed to locate data dir")?;
     let data_dir = base.data_dir();
 
@@ -1372,29 +4619,48 @@ fn install_linux_share_menus() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn refresh_tokens_once(db_path: &PathBuf) -> Result<(), Box<dyn Error>> {
-    let conn = Connection::open(db_path)?;
-    init_db(&conn)?;
-    let accounts = list_accounts(&conn)?;
-    for account in accounts {
-        let tokens = match load_tokens(&account.account_key) {
-            Ok(tokens) => tokens,
-            Err(_) => continue,
+/// 打开数据库前先尝试从损坏中恢复，再迁移并导入旧版配置。
+fn prepare_database(state: &AppState) -> Result<(), Box<dyn Error>> {
+    let db_path = &state.db_path;
+    let recovery = match backups_dir().and_then(|root| recover_corrupted_db(db_path, &root)) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("failed to recover corrupted database: {}", err);
+            None
+        }
+    };
+    *state.recovery.lock().unwrap() = recovery;
+    let conn = connect(db_path)?;
+    migrate_with_backup(&conn, || backup_now(db_path, "pre-migration").map(|_| ()))?;
+    if let Err(err) = import_legacy_files(db_path) {
+        eprintln!("failed to import legacy config: {}", err);
+        log_error(db_path, "", &format!("旧版配置导入失败: {}", err));
+    }
+    move_share_passwords(&conn, db_path)?;
+    Ok(())
+}
+
+/// 旧版本把分享密码写在任务的远端地址中，启动时移入钥匙串；钥匙串不可用时保留原地址，下次启动再试。
+fn move_share_passwords(conn: &Connection, db_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    for task in list_tasks(conn)? {
+        let Some(password) = CloudreveClient::share_password(&task.remote_root_uri) else {
+            continue;
         };
-        if tokens.refresh_token.is_empty() {
+        if let Err(err) = store_share_password(&task.task_id, password) {
+            log_warn(
+                db_path,
+                &task.task_id,
+                "settings",
+                &format!("无法把分享密码移入钥匙串: {}", err),
+            );
             continue;
         }
-        let refreshed =
-            tauri::async_runtime::block_on(refresh_token(&account.base_url, &tokens.refresh_token));
-        let refreshed = match refreshed {
-            Ok(value) => value,
-            Err(_) => continue,
-        };
-        let _ = store_tokens(
-            &account.account_key,
-            &refreshed.access_token,
-            &refreshed.refresh_token,
-        );
+        replace_task_remote_root(
+            conn,
+            &task.task_id,
+            &task.remote_root_uri,
+            &CloudreveClient::without_share_password(&task.remote_root_uri),
+        )?;
     }
     Ok(())
 }
@@ -1406,22 +4672,41 @@ fn main() {
             std::env::set_var("WEBKIT_DISABLE_DMABUF_RENDERER", "1");
         }
     }
-
-    let db_path = db_path().expect("db path");
-    let conn = Connection::open(&db_path).expect("db open");
-    init_db(&conn).expect("db init");
+    if let Some(log_file) = log_file_from_args(std::env::args()) {
+        if let Err(err) = redirect_output(&log_file) {
+            eprintln!(
+                "failed to redirect output to {}: {}",
+                log_file.display(),
+                err
+            );
+        }
+    }
 
     let state = AppState {
-        db_path,
+        db_path: db_path().expect("db path"),
         api_paths: ApiPaths::default(),
         runners: Mutex::new(HashMap::new()),
+        one_shots: Arc::new(Mutex::new(HashSet::new())),
         stats: Arc::new(Mutex::new(HashMap::new())),
+        health: HealthRegistry::new(),
+        global_stats: GlobalStats::new(),
+        recovery: Mutex::new(None),
+        remote_info: RemoteInfoCache::default(),
     };
 
     tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            handle_second_instance(app, args)
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .manage(state)
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
+            // 单实例插件先于 setup 初始化，第二个进程在那里就已退出，
+            // 恢复、迁移与导入只会由当前实例执行。
+            let state = app.state::<AppState>();
+            prepare_database(&state)?;
             let handle = app.handle();
             setup_tray(&handle)?;
             setup_window_events(&handle);
@@ -1431,22 +4716,73 @@ fn main() {
                     eprintln!("failed to install share menu: {}", err);
                 }
             }
-            emit_share_requests(&handle, collect_share_paths_from_args());
+            emit_share_requests(&handle, collect_share_paths_from_args(std::env::args()));
+            // 安装包会登记链接协议；便携版与开发环境在这里补登记。
+            #[cfg(any(windows, target_os = "linux"))]
+            if let Err(err) = app.deep_link().register_all() {
+                eprintln!("failed to register deep link scheme: {}", err);
+            }
+            let link_handle = handle.clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    handle_deep_link(&link_handle, url.as_str());
+                }
+            });
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                for url in urls {
+                    handle_deep_link(handle, url.as_str());
+                }
+            }
+            if std::env::args().any(|arg| arg == HEADLESS_ARG || arg == MINIMIZED_ARG) {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
+                }
+            }
+            if std::env::args().any(|arg| arg == HEADLESS_ARG) {
+                let state = app.state::<AppState>();
+                if let Err(err) = apply_provision_file(&state.db_path) {
+                    eprintln!("failed to apply provision file: {}", err);
+                    log_error(&state.db_path, "", &format!("配置文件应用失败: {}", err));
+                }
+            }
+            let app_settings = AppSettings::load().unwrap_or_default();
+            configure_transport(
+                app_settings.timeout_settings(),
+                app_settings.proxy_settings(),
+            );
             let state = app.state::<AppState>();
-            if let Ok(conn) = Connection::open(&state.db_path) {
+            if let Ok(conn) = connect(&state.db_path) {
                 if let Ok(tasks) = list_tasks(&conn) {
                     for task in tasks {
-                        if let Err(err) = start_sync_task(&handle, &state, &task.task_id) {
+                        if !parse_settings(&task.settings_json).enabled {
+                            continue;
+                        }
+                        // 健康检查需要知道重启前最近一次成功同步的时间。
+                        if let Ok(Some(run)) = get_task_run(&conn, &task.task_id) {
+                            if let Some(last_success_ms) = run.last_success_ms {
+                                state.health.record_success(&task.task_id, last_success_ms);
+                            }
+                        }
+                        if let Err(err) = start_sync_task(&handle, &state, &task.task_id, true) {
                             eprintln!("failed to auto start task {}: {}", task.task_id, err);
                         }
                     }
                 }
             }
             let db_path = state.db_path.clone();
+            let api_paths = state.api_paths.clone();
             thread::spawn(move || loop {
-                let _ = refresh_tokens_once(&db_path);
+                let _ = refresh_tokens_once(&db_path, &api_paths);
                 thread::sleep(Duration::from_secs(TOKEN_REFRESH_INTERVAL_SECS));
             });
+            let db_path = state.db_path.clone();
+            thread::spawn(move || loop {
+                run_scheduled_backup(&db_path);
+                run_scheduled_cleanup(&db_path);
+                thread::sleep(Duration::from_secs(BACKUP_CHECK_INTERVAL_SECS));
+            });
+            start_metrics_server(&state.health, &state.db_path);
+            start_global_stats_emitter(handle, &state.global_stats);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -1455,26 +4791,70 @@ fn main() {
             finish_sign_in_with_2fa_command,
             get_captcha_command,
             test_connection,
+            get_account_capacity_command,
+            test_proxy_command,
             create_task_command,
             list_tasks_command,
             list_accounts_command,
+            delete_remote_entries_command,
+            rename_remote_entry_command,
+            create_remote_folder_command,
+            get_account_defaults_command,
+            save_account_defaults_command,
+            apply_account_defaults_command,
+            set_task_audit_mode_command,
+            unlock_task_encryption_command,
+            set_task_enabled_command,
+            pause_sync_command,
+            resume_sync_command,
             list_remote_entries_command,
+            list_remote_page_command,
             create_share_link_command,
             get_settings_command,
             save_settings_command,
+            get_global_stats_command,
+            get_network_status_command,
             clear_credentials_command,
             open_local_path,
             open_external,
             mark_conflict_resolved,
             download_conflict_remote,
+            list_file_versions_command,
+            restore_version_command,
+            list_selective_sync_command,
+            set_selective_sync_command,
+            explain_path_command,
+            set_pin_state_command,
+            get_server_summaries_command,
+            list_transfers_command,
+            list_sync_runs_command,
+            manage_api_tokens_command,
+            set_task_schedule_command,
+            get_tree_at_run_command,
+            cancel_transfer_command,
+            bump_transfer_command,
+            preview_sync_command,
             hash_local_file,
             get_diagnostics_command,
             export_logs_command,
             list_conflicts_command,
+            purge_local_trash_command,
             list_logs_command,
+            list_batch_logs_command,
             run_sync_command,
             stop_sync_command,
-            delete_task_command
+            run_deep_scan_command,
+            start_all_tasks_command,
+            stop_all_tasks_command,
+            run_all_once_command,
+            delete_task_command,
+            list_backups_command,
+            create_backup_command,
+            compact_database_command,
+            restore_backup_command,
+            take_recovery_report_command,
+            install_service_command,
+            uninstall_service_command
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,376 @@
+//! 同步场景测试的构建器：临时本地目录、基于 httpmock 的模拟远端目录树与预置数据的数据库。
+//! 需要开启 `testsupport` 特性。
+
+use crate::core::config::ApiPaths;
+use crate::core::db::{
+    create_task, init_db, now_ms, update_task_settings, upsert_dir_entry, upsert_entry, EntryRow,
+    TaskRow,
+};
+use crate::core::events::EventBus;
+use crate::core::sync::{SyncEngine, META_MTIME, META_SHA256};
+use httpmock::{Method, Mock, MockServer};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+/// 模拟远端文件与目录统一使用的修改时间。
+pub const REMOTE_UPDATED_AT: &str = "2024-01-01T00:00:00Z";
+/// 带同步元数据的模拟远端文件写入的修改时间（毫秒）。
+pub const REMOTE_MTIME_MS: i64 = 1000;
+
+pub fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// 成功的空响应，用于模拟各类写入接口。
+pub fn ok_body() -> String {
+    json!({"code": 0, "data": {}, "msg": ""}).to_string()
+}
+
+/// 以成功的空响应模拟 `/api/v4{path}` 上的请求，返回的 Mock 可用于检查调用次数。
+pub fn mock_ok<'a>(server: &'a MockServer, method: Method, path: &str) -> Mock<'a> {
+    let path = format!("/api/v4{}", path);
+    server.mock(|when, then| {
+        when.method(method).path(path);
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(ok_body());
+    })
+}
+
+/// 临时本地同步根目录，丢弃时删除。
+pub struct SyncRoot {
+    dir: TempDir,
+}
+
+impl SyncRoot {
+    pub fn new() -> Self {
+        Self {
+            dir: TempDir::new().expect("temp sync root"),
+        }
+    }
+
+    /// 写入文件，缺少的上级目录一并创建。
+    pub fn file(self, relpath: &str, content: &str) -> Self {
+        let path = self.join(relpath);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("create parent dir");
+        }
+        fs::write(&path, content).expect("write file");
+        self
+    }
+
+    pub fn dir(self, relpath: &str) -> Self {
+        fs::create_dir_all(self.join(relpath)).expect("create dir");
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn join(&self, relpath: &str) -> PathBuf {
+        relpath
+            .split('/')
+            .fold(self.dir.path().to_path_buf(), |path, part| path.join(part))
+    }
+}
+
+impl Default for SyncRoot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum RemoteNode {
+    Dir,
+    File {
+        size: u64,
+        metadata: BTreeMap<String, String>,
+    },
+}
+
+/// 模拟远端目录树：挂载后按目录应答列表请求。
+#[derive(Debug, Clone)]
+pub struct FakeRemote {
+    root_uri: String,
+    nodes: BTreeMap<String, RemoteNode>,
+}
+
+impl FakeRemote {
+    pub fn new(root_uri: &str) -> Self {
+        Self {
+            root_uri: root_uri.trim_end_matches('/').to_string(),
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    pub fn dir(mut self, relpath: &str) -> Self {
+        self.add_parents(relpath);
+        self.nodes.insert(relpath.to_string(), RemoteNode::Dir);
+        self
+    }
+
+    /// 由本设备上传过的文件：带有内容哈希与修改时间元数据。
+    pub fn file(self, relpath: &str, content: &str) -> Self {
+        let metadata = BTreeMap::from([
+            (META_SHA256.to_string(), sha256_hex(content.as_bytes())),
+            (META_MTIME.to_string(), REMOTE_MTIME_MS.to_string()),
+        ]);
+        self.file_with_metadata(relpath, content.len() as u64, metadata)
+    }
+
+    /// 由其他客户端上传、没有同步元数据的文件。
+    pub fn plain_file(self, relpath: &str, size: u64) -> Self {
+        self.file_with_metadata(relpath, size, BTreeMap::new())
+    }
+
+    pub fn file_with_metadata(
+        mut self,
+        relpath: &str,
+        size: u64,
+        metadata: BTreeMap<String, String>,
+    ) -> Self {
+        self.add_parents(relpath);
+        self.nodes
+            .insert(relpath.to_string(), RemoteNode::File { size, metadata });
+        self
+    }
+
+    pub fn uri(&self, relpath: &str) -> String {
+        if relpath.is_empty() {
+            self.root_uri.clone()
+        } else {
+            format!("{}/{}", self.root_uri, relpath)
+        }
+    }
+
+    /// 为根目录及每个子目录注册列表应答。
+    pub fn mount<'a>(&self, server: &'a MockServer) -> Vec<Mock<'a>> {
+        let dirs = std::iter::once(String::new()).chain(
+            self.nodes
+                .iter()
+                .filter(|(_, node)| matches!(node, RemoteNode::Dir))
+                .map(|(relpath, _)| relpath.clone()),
+        );
+        dirs.map(|dir| {
+            let body = json!({
+                "code": 0,
+                "data": {"files": self.children(&dir), "next_marker": null},
+                "msg": ""
+            })
+            .to_string();
+            let uri = self.uri(&dir);
+            server.mock(|when, then| {
+                when.method(Method::GET)
+                    .path("/api/v4/file")
+                    .query_param("uri", uri);
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(body);
+            })
+        })
+        .collect()
+    }
+
+    fn children(&self, dir: &str) -> Vec<Value> {
+        self.nodes
+            .iter()
+            .filter(|(relpath, _)| parent_of(relpath) == dir)
+            .map(|(relpath, node)| {
+                let name = relpath.rsplit('/').next().unwrap_or(relpath);
+                let (kind, size, metadata) = match node {
+                    RemoteNode::Dir => (1, 0, BTreeMap::new()),
+                    RemoteNode::File { size, metadata } => (0, *size, metadata.clone()),
+                };
+                json!({
+                    "type": kind,
+                    "id": format!("id-{}", relpath),
+                    "name": name,
+                    "size": size,
+                    "updated_at": REMOTE_UPDATED_AT,
+                    "path": self.uri(relpath),
+                    "metadata": metadata,
+                })
+            })
+            .collect()
+    }
+
+    fn add_parents(&mut self, relpath: &str) {
+        let mut parent = parent_of(relpath);
+        while !parent.is_empty() {
+            self.nodes
+                .entry(parent.to_string())
+                .or_insert(RemoteNode::Dir);
+            parent = parent_of(parent);
+        }
+    }
+}
+
+fn parent_of(relpath: &str) -> &str {
+    relpath
+        .rsplit_once('/')
+        .map(|(parent, _)| parent)
+        .unwrap_or("")
+}
+
+/// 指向临时数据库文件的任务数据库，可预置任务与索引记录。
+pub struct TestDb {
+    file: NamedTempFile,
+    pub conn: Connection,
+}
+
+impl TestDb {
+    pub fn new() -> Self {
+        let file = NamedTempFile::new().expect("temp db");
+        let conn = Connection::open(file.path()).expect("open db");
+        init_db(&conn).expect("init db");
+        Self { file, conn }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.file.path().to_path_buf()
+    }
+
+    pub fn with_task(self, task: &TaskRow) -> Self {
+        create_task(&self.conn, task).expect("create task");
+        self
+    }
+
+    pub fn with_entry(self, entry: EntryRow) -> Self {
+        upsert_entry(&self.conn, &entry).expect("entry");
+        self
+    }
+
+    pub fn with_dir_entry(self, entry: EntryRow) -> Self {
+        upsert_dir_entry(&self.conn, &entry).expect("dir entry");
+        self
+    }
+
+    /// 以默认接口路径、无令牌创建同步引擎。
+    pub fn engine(&self, task: TaskRow) -> SyncEngine {
+        SyncEngine::new(
+            task,
+            ApiPaths::default(),
+            None,
+            self.path(),
+            EventBus::new(),
+        )
+    }
+}
+
+impl Default for TestDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 最常见的同步场景：启动模拟服务端并挂载远端目录树，在新数据库中创建以 `local` 为本地根目录的任务。
+/// 字段公开，测试解构后继续注册接口应答、预置数据。
+pub struct Scenario {
+    pub server: MockServer,
+    pub remote: FakeRemote,
+    pub local: SyncRoot,
+    pub task: TaskRow,
+    pub db: TestDb,
+}
+
+impl Scenario {
+    pub fn new(task_id: &str, mode: &str, remote: FakeRemote, local: SyncRoot) -> Self {
+        let server = MockServer::start();
+        remote.mount(&server);
+        let task = task_row(task_id, &server, &local, &remote, mode);
+        let db = TestDb::new().with_task(&task);
+        Self {
+            server,
+            remote,
+            local,
+            task,
+            db,
+        }
+    }
+
+    /// 替换任务设置并写回数据库。
+    pub fn settings(mut self, settings: Value) -> Self {
+        self.task.settings_json = settings.to_string();
+        update_task_settings(&self.db.conn, &self.task.task_id, &self.task.settings_json)
+            .expect("task settings");
+        self
+    }
+
+    /// 预置上次同步时两端内容都为 `content` 的索引记录，见 [`synced_entry`]。
+    pub fn synced(self, relpath: &str, content: &str) -> Self {
+        upsert_entry(&self.db.conn, &synced_entry(&self.task, relpath, content)).expect("entry");
+        self
+    }
+
+    /// 预置上次同步过的目录记录，见 [`synced_dir_entry`]。
+    pub fn synced_dir(self, relpath: &str) -> Self {
+        upsert_dir_entry(&self.db.conn, &synced_dir_entry(&self.task, relpath)).expect("dir entry");
+        self
+    }
+
+    pub fn engine(&self) -> SyncEngine {
+        self.db.engine(self.task.clone())
+    }
+}
+
+/// 以 `server` 为服务端、`local` 为本地根目录的任务，`settings_json` 为空设置。
+pub fn task_row(
+    task_id: &str,
+    server: &MockServer,
+    local: &SyncRoot,
+    remote: &FakeRemote,
+    mode: &str,
+) -> TaskRow {
+    TaskRow {
+        task_id: task_id.to_string(),
+        base_url: server.base_url(),
+        local_root: local.path().to_string_lossy().to_string(),
+        remote_root_uri: remote.uri(""),
+        device_id: "device".to_string(),
+        mode: mode.to_string(),
+        settings_json: "{}".to_string(),
+        created_at_ms: now_ms(),
+    }
+}
+
+/// 上次同步时两端内容都为 `content` 的索引记录，与 [`FakeRemote::file`] 的元数据一致。
+pub fn synced_entry(task: &TaskRow, relpath: &str, content: &str) -> EntryRow {
+    let sha256 = sha256_hex(content.as_bytes());
+    EntryRow {
+        task_id: task.task_id.clone(),
+        local_relpath: relpath.to_string(),
+        cloud_file_id: format!("id-{}", relpath),
+        cloud_uri: format!("{}/{}", task.remote_root_uri, relpath),
+        last_local_mtime_ms: REMOTE_MTIME_MS,
+        last_local_sha256: sha256.clone(),
+        last_local_size: content.len() as i64,
+        last_remote_mtime_ms: REMOTE_MTIME_MS,
+        last_remote_sha256: sha256,
+        last_sync_ts_ms: REMOTE_MTIME_MS,
+        state: "ok".to_string(),
+    }
+}
+
+/// 同步过的目录的索引记录。
+pub fn synced_dir_entry(task: &TaskRow, relpath: &str) -> EntryRow {
+    EntryRow {
+        task_id: task.task_id.clone(),
+        local_relpath: relpath.to_string(),
+        cloud_file_id: String::new(),
+        cloud_uri: format!("{}/{}", task.remote_root_uri, relpath),
+        last_local_mtime_ms: 0,
+        last_local_sha256: String::new(),
+        last_local_size: -1,
+        last_remote_mtime_ms: 0,
+        last_remote_sha256: String::new(),
+        last_sync_ts_ms: 0,
+        state: "ok".to_string(),
+    }
+}
@@ -2,11 +2,21 @@ use rusqlite::Connection;
 use tempfile::NamedTempFile;
 
 use cloudreve_sync_app::core::db::{
-    create_task, delete_task, init_db, insert_conflict, insert_log, insert_tombstone,
-    list_accounts, list_conflicts, list_entries_by_task, list_logs, list_tasks, list_tombstones,
-    now_ms, upsert_account, upsert_entry, AccountRow, ConflictRow, EntryRow, LogRow, TaskRow,
-    TombstoneRow,
+    bump_transfer, cancel_transfer, claim_next_transfer, clear_transfer_queue, create_task,
+    delete_all_accounts, delete_conflict_archive, delete_entries_under, delete_partial_download,
+    delete_task, enqueue_transfers, finish_transfers, get_account_defaults, get_account_status,
+    get_next_due, get_partial_download, get_scan_schedule, get_task_run, init_db, insert_conflict,
+    insert_conflict_archive, insert_log, insert_tombstone, list_accounts, list_batch_logs,
+    list_conflicts, list_entries_by_task, list_expired_conflict_archives, list_log_batches,
+    list_logs, list_queued_transfers, list_selective_excludes, list_sync_queue, list_sync_runs,
+    list_tasks, list_tombstones, list_transfers, mark_account_needs_login, now_ms,
+    record_account_auth, record_batch_item, record_scan, record_task_attempt, record_task_run,
+    request_deep_scan, set_next_due, set_selective_excludes, set_sync_queue, tree_at_run,
+    update_task_settings, upsert_account, upsert_account_defaults, upsert_entry,
+    upsert_partial_download, AccountDefaultsRow, AccountRow, ConflictArchiveRow, ConflictRow,
+    EntryRow, LogBatchRow, LogRow, PartialDownloadRow, TaskRow, TombstoneRow, TransferRow,
 };
+use cloudreve_sync_app::core::migrations::needs_migration;
 
 #[test]
 fn db_init_and_crud() {
@@ -35,6 +45,7 @@ fn db_init_and_crud() {
         cloud_uri: "cloudreve://my/Work/doc.txt".to_string(),
         last_local_mtime_ms: 1,
         last_local_sha256: "a".to_string(),
+        last_local_size: 3,
         last_remote_mtime_ms: 1,
         last_remote_sha256: "a".to_string(),
         last_sync_ts_ms: 1,
@@ -68,6 +79,7 @@ fn db_init_and_crud() {
         event: "upload".to_string(),
         detail: "doc.txt".to_string(),
         created_at_ms: now_ms(),
+        batch_id: None,
     };
     insert_log(&conn, &log).expect("insert log");
     let logs = list_logs(&conn, Some(&task.task_id), None, None, None).expect("list logs");
@@ -121,6 +133,7 @@ fn db_filters_and_updates() {
         cloud_uri: "cloudreve://root/Work/doc.txt".to_string(),
         last_local_mtime_ms: 1,
         last_local_sha256: "a".to_string(),
+        last_local_size: -1,
         last_remote_mtime_ms: 1,
         last_remote_sha256: "a".to_string(),
         last_sync_ts_ms: 1,
@@ -130,6 +143,7 @@ fn db_filters_and_updates() {
     let entry_v2 = EntryRow {
         last_local_mtime_ms: 2,
         last_local_sha256: "b".to_string(),
+        last_local_size: -1,
         last_remote_mtime_ms: 2,
         last_remote_sha256: "b".to_string(),
         last_sync_ts_ms: 2,
@@ -196,6 +210,7 @@ fn db_filters_and_updates() {
         event: "upload".to_string(),
         detail: "doc.txt".to_string(),
         created_at_ms: now_ms(),
+        batch_id: None,
     };
     let log_warn = LogRow {
         task_id: task_a.task_id.clone(),
@@ -203,6 +218,7 @@ fn db_filters_and_updates() {
         event: "delete".to_string(),
         detail: "old.txt".to_string(),
         created_at_ms: now_ms(),
+        batch_id: None,
     };
     insert_log(&conn, &log_info).expect("insert log info");
     insert_log(&conn, &log_warn).expect("insert log warn");
@@ -245,6 +261,7 @@ fn delete_task_removes_related_rows() {
         cloud_uri: "cloudreve://root/Work/doc.txt".to_string(),
         last_local_mtime_ms: 1,
         last_local_sha256: "a".to_string(),
+        last_local_size: -1,
         last_remote_mtime_ms: 1,
         last_remote_sha256: "a".to_string(),
         last_sync_ts_ms: 1,
@@ -276,6 +293,7 @@ fn delete_task_removes_related_rows() {
         event: "upload".to_string(),
         detail: "doc.txt".to_string(),
         created_at_ms: now_ms(),
+        batch_id: None,
     };
     insert_log(&conn, &log).expect("insert log");
 
@@ -294,3 +312,553 @@ fn delete_task_removes_related_rows() {
         .expect("list logs")
         .is_empty());
 }
+
+#[test]
+fn selective_excludes_replace_previous_selection() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    set_selective_excludes(
+        &conn,
+        "task-1",
+        &["Photos".to_string(), "Video/Raw".to_string()],
+    )
+    .expect("set excludes");
+    assert_eq!(
+        list_selective_excludes(&conn, "task-1").expect("list excludes"),
+        vec!["Photos".to_string(), "Video/Raw".to_string()]
+    );
+
+    set_selective_excludes(&conn, "task-1", &["Archive".to_string()]).expect("replace excludes");
+    assert_eq!(
+        list_selective_excludes(&conn, "task-1").expect("list excludes"),
+        vec!["Archive".to_string()]
+    );
+    assert!(list_selective_excludes(&conn, "task-2")
+        .expect("other task")
+        .is_empty());
+
+    delete_task(&conn, "task-1").expect("delete task");
+    assert!(list_selective_excludes(&conn, "task-1")
+        .expect("list excludes")
+        .is_empty());
+}
+
+#[test]
+fn transfer_queue_claims_by_priority_and_tracks_status() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    let items = ["a.txt", "b.txt", "c.txt", "d.txt"]
+        .iter()
+        .enumerate()
+        .map(|(position, relpath)| TransferRow {
+            task_id: "task-1".to_string(),
+            relpath: relpath.to_string(),
+            kind: "upload".to_string(),
+            size: 10,
+            status: "queued".to_string(),
+            priority: 0,
+            position: position as i64,
+            error: None,
+            updated_at_ms: 1,
+        })
+        .collect::<Vec<_>>();
+    enqueue_transfers(&conn, &items).expect("enqueue");
+
+    assert!(bump_transfer(&conn, "task-1", "c.txt").expect("bump"));
+    assert!(cancel_transfer(&conn, "task-1", "b.txt").expect("cancel"));
+    assert_eq!(
+        claim_next_transfer(&conn, "task-1")
+            .expect("claim")
+            .as_deref(),
+        Some("c.txt")
+    );
+    assert!(!cancel_transfer(&conn, "task-1", "c.txt").expect("cancel active"));
+    assert_eq!(
+        claim_next_transfer(&conn, "task-1")
+            .expect("claim")
+            .as_deref(),
+        Some("a.txt")
+    );
+    finish_transfers(
+        &conn,
+        "task-1",
+        &[
+            ("c.txt".to_string(), Some("网络错误".to_string())),
+            ("a.txt".to_string(), None),
+        ],
+    )
+    .expect("finish");
+    assert_eq!(
+        list_queued_transfers(&conn, "task-1").expect("queued"),
+        vec!["d.txt".to_string()]
+    );
+
+    let rows = list_transfers(&conn, Some("task-1")).expect("list");
+    let status = |relpath: &str| {
+        rows.iter()
+            .find(|row| row.relpath == relpath)
+            .map(|row| (row.status.clone(), row.error.clone()))
+            .expect("row")
+    };
+    assert_eq!(status("a.txt"), ("done".to_string(), None));
+    assert_eq!(status("b.txt").0, "cancelled");
+    assert_eq!(
+        status("c.txt"),
+        ("failed".to_string(), Some("网络错误".to_string()))
+    );
+    assert_eq!(rows[0].relpath, "d.txt");
+
+    // 重新排队时保留手动调整的优先级。
+    enqueue_transfers(&conn, &items[2..3]).expect("requeue");
+    assert_eq!(
+        claim_next_transfer(&conn, "task-1")
+            .expect("claim")
+            .as_deref(),
+        Some("c.txt")
+    );
+    clear_transfer_queue(&conn, "task-1").expect("clear");
+    assert!(list_transfers(&conn, None).expect("list").is_empty());
+}
+
+#[test]
+fn sync_queue_keeps_order_and_is_replaced() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    let queue = vec![
+        "z.txt".to_string(),
+        "a/b.txt".to_string(),
+        "m.txt".to_string(),
+    ];
+    set_sync_queue(&conn, "task-1", &queue).expect("save queue");
+    assert_eq!(list_sync_queue(&conn, "task-1").expect("list queue"), queue);
+
+    set_sync_queue(&conn, "task-1", &["m.txt".to_string()]).expect("replace queue");
+    assert_eq!(
+        list_sync_queue(&conn, "task-1").expect("list queue"),
+        vec!["m.txt".to_string()]
+    );
+    set_sync_queue(&conn, "task-1", &[]).expect("clear queue");
+    assert!(list_sync_queue(&conn, "task-1")
+        .expect("list queue")
+        .is_empty());
+}
+
+#[test]
+fn task_runs_track_attempts_separately_from_successes() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    assert!(get_task_run(&conn, "task-1").expect("get run").is_none());
+    record_task_run(&conn, "task-1", true, 100).expect("record success");
+    record_task_run(&conn, "task-1", false, 200).expect("record failure");
+    let run = get_task_run(&conn, "task-1")
+        .expect("get run")
+        .expect("run row");
+    assert_eq!(run.last_attempt_ms, Some(200));
+    assert_eq!(run.last_success_ms, Some(100));
+
+    record_task_attempt(&conn, "task-1", 300).expect("record paused attempt");
+    let run = get_task_run(&conn, "task-1")
+        .expect("get run")
+        .expect("run row");
+    assert_eq!(run.last_attempt_ms, Some(300));
+    assert_eq!(run.last_success_ms, Some(100));
+    assert_eq!(list_sync_runs(&conn, "task-1").expect("list runs").len(), 2);
+
+    delete_task(&conn, "task-1").expect("delete task");
+    assert!(get_task_run(&conn, "task-1").expect("get run").is_none());
+}
+
+#[test]
+fn tree_at_run_reconstructs_files_as_of_that_run() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    let entry = |relpath: &str, sha256: &str, size: i64| EntryRow {
+        task_id: "task-1".to_string(),
+        local_relpath: relpath.to_string(),
+        cloud_file_id: format!("id-{}", relpath),
+        cloud_uri: String::new(),
+        last_local_mtime_ms: 1,
+        last_local_sha256: sha256.to_string(),
+        last_local_size: size,
+        last_remote_mtime_ms: 1,
+        last_remote_sha256: sha256.to_string(),
+        last_sync_ts_ms: 1,
+        state: "ok".to_string(),
+    };
+    upsert_entry(&conn, &entry("a.txt", "h1", 3)).expect("upsert a");
+    upsert_entry(&conn, &entry("docs/b.txt", "h1", 5)).expect("upsert b");
+    record_task_run(&conn, "task-1", true, now_ms()).expect("record first run");
+    std::thread::sleep(std::time::Duration::from_millis(5));
+
+    upsert_entry(&conn, &entry("a.txt", "h2", 4)).expect("update a");
+    delete_entries_under(&conn, "task-1", "docs").expect("delete docs");
+    record_task_run(&conn, "task-1", false, now_ms()).expect("record second run");
+
+    let runs = list_sync_runs(&conn, "task-1").expect("list runs");
+    assert_eq!(runs.len(), 2);
+    assert!(!runs[0].success);
+    assert!(runs[1].success);
+
+    let before = tree_at_run(&conn, "task-1", runs[1].run_id)
+        .expect("tree")
+        .expect("run exists");
+    let summary = |tree: &[cloudreve_sync_app::core::db::EntryVersionRow]| {
+        tree.iter()
+            .map(|version| {
+                (
+                    version.relpath.clone(),
+                    version.sha256.clone(),
+                    version.size,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        summary(&before),
+        vec![
+            ("a.txt".to_string(), "h1".to_string(), 3),
+            ("docs/b.txt".to_string(), "h1".to_string(), 5),
+        ]
+    );
+    let after = tree_at_run(&conn, "task-1", runs[0].run_id)
+        .expect("tree")
+        .expect("run exists");
+    assert_eq!(
+        summary(&after),
+        vec![("a.txt".to_string(), "h2".to_string(), 4)]
+    );
+    assert!(tree_at_run(&conn, "task-2", runs[0].run_id)
+        .expect("tree")
+        .is_none());
+
+    delete_task(&conn, "task-1").expect("delete task");
+    assert!(list_sync_runs(&conn, "task-1")
+        .expect("list runs")
+        .is_empty());
+}
+
+#[test]
+fn scan_schedule_tracks_quick_and_deep_passes() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    assert!(get_scan_schedule(&conn, "task-1")
+        .expect("get schedule")
+        .is_none());
+
+    record_scan(&conn, "task-1", true, 100).expect("record deep");
+    record_scan(&conn, "task-1", false, 200).expect("record quick");
+    let state = get_scan_schedule(&conn, "task-1")
+        .expect("get schedule")
+        .expect("schedule row");
+    assert_eq!(state.last_quick_scan_ms, Some(200));
+    assert_eq!(state.last_deep_scan_ms, Some(100));
+
+    request_deep_scan(&conn, "task-1").expect("request deep");
+    let state = get_scan_schedule(&conn, "task-1")
+        .expect("get schedule")
+        .expect("schedule row");
+    assert_eq!(state.last_quick_scan_ms, Some(200));
+    assert_eq!(state.last_deep_scan_ms, None);
+
+    delete_task(&conn, "task-1").expect("delete task");
+    assert!(get_scan_schedule(&conn, "task-1")
+        .expect("get schedule")
+        .is_none());
+}
+
+#[test]
+fn next_due_roundtrip_and_cleanup() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    assert_eq!(get_next_due(&conn, "task-1").expect("get due"), None);
+    set_next_due(&conn, "task-1", 1_000).expect("set due");
+    set_next_due(&conn, "task-1", 2_000).expect("update due");
+    assert_eq!(get_next_due(&conn, "task-1").expect("get due"), Some(2_000));
+
+    delete_task(&conn, "task-1").expect("delete task");
+    assert_eq!(get_next_due(&conn, "task-1").expect("get due"), None);
+}
+
+#[test]
+fn delete_entries_under_removes_path_and_children_only() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    for relpath in ["a", "a/x.txt", "a/b/y.txt", "ab.txt", "b/a"] {
+        upsert_entry(
+            &conn,
+            &EntryRow {
+                task_id: "task-1".to_string(),
+                local_relpath: relpath.to_string(),
+                cloud_file_id: String::new(),
+                cloud_uri: String::new(),
+                last_local_mtime_ms: 1,
+                last_local_sha256: "a".to_string(),
+                last_local_size: -1,
+                last_remote_mtime_ms: 1,
+                last_remote_sha256: "a".to_string(),
+                last_sync_ts_ms: 1,
+                state: "ok".to_string(),
+            },
+        )
+        .expect("upsert entry");
+    }
+
+    delete_entries_under(&conn, "task-1", "a").expect("delete entries");
+    let mut remaining = list_entries_by_task(&conn, "task-1")
+        .expect("entries")
+        .into_iter()
+        .map(|entry| entry.local_relpath)
+        .collect::<Vec<_>>();
+    remaining.sort();
+    assert_eq!(remaining, vec!["ab.txt", "b/a"]);
+}
+
+#[test]
+fn account_defaults_roundtrip_and_task_settings_update() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    assert!(get_account_defaults(&conn, "acc-1")
+        .expect("get defaults")
+        .is_none());
+    upsert_account_defaults(
+        &conn,
+        &AccountDefaultsRow {
+            account_key: "acc-1".to_string(),
+            settings_json: r#"{"sync_interval_secs":30}"#.to_string(),
+            updated_at_ms: 1,
+        },
+    )
+    .expect("upsert defaults");
+    upsert_account_defaults(
+        &conn,
+        &AccountDefaultsRow {
+            account_key: "acc-1".to_string(),
+            settings_json: r#"{"sync_interval_secs":90}"#.to_string(),
+            updated_at_ms: 2,
+        },
+    )
+    .expect("update defaults");
+    let defaults = get_account_defaults(&conn, "acc-1")
+        .expect("get defaults")
+        .expect("defaults row");
+    assert_eq!(defaults.settings_json, r#"{"sync_interval_secs":90}"#);
+    assert_eq!(defaults.updated_at_ms, 2);
+
+    let task = TaskRow {
+        task_id: "task-1".to_string(),
+        base_url: "https://example.com".to_string(),
+        local_root: "/tmp/local".to_string(),
+        remote_root_uri: "cloudreve://my/Work".to_string(),
+        device_id: "device-1".to_string(),
+        mode: "Bidirectional".to_string(),
+        settings_json: "{}".to_string(),
+        created_at_ms: now_ms(),
+    };
+    create_task(&conn, &task).expect("create task");
+    update_task_settings(&conn, "task-1", &defaults.settings_json).expect("update settings");
+    let tasks = list_tasks(&conn).expect("list tasks");
+    assert_eq!(tasks[0].settings_json, defaults.settings_json);
+
+    delete_all_accounts(&conn).expect("delete accounts");
+    assert!(get_account_defaults(&conn, "acc-1")
+        .expect("get defaults")
+        .is_none());
+}
+
+#[test]
+fn needs_migration_only_for_partial_schema() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    assert!(!needs_migration(&conn).expect("fresh db"));
+    conn.execute_batch("CREATE TABLE tasks (task_id TEXT PRIMARY KEY);")
+        .expect("legacy table");
+    assert!(needs_migration(&conn).expect("legacy db"));
+    init_db(&conn).expect("init db");
+    assert!(!needs_migration(&conn).expect("current db"));
+}
+
+#[test]
+fn log_batches_accumulate_and_expand() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    let items = [
+        ("b1", "Photos/a.jpg", 100),
+        ("b1", "Photos/b.jpg", 50),
+        ("b2", "doc.txt", 7),
+    ];
+    for (batch_id, relpath, bytes) in items {
+        record_batch_item(
+            &conn,
+            &LogBatchRow {
+                batch_id: batch_id.to_string(),
+                task_id: "task-1".to_string(),
+                event: "upload".to_string(),
+                dir: "/Photos".to_string(),
+                file_count: 1,
+                total_bytes: bytes,
+                updated_at_ms: now_ms(),
+            },
+        )
+        .expect("record batch");
+        insert_log(
+            &conn,
+            &LogRow {
+                task_id: "task-1".to_string(),
+                level: "info".to_string(),
+                event: "upload".to_string(),
+                detail: relpath.to_string(),
+                created_at_ms: now_ms(),
+                batch_id: Some(batch_id.to_string()),
+            },
+        )
+        .expect("insert log");
+    }
+
+    let batches = list_log_batches(&conn).expect("list batches");
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].batch_id, "b1");
+    assert_eq!(batches[0].file_count, 2);
+    assert_eq!(batches[0].total_bytes, 150);
+    assert_eq!(list_batch_logs(&conn, "b1").expect("batch logs").len(), 2);
+
+    delete_task(&conn, "task-1").expect("delete task");
+    assert!(list_log_batches(&conn).expect("list batches").is_empty());
+}
+
+#[test]
+fn account_status_tracks_auth_and_relogin() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    assert!(get_account_status(&conn, "acc-1")
+        .expect("get status")
+        .is_none());
+    record_account_auth(&conn, "acc-1", 10).expect("record auth");
+    assert!(mark_account_needs_login(&conn, "acc-1", "40020", 20).expect("mark"));
+    assert!(!mark_account_needs_login(&conn, "acc-1", "40020", 30).expect("mark again"));
+    let status = get_account_status(&conn, "acc-1")
+        .expect("get status")
+        .expect("status row");
+    assert!(status.needs_login);
+    assert_eq!(status.last_auth_ms, Some(10));
+    assert_eq!(
+        (status.reason.as_str(), status.updated_at_ms),
+        ("40020", 30)
+    );
+
+    record_account_auth(&conn, "acc-1", 40).expect("re-login");
+    let status = get_account_status(&conn, "acc-1")
+        .expect("get status")
+        .expect("status row");
+    assert!(!status.needs_login);
+    assert_eq!(status.last_auth_ms, Some(40));
+    assert!(status.reason.is_empty());
+
+    delete_all_accounts(&conn).expect("delete accounts");
+    assert!(get_account_status(&conn, "acc-1")
+        .expect("get status")
+        .is_none());
+}
+
+#[test]
+fn partial_download_progress_roundtrip() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    let mut row = PartialDownloadRow {
+        task_id: "task-1".to_string(),
+        relpath: "big.iso".to_string(),
+        file_id: "f1".to_string(),
+        sha256: "abc".to_string(),
+        total_bytes: 100,
+        downloaded_bytes: 0,
+        temp_path: "/tmp/big.part".to_string(),
+        updated_at_ms: 1,
+        etag: "\"v1\"".to_string(),
+    };
+    upsert_partial_download(&conn, &row).expect("insert partial");
+    row.downloaded_bytes = 40;
+    upsert_partial_download(&conn, &row).expect("update partial");
+    assert_eq!(
+        get_partial_download(&conn, "task-1", "big.iso").expect("get partial"),
+        Some(row.clone())
+    );
+
+    delete_partial_download(&conn, "task-1", "big.iso").expect("delete partial");
+    assert_eq!(
+        get_partial_download(&conn, "task-1", "big.iso").expect("get partial"),
+        None
+    );
+    upsert_partial_download(&conn, &row).expect("insert partial");
+    delete_task(&conn, "task-1").expect("delete task");
+    assert_eq!(
+        get_partial_download(&conn, "task-1", "big.iso").expect("get partial"),
+        None
+    );
+}
+
+#[test]
+fn conflict_archive_lists_only_expired() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    init_db(&conn).expect("init db");
+
+    let archive = |relpath: &str, expires_at_ms: i64| ConflictArchiveRow {
+        task_id: "task-1".to_string(),
+        archive_relpath: relpath.to_string(),
+        original_relpath: "a.txt".to_string(),
+        archived_at_ms: 0,
+        expires_at_ms,
+    };
+    insert_conflict_archive(&conn, &archive(".conflicts-archive/old", 100)).expect("insert old");
+    insert_conflict_archive(&conn, &archive(".conflicts-archive/new", 300)).expect("insert new");
+
+    let expired = list_expired_conflict_archives(&conn, "task-1", 200).expect("list expired");
+    assert_eq!(expired, vec![archive(".conflicts-archive/old", 100)]);
+    delete_conflict_archive(&conn, "task-1", ".conflicts-archive/old").expect("delete archive");
+    assert!(list_expired_conflict_archives(&conn, "task-1", 200)
+        .expect("list expired")
+        .is_empty());
+
+    delete_task(&conn, "task-1").expect("delete task");
+    assert!(list_expired_conflict_archives(&conn, "task-1", 1_000)
+        .expect("list expired")
+        .is_empty());
+}
+
+#[test]
+fn init_db_adds_batch_column_to_legacy_logs() {
+    let file = NamedTempFile::new().expect("temp db");
+    let conn = Connection::open(file.path()).expect("open db");
+    conn.execute_batch(
+        "CREATE TABLE logs (id INTEGER PRIMARY KEY AUTOINCREMENT, task_id TEXT NOT NULL, level TEXT NOT NULL, event TEXT NOT NULL, detail TEXT NOT NULL, created_at_ms INTEGER NOT NULL);
+         INSERT INTO logs (task_id, level, event, detail, created_at_ms) VALUES ('t', 'info', 'upload', 'a', 1);",
+    )
+    .expect("legacy logs");
+    init_db(&conn).expect("init db");
+    let logs = list_logs(&conn, Some("t"), None, None, None).expect("list logs");
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].batch_id, None);
+}
@@ -63,12 +63,32 @@ export default {
     performance: "Performance",
     security: "Security",
     advanced: "Advanced",
-    autostart: "Launch at startup",
+    notifications: "Notifications",
+    notifyCompleted: "Notify when a sync finishes (only if files were transferred)",
+    notifyErrors: "Notify when a sync fails",
+    notifyConflicts: "Notify when conflicts need attention",
+    notifyAuth: "Notify when the sign-in expires",
+    notifyQuietStart: "Notification quiet hours start (hour, same as end = off)",
+    notifyQuietEnd: "Notification quiet hours end (hour)",
+    autostart: "Launch at login (starts in the tray)",
     tray: "Tray icon",
+    watchLocal: "Sync local changes in real time (restart tasks to apply)",
+    watchRemote: "Sync remote changes in real time when the server supports push (restart tasks to apply)",
+    startupCheck: "Check local files against the index at startup; changed tasks get a full scan",
     language: "Language",
     languageZh: "Simplified Chinese",
     languageEn: "English",
-    proxyPlaceholder: "Proxy URL",
+    proxyPlaceholder: "Proxy URL, e.g. http://127.0.0.1:8080 or socks5://host:1080",
+    proxyMode: "Proxy",
+    proxySystem: "Use system proxy",
+    proxyNone: "No proxy",
+    proxyManual: "Manual proxy",
+    proxyUsername: "Proxy username (optional)",
+    proxyPassword: "Proxy password",
+    proxyPasswordSaved: "Password saved in the keychain; leave empty to keep it",
+    proxyTest: "Test proxy",
+    proxyTestOk: "Server reached through the proxy in {ms} ms",
+    proxyTestFailed: "Proxy test failed: {msg}",
     retriesPlaceholder: "Max retries",
     backoffPlaceholder: "Backoff strategy",
     backoffExponential: "Exponential",
@@ -77,12 +97,70 @@ export default {
     uploadConcurrency: "Upload concurrency",
     downloadConcurrency: "Download concurrency",
     shaThreads: "SHA256 threads",
+    maxChunkKb: "Max upload chunk (KB, 0 = server default)",
+    chunkTimeoutSecs: "Chunk request timeout (seconds, extended for large chunks)",
+    connectTimeoutSecs: "Connect timeout (seconds)",
+    readTimeoutSecs: "Read timeout (seconds without data)",
+    fileDeadlineSecs: "Per-file transfer deadline (seconds, 0 = unlimited)",
+    idleDeferMinutes: "Defer deep scans until idle (minutes, 0 = off)",
+    quietHoursStart: "Quiet hours start (hour, same as end = off)",
+    quietHoursEnd: "Quiet hours end (hour)",
+    pauseOnMetered: "Pause on metered connections",
+    pauseOnBattery: "Pause on battery power or battery saver",
+    lowBatteryPercent: "Pause below battery level (%, 0 = off)",
+    pauseInterfaces: "Pause on network interfaces",
+    pauseInterfacesPlaceholder: "Comma separated, e.g. wwan0, usb0",
+    mimeTypes: "Custom MIME types",
+    mimeTypesPlaceholder: "One per line, e.g. dwg=image/vnd.dwg",
     clearCredentials: "Clear credentials",
-    lockPause: "Pause sync on lock screen",
+    lockPause: "Hold back uploads of files open in Office and similar programs",
+    scanCommandPlaceholder: "Post-download scan command (optional), e.g. clamscan --no-summary {path}",
+    scanTimeoutSecs: "Scan timeout (seconds)",
+    metricsPort: "Monitoring port",
+    metricsPortHint: "When non-zero, serves /metrics and /healthz on 127.0.0.1. Takes effect after restart.",
+    apiTokens: "Local API tokens",
+    apiTokenName: "Token name",
+    apiTokenCreate: "Create token",
+    apiTokenRevoke: "Revoke",
+    apiTokenCreated: "Copy this token now; it will not be shown again",
+    apiTokensHint: "Once any token exists, /metrics and /healthz require an Authorization: Bearer header with a status or full-control token.",
+    apiScopes: {
+      status: "Read-only status",
+      share: "Create shares only",
+      full: "Full control"
+    },
     debug: "Debug mode",
     trace: "API Trace",
+    serviceInstall: "Install background service",
+    serviceUninstall: "Remove background service",
+    serviceHint: "Starts a windowless instance at login and restarts it if it crashes (systemd user unit on Linux, scheduled task on Windows).",
+    serviceInstalled: "Background service installed; it starts at next login",
+    serviceUninstalled: "Background service removed",
+    serviceFailed: "Service operation failed: {msg}",
     saved: "Settings saved",
-    cleared: "Credentials cleared"
+    cleared: "Credentials cleared",
+    backup: "Backup",
+    backupInterval: "Backup interval (hours, 0 = off)",
+    backupKeep: "Backups to keep",
+    backupNow: "Back up now",
+    backupSelect: "Select a backup",
+    backupRestore: "Restore backup",
+    backupRestoreConfirm: "Restore {name}? All tasks will restart with the restored database and settings.",
+    backupCreated: "Backup created",
+    backupRestored: "Backup restored",
+    backupFailed: "Backup operation failed: {msg}",
+    retention: "Log retention",
+    logMaxRows: "Max log entries (0 = unlimited)",
+    logInfoDays: "Keep info logs for (days, 0 = forever)",
+    logWarnDays: "Keep warnings for (days, 0 = forever)",
+    logErrorDays: "Keep errors for (days, 0 = forever)",
+    tombstoneDays: "Keep deletion records for (days, 0 = forever)",
+    compactNow: "Clean up and compact database",
+    compacted: "Removed {logs} log entries and {tombstones} deletion records, reclaimed {size} MB",
+    compactFailed: "Compaction failed: {msg}",
+    networkProfile: "Network profile",
+    networkProfileAuto: "Auto detect",
+    networkDetected: "Active profile: {name}"
   },
   dashboard: {
     currentTasks: "Current Tasks",
@@ -101,22 +179,131 @@ export default {
     cardConflicts: "Unresolved Conflicts",
     running: "Running",
     paused: "Paused",
-    filesSuffix: "files"
+    filesSuffix: "files",
+    throughput: "Throughput",
+    throughputSub: "{tasks} task(s) syncing, {transfers} active transfer(s)",
+    bytesToday: "Today ↑ {up} ↓ {down}",
+    eta: "{eta} left",
+    expandBatch: "Details",
+    collapseBatch: "Hide",
+    servers: "Servers",
+    serversSub: "Health of each Cloudreve server across its accounts and tasks",
+    quota: "Storage",
+    quotaSub: "Cloud capacity and usage of each account",
+    quotaUsage: "{used} of {total} used",
+    serverTasks: "{tasks} task(s), {failing} failing, {errors} file error(s)",
+    serverStatus: {
+      idle: "No tasks",
+      ok: "Healthy",
+      running: "Running",
+      stale: "Stale",
+      failing: "Failing",
+      needs_login: "Sign-in required"
+    }
   },
   tasks: {
     newTask: "New Task",
+    startAll: "Start all",
+    stopAll: "Stop all",
+    runAllNow: "Run all now",
+    bulkDone: "{affected} task(s) affected, {skipped} skipped",
+    selectiveSync: "Folders",
+    selectiveTitle: "Choose folders to keep locally",
+    selectiveHint: "Unchecked folders are not downloaded; unmodified local copies are removed on the next sync.",
+    selectiveSaved: "Folder selection saved",
+    explainPath: "Explain",
+    explainTitle: "Why is this file synced this way?",
+    explainPlaceholder: "Path relative to the task folder, e.g. docs/report.pdf",
+    explainRun: "Explain",
+    pinFile: "Always keep on this device",
+    unpinFile: "Free up space",
+    pinned: "{count} file(s) will be kept on this device",
+    unpinned: "Freed local space for {count} file(s)",
+    preview: "Preview",
+    previewTitle: "Sync preview",
+    previewSummary: "Upload {uploads} files ({uploadSize}), download {downloads} files ({downloadSize}), delete {deletes}, conflicts {conflicts}",
+    previewTruncated: "The local scan hit its limits; unscanned folders are not included",
+    previewOverQuota: "{uploadSize} to upload exceeds the {remaining} of cloud storage left",
+    previewEmpty: "Both sides are in sync, nothing to do",
+    previewAction: "Action",
+    previewPath: "Path",
+    previewSize: "Size",
+    previewReason: "Reason",
+    previewStart: "Start sync",
+    queue: "Queue",
+    queueTitle: "Transfer queue",
+    queueEmpty: "No transfers in this sync pass",
+    queueKind: "Type",
+    queueStatus: "Status",
+    queueBump: "Move to top",
+    queueKinds: {
+      upload: "Upload",
+      download: "Download",
+      conflict: "Conflict"
+    },
+    history: "History",
+    historyTitle: "Sync history",
+    historyRun: "Sync run",
+    historyNoRuns: "No sync runs recorded yet",
+    historyEmpty: "No files were synced at this point",
+    historySuccess: "Succeeded",
+    historyFailed: "Failed",
+    historyHash: "SHA-256",
+    historyChangedAt: "Changed at",
+    queueStatuses: {
+      queued: "Queued",
+      active: "Transferring",
+      failed: "Failed",
+      done: "Done",
+      cancelled: "Cancelled (retried next sync)"
+    },
+    close: "Close",
+    previewActions: {
+      audit: "Record pending local write",
+      delete_local: "Delete local",
+      mark_remote_deleted: "Mark deleted remotely",
+      upload: "Upload",
+      upload_new: "Upload new",
+      download: "Download",
+      download_new: "Download new",
+      conflict: "Conflict",
+      keep_both: "Keep both"
+    },
+    stopSync: "Stop",
+    resumeSync: "Resume",
+    disableTask: "Disable",
+    enableTask: "Enable",
+    taskDisabled: "Task disabled; it will be skipped by start all and scheduled runs",
+    taskEnabled: "Task enabled",
     refresh: "Refresh",
     onlyErrors: "Errors only",
     onlyConflicts: "Conflicts only",
+    serverFilter: "Server: {server}",
     recentActive: "Recently active",
     tableName: "Task",
     tableMode: "Mode",
     tableLocal: "Local Directory",
     tableRemote: "Remote Directory",
     tableProgress: "Progress",
+    tableLastSync: "Last success",
+    lastAttempt: "Attempted: {time}",
+    staleSuccess: "No recent success",
+    encrypted: "Encrypted",
+    tableNextRun: "Next run",
     tableStatus: "Status",
     tableActions: "Actions",
     remove: "Remove",
+    deepScan: "Verify",
+    deepScanStarted: "Deep verification scheduled",
+    deepScanFailed: "Deep verification failed: {msg}",
+    emptyTrash: "Empty trash",
+    emptyTrashConfirm: "Files in the local .cloudreve-trash of \"{name}\" will be deleted permanently. Continue?",
+    emptyTrashDone: "Trash emptied ({count} batches)",
+    unlockEncryption: "Enter passphrase",
+    unlockEncryptionPrompt: "Enter the encryption passphrase of \"{name}\". It is checked against the key fingerprint and stored in the system keychain.",
+    unlockEncryptionDone: "Encryption key saved",
+    switchToAudit: "Switch to audit mode",
+    auditEnabled: "Audit mode enabled: uploads continue and pending local writes are logged",
     wizardTitle: "Create Sync Task",
     stepAccount: "Account & Site",
     stepDirectory: "Directories",
@@ -135,7 +322,7 @@ export default {
     captchaHint: "Fill in Base URL first, then click \"Refresh captcha\".",
     taskNamePlaceholder: "Task name",
     localDirPlaceholder: "Local directory",
-    remoteDirPlaceholder: "Remote directory (URI or path)",
+    remoteDirPlaceholder: "Remote directory (path, URI, or share cloudreve://<share id>@share/...)",
     browse: "Browse",
     modeBoth: "Bidirectional (default)",
     modeUploadOnly: "Local -> Remote",
@@ -143,7 +330,74 @@ export default {
     strategyHint: "Conflict dual-retention and soft-delete strategy are fixed.",
     firstSyncNow: "Sync now",
     firstSyncIndexOnly: "Build index only",
+    firstSyncPreview: "Preview first, then confirm",
     syncIntervalLabel: "Sync interval (seconds)",
+    firstSyncPolicy: "Existing files on both sides",
+    firstSyncKeepBoth: "Keep both (create conflict copy)",
+    firstSyncPreferLocal: "Prefer local",
+    firstSyncPreferNewer: "Prefer newer",
+    syncCronPlaceholder: "Cron expression (optional, e.g. 0 22 * * *); replaces the interval",
+    windowStart: "Window start",
+    windowEnd: "Window end",
+    schedule: "Schedule",
+    scheduleTitle: "Sync schedule",
+    addWindow: "Add time window",
+    scheduleHint: "Scheduled syncs run only inside the time windows (local time; an end before the start wraps past midnight). Manual syncs always run.",
+    scheduleSaved: "Schedule saved, next run: {time}",
+    catchUpMissedRuns: "Catch up missed runs on launch",
+    scanMaxDepth: "Max scan depth (0 = unlimited)",
+    scanMaxEntries: "Max scanned entries (0 = unlimited)",
+    scanDirConcurrency: "Directories read in parallel",
+    scanIncremental: "Scan directory by directory, syncing between batches",
+    scanAdaptive: "Scan rarely changing directories less often (deep scans still cover everything)",
+    remoteDelta: "Incremental remote listing",
+    remoteDeltaHint: "Quick scans skip remote folders whose modified time has not changed; deep scans always re-list everything.",
+    customMetadataPlaceholder: "Custom metadata, one key=value per line",
+    customMetadataHint: "Uploaded files are tagged with these entries, visible in the web UI. Keys must not start with sync_.",
+    annotateConflicts: "Annotate conflict copies with device, user, local path and reason",
+    preserveDirTimes: "Preserve folder modified times (restored after a folder's files are written)",
+    preservePermissions: "Preserve file permissions and executable bits (Linux/macOS only)",
+    xattrs: "Extended attributes and alternate data streams",
+    xattrsOff: "Don't sync extended attributes",
+    xattrsMetadata: "Store in remote metadata (suits small data such as tags)",
+    xattrsSidecar: "Store as sidecar files in a hidden remote folder",
+    onDemand: "Files on demand (create placeholders only; Windows downloads them when opened, on macOS/Linux choose \"Always keep on this device\" first)",
+    verifyWrites: "Verify every write (re-read downloaded files and re-check uploads; slower, for critical folders)",
+    skipUploadsOverQuota: "When cloud storage runs out, upload only the files that fit and sync the rest once space frees up",
+    dedupeUploads: "Copy identical files on the server instead of uploading them again",
+    symlinks: "Symbolic links",
+    symlinksSkip: "Skip symbolic links",
+    symlinksFollow: "Follow links and sync their targets (loops are skipped)",
+    symlinksPreserve: "Sync links as links (restored on macOS/Linux)",
+    compress: "Compress file content during transfer (the server stores compressed files)",
+    compressCodec: "Compression algorithm",
+    compressCodecZstd: "zstd (faster; other tools need zstd support to open the files)",
+    compressMinKib: "Only compress files larger than (KiB)",
+    compressExclude: "Extra extensions not to compress, comma separated (e.g. log, csv)",
+    compressHint: "Images, video, archives and other already compressed formats are always sent as is. Compressed files cannot be previewed or downloaded as-is from the web UI.",
+    encryptionPassphrase: "Encryption passphrase (optional; encrypts files before upload)",
+    encryptionPassphraseConfirm: "Repeat the passphrase",
+    encryptionMismatch: "The passphrases do not match",
+    obfuscateNames: "Also encrypt file and folder names",
+    encryptionHint: "The server only stores ciphertext. The passphrase cannot be recovered: without it the files cannot be decrypted. Other devices need the same passphrase; files on demand is not available.",
+    maxConflictCopies: "Max unresolved conflict copies per file (0 = unlimited)",
+    conflictRetentionDays: "Days to keep the losing version after resolving a conflict (0 deletes it)",
+    localTrashDays: "Days to keep remotely deleted files in the local .cloudreve-trash (0 deletes them)",
+    includePatternsPlaceholder: "Only sync files matching these patterns, one per line (empty syncs everything)",
+    excludePatternsPlaceholder: "Exclude patterns, one per line, e.g. node_modules/ or *.tmp",
+    ignorePatternsHint: "Patterns follow .gitignore syntax; a .cloudreveignore file in the local folder adds more exclusions. Ignored paths are never uploaded or downloaded.",
+    skipDownloadTypes: "Do not download these file types (files already on this computer keep syncing)",
+    fileTypeGroups: {
+      documents: "Documents",
+      images: "Images",
+      videos: "Videos",
+      other: "Other"
+    },
+    transferOrder: "Transfer order",
+    transferOrderPriority: "Pinned paths, recently modified and small files first",
+    transferOrderPath: "Alphabetical by path",
+    priorityPatternsPlaceholder: "Paths to sync first and upload right after saving, one per line, same syntax as exclude patterns, e.g. Documents/ or *.docx",
+    scanLimitsHint: "For very large roots (e.g. NAS). Files beyond the limits are skipped this pass, never treated as deleted.",
     cancel: "Cancel",
     previous: "Previous",
     next: "Next",
@@ -160,6 +414,18 @@ export default {
     file: "File",
     open: "Open",
     selectCurrentDir: "Select current directory",
+    newFolder: "New folder",
+    newFolderPrompt: "Folder name",
+    needsLogin: "sign-in required",
+    reloginHint: "This account's session has expired. Enter the password to sign in again; its tasks resume automatically afterwards.",
+    searchRemoteFolders: "Search folders under this directory, press Enter",
+    loadMore: "Load more",
+    rename: "Rename",
+    renamePrompt: "New name",
+    delete: "Delete",
+    confirm: "OK",
+    deleteRemoteConfirm: "Delete remote item {name}? It will be moved to the cloud trash; synced tasks containing it will treat this as a remote change.",
+    remoteActionFailed: "Remote operation failed: {msg}",
     modeBothValue: "Bidirectional",
     modeUploadValue: "UploadOnly",
     modeDownloadValue: "DownloadOnly",
@@ -170,6 +436,15 @@ export default {
     selectExistingFirst: "Please select an existing account",
     connectSuccess: "Connection successful",
     connectFailed: "Connection failed: {msg}",
+    serverVersion: "Server version",
+    latency: "Latency",
+    clockSkew: "Clock offset",
+    storagePolicies: "Storage policies",
+    notAuthenticated: "Not signed in",
+    unknown: "Unknown",
+    clockInSync: "In sync",
+    clockAhead: "Server {seconds}s ahead",
+    clockBehind: "Server {seconds}s behind",
     fillBaseUrlFirst: "Please fill in Base URL first",
     captchaRefreshed: "Captcha refreshed",
     captchaRefreshFailed: "Refresh captcha failed: {msg}",
@@ -196,6 +471,8 @@ export default {
     listTitle: "Conflict List",
     listSub: "Conflict records with dual-retention strategy",
     refresh: "Refresh",
+    atLimit: "These files reached the conflict copy limit and are no longer synced until resolved: {files}",
+    copies: "{count} copies",
     filterPlaceholder: "Filter by file name / directory",
     colName: "File",
     colTask: "Task",
@@ -216,7 +493,25 @@ export default {
     localDevice: "LOCAL",
     marked: "Marked as resolved",
     openedDownload: "Download link opened",
-    copied: "sha256 copied"
+    copied: "sha256 copied",
+    keepLocal: "Keep local",
+    keepRemote: "Keep remote",
+    keepConfirm: "The other version is moved to .conflicts-archive (or deleted if retention is 0). Continue?",
+    nameCollision: "Name collision",
+    nameCollisionHint: "{name} differs from another remote file only by letter case or Unicode form; this computer cannot store both, so it is not synced until renamed.",
+    renameRemote: "Rename remote file",
+    renameConfirm: "The remote file gets a numbered name and both files are synced separately. Continue?",
+    kept: "Conflict resolved",
+    resolveFailed: "Failed to resolve conflict: {msg}",
+    restorePrevious: "Restore previous version",
+    versionsTitle: "Remote versions",
+    versionTime: "Saved at",
+    versionSize: "Size (bytes)",
+    currentVersion: "Current",
+    restoreVersion: "Restore",
+    restoreConfirm: "The remote file is switched to this version and downloaded; the conflict copy stays in place. Continue?",
+    restored: "Version restored",
+    versionsFailed: "Failed to load versions: {msg}"
   },
   logs: {
     title: "Activity Logs",
@@ -247,14 +542,48 @@ export default {
     db: "Database",
     accounts: "Accounts",
     tasks: "Tasks",
+    connections: "Connections",
+    connectionsValue: "{requests} requests · {opened} connections opened · {reused} reused · {http2} over HTTP/2",
     close: "Close"
   },
+  relogin: {
+    title: "Sign-in required",
+    message: "The session for {account} has expired. Its tasks are paused and resume automatically after you sign in again.",
+    action: "Sign in again",
+    later: "Later"
+  },
+  deepLink: {
+    syncTitle: "Sync now",
+    syncMessage: "A link asked to sync the task \"{name}\" now. Continue?",
+    syncAction: "Sync",
+    cancel: "Cancel",
+    syncFailed: "Failed to start sync: {msg}"
+  },
+  recovery: {
+    title: "Database recovered",
+    restored: "The local database was corrupted and has been restored from backup {name}. The damaged file was kept at {path}.",
+    rebuilt: "The local database was corrupted and no usable backup was found, so an empty database was created. Please add your accounts and tasks again. The damaged file was kept at {path}.",
+    problem: "Integrity check: {msg}"
+  },
   common: {
     saveFailed: "Save failed: {msg}",
     statusSyncing: "Syncing",
     statusHashing: "Hashing",
     statusListingRemote: "Listing remote",
+    statusVerifying: "Verifying",
     statusPaused: "Paused",
+    statusUserPaused: "Paused (manual)",
+    statusOutsideWindow: "Outside sync window",
+    statusPausedMetered: "Paused (metered)",
+    statusPausedBattery: "Paused (battery)",
+    statusPausedInterface: "Paused (network interface)",
+    statusQuiet: "Quiet hours",
+    statusLockedElsewhere: "Syncing in another process",
+    statusRootReadOnly: "Local folder read-only",
+    statusNeedsAttention: "Too many conflict copies",
+    statusNeedsLogin: "Sign-in required",
+    statusDisabled: "Disabled",
+    statusCancelled: "Cancelled",
     statusError: "Error",
     statusConflict: "Conflict"
   }
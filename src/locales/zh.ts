@@ -63,12 +63,32 @@ export default {
     performance: "性能",
     security: "安全",
     advanced: "高级",
-    autostart: "开机自启动",
+    notifications: "通知",
+    notifyCompleted: "同步完成时通知（仅限有传输的同步）",
+    notifyErrors: "同步失败时通知",
+    notifyConflicts: "出现需要处理的冲突时通知",
+    notifyAuth: "登录失效时通知",
+    notifyQuietStart: "通知免打扰开始（时，与结束相同为关闭）",
+    notifyQuietEnd: "通知免打扰结束（时）",
+    autostart: "开机自启动（启动后只显示托盘图标）",
     tray: "托盘图标",
+    watchLocal: "实时同步本地修改（重启任务后生效）",
+    watchRemote: "服务器支持推送时实时同步远端修改（重启任务后生效）",
+    startupCheck: "启动时比对本地文件与索引，有变化的任务下次完整扫描",
     language: "语言",
     languageZh: "简体中文",
     languageEn: "English",
-    proxyPlaceholder: "代理地址",
+    proxyPlaceholder: "代理地址，如 http://127.0.0.1:8080 或 socks5://host:1080",
+    proxyMode: "代理",
+    proxySystem: "使用系统代理",
+    proxyNone: "不使用代理",
+    proxyManual: "手动设置代理",
+    proxyUsername: "代理用户名（可选）",
+    proxyPassword: "代理密码",
+    proxyPasswordSaved: "密码已保存在钥匙串中，留空则保持不变",
+    proxyTest: "测试代理",
+    proxyTestOk: "已通过代理连接服务器，耗时 {ms} 毫秒",
+    proxyTestFailed: "代理测试失败：{msg}",
     retriesPlaceholder: "最大重试次数",
     backoffPlaceholder: "退避策略",
     backoffExponential: "指数退避",
@@ -77,12 +97,70 @@ export default {
     uploadConcurrency: "上传并发",
     downloadConcurrency: "下载并发",
     shaThreads: "SHA256 线程数",
+    maxChunkKb: "最大上传分片 (KB，0 为服务端默认)",
+    chunkTimeoutSecs: "分片请求超时 (秒，大分片自动延长)",
+    connectTimeoutSecs: "连接超时 (秒)",
+    readTimeoutSecs: "读取超时 (秒，期间无数据即超时)",
+    fileDeadlineSecs: "单文件传输时限 (秒，0 为不限制)",
+    idleDeferMinutes: "空闲多少分钟后再执行深度扫描（0 为关闭）",
+    quietHoursStart: "静默时段开始（时，与结束相同为关闭）",
+    quietHoursEnd: "静默时段结束（时）",
+    pauseOnMetered: "按流量计费的网络下暂停同步",
+    pauseOnBattery: "使用电池或省电模式时暂停同步",
+    lowBatteryPercent: "电量低于此值时暂停（%，0 为关闭）",
+    pauseInterfaces: "使用以下网络接口时暂停",
+    pauseInterfacesPlaceholder: "以逗号分隔，例如 wwan0, usb0",
+    mimeTypes: "自定义 MIME 类型",
+    mimeTypesPlaceholder: "每行一条，如 dwg=image/vnd.dwg",
     clearCredentials: "清除登录凭据",
-    lockPause: "锁屏后暂停同步",
+    lockPause: "文件在 Office 等程序中打开时暂缓上传",
+    scanCommandPlaceholder: "下载后扫描命令（可选），如 clamscan --no-summary {path}",
+    scanTimeoutSecs: "扫描超时 (秒)",
+    metricsPort: "监控端口",
+    metricsPortHint: "非 0 时在 127.0.0.1 上提供 /metrics 与 /healthz，重启应用后生效",
+    apiTokens: "本地接口令牌",
+    apiTokenName: "令牌名称",
+    apiTokenCreate: "创建令牌",
+    apiTokenRevoke: "吊销",
+    apiTokenCreated: "请立即复制该令牌，之后不会再次显示",
+    apiTokensHint: "创建任意令牌后，访问 /metrics 与 /healthz 需在 Authorization: Bearer 头中携带只读状态或完全控制权限的令牌",
+    apiScopes: {
+      status: "只读状态",
+      share: "仅创建分享",
+      full: "完全控制"
+    },
     debug: "调试模式",
     trace: "API Trace",
+    serviceInstall: "安装后台服务",
+    serviceUninstall: "移除后台服务",
+    serviceHint: "登录后自动以无窗口方式启动，崩溃后自动重启（Linux 使用 systemd 用户单元，Windows 使用计划任务）",
+    serviceInstalled: "后台服务已安装，下次登录时启动",
+    serviceUninstalled: "后台服务已移除",
+    serviceFailed: "服务操作失败：{msg}",
     saved: "设置已保存",
-    cleared: "登录凭据已清除"
+    cleared: "登录凭据已清除",
+    backup: "备份",
+    backupInterval: "备份间隔（小时，0 为关闭）",
+    backupKeep: "保留份数",
+    backupNow: "立即备份",
+    backupSelect: "选择备份",
+    backupRestore: "恢复备份",
+    backupRestoreConfirm: "确定恢复 {name}？所有任务将使用恢复后的数据库与设置重新启动。",
+    backupCreated: "备份已创建",
+    backupRestored: "备份已恢复",
+    backupFailed: "备份操作失败：{msg}",
+    retention: "日志保留",
+    logMaxRows: "日志条数上限（0 表示不限）",
+    logInfoDays: "普通日志保留天数（0 表示一直保留）",
+    logWarnDays: "警告日志保留天数（0 表示一直保留）",
+    logErrorDays: "错误日志保留天数（0 表示一直保留）",
+    tombstoneDays: "删除记录保留天数（0 表示一直保留）",
+    compactNow: "清理并压缩数据库",
+    compacted: "已删除 {logs} 条日志、{tombstones} 条删除记录，回收 {size} MB",
+    compactFailed: "压缩失败：{msg}",
+    networkProfile: "网络配置",
+    networkProfileAuto: "自动识别",
+    networkDetected: "当前网络配置：{name}"
   },
   dashboard: {
     currentTasks: "当前任务",
@@ -101,22 +179,132 @@ export default {
     cardConflicts: "未处理冲突",
     running: "运行中",
     paused: "已暂停",
-    filesSuffix: "文件"
+    filesSuffix: "文件",
+    throughput: "传输概览",
+    throughputSub: "{tasks} 个任务同步中，{transfers} 个传输进行中",
+    bytesToday: "今日 ↑ {up} ↓ {down}",
+    eta: "剩余 {eta}",
+    expandBatch: "展开",
+    collapseBatch: "收起",
+    servers: "服务器",
+    serversSub: "各 Cloudreve 服务器下所有账户与任务的状态",
+    quota: "存储空间",
+    quotaSub: "各账户的云端容量与已用空间",
+    quotaUsage: "已用 {used} / 共 {total}",
+    serverTasks: "{tasks} 个任务，{failing} 个失败，{errors} 个文件出错",
+    serverStatus: {
+      idle: "无任务",
+      ok: "正常",
+      running: "运行中",
+      stale: "长时间未成功同步",
+      failing: "同步失败",
+      needs_login: "需要重新登录"
+    }
   },
   tasks: {
     newTask: "新建任务",
+    startAll: "全部启动",
+    stopAll: "全部停止",
+    runAllNow: "全部立即同步",
+    bulkDone: "已处理 {affected} 个任务，跳过 {skipped} 个",
+    selectiveSync: "选择目录",
+    selectiveTitle: "选择在本地保留的目录",
+    selectiveHint: "未勾选的目录不会下载，下次同步时会删除其中未修改的本地副本。",
+    selectiveSaved: "目录选择已保存",
+    explainPath: "路径诊断",
+    explainTitle: "查看文件的同步决定",
+    explainPlaceholder: "相对任务目录的路径，例如 docs/report.pdf",
+    explainRun: "诊断",
+    pinFile: "始终保留在此设备上",
+    unpinFile: "释放空间",
+    pinned: "{count} 个文件已设为始终保留在此设备上",
+    unpinned: "已释放 {count} 个文件的本地空间",
+    preview: "预览",
+    previewTitle: "同步预览",
+    previewSummary: "将上传 {uploads} 个文件（{uploadSize}）、下载 {downloads} 个文件（{downloadSize}），删除 {deletes} 个，冲突 {conflicts} 个",
+    previewTruncated: "本地扫描达到限制，未扫描的部分不在预览中",
+    previewOverQuota: "待上传 {uploadSize}，超出云端剩余空间 {remaining}",
+    previewOverQuota: "待上传 {uploadSize}，超出云端剩余空间 {remaining}",
+    previewEmpty: "两端已一致，没有需要处理的文件",
+    previewAction: "操作",
+    previewPath: "路径",
+    previewSize: "大小",
+    previewReason: "原因",
+    previewStart: "开始同步",
+    queue: "传输队列",
+    queueTitle: "传输队列",
+    queueEmpty: "本轮同步没有需要传输的文件",
+    queueKind: "类型",
+    queueStatus: "状态",
+    queueBump: "移到最前",
+    queueKinds: {
+      upload: "上传",
+      download: "下载",
+      conflict: "冲突"
+    },
+    history: "历史",
+    historyTitle: "同步历史",
+    historyRun: "同步记录",
+    historyNoRuns: "暂无同步记录",
+    historyEmpty: "该时间点没有已同步的文件",
+    historySuccess: "成功",
+    historyFailed: "失败",
+    historyHash: "SHA-256",
+    historyChangedAt: "修改时间",
+    queueStatuses: {
+      queued: "排队中",
+      active: "传输中",
+      failed: "失败",
+      done: "已完成",
+      cancelled: "已取消（下次同步重试）"
+    },
+    close: "关闭",
+    previewActions: {
+      audit: "记录待写入本地",
+      delete_local: "删除本地",
+      mark_remote_deleted: "远端标记删除",
+      upload: "上传",
+      upload_new: "上传新文件",
+      download: "下载",
+      download_new: "下载新文件",
+      conflict: "冲突",
+      keep_both: "保留两份"
+    },
+    stopSync: "停止",
+    resumeSync: "继续",
+    disableTask: "停用",
+    enableTask: "启用",
+    taskDisabled: "任务已停用，批量启动与定时同步将跳过该任务",
+    taskEnabled: "任务已启用",
     refresh: "刷新",
     onlyErrors: "仅错误",
     onlyConflicts: "仅冲突",
+    serverFilter: "服务器：{server}",
     recentActive: "最近活跃",
     tableName: "任务名",
     tableMode: "模式",
     tableLocal: "本地目录",
     tableRemote: "云端目录",
     tableProgress: "进度",
+    tableLastSync: "最近成功",
+    lastAttempt: "最近尝试：{time}",
+    staleSuccess: "长时间未成功",
+    encrypted: "已加密",
+    tableNextRun: "下次运行",
     tableStatus: "状态",
     tableActions: "操作",
     remove: "移除",
+    deepScan: "深度校验",
+    deepScanStarted: "已安排深度校验",
+    deepScanFailed: "深度校验失败: {msg}",
+    emptyTrash: "清空回收站",
+    emptyTrashConfirm: "将永久删除任务“{name}”本地 .cloudreve-trash 中的文件，是否继续？",
+    emptyTrashDone: "已清空回收站（{count} 批）",
+    unlockEncryption: "输入加密密码",
+    unlockEncryptionPrompt: "请输入“{name}”的加密密码，核对密钥指纹后保存到系统钥匙串。",
+    unlockEncryptionDone: "已保存加密密钥",
+    switchToAudit: "切换为审计模式",
+    auditEnabled: "已切换为审计模式：仅上传，需写入本地的文件会记录在日志中",
     wizardTitle: "新建同步任务",
     stepAccount: "账号与站点",
     stepDirectory: "选择目录",
@@ -135,7 +323,7 @@ export default {
     captchaHint: "请先填写 Base URL，再点击“刷新验证码”获取图片。",
     taskNamePlaceholder: "任务名称",
     localDirPlaceholder: "本地目录",
-    remoteDirPlaceholder: "云端目录 (URI 或路径)",
+    remoteDirPlaceholder: "云端目录 (路径、URI，或分享地址 cloudreve://分享ID@share/...)",
     browse: "浏览",
     modeBoth: "双向同步（默认）",
     modeUploadOnly: "本地 → 云端",
@@ -143,7 +331,75 @@ export default {
     strategyHint: "冲突双保留与软删除策略不可修改",
     firstSyncNow: "立即同步",
     firstSyncIndexOnly: "仅建立索引",
+    firstSyncPreview: "先预览，确认后再同步",
     syncIntervalLabel: "同步间隔 (秒)",
+    firstSyncPolicy: "两端已存在的文件",
+    firstSyncKeepBoth: "保留两份（生成冲突副本）",
+    firstSyncPreferLocal: "以本地为准",
+    firstSyncPreferNewer: "以较新者为准",
+    syncCronPlaceholder: "cron 表达式（可选，如 0 22 * * *），设置后取代同步间隔",
+    windowStart: "窗口开始",
+    windowEnd: "窗口结束",
+    schedule: "定时",
+    scheduleTitle: "定时规则",
+    addWindow: "添加时间窗口",
+    scheduleHint: "定时同步只在时间窗口内运行（本地时间，结束早于开始表示跨午夜），手动同步不受限制",
+    scheduleSaved: "定时规则已保存，下次运行: {time}",
+    catchUpMissedRuns: "启动时补跑错过的同步",
+    scanMaxDepth: "最大扫描深度（0 为不限）",
+    scanMaxEntries: "最大扫描条目数（0 为不限）",
+    scanDirConcurrency: "并行读取的目录数",
+    scanIncremental: "按目录分批扫描，每批之间先同步",
+    scanAdaptive: "降低长期未变化目录的扫描频率（深度扫描仍覆盖全部目录）",
+    remoteDelta: "远端增量列表",
+    remoteDeltaHint: "快速扫描时跳过修改时间未变化的远端目录，深度扫描仍会完整列出",
+    customMetadataPlaceholder: "自定义元数据，每行一项：键=值",
+    customMetadataHint: "上传的文件会带上这些元数据，可在网页端查看；键不能以 sync_ 开头",
+    annotateConflicts: "在冲突副本上附加说明（设备、用户、本地路径、原因）",
+    preserveDirTimes: "保留文件夹修改时间（文件夹内文件写入完成后恢复）",
+    preservePermissions: "保留文件权限与可执行位（仅 Linux/macOS）",
+    xattrs: "扩展属性与备用数据流",
+    xattrsOff: "不同步扩展属性",
+    xattrsMetadata: "保存在远端元数据中（适合标签等少量数据）",
+    xattrsSidecar: "保存为远端隐藏目录中的附属文件",
+    onDemand: "按需下载（只创建占位文件；Windows 打开时自动下载，macOS/Linux 需先选择“始终保留在此设备上”）",
+    verifyWrites: "写入后回读校验（重新读取下载的文件并核对上传结果；较慢，适合重要资料）",
+    skipUploadsOverQuota: "云端空间不足时只上传放得下的文件，其余等空间足够后再同步",
+    skipUploadsOverQuota: "云端空间不足时只上传放得下的文件，其余等空间足够后再同步",
+    dedupeUploads: "相同内容的文件在服务端复制，不再重复上传",
+    symlinks: "符号链接",
+    symlinksSkip: "跳过符号链接",
+    symlinksFollow: "跟随链接同步目标内容（形成循环的链接跳过）",
+    symlinksPreserve: "按链接本身同步（在 macOS/Linux 上还原）",
+    compress: "传输时压缩文件内容（服务器保存压缩后的文件）",
+    compressCodec: "压缩算法",
+    compressCodecZstd: "zstd（更快，其他工具需支持 zstd 才能打开）",
+    compressMinKib: "仅压缩大于此大小的文件（KiB）",
+    compressExclude: "额外不压缩的扩展名，以逗号分隔（如 log, csv）",
+    compressHint: "图片、视频、压缩包等已压缩格式始终原样传输。压缩后的文件无法在网页端直接预览或下载使用。",
+    encryptionPassphrase: "加密密码（可选，上传前在本机加密文件）",
+    encryptionPassphraseConfirm: "再次输入加密密码",
+    encryptionMismatch: "两次输入的密码不一致",
+    obfuscateNames: "同时加密文件名与目录名",
+    encryptionHint: "服务器只保存密文。密码无法找回，丢失后文件无法解密；其他设备需要输入相同的密码，且不能使用按需下载。",
+    maxConflictCopies: "同一文件未处理冲突副本上限（0 表示不限）",
+    conflictRetentionDays: "冲突处理后落选版本的保留天数（0 表示直接删除）",
+    localTrashDays: "远端删除的文件在本地回收站 .cloudreve-trash 的保留天数（0 表示直接删除）",
+    includePatternsPlaceholder: "只同步匹配的文件，每行一条规则（留空则同步全部）",
+    excludePatternsPlaceholder: "排除规则，每行一条，例如 node_modules/ 或 *.tmp",
+    ignorePatternsHint: "规则语法与 .gitignore 相同；本地目录下的 .cloudreveignore 文件可追加排除规则。被忽略的路径不会上传或下载。",
+    skipDownloadTypes: "不下载以下类型的文件（本机已有的文件照常同步）",
+    fileTypeGroups: {
+      documents: "文档",
+      images: "图片",
+      videos: "视频",
+      other: "其他"
+    },
+    transferOrder: "处理顺序",
+    transferOrderPriority: "优先路径、最近修改与小文件在前",
+    transferOrderPath: "按路径字母顺序",
+    priorityPatternsPlaceholder: "优先同步的路径，保存后立即上传；每行一条，语法同排除规则，例如 Documents/ 或 *.docx",
+    scanLimitsHint: "适用于 NAS 等超大目录。超出限制的文件本轮跳过，不会被当作已删除。",
     cancel: "取消",
     previous: "上一步",
     next: "下一步",
@@ -160,6 +416,18 @@ export default {
     file: "文件",
     open: "打开",
     selectCurrentDir: "选择当前目录",
+    newFolder: "新建文件夹",
+    newFolderPrompt: "文件夹名称",
+    needsLogin: "需要重新登录",
+    reloginHint: "该账户的登录已失效，请重新输入密码登录；登录成功后使用该账户的任务会自动恢复",
+    searchRemoteFolders: "在当前目录下搜索文件夹，回车开始",
+    loadMore: "加载更多",
+    rename: "重命名",
+    renamePrompt: "新名称",
+    delete: "删除",
+    confirm: "确定",
+    deleteRemoteConfirm: "确定删除云端项目 {name}？它将被移入云端回收站，包含它的同步任务会将其视为远端变更。",
+    remoteActionFailed: "云端操作失败：{msg}",
     modeBothValue: "双向",
     modeUploadValue: "单向→",
     modeDownloadValue: "单向←",
@@ -170,6 +438,15 @@ export default {
     selectExistingFirst: "请选择已有账号",
     connectSuccess: "连接成功",
     connectFailed: "连接失败：{msg}",
+    serverVersion: "服务端版本",
+    latency: "延迟",
+    clockSkew: "时钟偏差",
+    storagePolicies: "存储策略",
+    notAuthenticated: "未登录",
+    unknown: "未知",
+    clockInSync: "一致",
+    clockAhead: "服务端快 {seconds} 秒",
+    clockBehind: "服务端慢 {seconds} 秒",
     fillBaseUrlFirst: "请先填写 Base URL",
     captchaRefreshed: "验证码已刷新",
     captchaRefreshFailed: "刷新验证码失败：{msg}",
@@ -196,6 +473,8 @@ export default {
     listTitle: "冲突列表",
     listSub: "双保留策略下的冲突记录",
     refresh: "刷新",
+    atLimit: "以下文件的冲突副本已达上限，处理前不再同步：{files}",
+    copies: "{count} 个副本",
     filterPlaceholder: "筛选文件名 / 目录",
     colName: "文件名",
     colTask: "任务",
@@ -216,7 +495,25 @@ export default {
     localDevice: "LOCAL",
     marked: "已标记为处理",
     openedDownload: "已打开下载链接",
-    copied: "已复制 sha256"
+    copied: "已复制 sha256",
+    keepLocal: "保留本地版本",
+    keepRemote: "保留云端版本",
+    keepConfirm: "另一版本将移入 .conflicts-archive（保留天数为 0 时直接删除），是否继续？",
+    nameCollision: "名称冲突",
+    nameCollisionHint: "{name} 与另一个远端文件只差大小写或 Unicode 形式，本机无法同时保存，改名前不会同步。",
+    renameRemote: "远端文件改名",
+    renameConfirm: "远端文件将改为带序号的名称，之后两个文件分别同步，是否继续？",
+    kept: "冲突已处理",
+    resolveFailed: "处理冲突失败：{msg}",
+    restorePrevious: "恢复历史版本",
+    versionsTitle: "云端历史版本",
+    versionTime: "保存时间",
+    versionSize: "大小（字节）",
+    currentVersion: "当前版本",
+    restoreVersion: "恢复",
+    restoreConfirm: "云端文件将切换到该版本并下载到本地，冲突副本保留在原处，是否继续？",
+    restored: "已恢复该版本",
+    versionsFailed: "读取历史版本失败：{msg}"
   },
   logs: {
     title: "活动日志",
@@ -247,14 +544,48 @@ export default {
     db: "数据库",
     accounts: "账号数量",
     tasks: "任务数量",
+    connections: "网络连接",
+    connectionsValue: "请求 {requests} 次 · 新建连接 {opened} 个 · 复用 {reused} 次 · HTTP/2 {http2} 次",
     close: "关闭"
   },
+  relogin: {
+    title: "需要重新登录",
+    message: "账户 {account} 的登录已失效，使用该账户的任务已暂停，重新登录后自动恢复。",
+    action: "重新登录",
+    later: "稍后"
+  },
+  deepLink: {
+    syncTitle: "立即同步",
+    syncMessage: "外部链接请求立即同步任务“{name}”，是否继续？",
+    syncAction: "同步",
+    cancel: "取消",
+    syncFailed: "启动同步失败: {msg}"
+  },
+  recovery: {
+    title: "数据库已恢复",
+    restored: "本地数据库已损坏，已从备份 {name} 恢复。损坏的文件保留在 {path}。",
+    rebuilt: "本地数据库已损坏且没有可用备份，已重建空数据库，请重新添加账户和任务。损坏的文件保留在 {path}。",
+    problem: "完整性检查: {msg}"
+  },
   common: {
     saveFailed: "保存失败: {msg}",
     statusSyncing: "同步中",
     statusHashing: "计算哈希中",
     statusListingRemote: "扫描云端中",
+    statusVerifying: "校验中",
     statusPaused: "已暂停",
+    statusUserPaused: "已暂停（手动）",
+    statusOutsideWindow: "不在同步时段",
+    statusPausedMetered: "已暂停（按流量计费）",
+    statusPausedBattery: "已暂停（电池供电）",
+    statusPausedInterface: "已暂停（网络接口）",
+    statusQuiet: "静默时段",
+    statusLockedElsewhere: "其他进程同步中",
+    statusRootReadOnly: "本地只读",
+    statusNeedsAttention: "冲突过多",
+    statusNeedsLogin: "需要重新登录",
+    statusDisabled: "已停用",
+    statusCancelled: "已取消",
     statusError: "错误",
     statusConflict: "冲突"
   }
@@ -1,14 +1,35 @@
 import { invoke } from "@tauri-apps/api/core";
 import type {
   BootstrapPayload,
+  RecoveryReport,
   ConflictItem,
+  FileVersions,
   TaskItem,
   ActivityItem,
   AccountItem,
   AppSettings,
+  Capacity,
   DiagnosticInfo,
+  GlobalStats,
+  ServerReport,
+  ServerSummary,
   RemoteEntry,
-  LogsPage
+  RemoteEntryPage,
+  LogsPage,
+  LocalRoot,
+  NetworkStatus,
+  BackupInfo,
+  CompactReport,
+  BulkTaskSummary,
+  PathExplanation,
+  SelectiveNode,
+  SyncPreview,
+  TransferRow,
+  ApiScope,
+  ApiTokensResult,
+  SyncRunRow,
+  SyncWindow,
+  EntryVersionRow
 } from "./types";
 
 export interface LoginRequest {
@@ -30,6 +51,8 @@ export interface TwoFaFinishRequest {
   opt: string;
 }
 
+export type FileTypeGroup = "documents" | "images" | "videos" | "other";
+
 export interface CreateTaskRequest {
   name: string;
   base_url: string;
@@ -37,7 +60,51 @@ export interface CreateTaskRequest {
   local_root: string;
   remote_root_uri: string;
   mode: string;
-  sync_interval_secs: number;
+  sync_interval_secs?: number;
+  deep_scan_interval_secs?: number;
+  local_roots?: LocalRoot[];
+  first_sync_policy?: "keep_both" | "prefer_local" | "prefer_newer";
+  catch_up_missed_runs?: boolean;
+  sync_cron?: string;
+  sync_windows?: SyncWindow[];
+  scan_limits?: ScanLimits;
+  remote_delta?: boolean;
+  custom_metadata?: Record<string, string>;
+  annotate_conflicts?: boolean;
+  conflict_retention_days?: number;
+  max_conflict_copies?: number;
+  local_trash_days?: number;
+  include_patterns?: string[];
+  exclude_patterns?: string[];
+  transfer_order?: "priority" | "path";
+  skip_download_types?: FileTypeGroup[];
+  priority_patterns?: string[];
+  preserve_dir_times?: boolean;
+  preserve_permissions?: boolean;
+  xattrs?: "off" | "metadata" | "sidecar";
+  on_demand?: boolean;
+  verify_writes?: boolean;
+  skip_uploads_over_quota?: boolean;
+  dedupe_uploads?: boolean;
+  symlinks?: "skip" | "follow" | "preserve";
+  compression?: CompressionSettings;
+  encryption_passphrase?: string;
+  obfuscate_names?: boolean;
+}
+
+export interface ScanLimits {
+  max_depth: number | null;
+  max_entries: number | null;
+  dir_concurrency: number | null;
+  incremental: boolean;
+  adaptive: boolean;
+}
+
+export interface CompressionSettings {
+  enabled: boolean;
+  codec: "gzip" | "zstd";
+  min_size_bytes: number;
+  exclude_extensions: string[];
 }
 
 export interface LogsQuery {
@@ -59,6 +126,8 @@ export interface ListRemoteEntriesRequest {
   account_key: string;
   base_url: string;
   uri: string;
+  page_token?: string | null;
+  search?: string;
 }
 
 export interface CreateShareLinkRequest {
@@ -79,10 +148,14 @@ export async function getCaptcha(baseUrl: string) {
   return invoke("get_captcha_command", { payload: baseUrl });
 }
 
-export async function testConnection(account_key: string, base_url: string) {
+export async function testConnection(account_key: string, base_url: string): Promise<ServerReport> {
   return invoke("test_connection", { accountKey: account_key, baseUrl: base_url });
 }
 
+export async function getAccountCapacity(account_key: string, base_url: string): Promise<Capacity> {
+  return invoke("get_account_capacity_command", { accountKey: account_key, baseUrl: base_url });
+}
+
 export async function createTask(payload: CreateTaskRequest): Promise<string> {
   return invoke("create_task_command", { payload });
 }
@@ -95,6 +168,24 @@ export async function listAccounts(): Promise<AccountItem[]> {
   return invoke("list_accounts_command");
 }
 
+export async function getAccountDefaults(accountKey: string): Promise<Record<string, unknown>> {
+  return invoke("get_account_defaults_command", { payload: { account_key: accountKey } });
+}
+
+export async function saveAccountDefaults(accountKey: string, defaults: Record<string, unknown>) {
+  return invoke("save_account_defaults_command", {
+    payload: { account_key: accountKey, defaults }
+  });
+}
+
+export async function applyAccountDefaults(payload: {
+  account_key: string;
+  task_ids?: string[];
+  keys?: string[];
+}): Promise<number> {
+  return invoke("apply_account_defaults_command", { payload });
+}
+
 export async function getSettings(): Promise<AppSettings> {
   return invoke("get_settings_command");
 }
@@ -103,6 +194,43 @@ export async function saveSettings(payload: AppSettings) {
   return invoke("save_settings_command", { payload });
 }
 
+export type ManageApiTokensRequest =
+  | { action: "list" }
+  | { action: "create"; name: string; scope: ApiScope }
+  | { action: "revoke"; token_id: string };
+
+export async function manageApiTokens(payload: ManageApiTokensRequest): Promise<ApiTokensResult> {
+  return invoke("manage_api_tokens_command", { payload });
+}
+
+export async function testProxy(payload: AppSettings): Promise<number> {
+  return invoke("test_proxy_command", { payload });
+}
+
+export async function getGlobalStats(): Promise<GlobalStats> {
+  return invoke("get_global_stats_command");
+}
+
+export async function getNetworkStatus(): Promise<NetworkStatus> {
+  return invoke("get_network_status_command");
+}
+
+export async function listBackups(): Promise<BackupInfo[]> {
+  return invoke("list_backups_command");
+}
+
+export async function createBackup(): Promise<BackupInfo> {
+  return invoke("create_backup_command");
+}
+
+export async function compactDatabase(): Promise<CompactReport> {
+  return invoke("compact_database_command");
+}
+
+export async function restoreBackup(name: string) {
+  return invoke("restore_backup_command", { payload: { name } });
+}
+
 export async function clearCredentials() {
   return invoke("clear_credentials_command");
 }
@@ -119,14 +247,56 @@ export async function listRemoteEntries(payload: ListRemoteEntriesRequest): Prom
   return invoke("list_remote_entries_command", { payload });
 }
 
-export async function markConflictResolved(task_id: string, conflict_relpath: string) {
-  return invoke("mark_conflict_resolved", { task_id, conflict_relpath });
+export async function listRemotePage(payload: ListRemoteEntriesRequest): Promise<RemoteEntryPage> {
+  return invoke("list_remote_page_command", { payload });
+}
+
+export async function deleteRemoteEntries(payload: {
+  account_key: string;
+  base_url: string;
+  uris: string[];
+}) {
+  return invoke("delete_remote_entries_command", { payload });
+}
+
+export async function renameRemoteEntry(payload: {
+  account_key: string;
+  base_url: string;
+  uri: string;
+  new_name: string;
+}) {
+  return invoke("rename_remote_entry_command", { payload });
+}
+
+export async function createRemoteFolder(payload: {
+  account_key: string;
+  base_url: string;
+  parent_uri: string;
+  name: string;
+}): Promise<string> {
+  return invoke("create_remote_folder_command", { payload });
+}
+
+export async function markConflictResolved(
+  task_id: string,
+  conflict_relpath: string,
+  keep?: "local" | "remote" | "rename"
+) {
+  return invoke("mark_conflict_resolved", { task_id, conflict_relpath, keep });
 }
 
 export async function downloadConflictRemote(task_id: string, original_relpath: string) {
   return invoke("download_conflict_remote", { task_id, original_relpath });
 }
 
+export async function listFileVersions(task_id: string, relpath: string): Promise<FileVersions> {
+  return invoke("list_file_versions_command", { task_id, relpath });
+}
+
+export async function restoreVersion(task_id: string, relpath: string, version_id: string) {
+  return invoke("restore_version_command", { task_id, relpath, version_id });
+}
+
 export async function hashLocalFile(path: string): Promise<string> {
   return invoke("hash_local_file", { path });
 }
@@ -158,6 +328,99 @@ export async function stopSync(payload: SyncRequest) {
   return invoke("stop_sync_command", { payload });
 }
 
+export async function pauseSync(payload: SyncRequest) {
+  return invoke("pause_sync_command", { payload });
+}
+
+export async function resumeSync(payload: SyncRequest) {
+  return invoke("resume_sync_command", { payload });
+}
+
+export async function runDeepScan(payload: SyncRequest) {
+  return invoke("run_deep_scan_command", { payload });
+}
+
+export async function purgeLocalTrash(payload: SyncRequest): Promise<number> {
+  return invoke("purge_local_trash_command", { payload });
+}
+
+export async function startAllTasks(): Promise<BulkTaskSummary> {
+  return invoke("start_all_tasks_command");
+}
+
+export async function stopAllTasks(): Promise<BulkTaskSummary> {
+  return invoke("stop_all_tasks_command");
+}
+
+export async function runAllOnce(): Promise<BulkTaskSummary> {
+  return invoke("run_all_once_command");
+}
+
+export async function listSelectiveSync(payload: { task_id: string; relpath: string }): Promise<SelectiveNode[]> {
+  return invoke("list_selective_sync_command", { payload });
+}
+
+export async function setSelectiveSync(payload: { task_id: string; excluded: string[] }) {
+  return invoke("set_selective_sync_command", { payload });
+}
+
+export async function explainPath(payload: { task_id: string; relpath: string }): Promise<PathExplanation> {
+  return invoke("explain_path_command", { payload });
+}
+
+export async function setPinState(payload: { task_id: string; relpath: string; pinned: boolean }): Promise<number> {
+  return invoke("set_pin_state_command", { payload });
+}
+
+export async function getServerSummaries(): Promise<ServerSummary[]> {
+  return invoke("get_server_summaries_command");
+}
+
+export async function listTransfers(task_id?: string): Promise<TransferRow[]> {
+  return invoke("list_transfers_command", { task_id });
+}
+
+export async function setTaskSchedule(payload: {
+  task_id: string;
+  sync_interval_secs: number;
+  sync_cron: string;
+  sync_windows: SyncWindow[];
+}): Promise<string> {
+  return invoke("set_task_schedule_command", { payload });
+}
+
+export async function listSyncRuns(task_id: string): Promise<SyncRunRow[]> {
+  return invoke("list_sync_runs_command", { task_id });
+}
+
+export async function getTreeAtRun(task_id: string, run_id: number): Promise<EntryVersionRow[]> {
+  return invoke("get_tree_at_run_command", { task_id, run_id });
+}
+
+export async function cancelTransfer(payload: { task_id: string; relpath: string }): Promise<void> {
+  return invoke("cancel_transfer_command", { payload });
+}
+
+export async function bumpTransfer(payload: { task_id: string; relpath: string }): Promise<void> {
+  return invoke("bump_transfer_command", { payload });
+}
+
+export async function previewSync(payload: SyncRequest): Promise<SyncPreview> {
+  return invoke("preview_sync_command", { payload });
+}
+
+export async function setTaskEnabled(payload: { task_id: string; enabled: boolean }) {
+  return invoke("set_task_enabled_command", { payload });
+}
+
+export async function setTaskAuditMode(payload: { task_id: string; enabled: boolean }) {
+  return invoke("set_task_audit_mode_command", { payload });
+}
+
+export async function unlockTaskEncryption(payload: { task_id: string; passphrase: string }) {
+  return invoke("unlock_task_encryption_command", { payload });
+}
+
 export async function deleteTask(payload: DeleteTaskRequest) {
   return invoke("delete_task_command", { payload });
 }
@@ -166,6 +429,22 @@ export async function createShareLink(payload: CreateShareLinkRequest): Promise<
   return invoke("create_share_link_command", { payload });
 }
 
+export async function listBatchLogs(batchId: string): Promise<ActivityItem[]> {
+  return invoke("list_batch_logs_command", { batchId });
+}
+
+export async function takeRecoveryReport(): Promise<RecoveryReport | null> {
+  return invoke("take_recovery_report_command");
+}
+
+export async function installService(): Promise<void> {
+  return invoke("install_service_command");
+}
+
+export async function uninstallService(): Promise<void> {
+  return invoke("uninstall_service_command");
+}
+
 export async function fetchBootstrap(): Promise<BootstrapPayload> {
   return invoke("bootstrap");
 }
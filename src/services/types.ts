@@ -7,7 +7,9 @@ export interface DashboardCard {
 export interface TaskItem {
   id: string;
   name: string;
+  base_url: string;
   mode: string;
+  direction: "bidirectional" | "upload_only" | "download_only";
   local_path: string;
   remote_path: string;
   status: string;
@@ -16,6 +18,25 @@ export interface TaskItem {
   rate_down: string;
   queue: number;
   last_sync: string;
+  last_attempt: string;
+  next_run: string;
+  sync_interval_secs: number;
+  sync_cron: string;
+  sync_windows: SyncWindow[];
+  enabled: boolean;
+  paused: boolean;
+  stale: boolean;
+  encrypted: boolean;
+}
+
+export interface SyncWindow {
+  start: string;
+  end: string;
+}
+
+export interface LocalRoot {
+  local_root: string;
+  remote_prefix: string;
 }
 
 export interface ActivityItem {
@@ -23,6 +44,7 @@ export interface ActivityItem {
   event: string;
   detail: string;
   level: string;
+  batch_id?: string | null;
 }
 
 export interface LogsPage {
@@ -43,6 +65,21 @@ export interface ConflictItem {
   device: string;
   time: string;
   status: string;
+  copies: number;
+  at_limit: boolean;
+  reason: string;
+}
+
+export interface FileVersion {
+  id: string;
+  size: number;
+  created_at: string;
+  current: boolean;
+}
+
+export interface FileVersions {
+  versions: FileVersion[];
+  restored_version_id: string;
 }
 
 export type RemoteEntry = {
@@ -51,11 +88,18 @@ export type RemoteEntry = {
   is_dir: boolean;
 };
 
+export type RemoteEntryPage = {
+  entries: RemoteEntry[];
+  next_page_token: string | null;
+};
+
 export interface AccountItem {
   account_key: string;
   base_url: string;
   email: string;
   created_at_ms: number;
+  last_auth_ms: number | null;
+  needs_login: boolean;
 }
 
 export interface AppSettings {
@@ -63,6 +107,10 @@ export interface AppSettings {
   tray: boolean;
   language: string;
   proxy: string;
+  proxy_mode?: string;
+  proxy_username?: string;
+  proxy_password?: string;
+  proxy_password_saved?: boolean;
   retries: number;
   backoff: string;
   upload: number;
@@ -71,6 +119,122 @@ export interface AppSettings {
   lock_pause: boolean;
   debug: boolean;
   trace: boolean;
+  network_profiles: NetworkProfile[];
+  network_profile: string;
+  backup_keep: number;
+  backup_interval_hours: number;
+  log_max_rows: number;
+  log_info_days: number;
+  log_warn_days: number;
+  log_error_days: number;
+  tombstone_days: number;
+  idle_defer_minutes: number;
+  quiet_hours_start: number;
+  quiet_hours_end: number;
+  pause_on_metered: boolean;
+  pause_on_battery: boolean;
+  low_battery_percent: number;
+  pause_interfaces: string[];
+  mime_types: Record<string, string>;
+  scan_command: string;
+  scan_timeout_secs: number;
+  metrics_port: number;
+  watch_local: boolean;
+  watch_remote: boolean;
+  max_chunk_kb: number;
+  chunk_timeout_secs: number;
+  connect_timeout_secs: number;
+  read_timeout_secs: number;
+  file_deadline_secs: number;
+  startup_check: boolean;
+  notify_completed: boolean;
+  notify_errors: boolean;
+  notify_conflicts: boolean;
+  notify_auth: boolean;
+  notify_quiet_start: number;
+  notify_quiet_end: number;
+}
+
+export interface CompactReport {
+  logs_deleted: number;
+  batches_deleted: number;
+  tombstones_deleted: number;
+  size_before: number;
+  size_after: number;
+  reclaimed_bytes: number;
+}
+
+export interface BulkTaskSummary {
+  affected: number;
+  skipped: number;
+}
+
+export interface PathExplanation {
+  relpath: string;
+  action: string;
+  reason: string;
+  trace: string[];
+}
+
+export interface PreviewItem {
+  relpath: string;
+  action: string;
+  reason: string;
+  size: number;
+}
+
+export interface SyncPreview {
+  items: PreviewItem[];
+  uploads: number;
+  upload_bytes: number;
+  downloads: number;
+  download_bytes: number;
+  deletes: number;
+  conflicts: number;
+  truncated: boolean;
+  remaining_quota: number | null;
+}
+
+export interface SelectiveNode {
+  relpath: string;
+  name: string;
+  excluded: boolean;
+}
+
+export type ApiScope = "status" | "share" | "full";
+
+export interface ApiToken {
+  token_id: string;
+  name: string;
+  scope: ApiScope;
+  created_at_ms: number;
+}
+
+export interface ApiTokensResult {
+  tokens: ApiToken[];
+  created_token: string | null;
+}
+
+export interface BackupInfo {
+  name: string;
+  path: string;
+  reason: string;
+  created_at_ms: number;
+}
+
+export interface NetworkProfile {
+  name: string;
+  ssids: string[];
+  gateways: string[];
+  upload_limit_kbps?: number | null;
+  download_limit_kbps?: number | null;
+  concurrency?: number | null;
+  pause_sync: boolean;
+}
+
+export interface NetworkStatus {
+  snapshot: { ssid?: string | null; gateway?: string | null };
+  active_profile?: string | null;
 }
 
 export interface DiagnosticInfo {
@@ -81,6 +245,21 @@ export interface DiagnosticInfo {
   config_dir: string;
   accounts: number;
   tasks: number;
+  transport: TransportStats;
+}
+
+export interface TransportStats {
+  connections_opened: number;
+  requests: number;
+  http2_requests: number;
+  reused_requests: number;
+}
+
+export interface RecoveryReport {
+  problem: string;
+  moved_to: string;
+  restored_from: string | null;
+  recovered_at_ms: number;
 }
 
 export interface BootstrapPayload {
@@ -90,6 +269,77 @@ export interface BootstrapPayload {
   conflicts: ConflictItem[];
 }
 
+export interface AccountStats {
+  account_key: string;
+  rate_up: number;
+  rate_down: number;
+  active_tasks: number;
+  active_transfers: number;
+  uploaded_today: number;
+  downloaded_today: number;
+}
+
+export interface GlobalStats {
+  rate_up: number;
+  rate_down: number;
+  active_tasks: number;
+  active_transfers: number;
+  uploaded_today: number;
+  downloaded_today: number;
+  accounts: AccountStats[];
+}
+
+export interface TransferRow {
+  task_id: string;
+  relpath: string;
+  kind: "upload" | "download" | "conflict";
+  size: number;
+  status: "queued" | "active" | "failed" | "done" | "cancelled";
+  priority: number;
+  position: number;
+  error: string | null;
+  updated_at_ms: number;
+}
+
+export interface SyncRunRow {
+  run_id: number;
+  task_id: string;
+  finished_at_ms: number;
+  success: boolean;
+}
+
+export interface EntryVersionRow {
+  relpath: string;
+  size: number;
+  sha256: string;
+  cloud_file_id: string;
+  recorded_at_ms: number;
+}
+
+export interface TransferProgress {
+  task_id: string;
+  relpath: string;
+  direction: "upload" | "download";
+  transferred: number;
+  total: number;
+  bytes_per_sec: number;
+  eta_secs: number | null;
+  done: boolean;
+}
+
+export interface ServerSummary {
+  base_url: string;
+  accounts: string[];
+  needs_login: number;
+  tasks: number;
+  running: number;
+  failing: number;
+  stale: number;
+  file_errors: number;
+  last_success_ms: number | null;
+  status: "idle" | "ok" | "running" | "stale" | "failing" | "needs_login";
+}
+
 export interface TaskRuntimePayload {
   task_id: string;
   status: string;
@@ -98,4 +348,19 @@ export interface TaskRuntimePayload {
   rate_down: string;
   queue: number;
   last_sync: string;
+  last_attempt: string;
+}
+
+export interface Capacity {
+  total: number;
+  used: number;
+  storage_pack_total: number;
+}
+
+export interface ServerReport {
+  version: string | null;
+  latency_ms: number;
+  clock_skew_ms: number | null;
+  authenticated: boolean;
+  storage_policies: string[];
 }